@@ -0,0 +1,118 @@
+use crate::{
+    items::{self, ItemDef},
+    rng::Rng,
+    spells::{Status, StatusEffect},
+    strings::Strings,
+    world::{Entity, World},
+};
+use serde::{Deserialize, Serialize};
+
+/// How potions the player hasn't drunk yet can look. Each kind of potion looks
+/// like one of these for a whole run, and another the next.
+pub const APPEARANCES: [&str; 8] = ["red", "blue", "green", "black", "white", "golden", "violet", "murky"];
+
+/// How much a mixture that goes wrong hurts, for each essence in it.
+const MISHAP_DAMAGE: i32 = 1;
+
+/// Something potions and herbs are made of. Mixing two things brings together
+/// their essences and makes whichever potion has exactly those, so new potions
+/// can be found by reasoning about what goes into them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Essence {
+    Vital,
+    Arcane,
+    Fire,
+    Frost,
+    Venom,
+}
+
+impl Essence {
+    /// What a mixture with this essence in it does to the mixer when it goes
+    /// wrong, besides hurting them.
+    fn hazard(self) -> Option<StatusEffect> {
+        let (status, turns) = match self {
+            Essence::Vital | Essence::Arcane => return None,
+            Essence::Fire => (Status::Burning, 3),
+            Essence::Frost => (Status::Frozen, 2),
+            Essence::Venom => (Status::Poisoned, 4),
+        };
+        Some(StatusEffect { status, turns })
+    }
+}
+
+/// What comes of mixing two things.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Mixture {
+    /// The potion of this name from the item file.
+    Potion(String),
+    /// No potion has those essences, and the mixture blows up in the mixer's face.
+    Mishap { damage: i32, statuses: Vec<StatusEffect> },
+}
+
+fn essences(items: &[&ItemDef]) -> Vec<Essence> {
+    let mut essences: Vec<Essence> = items.iter().flat_map(|item| item.essences.iter().copied()).collect();
+    essences.sort();
+    essences.dedup();
+    essences
+}
+
+/// What mixing `first` with `second` makes: the potion with all of their essences
+/// and no others, or a mishap that's worse the more essences went in.
+pub fn mix(first: &ItemDef, second: &ItemDef, items: &[ItemDef]) -> Mixture {
+    let mixed = essences(&[first, second]);
+    match items.iter().find(|item| item.potion && essences(&[item]) == mixed) {
+        Some(potion) => Mixture::Potion(potion.name.clone()),
+        None => Mixture::Mishap {
+            damage: MISHAP_DAMAGE * mixed.len() as i32,
+            statuses: mixed.into_iter().filter_map(Essence::hazard).collect(),
+        },
+    }
+}
+
+/// Whether two potions would make the same thing, which would leave one of them
+/// impossible to mix.
+pub fn same_essences(first: &ItemDef, second: &ItemDef) -> bool {
+    essences(&[first]) == essences(&[second])
+}
+
+/// How the potion called `name` looks in the run from `seed`.
+pub fn appearance(seed: u64, name: &str, items: &[ItemDef]) -> &'static str {
+    let mut looks = APPEARANCES;
+    let mut rng = Rng::new(seed);
+    for index in (1..looks.len()).rev() {
+        looks.swap(index, rng.range(0, index as u32 + 1) as usize);
+    }
+    let potion = items.iter().filter(|item| item.potion).position(|item| item.name == name);
+    looks[potion.unwrap_or(0) % looks.len()]
+}
+
+/// Whether the player knows what `item` is: anything but a potion of a kind they
+/// haven't drunk yet.
+pub fn is_known(world: &World, item: &ItemDef) -> bool {
+    !item.potion || world.identified.contains(&item.name)
+}
+
+/// What the player knows the item called `name` as: its own name, or how it looks
+/// if they don't know what it is.
+pub fn name(world: &World, items: &[ItemDef], strings: &Strings, name: &str) -> String {
+    match items::find(items, name) {
+        Some(item) if !is_known(world, item) => {
+            let appearance = strings.name("appearance", appearance(world.seed, name, items));
+            strings.format("potion.unknown", &[("appearance", &appearance)])
+        }
+        _ => strings.name("item", name).to_string(),
+    }
+}
+
+/// The inventory indices of what `player` can use up for its effects.
+pub fn usable(player: &Entity, items: &[ItemDef]) -> Vec<usize> {
+    let usable = |name: &String| items::find(items, name).is_some_and(ItemDef::is_usable);
+    player.inventory.iter().enumerate().filter(|(_, name)| usable(name)).map(|(index, _)| index).collect()
+}
+
+/// The inventory indices of what `player` can mix, leaving out `except`.
+pub fn mixable(player: &Entity, items: &[ItemDef], except: Option<usize>) -> Vec<usize> {
+    let mixable = |name: &String| items::find(items, name).is_some_and(|item| !item.essences.is_empty());
+    let indices = player.inventory.iter().enumerate().filter(|&(index, name)| Some(index) != except && mixable(name));
+    indices.map(|(index, _)| index).collect()
+}
@@ -0,0 +1,80 @@
+use quicksilver::{geom::Vector, graphics::Color};
+use serde::{Deserialize, Serialize};
+
+/// How a tile or entity looks at one moment of its animation.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    pub glyph: char,
+    pub color: Color,
+    /// Nudge from the usual position, in tiles.
+    pub offset: Vector,
+}
+
+impl Frame {
+    pub fn still(glyph: char, color: Color) -> Frame {
+        Frame {
+            glyph,
+            color,
+            offset: Vector::ZERO,
+        }
+    }
+}
+
+/// Frames shown one after another in a loop, each for `frame_ms`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Animation {
+    pub frames: Vec<Frame>,
+    pub frame_ms: f64,
+}
+
+impl Animation {
+    /// The frame to show once `clock_ms` has passed since the game started.
+    pub fn frame(&self, clock_ms: f64) -> Option<Frame> {
+        if self.frames.is_empty() || self.frame_ms <= 0.0 {
+            return None;
+        }
+        let index = (clock_ms / self.frame_ms) as usize % self.frames.len();
+        Some(self.frames[index])
+    }
+
+    /// A flame flickering between a few shades of `color`.
+    pub fn flicker(glyph: char, color: Color) -> Animation {
+        let shades = [1.0, 0.8, 0.95, 0.7];
+        Animation {
+            frames: shades
+                .iter()
+                .map(|&shade| Frame::still(glyph, color.multiply(Color::WHITE.with_green(shade).with_blue(shade))))
+                .collect(),
+            frame_ms: 120.0,
+        }
+    }
+
+    /// Water rippling back and forth.
+    pub fn ripple(glyph: char, color: Color) -> Animation {
+        let offsets = [0.0, 0.06, 0.0, -0.06];
+        Animation {
+            frames: offsets
+                .iter()
+                .map(|&x| Frame {
+                    offset: Vector::new(x, 0.0),
+                    ..Frame::still(glyph, color)
+                })
+                .collect(),
+            frame_ms: 300.0,
+        }
+    }
+
+    /// A creature bobbing gently in place.
+    pub fn bob(glyph: char, color: Color) -> Animation {
+        Animation {
+            frames: vec![
+                Frame::still(glyph, color),
+                Frame {
+                    offset: Vector::new(0.0, -0.08),
+                    ..Frame::still(glyph, color)
+                },
+            ],
+            frame_ms: 450.0,
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use crate::strings::Strings;
+use serde::{Deserialize, Serialize};
+
+/// What every attribute starts at, and where it stops giving a bonus.
+pub const BASE_ATTRIBUTE: i32 = 3;
+
+/// How many points the player spends on their attributes when a run starts.
+pub const CREATION_POINTS: u32 = 3;
+
+/// The player gets a point to raise an attribute with on every level that's a
+/// multiple of this.
+pub const ATTRIBUTE_LEVELS: u32 = 3;
+
+/// How much less likely each point of dexterity over the base makes a hit to be
+/// blocked.
+const ACCURACY_PER_POINT: f32 = 0.05;
+
+/// How many percent each point of charisma over the base takes off shop prices,
+/// up to `MAX_DISCOUNT`. Each point under the base puts them up as much.
+const DISCOUNT_PER_POINT: i32 = 5;
+const MAX_DISCOUNT: i32 = 50;
+
+/// How many turns the player takes to heal a hp on their own, and how many fewer
+/// each point of constitution over the base takes, down to `FASTEST_HEAL_TURNS`.
+/// Each point under the base makes it take as many more.
+const HEAL_TURNS: i32 = 20;
+const HEAL_TURNS_PER_POINT: i32 = 2;
+const FASTEST_HEAL_TURNS: i32 = 5;
+
+/// The chance in percent of forcing a locked door open on each try, and how much
+/// more each point of strength or dexterity over the base adds, bashing or
+/// picking it, whichever the player is better at.
+const FORCE_CHANCE: i32 = 10;
+const FORCE_PER_POINT: i32 = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Attribute {
+    /// Melee damage.
+    Strength,
+    /// Getting melee hits past the target's defense.
+    Dexterity,
+    /// Spell damage.
+    Intelligence,
+    /// Max hp.
+    Constitution,
+    /// Shop prices.
+    Charisma,
+}
+
+impl Attribute {
+    pub const ALL: [Attribute; 5] = [
+        Attribute::Strength,
+        Attribute::Dexterity,
+        Attribute::Intelligence,
+        Attribute::Constitution,
+        Attribute::Charisma,
+    ];
+
+    pub fn name(self, strings: &Strings) -> &str {
+        strings.get(match self {
+            Attribute::Strength => "attribute.strength",
+            Attribute::Dexterity => "attribute.dexterity",
+            Attribute::Intelligence => "attribute.intelligence",
+            Attribute::Constitution => "attribute.constitution",
+            Attribute::Charisma => "attribute.charisma",
+        })
+    }
+
+    /// What the attribute does, for the menu it's raised from.
+    pub fn effect(self, strings: &Strings) -> &str {
+        strings.get(match self {
+            Attribute::Strength => "attribute.strength_effect",
+            Attribute::Dexterity => "attribute.dexterity_effect",
+            Attribute::Intelligence => "attribute.intelligence_effect",
+            Attribute::Constitution => "attribute.constitution_effect",
+            Attribute::Charisma => "attribute.charisma_effect",
+        })
+    }
+}
+
+/// A creature's strength, dexterity, intelligence, constitution and charisma.
+/// Monsters keep the base values, which give no bonus either way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attributes {
+    #[serde(default = "base")]
+    pub strength: i32,
+    #[serde(default = "base")]
+    pub dexterity: i32,
+    #[serde(default = "base")]
+    pub intelligence: i32,
+    #[serde(default = "base")]
+    pub constitution: i32,
+    #[serde(default = "base")]
+    pub charisma: i32,
+}
+
+fn base() -> i32 {
+    BASE_ATTRIBUTE
+}
+
+impl Default for Attributes {
+    fn default() -> Attributes {
+        Attributes {
+            strength: BASE_ATTRIBUTE,
+            dexterity: BASE_ATTRIBUTE,
+            intelligence: BASE_ATTRIBUTE,
+            constitution: BASE_ATTRIBUTE,
+            charisma: BASE_ATTRIBUTE,
+        }
+    }
+}
+
+impl Attributes {
+    pub fn get(&self, attribute: Attribute) -> i32 {
+        match attribute {
+            Attribute::Strength => self.strength,
+            Attribute::Dexterity => self.dexterity,
+            Attribute::Intelligence => self.intelligence,
+            Attribute::Constitution => self.constitution,
+            Attribute::Charisma => self.charisma,
+        }
+    }
+
+    pub fn raise(&mut self, attribute: Attribute) {
+        match attribute {
+            Attribute::Strength => self.strength += 1,
+            Attribute::Dexterity => self.dexterity += 1,
+            Attribute::Intelligence => self.intelligence += 1,
+            Attribute::Constitution => self.constitution += 1,
+            Attribute::Charisma => self.charisma += 1,
+        }
+    }
+
+    /// Extra melee damage: one for every two points of strength over the base.
+    pub fn melee_damage(&self) -> i32 {
+        (self.strength - BASE_ATTRIBUTE) / 2
+    }
+
+    /// How much less likely a melee hit is to be blocked.
+    pub fn accuracy(&self) -> f32 {
+        (self.dexterity - BASE_ATTRIBUTE) as f32 * ACCURACY_PER_POINT
+    }
+
+    /// Extra damage for damaging spells: one for every two points of intelligence
+    /// over the base.
+    pub fn spell_damage(&self) -> i32 {
+        (self.intelligence - BASE_ATTRIBUTE) / 2
+    }
+
+    /// Extra max hp: one for every point of constitution over the base.
+    pub fn hp(&self) -> i32 {
+        self.constitution - BASE_ATTRIBUTE
+    }
+
+    /// How many turns it takes to heal a hp on their own, fewer the more
+    /// constitution.
+    pub fn heal_turns(&self) -> usize {
+        (HEAL_TURNS - (self.constitution - BASE_ATTRIBUTE) * HEAL_TURNS_PER_POINT).max(FASTEST_HEAL_TURNS) as usize
+    }
+
+    /// The chance in percent of forcing a locked door open on one try.
+    pub fn force_chance(&self) -> u32 {
+        let best = self.strength.max(self.dexterity) - BASE_ATTRIBUTE;
+        (FORCE_CHANCE + best * FORCE_PER_POINT).clamp(0, 100) as u32
+    }
+
+    /// How many percent off shop prices charisma gets; less than nothing puts them
+    /// up instead.
+    pub fn discount(&self) -> i32 {
+        ((self.charisma - BASE_ATTRIBUTE) * DISCOUNT_PER_POINT).clamp(-MAX_DISCOUNT, MAX_DISCOUNT)
+    }
+}
@@ -0,0 +1,389 @@
+use crate::{
+    strings::Strings,
+    world::{Biome, Event},
+};
+use quicksilver::geom::Vector;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "sounds")]
+use quicksilver::{
+    lifecycle::Asset,
+    sound::{Sound, SoundError},
+};
+#[cfg(feature = "sounds")]
+use rodio::{source::ChannelVolume, Sink, Source};
+#[cfg(feature = "sounds")]
+use std::{collections::HashMap, io::Cursor, path::Path};
+
+/// How far away, in tiles, a sound fades out completely.
+#[cfg(feature = "sounds")]
+const HEARING_RANGE: f32 = 32.0;
+
+/// How far to one side, in tiles, a sound must be to come only from that side.
+#[cfg(feature = "sounds")]
+const PAN_RANGE: f32 = 16.0;
+
+/// Whether the sound at `path`, under `static/`, is there to be loaded. Sounds are
+/// optional, so one that isn't is simply never played.
+#[cfg(feature = "sounds")]
+fn shipped(path: &str) -> bool {
+    Path::new(path).exists()
+}
+
+/// How long one level's music takes to fade into the next.
+#[cfg(feature = "sounds")]
+const CROSSFADE_MS: f64 = 2000.0;
+
+/// Everything that makes a sound, each with its own file under `static/sounds`.
+/// The files aren't shipped with the game; any that are missing are left silent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SoundEvent {
+    Footstep,
+    Hit,
+    Death,
+    Pickup,
+    MenuMove,
+}
+
+impl SoundEvent {
+    #[cfg(feature = "sounds")]
+    const ALL: [SoundEvent; 5] = [
+        SoundEvent::Footstep,
+        SoundEvent::Hit,
+        SoundEvent::Death,
+        SoundEvent::Pickup,
+        SoundEvent::MenuMove,
+    ];
+
+    #[cfg(feature = "sounds")]
+    fn path(self) -> &'static str {
+        match self {
+            SoundEvent::Footstep => "sounds/footstep.ogg",
+            SoundEvent::Hit => "sounds/hit.ogg",
+            SoundEvent::Death => "sounds/death.ogg",
+            SoundEvent::Pickup => "sounds/pickup.ogg",
+            SoundEvent::MenuMove => "sounds/menu.ogg",
+        }
+    }
+
+    /// The sound a world event makes, if any. Only the player's own steps are heard.
+    pub fn for_event(event: &Event, player_id: usize) -> Option<SoundEvent> {
+        match *event {
+            Event::Moved { entity } if entity == player_id => Some(SoundEvent::Footstep),
+            Event::Moved { .. }
+            | Event::Spotted { .. }
+            | Event::LevelUp { .. }
+            | Event::Robbed { .. }
+            | Event::WoreOff { .. }
+            | Event::Unlocked { .. }
+            | Event::DoorHeld { .. }
+            | Event::Worked { .. }
+            | Event::Trapped { .. }
+            | Event::TorchLow { .. }
+            | Event::TorchOut { .. }
+            | Event::Hungry { .. }
+            | Event::Starving { .. }
+            | Event::Wedged { .. }
+            | Event::Plugged { .. }
+            | Event::Burned { .. }
+            | Event::Sank { .. }
+            | Event::Drowning { .. }
+            | Event::Warped { .. }
+            | Event::Visited { .. }
+            | Event::Spent { .. }
+            | Event::Kindled { .. }
+            | Event::Slid { .. } => None,
+            Event::Battered { .. } | Event::Collapsed { .. } => Some(SoundEvent::Hit),
+            Event::Hit { .. } => Some(SoundEvent::Hit),
+            Event::Died { .. } => Some(SoundEvent::Death),
+            Event::PickedUp { .. } => Some(SoundEvent::Pickup),
+        }
+    }
+}
+
+/// One of the volume controls in the settings menu.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Channel {
+    Master,
+    Music,
+    Effects,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 3] = [Channel::Master, Channel::Music, Channel::Effects];
+
+    pub fn name(self, strings: &Strings) -> &str {
+        strings.get(match self {
+            Channel::Master => "channel.master",
+            Channel::Music => "channel.music",
+            Channel::Effects => "channel.effects",
+        })
+    }
+}
+
+/// Volume of each channel in percent. Music and effects are scaled by the master
+/// volume, and silenced altogether while muted.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Volume {
+    pub master: u32,
+    pub music: u32,
+    pub effects: u32,
+    #[serde(default)]
+    pub muted: bool,
+}
+
+impl Default for Volume {
+    fn default() -> Self {
+        Volume {
+            master: 100,
+            music: 100,
+            effects: 100,
+            muted: false,
+        }
+    }
+}
+
+impl Volume {
+    /// How much the settings menu changes a volume by per press.
+    const STEP: u32 = 10;
+
+    pub fn get(&self, channel: Channel) -> u32 {
+        match channel {
+            Channel::Master => self.master,
+            Channel::Music => self.music,
+            Channel::Effects => self.effects,
+        }
+    }
+
+    fn get_mut(&mut self, channel: Channel) -> &mut u32 {
+        match channel {
+            Channel::Master => &mut self.master,
+            Channel::Music => &mut self.music,
+            Channel::Effects => &mut self.effects,
+        }
+    }
+
+    pub fn raise(&mut self, channel: Channel) {
+        let volume = self.get_mut(channel);
+        *volume = (*volume + Self::STEP).min(100);
+    }
+
+    pub fn lower(&mut self, channel: Channel) {
+        let volume = self.get_mut(channel);
+        *volume = volume.saturating_sub(Self::STEP);
+    }
+
+    /// The volume to play `channel` at, as a factor from 0 to 1.
+    #[cfg(feature = "sounds")]
+    fn level(&self, channel: Channel) -> f32 {
+        if self.muted {
+            return 0.0;
+        }
+        (self.master * self.get(channel)) as f32 / (100.0 * 100.0)
+    }
+}
+
+#[cfg(feature = "sounds")]
+fn music_path(biome: Biome) -> Option<&'static str> {
+    Some(match biome {
+        Biome::Surface => "music/surface.ogg",
+        Biome::Caves => "music/caves.ogg",
+        Biome::Crypt => "music/crypt.ogg",
+        Biome::Swamp => "music/swamp.ogg",
+        Biome::Abyss => "music/abyss.ogg",
+    })
+}
+
+/// Background noise for each biome, played quietly under the music.
+#[cfg(feature = "sounds")]
+fn ambience_path(biome: Biome) -> Option<&'static str> {
+    match biome {
+        Biome::Surface => None,
+        Biome::Caves => Some("ambience/dripping.ogg"),
+        Biome::Crypt => Some("ambience/wind.ogg"),
+        Biome::Swamp => Some("ambience/frogs.ogg"),
+        Biome::Abyss => Some("ambience/heartbeat.ogg"),
+    }
+}
+
+/// How loud ambience is next to the music.
+#[cfg(feature = "sounds")]
+const AMBIENCE_LEVEL: f32 = 0.4;
+
+/// A looping sound that's playing, and how far it has faded in.
+#[cfg(feature = "sounds")]
+struct Track {
+    biome: Biome,
+    sink: Sink,
+    volume: f64,
+}
+
+/// A sound that loops for as long as the player stays in a biome, crossfading into
+/// the next biome's. A biome whose file isn't shipped has silence instead.
+#[cfg(feature = "sounds")]
+struct Loop {
+    path: fn(Biome) -> Option<&'static str>,
+    sounds: HashMap<Biome, Asset<Sound>>,
+    /// The track for the current level, fading in.
+    track: Option<Track>,
+    /// Tracks for earlier levels, fading out.
+    fading: Vec<Track>,
+}
+
+#[cfg(feature = "sounds")]
+impl Loop {
+    fn new(path: fn(Biome) -> Option<&'static str>) -> Loop {
+        let sounds = [Biome::Surface, Biome::Caves, Biome::Crypt, Biome::Swamp, Biome::Abyss]
+            .iter()
+            .filter_map(|&biome| Some((biome, path(biome).filter(|path| shipped(path))?)))
+            .map(|(biome, path)| (biome, Asset::new(Sound::load(path))))
+            .collect();
+        Loop {
+            path,
+            sounds,
+            track: None,
+            fading: Vec::new(),
+        }
+    }
+
+    fn update(&mut self, biome: Biome, paused: bool, elapsed_ms: f64, level: f32) {
+        if self.track.as_ref().map(|track| track.biome) != Some(biome) {
+            self.fading.extend(self.track.take());
+            self.track = self.start(biome);
+        }
+
+        let step = if paused { 0.0 } else { elapsed_ms / CROSSFADE_MS };
+        for track in &mut self.fading {
+            track.volume = (track.volume - step).max(0.0);
+        }
+        self.fading.retain(|track| track.volume > 0.0);
+        if let Some(track) = &mut self.track {
+            track.volume = (track.volume + step).min(1.0);
+        }
+
+        for track in self.track.iter().chain(&self.fading) {
+            track.sink.set_volume(track.volume as f32 * level);
+            if paused {
+                track.sink.pause();
+            } else {
+                track.sink.play();
+            }
+        }
+    }
+
+    /// Start a biome's track looping from silence, once it has loaded.
+    fn start(&mut self, biome: Biome) -> Option<Track> {
+        let asset = self.sounds.get_mut(&biome)?;
+        let mut track = None;
+        // Still loading is fine; this runs again next update until the track starts
+        let result = asset.execute(|sound| {
+            let device = rodio::default_output_device().ok_or(SoundError::NoOutputAvailable)?;
+            let source = rodio::Decoder::new(Cursor::new(sound.clone())).map_err(SoundError::from)?;
+            let sink = Sink::new(&device);
+            sink.set_volume(0.0);
+            sink.append(source.buffered().repeat_infinite());
+            track = Some(Track {
+                biome,
+                sink,
+                volume: 0.0,
+            });
+            Ok(())
+        });
+        if let Err(err) = result {
+            log::warn!(target: "audio", "Could not play {}: {}", (self.path)(biome).unwrap_or_default(), err);
+            self.sounds.remove(&biome);
+        }
+        track
+    }
+}
+
+/// Plays sound effects, music and ambience. Without the `sounds` feature it does nothing, so
+/// the game builds without the audio libraries.
+pub struct Audio {
+    #[cfg(feature = "sounds")]
+    sounds: HashMap<SoundEvent, Asset<Sound>>,
+    #[cfg(feature = "sounds")]
+    music: Loop,
+    #[cfg(feature = "sounds")]
+    ambience: Loop,
+    #[cfg(feature = "sounds")]
+    volume: Volume,
+}
+
+impl Audio {
+    #[cfg(feature = "sounds")]
+    pub fn new() -> Audio {
+        let sounds = SoundEvent::ALL
+            .iter()
+            .filter(|event| shipped(event.path()))
+            .map(|&event| (event, Asset::new(Sound::load(event.path()))))
+            .collect();
+        Audio {
+            sounds,
+            music: Loop::new(music_path),
+            ambience: Loop::new(ambience_path),
+            volume: Volume::default(),
+        }
+    }
+
+    #[cfg(not(feature = "sounds"))]
+    pub fn new() -> Audio {
+        Audio {}
+    }
+
+    /// Play the sound for `event` as if it happened where the player is.
+    pub fn play(&mut self, event: SoundEvent) {
+        self.play_at(event, Vector::ZERO);
+    }
+
+    /// Play the sound for `event` from `offset` tiles away from the player: quieter
+    /// the further away it is, and panned towards its side. A sound with no file
+    /// stays silent, and one that fails to load is logged once and then left silent.
+    #[cfg(feature = "sounds")]
+    pub fn play_at(&mut self, event: SoundEvent, offset: Vector) {
+        let level = self.volume.level(Channel::Effects) * (1.0 - offset.len() / HEARING_RANGE).max(0.0);
+        if level <= 0.0 {
+            return;
+        }
+        let pan = (offset.x / PAN_RANGE).clamp(-1.0, 1.0);
+        let channels = vec![level * (1.0 - pan.max(0.0)), level * (1.0 + pan.min(0.0))];
+
+        let result = match self.sounds.get_mut(&event) {
+            Some(sound) => sound.execute(|sound| {
+                let device = rodio::default_output_device().ok_or(SoundError::NoOutputAvailable)?;
+                let source = rodio::Decoder::new(Cursor::new(sound.clone())).map_err(SoundError::from)?;
+                rodio::play_raw(&device, ChannelVolume::new(source, channels.clone()).convert_samples());
+                Ok(())
+            }),
+            None => return,
+        };
+        if let Err(err) = result {
+            log::warn!(target: "audio", "Could not play {}: {}", event.path(), err);
+            self.sounds.remove(&event);
+        }
+    }
+
+    #[cfg(not(feature = "sounds"))]
+    pub fn play_at(&mut self, _event: SoundEvent, _offset: Vector) {}
+
+    /// Use the configured volumes from now on. Music follows at once; effects
+    /// already playing finish at their old volume.
+    #[cfg(feature = "sounds")]
+    pub fn set_volume(&mut self, volume: Volume) {
+        self.volume = volume;
+    }
+
+    #[cfg(not(feature = "sounds"))]
+    pub fn set_volume(&mut self, _volume: Volume) {}
+
+    /// Keep the current level's music and ambience looping, crossfading when the
+    /// level changes, and hold them while `paused`.
+    #[cfg(feature = "sounds")]
+    pub fn update_background(&mut self, biome: Biome, paused: bool, elapsed_ms: f64) {
+        let level = self.volume.level(Channel::Music);
+        self.music.update(biome, paused, elapsed_ms, level);
+        self.ambience.update(biome, paused, elapsed_ms, level * AMBIENCE_LEVEL);
+    }
+
+    #[cfg(not(feature = "sounds"))]
+    pub fn update_background(&mut self, _biome: Biome, _paused: bool, _elapsed_ms: f64) {}
+}
@@ -0,0 +1,96 @@
+use crate::{
+    input::Action,
+    interrupt::{Interruption, Watch},
+    items::ItemDef,
+    spells::MANA_REGEN_TURNS,
+    world::World,
+};
+use quicksilver::geom::Vector;
+use std::collections::VecDeque;
+
+/// The most turns a single long rest will pass.
+pub const MAX_REST_TURNS: u32 = 500;
+
+#[derive(Clone, Debug)]
+pub enum Plan {
+    /// Walk a precomputed path, one tile per step.
+    Walk(VecDeque<Vector>),
+    /// Pass turns in place.
+    Rest { turns_left: u32 },
+    /// Do the same turn action over again.
+    Repeat { action: Action, times_left: u32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Step {
+    MoveTo(Vector),
+    Wait,
+    Act(Action),
+}
+
+/// Something the player does over several turns without further input, taking one
+/// step per key repeat interval so it can be watched, and stopping when its
+/// `Watch` sees something the player should.
+#[derive(Clone, Debug)]
+pub struct AutoAction {
+    plan: Plan,
+    elapsed_ms: f64,
+    watch: Watch,
+}
+
+impl AutoAction {
+    pub fn new(plan: Plan, world: &World) -> AutoAction {
+        let resting = matches!(plan, Plan::Rest { .. });
+        AutoAction {
+            plan,
+            elapsed_ms: 0.0,
+            watch: Watch::new(world, resting),
+        }
+    }
+
+    /// The one place every kind of auto action checks whether it should stop, with
+    /// `items` being what the player's food could be.
+    pub fn interruption(&mut self, world: &World, items: &[ItemDef]) -> Option<Interruption> {
+        self.watch.check(world, items)
+    }
+
+    /// How many turns of rest the player needs to get all their hp and mana back at
+    /// the rate they regenerate, up to `MAX_REST_TURNS`.
+    pub fn rest_turns(world: &World) -> u32 {
+        let player = world.player();
+        let heal_turns = world.heal_turns().unwrap_or(MAX_REST_TURNS as usize);
+        let hp_turns = (player.max_hp - player.hp).max(0) as usize * heal_turns;
+        let mana_turns = (player.max_mana - player.mana).max(0) as usize * MANA_REGEN_TURNS;
+        // Regeneration goes by the turn count, so the first point can take a full interval
+        let turns = hp_turns.max(mana_turns) + heal_turns.max(MANA_REGEN_TURNS);
+        turns.min(MAX_REST_TURNS as usize) as u32
+    }
+
+    pub fn is_finished(&self) -> bool {
+        match &self.plan {
+            Plan::Walk(path) => path.is_empty(),
+            Plan::Rest { turns_left } => *turns_left == 0,
+            Plan::Repeat { times_left, .. } => *times_left == 0,
+        }
+    }
+
+    /// Advance the action's clock, returning the next step once it's due.
+    pub fn next_step(&mut self, elapsed_ms: f64, interval_ms: f64) -> Option<Step> {
+        self.elapsed_ms += elapsed_ms;
+        if self.elapsed_ms < interval_ms {
+            return None;
+        }
+        self.elapsed_ms = 0.0;
+        match &mut self.plan {
+            Plan::Walk(path) => path.pop_front().map(Step::MoveTo),
+            Plan::Rest { turns_left } => {
+                *turns_left = turns_left.checked_sub(1)?;
+                Some(Step::Wait)
+            }
+            Plan::Repeat { action, times_left } => {
+                *times_left = times_left.checked_sub(1)?;
+                Some(Step::Act(*action))
+            }
+        }
+    }
+}
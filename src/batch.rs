@@ -0,0 +1,47 @@
+use crate::layer::Layer;
+use quicksilver::{
+    geom::{Rectangle, Vector},
+    graphics::{Color, GpuTriangle, Image, Mesh, Vertex},
+    lifecycle::Window,
+};
+
+const CORNERS: [Vector; 4] = [Vector::ZERO, Vector::X, Vector::ONE, Vector::Y];
+
+/// Glyphs collected into one mesh and handed to the window together, instead of
+/// one `window.draw` per tile. The mesh is kept between frames so its buffers
+/// only grow once.
+pub struct GlyphBatch {
+    mesh: Mesh,
+}
+
+impl GlyphBatch {
+    pub fn new() -> GlyphBatch {
+        GlyphBatch { mesh: Mesh::new() }
+    }
+
+    pub fn clear(&mut self) {
+        self.mesh.clear();
+    }
+
+    /// Queue `image` stretched over `area` on `layer` and tinted with `color`.
+    pub fn push(&mut self, image: &Image, area: Rectangle, color: Color, layer: Layer) {
+        let tex = image.projection(Rectangle::new_sized((1, 1)));
+        let offset = self.mesh.vertices.len() as u32;
+        self.mesh.vertices.extend(CORNERS.iter().map(|&corner| Vertex {
+            pos: area.pos + corner.times(area.size),
+            tex_pos: Some(tex * corner),
+            col: color,
+        }));
+        for indices in [[0, 1, 2], [2, 3, 0]] {
+            self.mesh.triangles.push(GpuTriangle {
+                z: layer.z(),
+                indices: indices.map(|index| index + offset),
+                image: Some(image.clone()),
+            });
+        }
+    }
+
+    pub fn draw(&self, window: &mut Window) {
+        window.mesh().extend(&self.mesh);
+    }
+}
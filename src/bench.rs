@@ -0,0 +1,85 @@
+use crate::{data::Content, world::World};
+use std::{
+    collections::BTreeSet,
+    time::{Duration, Instant},
+};
+
+/// How many maps `--bench` generates, and how many monster turns it plays on each,
+/// when not told otherwise.
+const DEFAULT_MAPS: u32 = 50;
+const DEFAULT_TURNS: u32 = 100;
+
+/// How long each of a batch of runs of the same work took.
+#[derive(Default)]
+struct Timings(Vec<Duration>);
+
+impl Timings {
+    fn time<T>(&mut self, work: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = work();
+        self.0.push(start.elapsed());
+        result
+    }
+
+    /// One line with how many runs there were and the mean, median, 95th
+    /// percentile and slowest of them in milliseconds.
+    fn summary(&mut self, name: &str) -> String {
+        if self.0.is_empty() {
+            return format!("{:<12} no runs", name);
+        }
+        self.0.sort();
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        let percentile = |percent: usize| ms(self.0[(self.0.len() - 1) * percent / 100]);
+        let mean = ms(self.0.iter().sum::<Duration>()) / self.0.len() as f64;
+        format!(
+            "{:<12} {:>7} runs  mean {:>8.3} ms  median {:>8.3} ms  p95 {:>8.3} ms  max {:>8.3} ms",
+            name,
+            self.0.len(),
+            mean,
+            percentile(50),
+            percentile(95),
+            percentile(100)
+        )
+    }
+}
+
+/// Generate `maps` levels and play `turns` monster turns on each without opening a
+/// window, then print how long generation, monster turns and field of view took,
+/// so a change that slows down pathfinding or FOV shows up in the numbers. Run as
+/// `--bench [maps] [turns]`, ideally on a release build.
+pub fn run(args: &[String]) {
+    let count = |index: usize, default: u32| match args.get(index).map(|arg| arg.parse::<u32>()) {
+        Some(Ok(count)) => count,
+        Some(Err(_)) => {
+            eprintln!("'{}' isn't a number, so {} is used instead", args[index], default);
+            default
+        }
+        None => default,
+    };
+    let (maps, turns) = (count(0, DEFAULT_MAPS), count(1, DEFAULT_TURNS));
+    // The data files are found the same way the game finds them
+    if let Err(err) = std::env::set_current_dir("static") {
+        eprintln!("Couldn't find the static folder: {}", err);
+    }
+    let content = Content::load(None, false);
+    let unlocks = BTreeSet::new();
+    if cfg!(debug_assertions) {
+        println!("This is a debug build; build with --release for numbers worth comparing.");
+    }
+    println!("Generating {} maps and playing {} monster turns on each...", maps, turns);
+
+    let (mut generation, mut monster_turns, mut fov) = (Timings::default(), Timings::default(), Timings::default());
+    for seed in 0..maps as u64 {
+        let mut world: World = generation.time(|| crate::generate_run(seed, &unlocks, None, None, &content));
+        // The player stands still and can't die, so every monster comes for them
+        // and every turn has the full crowd to path and see around
+        world.invincible = true;
+        for _ in 0..turns {
+            monster_turns.time(|| world.monster_turns());
+            fov.time(|| world.update_fov());
+        }
+    }
+    println!("{}", generation.summary("generation"));
+    println!("{}", monster_turns.summary("monster turn"));
+    println!("{}", fov.summary("fov"));
+}
@@ -0,0 +1,16 @@
+use crate::world::Entity;
+use quicksilver::{geom::Vector, graphics::Color};
+
+/// A boulder, which stands in the way of creatures until it's pushed.
+pub const BOULDER: char = '0';
+
+/// How much a boulder pushed into a creature hurts it.
+pub const CRUSH_DAMAGE: i32 = 6;
+
+/// A boulder at `pos`, drawn in `color`.
+pub fn boulder(pos: Vector, color: Color) -> Entity {
+    Entity {
+        boulder: true,
+        ..Entity::new(pos, BOULDER, color, 0)
+    }
+}
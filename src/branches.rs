@@ -0,0 +1,115 @@
+use crate::{
+    data,
+    dungeon::Arrival,
+    spawn,
+    weather::Weather,
+    world::{Biome, World},
+};
+use log::debug;
+use quicksilver::{geom::Vector, graphics::Color};
+use serde::{Deserialize, Serialize};
+
+pub const BRANCHES_FILE: &str = "branches.json";
+
+/// The definitions the game was built with, for when the file can't be read.
+const BUILT_IN: &str = include_str!("../static/branches.json");
+
+/// One side branch, as written in `static/branches.json`: a short run of levels
+/// down a second staircase off the main dungeon or another branch, with a reward
+/// waiting at the bottom.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BranchDef {
+    pub name: String,
+    /// The branch its entrance is on, by name, or the main dungeon if `None`.
+    #[serde(default)]
+    pub parent: Option<String>,
+    /// The depth of the level its entrance is on. Its first level is one deeper.
+    pub depth: u32,
+    /// How many levels it has.
+    pub levels: u32,
+    pub biome: Biome,
+    #[serde(default)]
+    pub weather: Weather,
+    /// How its walls and floor look.
+    pub wall: Color,
+    pub floor: Color,
+    /// The item of this name from the item file, found once a run on its last level.
+    pub reward: String,
+}
+
+impl BranchDef {
+    /// The depth of its last level, which has no stairs down.
+    pub fn bottom(&self) -> u32 {
+        self.depth + self.levels
+    }
+}
+
+impl data::Named for BranchDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Stairs down into a branch, apart from the level's own stairs down.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entrance {
+    pub pos: Vector,
+    /// The branch it leads into, by name from the branch file.
+    pub branch: String,
+}
+
+/// Put the stairs into every branch that starts on `world` somewhere open.
+pub fn place_entrances(world: &mut World, branches: &[BranchDef], stairs: Color) {
+    let (branch, depth) = (world.branch.clone(), world.depth);
+    for def in branches.iter().filter(|def| def.parent == branch && def.depth == depth) {
+        if let Some(pos) = spawn::free_spot(world) {
+            debug!(target: "generate", branch = def.name.as_str(), x = pos.x, y = pos.y; "placed a branch entrance");
+            if let Some(tile) = world.tile_at_mut(pos) {
+                tile.glyph = '>';
+                tile.color = stairs;
+                tile.animation = None;
+            }
+            world.entrances.push(Entrance {
+                pos,
+                branch: def.name.clone(),
+            });
+        }
+    }
+}
+
+/// Where stairs on `world` at the player's feet lead: the depth of the level they
+/// go to, the branch it's in, if any, and how the player arrives there. Stairs
+/// down into a branch go to its first level, and its first level's stairs up go
+/// back to the level with the entrance.
+pub fn destination<'a>(
+    world: &World,
+    branches: &'a [BranchDef],
+    going_up: bool,
+) -> (u32, Option<&'a BranchDef>, Arrival) {
+    let current = world.branch.as_deref().and_then(|name| find(branches, name));
+    if !going_up {
+        let entrance = world.entrance_at(world.player().pos);
+        return match entrance.and_then(|index| find(branches, &world.entrances[index].branch)) {
+            Some(def) => (def.depth + 1, Some(def), Arrival::Descending),
+            None => (world.depth + 1, current, Arrival::Descending),
+        };
+    }
+    match current {
+        Some(def) if world.depth <= def.depth + 1 => {
+            let parent = def.parent.as_deref().and_then(|name| find(branches, name));
+            (def.depth, parent, Arrival::LeavingBranch(def.name.clone()))
+        }
+        _ => (world.depth.saturating_sub(1), current, Arrival::Climbing),
+    }
+}
+
+/// The definition of the branch called `name`.
+pub fn find<'a>(branches: &'a [BranchDef], name: &str) -> Option<&'a BranchDef> {
+    branches.iter().find(|branch| branch.name == name)
+}
+
+/// Read the branch definitions, so branches can be added or changed by editing
+/// the file or in a mod.
+pub fn load(problems: &mut Vec<data::Problem>) -> Vec<BranchDef> {
+    data::load_defs(BRANCHES_FILE, BUILT_IN, problems)
+}
@@ -0,0 +1,41 @@
+use quicksilver::geom::Vector;
+
+/// The window of map tiles that's on screen. It follows the player, but stops at
+/// the map's edges instead of showing empty space past them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub origin: Vector,
+    pub size: Vector,
+}
+
+impl Camera {
+    /// Center a view of up to `view_size` tiles on `focus`.
+    pub fn follow(focus: Vector, map_size: Vector, view_size: Vector) -> Camera {
+        let size = Vector::new(view_size.x.min(map_size.x), view_size.y.min(map_size.y));
+        let max_origin = map_size - size;
+        let origin = Vector::new(
+            (focus.x - (size.x / 2.0).floor()).clamp(0.0, max_origin.x),
+            (focus.y - (size.y / 2.0).floor()).clamp(0.0, max_origin.y),
+        );
+        Camera { origin, size }
+    }
+
+    /// Every map position in view, column by column.
+    pub fn positions(self) -> impl Iterator<Item = Vector> {
+        let Camera { origin, size } = self;
+        (0..size.x as i32).flat_map(move |x| (0..size.y as i32).map(move |y| origin + Vector::new(x, y)))
+    }
+
+    pub fn contains(self, pos: Vector) -> bool {
+        let view = self.to_view(pos);
+        view.x >= 0.0 && view.y >= 0.0 && view.x < self.size.x && view.y < self.size.y
+    }
+
+    pub fn to_view(self, pos: Vector) -> Vector {
+        pos - self.origin
+    }
+
+    pub fn to_map(self, view_pos: Vector) -> Vector {
+        view_pos + self.origin
+    }
+}
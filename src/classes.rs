@@ -0,0 +1,144 @@
+use crate::{
+    attributes::{Attribute, Attributes},
+    data,
+    items::{self, ItemDef, ItemEffect},
+    profile::Unlock,
+    shops::STARTING_GOLD,
+    strings::Strings,
+    world::Entity,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+pub const CLASSES_FILE: &str = "classes.json";
+
+/// The definitions the game was built with, for when the file can't be read.
+const BUILT_IN: &str = include_str!("../static/classes.json");
+
+/// A knack of the player's own, which the world checks for where it applies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Talent {
+    /// A melee attack also hits one other monster next to the player.
+    Cleave,
+    /// Melee attacks on a monster that hasn't been hurt yet do double damage.
+    Ambush,
+    /// Spells that do damage do one more.
+    Spellpower,
+}
+
+impl Talent {
+    pub fn describe(self, strings: &Strings) -> &str {
+        strings.get(match self {
+            Talent::Cleave => "talent.cleave",
+            Talent::Ambush => "talent.ambush",
+            Talent::Spellpower => "talent.spellpower",
+        })
+    }
+}
+
+/// One class the player can pick for a new run, as written in `static/classes.json`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ClassDef {
+    pub name: String,
+    /// The player's max hp before constitution, which they start the run with.
+    pub hp: i32,
+    /// What the player's attributes start at, before they spend their points.
+    #[serde(default)]
+    pub attributes: Attributes,
+    /// Items from the item file the player starts out wearing or holding.
+    #[serde(default)]
+    pub equipment: Vec<String>,
+    pub talent: Talent,
+    /// The player's max mana, which they start the run with.
+    #[serde(default)]
+    pub mana: i32,
+    /// Spells from the spell file the player starts out knowing.
+    #[serde(default)]
+    pub spells: Vec<String>,
+    #[serde(default = "starting_gold")]
+    pub gold: u32,
+    /// The profile unlock it needs before it can be picked, if any.
+    #[serde(default)]
+    pub unlock: Option<Unlock>,
+}
+
+fn starting_gold() -> u32 {
+    STARTING_GOLD
+}
+
+impl ClassDef {
+    /// Make `player` one of this class: set their attributes, hp, mana, spells, gold
+    /// and talent, and put on their equipment, adding its attack and defense bonuses.
+    pub fn apply(&self, player: &mut Entity, items: &[ItemDef]) {
+        player.attributes = self.attributes;
+        player.gold = self.gold;
+        player.max_hp = self.hp + self.attributes.hp();
+        player.hp = player.max_hp;
+        player.max_mana = self.mana;
+        player.mana = self.mana;
+        player.spells = self.spells.clone();
+        player.talents.push(self.talent);
+        for item in self.equipment.iter().filter_map(|name| items::find(items, name)) {
+            for effect in &item.effects {
+                match *effect {
+                    ItemEffect::Attack(bonus) => player.attack += bonus,
+                    ItemEffect::Defense(bonus) => player.defense += bonus,
+                    // The rest only do anything when the item is used up
+                    ItemEffect::Heal(_)
+                    | ItemEffect::Mana(_)
+                    | ItemEffect::Afflict(_)
+                    | ItemEffect::Cure
+                    | ItemEffect::Lasting { .. }
+                    | ItemEffect::Fuel(_)
+                    | ItemEffect::Nourish(_) => {}
+                }
+            }
+            player.equipment.push(item.name.clone());
+        }
+    }
+
+    /// A one-line summary for the class menu, such as "rogue: 5 hp, dagger, double
+    /// damage on unhurt monsters".
+    pub fn describe(&self, strings: &Strings) -> String {
+        let mut details = vec![strings.format("class.hp", &[("hp", &(self.hp + self.attributes.hp()))])];
+        for attribute in Attribute::ALL {
+            let value = self.attributes.get(attribute);
+            if value != Attributes::default().get(attribute) {
+                let name = attribute.name(strings);
+                details.push(strings.format("class.attribute", &[("attribute", &name), ("value", &value)]));
+            }
+        }
+        if self.mana > 0 {
+            details.push(strings.format("class.mana", &[("mana", &self.mana)]));
+        }
+        details.extend(self.spells.iter().map(|spell| strings.name("spell", spell).to_string()));
+        details.extend(self.equipment.iter().map(|item| strings.name("item", item).to_string()));
+        details.push(strings.format("class.gold", &[("gold", &self.gold)]));
+        details.push(self.talent.describe(strings).to_string());
+        let name = strings.name("class", &self.name);
+        strings.format("class.description", &[("class", &name), ("details", &details.join(", "))])
+    }
+}
+
+impl data::Named for ClassDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// The classes that can be picked with `unlocks` from the profile, in file order.
+pub fn available<'a>(classes: &'a [ClassDef], unlocks: &BTreeSet<Unlock>) -> Vec<&'a ClassDef> {
+    let open = |class: &&ClassDef| class.unlock.is_none_or(|unlock| unlocks.contains(&unlock));
+    classes.iter().filter(open).collect()
+}
+
+/// The definition of the class called `name`.
+pub fn find<'a>(classes: &'a [ClassDef], name: &str) -> Option<&'a ClassDef> {
+    classes.iter().find(|class| class.name == name)
+}
+
+/// Read the class definitions, so classes can be changed or added by editing the
+/// file or in a mod.
+pub fn load(problems: &mut Vec<data::Problem>) -> Vec<ClassDef> {
+    data::load_defs(CLASSES_FILE, BUILT_IN, problems)
+}
@@ -0,0 +1,114 @@
+use crate::{
+    audio::Volume,
+    input::{KeyBindings, Preset},
+    save,
+    theme::Theme,
+};
+use quicksilver::saving;
+use serde::{Deserialize, Serialize};
+
+const CONFIG_NAME: &str = "config";
+
+/// How long a movement key must be held before it starts repeating, and how often it
+/// repeats after that.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyRepeatConfig {
+    pub delay_ms: f64,
+    pub interval_ms: f64,
+}
+
+impl Default for KeyRepeatConfig {
+    fn default() -> Self {
+        KeyRepeatConfig {
+            delay_ms: 250.0,
+            interval_ms: 80.0,
+        }
+    }
+}
+
+/// Whether the world goes on without waiting for the player, and how long each
+/// turn lasts if they don't act in time.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RealTimeConfig {
+    pub enabled: bool,
+    pub tick_ms: f64,
+}
+
+impl RealTimeConfig {
+    const STEP_MS: f64 = 50.0;
+    const FASTEST_MS: f64 = 100.0;
+    const SLOWEST_MS: f64 = 1000.0;
+
+    pub fn faster(&mut self) {
+        self.tick_ms = (self.tick_ms - Self::STEP_MS).max(Self::FASTEST_MS);
+    }
+
+    pub fn slower(&mut self) {
+        self.tick_ms = (self.tick_ms + Self::STEP_MS).min(Self::SLOWEST_MS);
+    }
+}
+
+impl Default for RealTimeConfig {
+    fn default() -> Self {
+        RealTimeConfig {
+            enabled: false,
+            tick_ms: 300.0,
+        }
+    }
+}
+
+/// User settings that persist between sessions, kept apart from the save file.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub key_repeat: KeyRepeatConfig,
+    #[serde(default)]
+    pub theme: Theme,
+    #[serde(default)]
+    pub fullscreen: bool,
+    #[serde(default)]
+    pub hide_weather: bool,
+    #[serde(default)]
+    pub volume: Volume,
+    #[serde(default)]
+    pub real_time: RealTimeConfig,
+    /// A 16x16 code page 437 bitmap font to draw the map with, instead of the
+    /// built-in TrueType glyphs.
+    #[serde(default)]
+    pub cp437_tileset: Option<String>,
+    /// The language to show text in, such as "fr" for `static/lang/fr.json`;
+    /// English if left out.
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub preset: Preset,
+    #[serde(default)]
+    pub bindings: KeyBindings,
+}
+
+impl Config {
+    /// Load the config, writing out the defaults the first time so they can be edited.
+    pub fn load() -> Config {
+        match saving::load::<Config>(save::APP_NAME, CONFIG_NAME).map_err(save::SaveError::from) {
+            Ok(mut config) => {
+                config.bindings.fill_missing(config.preset);
+                config
+            }
+            Err(save::SaveError::NotFound) => {
+                let config = Config::default();
+                config.save();
+                config
+            }
+            Err(err) => {
+                eprintln!("Using the default config: {}", err);
+                Config::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = saving::save(save::APP_NAME, CONFIG_NAME, self) {
+            eprintln!("Failed to save the config: {}", err);
+        }
+    }
+}
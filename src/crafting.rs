@@ -0,0 +1,101 @@
+use crate::{
+    data::{self, Content},
+    spawn,
+    world::{Entity, World},
+};
+use log::debug;
+use quicksilver::geom::Vector;
+use serde::{Deserialize, Serialize};
+
+pub const RECIPES_FILE: &str = "recipes.json";
+
+/// The definitions the game was built with, for when the file can't be read.
+const BUILT_IN: &str = include_str!("../static/recipes.json");
+
+/// The glyph of a workbench tile.
+pub const WORKBENCH: char = '=';
+
+/// The chance each level has of a workbench.
+const WORKBENCH_CHANCE: f32 = 0.3;
+
+/// Some number of one item that goes into a recipe.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ingredient {
+    /// The item of this name from the item file.
+    pub item: String,
+    #[serde(default = "one")]
+    pub count: usize,
+}
+
+fn one() -> usize {
+    1
+}
+
+/// One recipe, as written in `static/recipes.json`: the components it uses up and
+/// the item it makes from them.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecipeDef {
+    pub name: String,
+    pub ingredients: Vec<Ingredient>,
+    /// The item of this name from the item file.
+    pub makes: String,
+    /// Whether it can only be made standing at or next to a workbench, rather
+    /// than anywhere from the craft menu.
+    #[serde(default)]
+    pub workbench: bool,
+}
+
+impl RecipeDef {
+    /// The ingredients `player` is short of, with how many more of each they need.
+    pub fn missing(&self, player: &Entity) -> Vec<Ingredient> {
+        self.ingredients
+            .iter()
+            .filter_map(|ingredient| {
+                let have = player.inventory.iter().filter(|&item| *item == ingredient.item).count();
+                (have < ingredient.count).then(|| Ingredient {
+                    item: ingredient.item.clone(),
+                    count: ingredient.count - have,
+                })
+            })
+            .collect()
+    }
+}
+
+impl data::Named for RecipeDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Whether there's a workbench on or next to `pos`.
+pub fn near_workbench(world: &World, pos: Vector) -> bool {
+    (-1..=1).any(|dx| {
+        (-1..=1).any(|dy| world.tile_at(pos + Vector::new(dx, dy)).is_some_and(|tile| tile.glyph == WORKBENCH))
+    })
+}
+
+/// Now and then put a workbench somewhere open on `world`.
+pub fn place_workbench(world: &mut World, content: &Content) {
+    if world.rng.next_f32() >= WORKBENCH_CHANCE {
+        return;
+    }
+    if let Some(pos) = spawn::free_spot(world) {
+        debug!(target: "generate", x = pos.x, y = pos.y; "placed a workbench");
+        let color = content.glyphs.color("workbench");
+        if let Some(tile) = world.tile_at_mut(pos) {
+            tile.glyph = WORKBENCH;
+            tile.color = color;
+            tile.animation = None;
+        }
+    }
+}
+
+/// The definition of the recipe called `name`.
+pub fn find<'a>(recipes: &'a [RecipeDef], name: &str) -> Option<&'a RecipeDef> {
+    recipes.iter().find(|recipe| recipe.name == name)
+}
+
+/// Read the recipes, so new ones can be added by editing the file or in a mod.
+pub fn load(problems: &mut Vec<data::Problem>) -> Vec<RecipeDef> {
+    data::load_defs(RECIPES_FILE, BUILT_IN, problems)
+}
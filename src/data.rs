@@ -0,0 +1,392 @@
+use crate::{
+    branches::BranchDef, classes::ClassDef, crafting::RecipeDef, dialogue::DialogueDef, glyphs::Glyphs, gods::GodDef,
+    happenings::HappeningDef, items::ItemDef, loot::LootTables, monsters::MonsterDef, perks::PerkDef, prefab::Prefab,
+    quests::QuestDef, races::RaceDef, shrines::ShrineDef, specials::SpecialDef, spells::SpellDef, strings::Strings,
+};
+use quicksilver::prelude::Future;
+use serde::{de::DeserializeOwned, Deserialize};
+use std::{fmt, path::Path};
+#[cfg(feature = "hot-reload")]
+use std::time::SystemTime;
+
+/// Where mods live, under `static/`. Each mod is a folder laid out like `static/`
+/// itself, holding only the files it changes. Mods load in order of their folder
+/// names, so later ones win, and numbering the folders sets the order.
+pub const MOD_DIR: &str = "mods";
+
+/// How often the hot-reload watcher checks the data files for changes.
+#[cfg(feature = "hot-reload")]
+const WATCH_INTERVAL_MS: f64 = 1000.0;
+
+/// Everything the game reads from data files at startup: what can spawn, where
+/// and with what, the classes and races the player can pick, the spells they know
+/// and the perks they can learn, what NPCs have to say and the quests they hand
+/// out, the gods there are to worship and the fountains and shrines to visit,
+/// what can be crafted, the side branches off the dungeon and the special levels
+/// in it, what can happen during a run, the rooms levels are built from, how it
+/// all looks and the text shown for it.
+#[derive(Clone)]
+pub struct Content {
+    pub monsters: Vec<MonsterDef>,
+    pub items: Vec<ItemDef>,
+    pub loot: LootTables,
+    pub spells: Vec<SpellDef>,
+    pub classes: Vec<ClassDef>,
+    pub races: Vec<RaceDef>,
+    pub perks: Vec<PerkDef>,
+    pub dialogue: Vec<DialogueDef>,
+    pub quests: Vec<QuestDef>,
+    pub gods: Vec<GodDef>,
+    pub shrines: Vec<ShrineDef>,
+    pub recipes: Vec<RecipeDef>,
+    pub branches: Vec<BranchDef>,
+    pub specials: Vec<SpecialDef>,
+    pub happenings: Vec<HappeningDef>,
+    pub prefabs: Vec<Prefab>,
+    pub glyphs: Glyphs,
+    pub strings: Strings,
+    /// Whether the map is drawn with a code page 437 tileset, which only has so
+    /// many glyphs for content to use.
+    pub cp437: bool,
+}
+
+impl Content {
+    /// Load the content, with its text in `language` (English if `None`), then
+    /// report every problem found along the way.
+    pub fn load(language: Option<&str>, cp437: bool) -> Content {
+        let mut problems = Vec::new();
+        let content = Content {
+            monsters: crate::monsters::load(&mut problems),
+            items: crate::items::load(&mut problems),
+            loot: crate::loot::load(&mut problems),
+            spells: crate::spells::load(&mut problems),
+            classes: crate::classes::load(&mut problems),
+            races: crate::races::load(&mut problems),
+            perks: crate::perks::load(&mut problems),
+            dialogue: crate::dialogue::load(&mut problems),
+            quests: crate::quests::load(&mut problems),
+            gods: crate::gods::load(&mut problems),
+            shrines: crate::shrines::load(&mut problems),
+            recipes: crate::crafting::load(&mut problems),
+            branches: crate::branches::load(&mut problems),
+            specials: crate::specials::load(&mut problems),
+            happenings: crate::happenings::load(&mut problems),
+            prefabs: crate::prefab::load(&mut problems),
+            glyphs: crate::glyphs::load(&mut problems),
+            strings: Strings::load(language, &mut problems),
+            cp437,
+        };
+        problems.extend(crate::validate::check(&content));
+        report(&problems);
+        content
+    }
+
+    /// Every file and folder the content comes from, for the hot-reload watcher.
+    #[cfg(feature = "hot-reload")]
+    pub fn files(&self) -> Vec<String> {
+        let mut files = vec![
+            crate::monsters::MONSTERS_FILE.to_string(),
+            crate::items::ITEMS_FILE.to_string(),
+            crate::loot::LOOT_FILE.to_string(),
+            crate::spells::SPELLS_FILE.to_string(),
+            crate::classes::CLASSES_FILE.to_string(),
+            crate::races::RACES_FILE.to_string(),
+            crate::perks::PERKS_FILE.to_string(),
+            crate::dialogue::DIALOGUE_FILE.to_string(),
+            crate::quests::QUESTS_FILE.to_string(),
+            crate::gods::GODS_FILE.to_string(),
+            crate::shrines::SHRINES_FILE.to_string(),
+            crate::crafting::RECIPES_FILE.to_string(),
+            crate::branches::BRANCHES_FILE.to_string(),
+            crate::specials::SPECIALS_FILE.to_string(),
+            crate::happenings::HAPPENINGS_FILE.to_string(),
+            crate::prefab::PREFAB_DIR.to_string(),
+            crate::glyphs::GLYPHS_FILE.to_string(),
+        ];
+        let mod_files: Vec<String> = files.iter().flat_map(|file| mod_files(file)).collect();
+        files.extend(mod_files);
+        files.extend(self.prefabs.iter().map(|prefab| prefab.name.clone()));
+        files.extend(self.strings.files());
+        files
+    }
+
+    /// Reload whatever `file` holds, along with every mod's copy of it, then check
+    /// the content again. Definitions that fail to parse keep their old versions,
+    /// and a prefab that fails is left out. Returns the problems found.
+    #[cfg(feature = "hot-reload")]
+    pub fn reload(&mut self, file: &str) -> Vec<Problem> {
+        let path = Path::new(file);
+        let name = path.file_name().and_then(|name| name.to_str()).unwrap_or(file);
+        let folder = path.parent().and_then(|folder| folder.file_name()).and_then(|folder| folder.to_str());
+        let mut problems = Vec::new();
+        let loaded = if name == crate::monsters::MONSTERS_FILE {
+            try_load_defs(name).map(|monsters| self.monsters = monsters)
+        } else if name == crate::items::ITEMS_FILE {
+            try_load_defs(name).map(|items| self.items = items)
+        } else if name == crate::loot::LOOT_FILE {
+            crate::loot::try_load().map(|loot| self.loot = loot)
+        } else if name == crate::spells::SPELLS_FILE {
+            try_load_defs(name).map(|spells| self.spells = spells)
+        } else if name == crate::classes::CLASSES_FILE {
+            try_load_defs(name).map(|classes| self.classes = classes)
+        } else if name == crate::races::RACES_FILE {
+            try_load_defs(name).map(|races| self.races = races)
+        } else if name == crate::perks::PERKS_FILE {
+            try_load_defs(name).map(|perks| self.perks = perks)
+        } else if name == crate::dialogue::DIALOGUE_FILE {
+            try_load_defs(name).map(|dialogue| self.dialogue = dialogue)
+        } else if name == crate::quests::QUESTS_FILE {
+            try_load_defs(name).map(|quests| self.quests = quests)
+        } else if name == crate::gods::GODS_FILE {
+            try_load_defs(name).map(|gods| self.gods = gods)
+        } else if name == crate::shrines::SHRINES_FILE {
+            try_load_defs(name).map(|shrines| self.shrines = shrines)
+        } else if name == crate::crafting::RECIPES_FILE {
+            try_load_defs(name).map(|recipes| self.recipes = recipes)
+        } else if name == crate::branches::BRANCHES_FILE {
+            try_load_defs(name).map(|branches| self.branches = branches)
+        } else if name == crate::specials::SPECIALS_FILE {
+            try_load_defs(name).map(|specials| self.specials = specials)
+        } else if name == crate::happenings::HAPPENINGS_FILE {
+            try_load_defs(name).map(|happenings| self.happenings = happenings)
+        } else if name == crate::glyphs::GLYPHS_FILE {
+            crate::glyphs::try_load().map(|glyphs| self.glyphs = glyphs)
+        } else if folder == Some(crate::strings::LANG_DIR) {
+            self.strings = Strings::load(self.strings.language.as_deref(), &mut problems);
+            Ok(())
+        } else {
+            self.prefabs = crate::prefab::load(&mut problems);
+            Ok(())
+        };
+        problems.extend(loaded.err());
+        problems.extend(crate::validate::check(self));
+        problems
+    }
+}
+
+/// The depths something turns up on, and how often next to the alternatives.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SpawnRange {
+    pub weight: u32,
+    pub min_depth: u32,
+    /// No limit if left out.
+    #[serde(default)]
+    pub max_depth: Option<u32>,
+}
+
+impl SpawnRange {
+    fn covers(&self, depth: u32) -> bool {
+        depth >= self.min_depth && self.max_depth.is_none_or(|max| depth <= max)
+    }
+}
+
+/// How likely something with these `spawns` is to turn up at `depth`; 0 if it doesn't.
+pub fn weight_at(spawns: &[SpawnRange], depth: u32) -> u32 {
+    spawns
+        .iter()
+        .filter(|range| range.covers(depth))
+        .map(|range| range.weight)
+        .sum()
+}
+
+/// Something wrong with a data file, and where, so it can be found and fixed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Problem {
+    pub file: String,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Problem {
+    pub fn new(file: &str, line: Option<usize>, message: String) -> Problem {
+        Problem {
+            file: file.to_string(),
+            line,
+            message,
+        }
+    }
+
+    /// A problem with whatever is called `name` in the `section` of `file`, found
+    /// in the last of the base file and mods' copies to mention it.
+    pub fn about(file: &str, section: &str, name: &str, message: String) -> Problem {
+        let quoted = format!("\"{}\"", name);
+        for path in mod_files(file).into_iter().rev().chain(Some(file.to_string())) {
+            let text = match read(&path) {
+                Ok(text) => text,
+                Err(_) => continue,
+            };
+            let start = text.find(&format!("\"{}\"", section)).unwrap_or(0);
+            if let Some(offset) = text[start..].find(&quoted) {
+                let line = text[..start + offset].lines().count().max(1);
+                return Problem::new(&path, Some(line), message);
+            }
+        }
+        Problem::new(file, None, message)
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file, line, self.message),
+            None => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+/// Print every problem at once, so they can all be fixed in one go.
+pub fn report(problems: &[Problem]) {
+    if problems.is_empty() {
+        return;
+    }
+    eprintln!("Found {} problem(s) in the game data:", problems.len());
+    for problem in problems {
+        eprintln!("  {}", problem);
+    }
+}
+
+/// Read a definitions file from `static/`, so content can change without a rebuild.
+/// Falls back to `built_in`, the copy the game was built with, if the file is
+/// missing or broken.
+pub fn load<T: DeserializeOwned>(file: &str, built_in: &str, problems: &mut Vec<Problem>) -> T {
+    try_load(file).unwrap_or_else(|mut problem| {
+        problem.message += ", so the built-in copy is used instead";
+        problems.push(problem);
+        serde_json::from_str(built_in).expect("the built-in definitions are valid")
+    })
+}
+
+/// Read a definitions file from `static/`, without falling back on failure.
+pub fn try_load<T: DeserializeOwned>(file: &str) -> Result<T, Problem> {
+    parse(file, &read(file)?)
+}
+
+/// The text of a file under `static/`.
+pub fn read(file: &str) -> Result<String, Problem> {
+    let bytes = quicksilver::load_file(file)
+        .wait()
+        .map_err(|err| Problem::new(file, None, err.to_string()))?;
+    String::from_utf8(bytes).map_err(|err| Problem::new(file, None, err.to_string()))
+}
+
+/// Parse the JSON `text` of `file`, pointing at the line where it goes wrong.
+pub fn parse<T: DeserializeOwned>(file: &str, text: &str) -> Result<T, Problem> {
+    serde_json::from_str(text).map_err(|err| {
+        // The message ends with the position, which goes up front instead
+        let message = err.to_string();
+        let message = message.split(" at line ").next().unwrap_or(&message).to_string();
+        Problem::new(file, Some(err.line()), message)
+    })
+}
+
+/// Definitions that mods can replace by giving theirs the same name.
+pub trait Named {
+    fn name(&self) -> &str;
+}
+
+/// Like `load`, then let every mod's copy of `file` replace definitions of the
+/// same name and add new ones. A broken mod file is skipped.
+pub fn load_defs<T: DeserializeOwned + Named>(file: &str, built_in: &str, problems: &mut Vec<Problem>) -> Vec<T> {
+    let mut defs = load(file, built_in, problems);
+    for path in mod_files(file) {
+        match try_load(&path) {
+            Ok(mod_defs) => merge(&mut defs, mod_defs),
+            Err(mut problem) => {
+                problem.message += ", so the mod's copy is skipped";
+                problems.push(problem);
+            }
+        }
+    }
+    defs
+}
+
+/// Like `load_defs`, without falling back or skipping anything on failure.
+#[cfg(feature = "hot-reload")]
+pub fn try_load_defs<T: DeserializeOwned + Named>(file: &str) -> Result<Vec<T>, Problem> {
+    let mut defs = try_load(file)?;
+    for path in mod_files(file) {
+        merge(&mut defs, try_load(&path)?);
+    }
+    Ok(defs)
+}
+
+/// Put each of `mod_defs` in place of the definition with the same name, or at
+/// the end if there isn't one.
+pub fn merge<T: Named>(defs: &mut Vec<T>, mod_defs: Vec<T>) {
+    for def in mod_defs {
+        match defs.iter().position(|existing| existing.name() == def.name()) {
+            Some(index) => defs[index] = def,
+            None => defs.push(def),
+        }
+    }
+}
+
+/// The mod folders, in the order they load.
+pub fn mods() -> Vec<String> {
+    let mut mods: Vec<String> = match std::fs::read_dir(MOD_DIR) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_dir())
+            .map(|path| path.display().to_string())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    mods.sort();
+    mods
+}
+
+/// Every mod's copy of `file`, a path under `static/`, in load order.
+pub fn mod_files(file: &str) -> Vec<String> {
+    mods()
+        .into_iter()
+        .map(|folder| format!("{}/{}", folder, file))
+        .filter(|path| Path::new(path).exists())
+        .collect()
+}
+
+/// Notices when data files change on disk, by checking their modification times
+/// every so often. Only built with the `hot-reload` feature, for working on content.
+#[cfg(feature = "hot-reload")]
+pub struct Watcher {
+    files: Vec<(String, Option<SystemTime>)>,
+    elapsed_ms: f64,
+}
+
+#[cfg(feature = "hot-reload")]
+impl Watcher {
+    pub fn new(files: Vec<String>) -> Watcher {
+        Watcher {
+            files: files
+                .into_iter()
+                .map(|file| {
+                    let modified = modified(&file);
+                    (file, modified)
+                })
+                .collect(),
+            elapsed_ms: 0.0,
+        }
+    }
+
+    /// The files that have changed since the last check, once the check is due.
+    pub fn changed(&mut self, elapsed_ms: f64) -> Vec<String> {
+        self.elapsed_ms += elapsed_ms;
+        if self.elapsed_ms < WATCH_INTERVAL_MS {
+            return Vec::new();
+        }
+        self.elapsed_ms = 0.0;
+        let mut changed = Vec::new();
+        for (file, last_modified) in &mut self.files {
+            let now = modified(file);
+            if now != *last_modified {
+                *last_modified = now;
+                changed.push(file.clone());
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(feature = "hot-reload")]
+fn modified(file: &str) -> Option<SystemTime> {
+    std::fs::metadata(file).and_then(|metadata| metadata.modified()).ok()
+}
@@ -0,0 +1,165 @@
+use crate::{
+    camera::Camera,
+    layer::Layer,
+    path,
+    snapshot::{self, History},
+    strings::Strings,
+    world::World,
+};
+use quicksilver::{
+    geom::{Rectangle, Transform, Vector},
+    graphics::{Background::Col, Color},
+    input::{ButtonState, Key, Keyboard},
+    lifecycle::Window,
+};
+
+/// Shows and hides the debug overlay.
+pub const OVERLAY_KEY: Key = Key::F3;
+
+/// While the overlay is up, these number row keys switch its map layers.
+const PATHS_KEY: Key = Key::Key1;
+const DISTANCES_KEY: Key = Key::Key2;
+const FOV_KEY: Key = Key::Key3;
+const DIFF_KEY: Key = Key::Key4;
+
+/// While the turn diff is up, these pick an earlier or later turn to look at.
+const EARLIER_KEY: Key = Key::Minus;
+const LATER_KEY: Key = Key::Equals;
+
+/// The most changes the turn diff lists; the rest are only counted.
+const MAX_DIFF_LINES: usize = 12;
+
+/// Steps from the player at which the distance map is hottest; anything further
+/// is drawn the same.
+const MAX_HEAT_DISTANCE: f32 = 20.0;
+
+/// Numbers for working on the game, drawn with the HUD, and map layers showing
+/// what the monsters are thinking: the path each would take to the player, how
+/// far every tile is from the player, and the edge of what the player can see.
+/// The turn diff lists everything a turn changed in the world, from the history
+/// of snapshots kept in wizard mode.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DebugOverlay {
+    pub shown: bool,
+    paths: bool,
+    distances: bool,
+    fov: bool,
+    diff: bool,
+    /// How many turns before the last one the diff is of.
+    diff_back: usize,
+}
+
+impl DebugOverlay {
+    pub fn update(&mut self, keyboard: &Keyboard) {
+        let pressed = |key| keyboard[key] == ButtonState::Pressed;
+        if pressed(OVERLAY_KEY) {
+            self.shown = !self.shown;
+        }
+        if !self.shown {
+            return;
+        }
+        if pressed(PATHS_KEY) {
+            self.paths = !self.paths;
+        }
+        if pressed(DISTANCES_KEY) {
+            self.distances = !self.distances;
+        }
+        if pressed(FOV_KEY) {
+            self.fov = !self.fov;
+        }
+        if pressed(DIFF_KEY) {
+            self.diff = !self.diff;
+            self.diff_back = 0;
+        }
+        if self.diff && pressed(EARLIER_KEY) {
+            self.diff_back += 1;
+        }
+        if self.diff && pressed(LATER_KEY) {
+            self.diff_back = self.diff_back.saturating_sub(1);
+        }
+    }
+
+    /// Lines for the HUD: frame rate, entity count and turn, then each layer and
+    /// the key that switches it, then the turn diff if it's up.
+    pub fn lines(&mut self, world: &World, history: &History, fps: f64, strings: &Strings) -> Vec<String> {
+        let on_off = |on: bool| strings.get(if on { "settings.on" } else { "settings.off" });
+        let layer = |key: Key, id: &str, on: bool| {
+            let key = format!("{:?}", key).replace("Key", "");
+            strings.format("debug.layer", &[("key", &key), ("layer", &strings.get(id)), ("state", &on_off(on))])
+        };
+        let mut lines = vec![
+            strings.format("debug.fps", &[("fps", &fps.round())]),
+            strings.format("debug.entities", &[("count", &world.entities.len())]),
+            strings.format("debug.turn", &[("turn", &world.turn())]),
+            layer(PATHS_KEY, "debug.paths", self.paths),
+            layer(DISTANCES_KEY, "debug.distances", self.distances),
+            layer(FOV_KEY, "debug.fov", self.fov),
+            layer(DIFF_KEY, "debug.diff", self.diff),
+        ];
+        if self.diff {
+            lines.extend(self.diff_lines(history, strings));
+        }
+        lines
+    }
+
+    fn diff_lines(&mut self, history: &History, strings: &Strings) -> Vec<String> {
+        self.diff_back = self.diff_back.min(history.len().saturating_sub(2));
+        let (before, after) = match history.pair(self.diff_back) {
+            Some(pair) => pair,
+            None => return vec![strings.get("debug.diff_empty").to_string()],
+        };
+        let (earlier, later) = (format!("{:?}", EARLIER_KEY), format!("{:?}", LATER_KEY));
+        let mut lines = vec![
+            strings.format("debug.diff_turns", &[("before", &before.turn), ("after", &after.turn)]),
+            strings.format("debug.diff_keys", &[("earlier", &earlier), ("later", &later)]),
+        ];
+        let changes = snapshot::diff(before, after);
+        if changes.is_empty() {
+            lines.push(strings.get("debug.diff_none").to_string());
+        }
+        lines.extend(changes.iter().take(MAX_DIFF_LINES).cloned());
+        if changes.len() > MAX_DIFF_LINES {
+            lines.push(strings.format("debug.diff_more", &[("count", &(changes.len() - MAX_DIFF_LINES))]));
+        }
+        lines
+    }
+
+    /// Draw the layers that are switched on over the part of the map in view.
+    pub fn draw(&self, window: &mut Window, world: &World, camera: Camera, offset_px: Vector, tile_size_px: Vector) {
+        let z = Layer::Ui.z();
+        let mut fill = |pos: Vector, inset: f32, color: Color| {
+            let pos_px = offset_px + camera.to_view(pos).times(tile_size_px) + tile_size_px * inset;
+            let area = Rectangle::new(pos_px, tile_size_px * (1.0 - 2.0 * inset));
+            window.draw_ex(&area, Col(color), Transform::IDENTITY, z);
+        };
+
+        if self.distances {
+            let distances = path::distance_map(world.player().pos, |pos| world.step_cost(pos));
+            for pos in camera.positions().filter(|&pos| world.is_explored(pos)) {
+                if let Some(&distance) = distances.get(&(pos.x as i32, pos.y as i32)) {
+                    let heat = 1.0 - (distance as f32 / MAX_HEAT_DISTANCE).min(1.0);
+                    fill(pos, 0.0, Color::BLUE.with_red(heat).with_blue(1.0 - heat).with_alpha(0.35));
+                }
+            }
+        }
+
+        if self.fov {
+            let neighbours = [Vector::new(1, 0), Vector::new(-1, 0), Vector::new(0, 1), Vector::new(0, -1)];
+            for pos in camera.positions().filter(|&pos| world.is_visible(pos)) {
+                if neighbours.iter().any(|&step| !world.is_visible(pos + step)) {
+                    fill(pos, 0.0, Color::YELLOW.with_alpha(0.3));
+                }
+            }
+        }
+
+        if self.paths {
+            for id in world.visible_hostiles() {
+                for step in world.monster_path(id).unwrap_or_default() {
+                    if camera.contains(step) {
+                        fill(step, 0.4, Color::RED.with_alpha(0.8));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{
+    animation::Animation,
+    destructibles::{Destructible, PILLAR_HP},
+    foliage::TALL_GRASS,
+    glyphs::Glyphs,
+    hazards::{DEEP_WATER, ICE, LAVA, LAVA_LIGHT_RADIUS},
+    world::{Biome, World},
+};
+use quicksilver::{geom::Vector, graphics::Color};
+
+/// How close to the player's starting spot nothing gets stamped.
+const CLEAR_DISTANCE: f32 = 2.0;
+
+/// Something stamped onto bare floor to give a room some character.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Feature {
+    Rubble,
+    Pillar,
+    Bones,
+    Web,
+    Fungus,
+    DeepWater,
+    Lava,
+    TallGrass,
+    Ice,
+}
+
+impl Feature {
+    fn glyph(self) -> char {
+        match self {
+            Feature::Rubble => ':',
+            // Pillars block movement and sight just like walls do
+            Feature::Pillar => '#',
+            Feature::Bones => '&',
+            Feature::Web => '"',
+            Feature::Fungus => ',',
+            Feature::DeepWater => DEEP_WATER,
+            Feature::Lava => LAVA,
+            Feature::TallGrass => TALL_GRASS,
+            Feature::Ice => ICE,
+        }
+    }
+
+    /// The name of its color in the glyphs file.
+    fn color(self) -> &'static str {
+        match self {
+            Feature::Rubble => "rubble",
+            Feature::Pillar => "pillar",
+            Feature::Bones => "bones",
+            Feature::Web => "web",
+            Feature::Fungus => "fungus",
+            Feature::DeepWater => "deep_water",
+            Feature::Lava => "lava",
+            Feature::TallGrass => "tall_grass",
+            Feature::Ice => "ice",
+        }
+    }
+
+    /// How it moves, if it does, drawn in `color`.
+    fn animation(self, color: Color) -> Option<Animation> {
+        match self {
+            Feature::DeepWater => Some(Animation::ripple(DEEP_WATER, color)),
+            Feature::Lava => Some(Animation::flicker(LAVA, color)),
+            _ => None,
+        }
+    }
+
+    /// How many tiles one stamp covers at most; fungus and rubble spread in patches.
+    fn patch_size(self) -> u32 {
+        match self {
+            Feature::Rubble => 3,
+            Feature::Fungus => 6,
+            Feature::DeepWater => 5,
+            Feature::Lava => 4,
+            Feature::TallGrass => 8,
+            Feature::Ice => 6,
+            Feature::Pillar | Feature::Bones | Feature::Web => 1,
+        }
+    }
+}
+
+/// The features each biome gets and how many stamps of each per level.
+fn features(biome: Biome) -> &'static [(Feature, u32)] {
+    match biome {
+        Biome::Surface => &[(Feature::Rubble, 4), (Feature::Fungus, 5), (Feature::TallGrass, 4)],
+        Biome::Caves => &[
+            (Feature::Rubble, 6),
+            (Feature::Fungus, 4),
+            (Feature::Web, 3),
+            (Feature::TallGrass, 2),
+            (Feature::Ice, 3),
+        ],
+        Biome::Crypt => &[(Feature::Pillar, 8), (Feature::Bones, 6), (Feature::Web, 4)],
+        Biome::Swamp => &[
+            (Feature::Fungus, 10),
+            (Feature::Rubble, 3),
+            (Feature::Bones, 2),
+            (Feature::DeepWater, 3),
+            (Feature::TallGrass, 4),
+        ],
+        Biome::Abyss => &[(Feature::Bones, 10), (Feature::Rubble, 6), (Feature::Pillar, 4), (Feature::Lava, 4)],
+    }
+}
+
+/// Whether `pos` is plain floor that can take a feature without getting in the
+/// player's way.
+fn is_bare(world: &World, pos: Vector) -> bool {
+    let start = world.player().pos;
+    world.tile_at(pos).is_some_and(|tile| tile.glyph == '.') && pos.distance(start) > CLEAR_DISTANCE
+}
+
+/// Pillars only go where all eight neighbours are floor, so they never close off
+/// a gap or touch a wall.
+fn has_room_around(world: &World, pos: Vector) -> bool {
+    (-1..=1).all(|dx| {
+        (-1..=1).all(|dy| {
+            let neighbour = pos + Vector::new(dx, dy);
+            world.tile_at(neighbour).is_some_and(|tile| tile.glyph == '.')
+        })
+    })
+}
+
+/// Stamp the biome's features onto a freshly generated level's bare floor.
+pub fn decorate(world: &mut World, glyphs: &Glyphs) {
+    for &(feature, stamps) in features(world.biome) {
+        for _ in 0..stamps {
+            let x = world.rng.range(1, world.map_size.x as u32 - 1);
+            let y = world.rng.range(1, world.map_size.y as u32 - 1);
+            let mut pos = Vector::new(x as f32, y as f32);
+            if feature == Feature::Pillar && !has_room_around(world, pos) {
+                continue;
+            }
+            for _ in 0..feature.patch_size() {
+                if is_bare(world, pos) {
+                    // Grass keeps the floor it grows on, for when it burns away
+                    if feature == Feature::TallGrass {
+                        let floor = world.tile_at(pos).cloned();
+                        world.beneath_grass.extend(floor);
+                    }
+                    if let Some(tile) = world.tile_at_mut(pos) {
+                        tile.glyph = feature.glyph();
+                        tile.color = glyphs.color(feature.color());
+                        tile.animation = feature.animation(tile.color);
+                        if feature == Feature::Lava {
+                            tile.light_radius = LAVA_LIGHT_RADIUS;
+                        }
+                    }
+                    if feature == Feature::Pillar {
+                        let pillar = world.tile_at(pos).map(|tile| Destructible::new(tile, PILLAR_HP, glyphs));
+                        world.destructibles.extend(pillar);
+                    }
+                }
+                let step = Vector::new(world.rng.range(0, 3) as f32 - 1.0, world.rng.range(0, 3) as f32 - 1.0);
+                pos += step;
+            }
+        }
+    }
+    world.update_fov();
+}
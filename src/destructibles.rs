@@ -0,0 +1,75 @@
+use crate::{
+    crafting,
+    glyphs::Glyphs,
+    world::{Tile, World},
+};
+use log::debug;
+use quicksilver::geom::Vector;
+use serde::{Deserialize, Serialize};
+
+/// How much damage it takes to knock down a wall.
+pub const WALL_HP: i32 = 12;
+
+/// How much damage it takes to knock down a pillar.
+pub const PILLAR_HP: i32 = 8;
+
+/// How much damage it takes to smash a workbench.
+pub const WORKBENCH_HP: i32 = 4;
+
+/// The least damage a melee attack has to do to knock at a wall; anything weaker
+/// just bumps into it.
+pub const HEAVY_DAMAGE: i32 = 3;
+
+/// The chance each bare floor tile next to something knocked down gets strewn with
+/// its debris, in percent.
+pub const DEBRIS_CHANCE: u32 = 40;
+
+/// What's left of anything knocked down.
+pub const RUBBLE: char = ':';
+
+/// A wall, pillar or piece of furniture on a level that heavy attacks and
+/// explosions can knock down.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Destructible {
+    pub pos: Vector,
+    /// How much more damage it takes before it comes down.
+    pub hp: i32,
+    /// The rubble it leaves, which is also strewn about it as debris.
+    pub rubble: Tile,
+}
+
+impl Destructible {
+    /// `tile`, coming down into rubble drawn with `glyphs` once it's taken `hp` damage.
+    pub fn new(tile: &Tile, hp: i32, glyphs: &Glyphs) -> Destructible {
+        let mut rubble = tile.clone();
+        rubble.glyph = RUBBLE;
+        rubble.color = glyphs.color("rubble");
+        rubble.animation = None;
+        rubble.light_radius = 0;
+        Destructible { pos: tile.pos, hp, rubble }
+    }
+}
+
+/// Make every wall inside the outer one and every workbench on `world` something
+/// that can be knocked down, apart from anything a mechanism switches or that can
+/// be already.
+pub fn mark(world: &mut World, glyphs: &Glyphs) {
+    let size = world.map_size;
+    for index in 0..world.map.len() {
+        let tile = &world.map[index];
+        let pos = tile.pos;
+        let edge = pos.x == 0.0 || pos.y == 0.0 || pos.x == size.x - 1.0 || pos.y == size.y - 1.0;
+        let hp = match tile.glyph {
+            '#' if !edge => WALL_HP,
+            crafting::WORKBENCH => WORKBENCH_HP,
+            _ => continue,
+        };
+        let switched = world.mechanisms.iter().any(|mechanism| mechanism.switches.iter().any(|tile| tile.pos == pos));
+        if switched || world.destructible_at(pos).is_some() {
+            continue;
+        }
+        let destructible = Destructible::new(tile, hp, glyphs);
+        world.destructibles.push(destructible);
+    }
+    debug!(target: "generate", count = world.destructibles.len(); "marked destructibles");
+}
@@ -0,0 +1,80 @@
+use crate::{data, world::Entity};
+use serde::{Deserialize, Serialize};
+
+pub const DIALOGUE_FILE: &str = "dialogue.json";
+
+/// The definitions the game was built with, for when the file can't be read.
+const BUILT_IN: &str = include_str!("../static/dialogue.json");
+
+/// What answering with a choice does, besides moving the conversation on.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DialogueEffect {
+    /// Hand the player the item of this name from the item file.
+    Give(String),
+    Gold(u32),
+    /// Start from the node with this id the next time the player talks to the
+    /// speaker, so something said once isn't said again.
+    Resume(String),
+    /// Hand the player the quest of this name from the quest file.
+    StartQuest(String),
+}
+
+/// One answer the player can give.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Choice {
+    pub text: String,
+    /// The id of the node it leads to; the conversation ends if there isn't one.
+    #[serde(default)]
+    pub next: Option<String>,
+    #[serde(default)]
+    pub effects: Vec<DialogueEffect>,
+}
+
+/// Something the speaker says, and the answers the player can give to it.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Node {
+    pub id: String,
+    pub text: String,
+    pub choices: Vec<Choice>,
+}
+
+/// One conversation, as written in `static/dialogue.json`. Its nodes and their
+/// choices form a tree, starting from the first node. Monsters with a dialogue
+/// name it in the monster file.
+#[derive(Clone, Debug, Deserialize)]
+pub struct DialogueDef {
+    pub name: String,
+    pub nodes: Vec<Node>,
+}
+
+impl DialogueDef {
+    pub fn node(&self, id: &str) -> Option<&Node> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    pub fn start(&self) -> Option<&Node> {
+        self.nodes.first()
+    }
+}
+
+impl data::Named for DialogueDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// The definition of the dialogue called `name`.
+pub fn find<'a>(dialogue: &'a [DialogueDef], name: &str) -> Option<&'a DialogueDef> {
+    dialogue.iter().find(|def| def.name == name)
+}
+
+/// The node with id `id` of what `speaker` has to say.
+pub fn node<'a>(dialogue: &'a [DialogueDef], speaker: &Entity, id: &str) -> Option<&'a Node> {
+    find(dialogue, speaker.dialogue.as_deref()?)?.node(id)
+}
+
+/// Read the dialogue definitions, so conversations can be written or changed by
+/// editing the file or in a mod.
+pub fn load(problems: &mut Vec<data::Problem>) -> Vec<DialogueDef> {
+    data::load_defs(DIALOGUE_FILE, BUILT_IN, problems)
+}
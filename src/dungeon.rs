@@ -0,0 +1,274 @@
+use crate::{
+    branches::{self, BranchDef},
+    crafting,
+    data::Content,
+    decorate, destructibles, gods, items, portals, prefab,
+    rng::Rng,
+    shrines, spawn,
+    specials::{self, SpecialDef},
+    weather::Weather,
+    world::{self, Biome, Entity, World},
+};
+use log::{debug, warn};
+use quicksilver::{geom::Vector, graphics::Color};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// How many levels there are; the amulet lies on the last of them.
+pub const DEEPEST_DEPTH: u32 = 8;
+
+/// The item the player has come down for, by name from the item file.
+pub const AMULET: &str = "amulet";
+
+/// How big every level is.
+const MAP_SIZE: Vector = Vector { x: 48.0, y: 32.0 };
+
+/// Where the stairs up are on every level, and where the player starts out.
+const UP_STAIRS: Vector = Vector { x: 5.0, y: 3.0 };
+
+/// The kind of place a level at `depth` is.
+pub fn biome(depth: u32) -> Biome {
+    match depth {
+        0..=1 => Biome::Surface,
+        2..=4 => Biome::Caves,
+        depth if depth < DEEPEST_DEPTH => Biome::Crypt,
+        _ => Biome::Abyss,
+    }
+}
+
+/// How the player comes onto a new level, which decides where on it they start.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Arrival {
+    /// Down stairs from above, onto its stairs up.
+    Descending,
+    /// Up stairs from below, onto its stairs down.
+    Climbing,
+    /// Up out of the branch of this name, onto the stairs down into it.
+    LeavingBranch(String),
+}
+
+/// Which part of the dungeon a level is in, which decides how it's built.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Section<'a> {
+    /// An ordinary level of the main dungeon.
+    Main,
+    /// A level of this side branch.
+    Branch(&'a BranchDef),
+    /// This special level, in the main dungeon.
+    Special(&'a SpecialDef),
+}
+
+impl<'a> Section<'a> {
+    /// The section the level at `depth` of `branch`, or of the main dungeon if
+    /// `None`, is in, with the special levels `planned` for the run.
+    pub fn of(
+        depth: u32,
+        branch: Option<&'a BranchDef>,
+        planned: &BTreeMap<u32, String>,
+        content: &'a Content,
+    ) -> Self {
+        if let Some(def) = branch {
+            return Section::Branch(def);
+        }
+        let special = planned.get(&depth).and_then(|name| specials::find(&content.specials, name));
+        special.map_or(Section::Main, Section::Special)
+    }
+
+    pub fn branch(self) -> Option<&'a BranchDef> {
+        match self {
+            Section::Branch(def) => Some(def),
+            Section::Main | Section::Special(_) => None,
+        }
+    }
+
+    pub fn special(self) -> Option<&'a SpecialDef> {
+        match self {
+            Section::Special(def) => Some(def),
+            Section::Main | Section::Branch(_) => None,
+        }
+    }
+}
+
+/// Whether `player` is carrying the amulet.
+pub fn has_amulet(player: &Entity) -> bool {
+    player.inventory.iter().any(|item| item == AMULET)
+}
+
+/// Where on `world` the player starts when they come by `arrival`, with `down`
+/// being its stairs down, if it has any.
+fn arrival_pos(world: &World, arrival: &Arrival, down: Option<Vector>) -> Vector {
+    match arrival {
+        Arrival::Descending => UP_STAIRS,
+        Arrival::Climbing => down.unwrap_or(UP_STAIRS),
+        Arrival::LeavingBranch(name) => {
+            let entrance = world.entrances.iter().find(|entrance| &entrance.branch == name);
+            entrance.map_or(UP_STAIRS, |entrance| entrance.pos)
+        }
+    }
+}
+
+/// `level` as the player left it, with `player` back on it where `arrival` says.
+/// A creature that has wandered onto that spot since moves aside. The level has
+/// been waiting as it was, but its random choices carry on from the run's `rng`
+/// rather than from where they were when it was left, and it knows of every one
+/// of the `artifacts` found this run.
+pub fn revisit(level: &World, player: Entity, rng: Rng, artifacts: &BTreeSet<String>, arrival: Arrival) -> World {
+    let mut world = level.clone();
+    world.rng = rng;
+    debug!(target: "generate", depth = world.depth, arrival:? = arrival; "revisited");
+    let down = world.map.iter().find(|tile| tile.glyph == '>' && world.entrance_at(tile.pos).is_none());
+    let pos = arrival_pos(&world, &arrival, down.map(|tile| tile.pos));
+    world.entities[world.player_id] = Entity { pos, ..player };
+    let blocking = (0..world.entities.len()).find(|&id| {
+        let entity = &world.entities[id];
+        id != world.player_id && entity.pos == pos && (entity.is_alive() || entity.boulder)
+    });
+    if let Some(id) = blocking {
+        if let Some(spot) = spawn::free_spot(&mut world) {
+            world.entities[id].pos = spot;
+        }
+    }
+    world.artifacts = artifacts.clone();
+    world
+}
+
+/// Build the level at `depth` of `section` for `player`, who starts on it where
+/// `arrival` says. Its random choices carry on from `rng`, and none of the
+/// `artifacts` already found this run turn up on it again. The first level's
+/// stairs up lead out of the dungeon. The last level of a branch has no stairs
+/// down and holds its reward. The last level of the main dungeon has none either,
+/// and is built around the lair where the boss guards the amulet, unless the
+/// player has it already; nothing can teleport there. A special level is built
+/// around its own prefab, if it has one, flooded as much as it says and has its
+/// happenings scheduled.
+pub fn generate(
+    depth: u32,
+    section: Section,
+    player: Entity,
+    rng: Rng,
+    artifacts: &BTreeSet<String>,
+    arrival: Arrival,
+    content: &Content,
+) -> World {
+    let mut rng = rng;
+    let (branch, special) = (section.branch(), section.special());
+    debug!(target: "generate", depth = depth, section:? = section, arrival:? = arrival; "level");
+    let map = world::generate_map(MAP_SIZE, &mut rng, &content.glyphs);
+    let mut world = World::new(MAP_SIZE, map, vec![player], 0);
+    world.rng = rng;
+    world.artifacts = artifacts.clone();
+    world.depth = depth;
+    world.branch = branch.map(|def| def.name.clone());
+    world.special = special.map(|def| def.name.clone());
+    world.biome = branch.map_or_else(|| biome(depth), |def| def.biome);
+    if let Some(def) = branch {
+        world.weather = def.weather;
+    } else if depth == 1 {
+        // The first level is out under the open sky
+        world.weather = Weather::Rain;
+    }
+    let stairs = content.glyphs.color("stairs");
+    if let Some(tile) = world.tile_at_mut(UP_STAIRS) {
+        tile.glyph = '<';
+        tile.color = stairs;
+        tile.animation = None;
+    }
+    let down = world.map.iter().find(|tile| tile.glyph == '>').map(|tile| tile.pos);
+    let bottom = branch.map_or(depth >= DEEPEST_DEPTH, |def| depth >= def.bottom());
+    if bottom {
+        let floor = content.glyphs.color("floor");
+        for tile in world.map.iter_mut().filter(|tile| tile.glyph == '>') {
+            tile.glyph = '.';
+            tile.color = floor;
+        }
+    }
+    branches::place_entrances(&mut world, &content.branches, stairs);
+    world.entities[world.player_id].pos = arrival_pos(&world, &arrival, down);
+
+    let last = branch.is_none() && bottom;
+    let special_prefab = special.and_then(|def| def.prefab.as_deref());
+    let placed = match special_prefab {
+        Some(file) => prefab::place_named(&mut world, file, content),
+        None => last && prefab::place_final(&mut world, content),
+    };
+    if !placed {
+        prefab::place(&mut world, content);
+    }
+    if let Some(def) = special.filter(|def| def.flood > 0) {
+        specials::flood(&mut world, def.flood, &content.glyphs);
+    }
+    if let Some(def) = special {
+        world.schedule.extend(def.schedule.iter().cloned());
+    }
+    // Only one amulet is ever needed
+    if has_amulet(world.player()) {
+        world.entities.retain(|entity| entity.item.as_deref() != Some(AMULET));
+    }
+    decorate::decorate(&mut world, &content.glyphs);
+    if last {
+        world.no_teleport = true;
+        recolor(&mut world, content.glyphs.color("abyss_wall"), content.glyphs.color("abyss_floor"));
+    } else {
+        gods::place_altar(&mut world, &content.gods);
+        crafting::place_workbench(&mut world, content);
+        portals::place(&mut world, &content.glyphs);
+        shrines::place(&mut world, &content.shrines);
+    }
+    if special.is_none_or(|def| def.populate) {
+        spawn::populate(&mut world, content);
+    }
+    let placed = world.entities.iter().any(|entity| entity.item.as_deref() == Some(AMULET));
+    if last && !placed && !has_amulet(world.player()) {
+        match items::find(&content.items, AMULET) {
+            Some(amulet) => {
+                if let Some(pos) = spawn::free_spot(&mut world) {
+                    debug!(target: "generate", x = pos.x, y = pos.y; "placed the amulet");
+                    world.entities.push(amulet.entity(pos));
+                }
+            }
+            None => warn!(target: "generate", "There's no {} in the item file to put on the last level", AMULET),
+        }
+    }
+    if let Some(def) = branch {
+        recolor(&mut world, def.wall, def.floor);
+        if bottom {
+            place_reward(&mut world, def, content);
+        }
+    }
+    destructibles::mark(&mut world, &content.glyphs);
+    world.update_fov();
+    world
+}
+
+/// Put the reward for reaching the bottom of `branch` somewhere open, unless it
+/// has turned up already this run.
+fn place_reward(world: &mut World, branch: &BranchDef, content: &Content) {
+    if world.artifacts.contains(&branch.reward) {
+        return;
+    }
+    let reward = match items::find(&content.items, &branch.reward) {
+        Some(reward) => reward,
+        None => {
+            let (reward, name) = (&branch.reward, &branch.name);
+            warn!(target: "generate", "There's no {} in the item file to reward reaching the {}", reward, name);
+            return;
+        }
+    };
+    if let Some(pos) = spawn::free_spot(world) {
+        debug!(target: "generate", item = reward.name.as_str(), x = pos.x, y = pos.y; "placed a branch reward");
+        world.entities.push(reward.entity(pos));
+        world.artifacts.insert(reward.name.clone());
+    }
+}
+
+/// Give the level's walls and floor colors of their own, apart from lit ones, and
+/// the floor under its tall grass too.
+fn recolor(world: &mut World, wall: Color, floor: Color) {
+    let tiles = world.map.iter_mut().chain(world.beneath_grass.iter_mut());
+    for tile in tiles.filter(|tile| tile.light_radius == 0) {
+        match tile.glyph {
+            '#' => tile.color = wall,
+            '.' => tile.color = floor,
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use crate::{
+    data::Content,
+    hazards::{DEEP_WATER, ICE, LAVA},
+    prefab::{self, Kind, Placement, Prefab, PREFAB_DIR},
+    strings::Strings,
+    world::{Entity, Tile, World},
+};
+use quicksilver::{geom::Vector, graphics::Color};
+use std::path::Path;
+
+/// How many tiles across and down the editor's canvas is.
+const CANVAS_WIDTH: u32 = 24;
+const CANVAS_HEIGHT: u32 = 15;
+
+/// What a click paints.
+#[derive(Clone, Debug, PartialEq)]
+enum Brush {
+    Tile(char),
+    Place(Kind, String),
+}
+
+/// Draws prefabs by hand on a blank canvas, shown by the game's own renderer as
+/// a level of its own. The left mouse button paints with the current brush, the
+/// right one rubs out, and the finished drawing is saved as a new file in the
+/// prefab folder.
+pub struct Editor {
+    /// The run that was going on, to go back to afterwards.
+    pub previous: Box<World>,
+    brushes: Vec<Brush>,
+    brush: usize,
+    placements: Vec<Placement>,
+}
+
+impl Editor {
+    /// Put the run in `world` aside and leave a blank canvas to draw on in its place.
+    pub fn open(world: &mut World, content: &Content) -> Editor {
+        let mut brushes: Vec<Brush> = ['#', '.', '~', DEEP_WATER, LAVA, ICE].into_iter().map(Brush::Tile).collect();
+        brushes.extend(content.monsters.iter().map(|monster| Brush::Place(Kind::Monster, monster.name.clone())));
+        brushes.extend(content.items.iter().map(|item| Brush::Place(Kind::Item, item.name.clone())));
+        let keys = content.items.iter().filter(|item| item.key);
+        brushes.extend(keys.map(|item| Brush::Place(Kind::Door, item.name.clone())));
+        let editor = Editor {
+            previous: Box::new(std::mem::replace(world, canvas())),
+            brushes,
+            brush: 0,
+            placements: Vec::new(),
+        };
+        editor.show(world, content);
+        editor
+    }
+
+    /// Pick the next brush, or the previous one if `back`.
+    pub fn cycle_brush(&mut self, back: bool) {
+        let count = self.brushes.len();
+        self.brush = if back { (self.brush + count - 1) % count } else { (self.brush + 1) % count };
+    }
+
+    /// Paint the tile at `pos` of the canvas with the current brush.
+    pub fn paint(&mut self, canvas: &mut World, pos: Vector, content: &Content) {
+        match &self.brushes[self.brush] {
+            Brush::Tile(glyph) => {
+                if let Some(tile) = canvas.tile_at_mut(pos) {
+                    prefab::paint(tile, *glyph, &content.glyphs);
+                }
+            }
+            Brush::Place(kind, name) => {
+                if canvas.tile_at(pos).is_none() {
+                    return;
+                }
+                self.placements.retain(|placement| placement.pos != pos);
+                self.placements.push(Placement {
+                    kind: *kind,
+                    name: name.clone(),
+                    pos,
+                    line: 0,
+                });
+            }
+        }
+        self.show(canvas, content);
+    }
+
+    /// Clear the tile at `pos` of the canvas and whatever was placed on it.
+    pub fn erase(&mut self, canvas: &mut World, pos: Vector, content: &Content) {
+        if let Some(tile) = canvas.tile_at_mut(pos) {
+            *tile = blank(pos);
+        }
+        self.placements.retain(|placement| placement.pos != pos);
+        self.show(canvas, content);
+    }
+
+    /// Save the drawing as the first free `custom-N.txt` in the prefab folder,
+    /// cropped to what was drawn, and return the file's name.
+    pub fn save(&self, canvas: &World) -> Result<String, String> {
+        let drawn: Vec<Vector> = canvas
+            .map
+            .iter()
+            .filter(|tile| tile.glyph != ' ')
+            .map(|tile| tile.pos)
+            .chain(self.placements.iter().map(|placement| placement.pos))
+            .collect();
+        if drawn.is_empty() {
+            return Err("nothing has been drawn".to_string());
+        }
+        let min = drawn.iter().fold(canvas.map_size, |min, pos| Vector::new(min.x.min(pos.x), min.y.min(pos.y)));
+        let max = drawn.iter().fold(Vector::ZERO, |max, pos| Vector::new(max.x.max(pos.x), max.y.max(pos.y)));
+        let rows = (min.y as u32..=max.y as u32)
+            .map(|y| {
+                let row = (min.x as u32..=max.x as u32).map(|x| {
+                    let pos = Vector::new(x as f32, y as f32);
+                    canvas.tile_at(pos).map_or(' ', |tile| tile.glyph)
+                });
+                row.collect::<String>().trim_end().chars().collect()
+            })
+            .collect();
+        let placements = self
+            .placements
+            .iter()
+            .map(|placement| Placement {
+                pos: placement.pos - min,
+                ..placement.clone()
+            })
+            .collect();
+
+        let file = (1..)
+            .map(|number| format!("{}/custom-{}.txt", PREFAB_DIR, number))
+            .find(|file| !Path::new(file).exists())
+            .expect("there is always a free file name");
+        let prefab = Prefab::new(&file, rows, placements);
+        std::fs::write(&file, prefab.text()).map_err(|err| format!("{}: {}", file, err))?;
+        Ok(file)
+    }
+
+    pub fn lines(&self, strings: &Strings) -> Vec<String> {
+        let brush = match &self.brushes[self.brush] {
+            Brush::Tile('#') => strings.get("editor.wall").to_string(),
+            Brush::Tile('~') => strings.get("editor.water").to_string(),
+            Brush::Tile(DEEP_WATER) => strings.get("editor.deep_water").to_string(),
+            Brush::Tile(LAVA) => strings.get("editor.lava").to_string(),
+            Brush::Tile(ICE) => strings.get("editor.ice").to_string(),
+            Brush::Tile(_) => strings.get("editor.floor").to_string(),
+            Brush::Place(Kind::Monster, name) => strings.name("monster", name).to_string(),
+            Brush::Place(Kind::Item, name) => strings.name("item", name).to_string(),
+            Brush::Place(Kind::Door, name) => strings.format("editor.door", &[("key", &strings.name("item", name))]),
+        };
+        vec![strings.format("editor.brush", &[("brush", &brush)]), strings.get("editor.help").to_string()]
+    }
+
+    /// Put what's been placed on the canvas and make all of it visible, since the
+    /// renderer only draws what the player can see.
+    fn show(&self, canvas: &mut World, content: &Content) {
+        canvas.entities.truncate(1);
+        let placed = self
+            .placements
+            .iter()
+            .filter_map(|placement| prefab::entity(placement, placement.pos, content));
+        canvas.entities.extend(placed);
+        canvas.explored.iter_mut().for_each(|explored| *explored = true);
+        canvas.visible.iter_mut().for_each(|visible| *visible = true);
+        canvas.light.iter_mut().for_each(|light| *light = 1.0);
+        canvas.revision += 1;
+    }
+}
+
+/// An empty canvas, with nothing but an unseen stand-in for the player in the
+/// middle for the camera to follow.
+fn canvas() -> World {
+    let size = Vector::new(CANVAS_WIDTH, CANVAS_HEIGHT);
+    let map = (0..CANVAS_WIDTH)
+        .flat_map(|x| (0..CANVAS_HEIGHT).map(move |y| blank(Vector::new(x, y))))
+        .collect();
+    let middle = Vector::new(CANVAS_WIDTH / 2, CANVAS_HEIGHT / 2);
+    World::new(size, map, vec![Entity::new(middle, ' ', Color::BLACK, 1)], 0)
+}
+
+/// A tile the prefab leaves as the generator made it.
+fn blank(pos: Vector) -> Tile {
+    Tile {
+        pos,
+        glyph: ' ',
+        color: Color::BLACK,
+        animation: None,
+        light_radius: 0,
+    }
+}
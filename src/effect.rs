@@ -0,0 +1,94 @@
+use crate::world::Event;
+use quicksilver::graphics::Color;
+
+/// Something brief the renderer shows when it happens in the world.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Effect {
+    /// An entity took damage and flashes white.
+    Hit { target: usize },
+    /// A creature died and fades away before its remains show up.
+    Death { target: usize, glyph: char, color: Color },
+    /// An entity came out of a portal and shimmers as it settles.
+    Warp { target: usize },
+}
+
+impl Effect {
+    pub fn for_event(event: &Event) -> Option<Effect> {
+        match *event {
+            Event::Moved { .. }
+            | Event::Spotted { .. }
+            | Event::LevelUp { .. }
+            | Event::Robbed { .. }
+            | Event::WoreOff { .. }
+            | Event::Unlocked { .. }
+            | Event::DoorHeld { .. }
+            | Event::Worked { .. }
+            | Event::Trapped { .. }
+            | Event::TorchLow { .. }
+            | Event::TorchOut { .. }
+            | Event::Hungry { .. }
+            | Event::Starving { .. }
+            | Event::Battered { .. }
+            | Event::Collapsed { .. }
+            | Event::Wedged { .. }
+            | Event::Plugged { .. }
+            | Event::Burned { .. }
+            | Event::Sank { .. }
+            | Event::Drowning { .. }
+            | Event::Visited { .. }
+            | Event::Spent { .. }
+            | Event::Kindled { .. }
+            | Event::Slid { .. }
+            | Event::PickedUp { .. } => None,
+            Event::Hit { target } => Some(Effect::Hit { target }),
+            Event::Died { target, glyph, color } => Some(Effect::Death { target, glyph, color }),
+            Event::Warped { entity } => Some(Effect::Warp { target: entity }),
+        }
+    }
+
+    pub fn duration_ms(&self) -> f64 {
+        match self {
+            Effect::Hit { .. } => 150.0,
+            Effect::Death { .. } => 400.0,
+            Effect::Warp { .. } => 300.0,
+        }
+    }
+}
+
+/// The effects currently playing.
+#[derive(Clone, Debug, Default)]
+pub struct Effects {
+    playing: Vec<(Effect, f64)>,
+}
+
+impl Effects {
+    pub fn start(&mut self, effects: impl IntoIterator<Item = Effect>) {
+        self.playing.extend(effects.into_iter().map(|effect| (effect, 0.0)));
+    }
+
+    pub fn clear(&mut self) {
+        self.playing.clear();
+    }
+
+    /// Move every effect on by `elapsed_ms`, dropping the ones that have finished.
+    pub fn advance(&mut self, elapsed_ms: f64) {
+        for (_, played_ms) in &mut self.playing {
+            *played_ms += elapsed_ms;
+        }
+        self.playing.retain(|(effect, played_ms)| *played_ms < effect.duration_ms());
+    }
+
+    /// Whether an entity's death is still playing, so its remains shouldn't show yet.
+    pub fn hides(&self, target: usize) -> bool {
+        self.playing
+            .iter()
+            .any(|(effect, _)| matches!(effect, Effect::Death { target: dying, .. } if *dying == target))
+    }
+
+    /// Each playing effect with how far through it is, from 0 to 1.
+    pub fn iter(&self) -> impl Iterator<Item = (&Effect, f32)> {
+        self.playing
+            .iter()
+            .map(|(effect, played_ms)| (effect, (played_ms / effect.duration_ms()) as f32))
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{strings::Strings, world::World};
+
+/// How much danger a level of the given depth usually holds, in total monster hp.
+fn usual_danger(depth: u32) -> i32 {
+    (2 + depth as i32) * 2
+}
+
+/// A hint about what a new level holds, the way classic roguelikes greet you on
+/// the stairs. Special levels, elites and legendary items always get a mention;
+/// ordinary levels only when they're unusually quiet or crowded.
+pub fn describe(world: &World, strings: &Strings) -> Option<String> {
+    let others = world
+        .entities
+        .iter()
+        .enumerate()
+        .filter(|&(id, _)| id != world.player_id)
+        .map(|(_, entity)| entity);
+    let mut danger = 0;
+    let mut elites = false;
+    let mut legendary = false;
+    for entity in others {
+        if entity.is_alive() {
+            danger += entity.max_hp;
+            elites |= entity.elite;
+        } else if entity.name.is_some() {
+            legendary = true;
+        }
+    }
+
+    let usual = usual_danger(world.depth);
+    let danger = if elites {
+        Some("feeling.great_danger")
+    } else if danger > usual * 3 / 2 {
+        Some("feeling.dangerous")
+    } else if danger < usual / 2 {
+        Some("feeling.quiet")
+    } else {
+        None
+    };
+    let treasure = legendary.then_some("feeling.treasure");
+
+    let special = world.special.as_deref().map(|name| strings.name("special", name));
+    let hints = danger.into_iter().chain(treasure).map(|id| strings.get(id));
+    let hints: Vec<&str> = special.into_iter().chain(hints).collect();
+    (!hints.is_empty()).then(|| hints.join(" "))
+}
@@ -0,0 +1,115 @@
+use crate::{
+    boulders,
+    foliage::TALL_GRASS,
+    monsters::Ai,
+    portals::{self, PORTAL},
+    replay::Command,
+    world::{Entity, Tile, World},
+};
+use quicksilver::{geom::Vector, graphics::Color};
+
+/// How much hp the monsters in a fixture start with.
+pub const MONSTER_HP: i32 = 3;
+
+/// How much hp the player in a fixture starts with.
+pub const PLAYER_HP: i32 = 5;
+
+/// A step to the east, for walking and attacking along a corridor.
+pub const EAST: Vector = Vector { x: 1.0, y: 0.0 };
+
+/// A step to the west.
+pub const WEST: Vector = Vector { x: -1.0, y: 0.0 };
+
+/// Build a world from a map drawn as ASCII art, for tests. `#` is wall, `.` is
+/// floor, `~` is water, `≈` deep water, `≋` lava, `░` ice, `>` is stairs down
+/// and `<` stairs up, and `+`, `|`, `_`, `}` and `^` are a locked door, a lever,
+/// a pressure plate, a portcullis and a trap, with nothing linked to them or
+/// opening them. `♣` is tall grass on floor. `Ω` is a portal, linked to the next
+/// one in reading order in pairs. `@` is the player, a lowercase letter a monster
+/// that chases, an uppercase one a monster that guards and `0` a boulder, each
+/// standing on floor. The player is entity 0 and the monsters and boulders follow
+/// in reading order.
+///
+/// Leading whitespace and blank lines are ignored, so the map can be indented in
+/// a raw string. Panics if the rows aren't all the same length or there isn't
+/// exactly one player, since a broken fixture is a broken test.
+pub fn world(map: &str) -> World {
+    let rows: Vec<Vec<char>> = map
+        .lines()
+        .map(str::trim)
+        .filter(|row| !row.is_empty())
+        .map(|row| row.chars().collect())
+        .collect();
+    let width = rows.first().map_or(0, Vec::len);
+    assert!(rows.iter().all(|row| row.len() == width), "fixture rows differ in length");
+
+    let mut player = None;
+    let mut monsters = Vec::new();
+    let mut portals = Vec::new();
+    for (y, row) in rows.iter().enumerate() {
+        for (x, &glyph) in row.iter().enumerate() {
+            let pos = Vector::new(x as f32, y as f32);
+            match glyph {
+                '@' => {
+                    assert!(player.is_none(), "fixture has more than one player");
+                    player = Some(Entity::new(pos, '@', Color::WHITE, PLAYER_HP));
+                }
+                'a'..='z' | 'A'..='Z' => {
+                    let ai = if glyph.is_lowercase() { Ai::Chase } else { Ai::Guard };
+                    monsters.push(Entity {
+                        ai,
+                        ..Entity::new(pos, glyph, Color::RED, MONSTER_HP)
+                    });
+                }
+                boulders::BOULDER => monsters.push(boulders::boulder(pos, Color::WHITE)),
+                PORTAL => portals.push(pos),
+                _ => {}
+            }
+        }
+    }
+    let player = player.expect("fixture has no player");
+
+    // The map is stored a column at a time
+    let mut tiles = Vec::with_capacity(width * rows.len());
+    let mut beneath_grass = Vec::new();
+    for x in 0..width {
+        for (y, row) in rows.iter().enumerate() {
+            let glyph = match row[x] {
+                glyph @ ('#' | '+' | '|' | '_' | '}' | '^' | '>' | '<') => glyph,
+                glyph @ ('~' | '≈' | '≋' | '░' | '♣' | 'Ω') => glyph,
+                _ => '.',
+            };
+            let tile = Tile {
+                pos: Vector::new(x as f32, y as f32),
+                glyph,
+                color: Color::WHITE,
+                animation: None,
+                light_radius: 0,
+            };
+            if glyph == TALL_GRASS {
+                beneath_grass.push(Tile { glyph: '.', ..tile.clone() });
+            }
+            tiles.push(tile);
+        }
+    }
+    let size = Vector::new(width as f32, rows.len() as f32);
+    let mut world = World::new(size, tiles, std::iter::once(player).chain(monsters).collect(), 0);
+    world.beneath_grass = beneath_grass;
+    for pair in portals.chunks_exact(2) {
+        portals::link(&mut world, pair[0], pair[1]);
+    }
+    world
+}
+
+/// Apply `commands` to `world` in order, the same way a replay would.
+pub fn play(world: &mut World, commands: &[Command]) {
+    for command in commands {
+        command.apply(world);
+    }
+}
+
+/// Put the items named in `items` in the player's pack.
+pub fn carry(world: &mut World, items: &[&str]) {
+    let player = world.player_id;
+    world.entities[player].inventory.extend(items.iter().map(|item| item.to_string()));
+}
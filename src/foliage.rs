@@ -0,0 +1,7 @@
+/// Grass grown tall enough to hide whatever is in it or beyond it, though not to
+/// stop anyone walking through. Drawn as a `"` unless the glyphs file says
+/// otherwise.
+pub const TALL_GRASS: char = '♣';
+
+/// How long whoever stands in tall grass as it burns is left burning, in turns.
+pub const GRASS_FIRE_TURNS: u32 = 2;
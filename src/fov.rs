@@ -0,0 +1,52 @@
+use quicksilver::geom::Vector;
+
+/// How far the player can see, in tiles.
+pub const FOV_RADIUS: i32 = 8;
+
+/// The tiles on the straight line from `from` to `to`, both ends included.
+pub fn line(from: Vector, to: Vector) -> Vec<Vector> {
+    let (mut x, mut y) = (from.x as i32, from.y as i32);
+    let (x1, y1) = (to.x as i32, to.y as i32);
+    let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+    let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+    let mut err = dx + dy;
+    let mut points = vec![Vector::new(x, y)];
+    while (x, y) != (x1, y1) {
+        let doubled = 2 * err;
+        if doubled >= dy {
+            err += dy;
+            x += sx;
+        }
+        if doubled <= dx {
+            err += dx;
+            y += sy;
+        }
+        points.push(Vector::new(x, y));
+    }
+    points
+}
+
+/// Which tiles of a `size` map can be seen from `origin`, indexed like the map itself.
+///
+/// A tile is visible when it's within `radius` and the line to it doesn't pass through
+/// an opaque tile; opaque tiles themselves are visible so walls get drawn.
+pub fn compute(origin: Vector, radius: i32, size: Vector, opaque: impl Fn(Vector) -> bool) -> Vec<bool> {
+    let (width, height) = (size.x as i32, size.y as i32);
+    let mut visible = vec![false; (width * height) as usize];
+    let (ox, oy) = (origin.x as i32, origin.y as i32);
+    for x in (ox - radius).max(0)..=(ox + radius).min(width - 1) {
+        for y in (oy - radius).max(0)..=(oy + radius).min(height - 1) {
+            let (dx, dy) = (x - ox, y - oy);
+            if dx * dx + dy * dy > radius * radius {
+                continue;
+            }
+            let target = Vector::new(x, y);
+            let path = line(origin, target);
+            let blocked = path.len() > 2 && path[1..path.len() - 1].iter().any(|&pos| opaque(pos));
+            if !blocked {
+                visible[(x * height + y) as usize] = true;
+            }
+        }
+    }
+    visible
+}
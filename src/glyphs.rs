@@ -0,0 +1,103 @@
+use crate::data::{self, Problem};
+use quicksilver::graphics::Color;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+pub const GLYPHS_FILE: &str = "glyphs.json";
+
+/// The glyphs and colors the game was built with. Files and mods go on top of
+/// these, so every color the game asks for is always there.
+const BUILT_IN: &str = include_str!("../static/glyphs.json");
+
+/// How the map looks, as written in `static/glyphs.json`: the glyph each of the
+/// game's own glyphs is drawn as, and the colors levels are generated with.
+/// Along with the monster, item and spell files, this is all a total conversion
+/// needs. A mod's copy only needs the entries it changes.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Glyphs {
+    /// The game still tells walls from floor by its own glyphs; these only change
+    /// what gets drawn.
+    #[serde(default)]
+    glyphs: BTreeMap<char, char>,
+    /// Colors by name, such as "wall" or "elite".
+    #[serde(default)]
+    colors: BTreeMap<String, Color>,
+}
+
+impl Glyphs {
+    fn merge(&mut self, glyphs: Glyphs) {
+        self.glyphs.extend(glyphs.glyphs);
+        self.colors.extend(glyphs.colors);
+    }
+
+    /// What to draw for the game's `glyph`.
+    pub fn glyph(&self, glyph: char) -> char {
+        self.glyphs.get(&glyph).copied().unwrap_or(glyph)
+    }
+
+    /// Every glyph that gets drawn in place of another.
+    pub fn drawn(&self) -> impl Iterator<Item = char> + '_ {
+        self.glyphs.values().copied()
+    }
+
+    /// The color called `name`.
+    pub fn color(&self, name: &str) -> Color {
+        self.colors.get(name).copied().unwrap_or_else(|| built_in().colors[name])
+    }
+
+    /// The colors given that the game never asks for, which are most likely typos.
+    pub fn unknown_colors(&self) -> Vec<&str> {
+        let known = built_in().colors;
+        self.colors.keys().filter(|name| !known.contains_key(*name)).map(String::as_str).collect()
+    }
+}
+
+fn built_in() -> Glyphs {
+    serde_json::from_str(BUILT_IN).expect("the built-in glyphs are valid")
+}
+
+/// Read the glyphs and colors along with every mod's, without falling back on failure.
+#[cfg(feature = "hot-reload")]
+pub fn try_load() -> Result<Glyphs, Problem> {
+    let mut glyphs = built_in();
+    glyphs.merge(data::try_load(GLYPHS_FILE)?);
+    for path in data::mod_files(GLYPHS_FILE) {
+        glyphs.merge(data::try_load(&path)?);
+    }
+    Ok(glyphs)
+}
+
+/// Like `try_load`, keeping the built-in glyphs and colors if the file is missing
+/// or broken and skipping broken mods.
+pub fn load(problems: &mut Vec<Problem>) -> Glyphs {
+    let mut glyphs = built_in();
+    glyphs.merge(data::load(GLYPHS_FILE, BUILT_IN, problems));
+    for path in data::mod_files(GLYPHS_FILE) {
+        match data::try_load(&path) {
+            Ok(mod_glyphs) => glyphs.merge(mod_glyphs),
+            Err(mut problem) => {
+                problem.message += ", so the mod's copy is skipped";
+                problems.push(problem);
+            }
+        }
+    }
+    glyphs
+}
+
+/// Code page 437 in order: the control characters' picture glyphs, ASCII, then the
+/// high half with its accented letters, box drawing, shading and math symbols.
+pub const CP437: &str = concat!(
+    "\0☺☻♥♦♣♠•◘○◙♂♀♪♫☼►◄↕‼¶§▬↨↑↓→←∟↔▲▼",
+    " !\"#$%&'()*+,-./0123456789:;<=>?",
+    "@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_",
+    "`abcdefghijklmnopqrstuvwxyz{|}~⌂",
+    "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒ",
+    "áíóúñÑªº¿⌐¬½¼¡«»░▒▓│┤╡╢╖╕╣║╗╝╜╛┐",
+    "└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀",
+    "αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}",
+);
+
+/// Whether a code page 437 tileset has a tile for `glyph`.
+pub fn in_cp437(glyph: char) -> bool {
+    glyph != '\0' && CP437.contains(glyph)
+}
@@ -0,0 +1,165 @@
+use crate::{
+    data, dungeon,
+    items::{self, ItemDef},
+    spawn,
+    world::World,
+};
+use log::debug;
+use quicksilver::{geom::Vector, graphics::Color};
+use serde::{Deserialize, Serialize};
+
+pub const GODS_FILE: &str = "gods.json";
+
+/// The definitions the game was built with, for when the file can't be read.
+const BUILT_IN: &str = include_str!("../static/gods.json");
+
+/// The chance each level has of an altar.
+const ALTAR_CHANCE: f32 = 0.3;
+
+/// What a god does when a prayer to it is answered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PrayerEffect {
+    /// Give the player back this much hp.
+    Heal(i32),
+    /// Strike the nearest monster the player can see for this much damage.
+    Smite(i32),
+}
+
+/// Something a god can be asked for, at a price in piety.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Prayer {
+    pub name: String,
+    pub piety: u32,
+    pub effect: PrayerEffect,
+}
+
+/// An item a god hands the player once their piety first reaches `piety`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Gift {
+    pub piety: u32,
+    /// The item of this name from the item file.
+    pub item: String,
+}
+
+/// One god, as written in `static/gods.json`. What it wants offered on its altars
+/// and what it grants in return is all there is to its personality.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GodDef {
+    pub name: String,
+    /// How its altars look.
+    pub color: Color,
+    /// Piety for each corpse offered.
+    #[serde(default)]
+    pub corpse_piety: u32,
+    /// Piety for each item offered, in percent of what it's worth.
+    #[serde(default)]
+    pub item_piety: u32,
+    #[serde(default)]
+    pub prayers: Vec<Prayer>,
+    /// Handed out in order, each once the piety it asks for is reached.
+    #[serde(default)]
+    pub gifts: Vec<Gift>,
+}
+
+impl GodDef {
+    /// How much piety `offering` earns with the god.
+    pub fn piety(&self, offering: Offering) -> u32 {
+        match offering {
+            Offering::Item { value, .. } => value * self.item_piety / 100,
+            Offering::Corpse(_) => self.corpse_piety,
+        }
+    }
+}
+
+impl data::Named for GodDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// An altar on the level, where offerings to its god are made.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Altar {
+    pub pos: Vector,
+    /// The god it's dedicated to, by name from the god file.
+    pub god: String,
+}
+
+/// Something the player can give up at an altar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Offering {
+    /// The item at this index of the player's inventory, and what it's worth.
+    Item { index: usize, value: u32 },
+    /// The corpse with this entity id, lying on or next to the altar.
+    Corpse(usize),
+}
+
+/// How the player stands with one god.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Devotion {
+    /// Earned by offerings and spent on prayers.
+    pub piety: u32,
+    /// How many of the god's gifts the player has had.
+    pub gifts: usize,
+}
+
+/// Something the player can do for or ask of a god.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rite {
+    /// Make an offering at the altar with this index.
+    Offer(usize, Offering),
+    /// Pray to the god at this index of the god file for the prayer at this index
+    /// of its prayers.
+    Pray(usize, usize),
+}
+
+/// Everything the player can do right now: make each offering they have at the
+/// altar they stand on, if any, then pray to each god they've made offerings to.
+pub fn rites(world: &World, gods: &[GodDef], items: &[ItemDef]) -> Vec<Rite> {
+    let mut rites = Vec::new();
+    let player = world.player();
+    if let Some(altar) = world.altar_at(player.pos) {
+        for (index, name) in player.inventory.iter().enumerate() {
+            if let Some(item) = items::find(items, name).filter(|item| item.name != dungeon::AMULET) {
+                rites.push(Rite::Offer(altar, Offering::Item { index, value: item.value }));
+            }
+        }
+        let corpses = world.corpses_near(world.altars[altar].pos);
+        rites.extend(corpses.into_iter().map(|id| Rite::Offer(altar, Offering::Corpse(id))));
+    }
+    for (god, def) in gods.iter().enumerate().filter(|(_, def)| world.devotion.contains_key(&def.name)) {
+        rites.extend((0..def.prayers.len()).map(|prayer| Rite::Pray(god, prayer)));
+    }
+    rites
+}
+
+/// Now and then put an altar to a god picked at random somewhere open on `world`.
+pub fn place_altar(world: &mut World, gods: &[GodDef]) {
+    if gods.is_empty() || world.rng.next_f32() >= ALTAR_CHANCE {
+        return;
+    }
+    let god = &gods[world.rng.range(0, gods.len() as u32) as usize];
+    if let Some(pos) = spawn::free_spot(world) {
+        debug!(target: "generate", god = god.name.as_str(), x = pos.x, y = pos.y; "placed an altar");
+        if let Some(tile) = world.tile_at_mut(pos) {
+            tile.glyph = '_';
+            tile.color = god.color;
+            tile.animation = None;
+        }
+        world.altars.push(Altar {
+            pos,
+            god: god.name.clone(),
+        });
+    }
+}
+
+/// The definition of the god called `name`.
+pub fn find<'a>(gods: &'a [GodDef], name: &str) -> Option<&'a GodDef> {
+    gods.iter().find(|god| god.name == name)
+}
+
+/// Read the god definitions, so gods can be added or changed by editing the file
+/// or in a mod.
+pub fn load(problems: &mut Vec<data::Problem>) -> Vec<GodDef> {
+    data::load_defs(GODS_FILE, BUILT_IN, problems)
+}
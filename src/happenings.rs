@@ -0,0 +1,190 @@
+use crate::{
+    data::{self, Content},
+    destructibles::{self, Destructible},
+    locks::Lock,
+    mechanisms::Mechanism,
+    monsters, prefab,
+    rng::Rng,
+    shops::Shop,
+    spawn, specials,
+    world::{Entity, Tile, World},
+};
+use log::{debug, warn};
+use quicksilver::geom::Vector;
+use serde::{Deserialize, Serialize};
+
+pub const HAPPENINGS_FILE: &str = "happenings.json";
+
+/// The definitions the game was built with, for when the file can't be read.
+const BUILT_IN: &str = include_str!("../static/happenings.json");
+
+/// How many turns go by between chances of something happening on a level, on
+/// top of the chance each time the player arrives on one.
+pub const HAPPENING_TURNS: usize = 300;
+
+/// The chance an earthquake shakes each tile within its reach.
+const QUAKE_CHANCE: u32 = 40;
+
+fn first() -> u32 {
+    1
+}
+
+/// What a happening does to the level.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HappeningEffect {
+    /// Shake up the ground within this many tiles of somewhere on the level:
+    /// walls crumble into rubble and floor heaves up into walls.
+    Earthquake(u32),
+    /// Stamp the prefab from the file of this name somewhere clear, the way a
+    /// merchant caravan sets up camp.
+    Camp(String),
+    /// Bring in this many of the monster of this name, from wherever there's room.
+    Migration { monster: String, count: u32 },
+    /// Put this much more of the floor under water, away from the player, in percent.
+    Flood(u32),
+}
+
+/// One thing that can happen during a run, as written in `static/happenings.json`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HappeningDef {
+    pub name: String,
+    /// The chance it happens each time something gets the chance to, in percent.
+    pub chance: u32,
+    /// The shallowest depth it happens at.
+    #[serde(default = "first")]
+    pub min_depth: u32,
+    pub effect: HappeningEffect,
+}
+
+impl HappeningDef {
+    /// Whether `prefab` is kept for this to stamp.
+    pub fn uses(&self, prefab: &prefab::Prefab) -> bool {
+        matches!(&self.effect, HappeningEffect::Camp(file) if prefab.is(file))
+    }
+}
+
+impl data::Named for HappeningDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// What a happening did to the level, worked out ahead so it can be logged and
+/// played back without the data files.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Happening {
+    /// The happening of this name from the happening file.
+    pub name: String,
+    /// Every tile it changed, as it is now.
+    pub tiles: Vec<Tile>,
+    /// Everything it brought onto the level.
+    pub arrivals: Vec<Entity>,
+    pub shops: Vec<Shop>,
+    #[serde(default)]
+    pub locks: Vec<Lock>,
+    #[serde(default)]
+    pub mechanisms: Vec<Mechanism>,
+    /// Whatever it put up that can be knocked down.
+    #[serde(default)]
+    pub destructibles: Vec<Destructible>,
+    /// Where the level's random choices carry on from.
+    pub rng: Rng,
+}
+
+/// Whether anything happens on `world` now, with the rolls for it taken from
+/// `rng`: each happening for its depth gets its chance in turn, and the first
+/// that comes up happens.
+pub fn roll<'a>(rng: &mut Rng, world: &World, happenings: &'a [HappeningDef]) -> Option<&'a HappeningDef> {
+    let mut possible = happenings.iter().filter(|def| def.min_depth <= world.depth);
+    possible.find(|def| rng.range(0, 100) < def.chance)
+}
+
+/// Work out what `def` does to `world`, without changing it, making its random
+/// choices with `rng`.
+pub fn prepare(world: &World, def: &HappeningDef, rng: Rng, content: &Content) -> Happening {
+    let mut after = world.clone();
+    after.log.clear();
+    after.rng = rng;
+    match &def.effect {
+        HappeningEffect::Earthquake(radius) => quake(&mut after, *radius, content),
+        HappeningEffect::Camp(file) => {
+            prefab::place_named(&mut after, file, content);
+        }
+        HappeningEffect::Migration { monster, count } => match monsters::find(&content.monsters, monster) {
+            Some(monster) => {
+                for _ in 0..*count {
+                    if let Some(pos) = spawn::free_spot(&mut after) {
+                        after.entities.push(monster.entity(pos));
+                    }
+                }
+            }
+            None => warn!(target: "generate", "There's no {} in the monster file to migrate", monster),
+        },
+        HappeningEffect::Flood(percent) => specials::flood(&mut after, *percent, &content.glyphs),
+    }
+    destructibles::mark(&mut after, &content.glyphs);
+    debug!(target: "generate", happening = def.name.as_str(); "prepared a happening");
+    let tiles = after.map.iter().zip(&world.map).filter(|(new, old)| new != old).map(|(new, _)| new.clone());
+    Happening {
+        name: def.name.clone(),
+        tiles: tiles.collect(),
+        arrivals: after.entities[world.entities.len()..].to_vec(),
+        shops: after.shops[world.shops.len()..].to_vec(),
+        locks: after.locks[world.locks.len()..].to_vec(),
+        mechanisms: after.mechanisms[world.mechanisms.len()..].to_vec(),
+        destructibles: after.destructibles[world.destructibles.len()..].to_vec(),
+        rng: after.rng,
+    }
+}
+
+/// Shake up the ground around somewhere random, leaving the outer wall, stairs,
+/// everything standing or lying on the ground and the tiles around the player
+/// alone.
+fn quake(world: &mut World, radius: u32, content: &Content) {
+    let (size, player) = (world.map_size, world.player().pos);
+    let center = Vector::new(world.rng.range(1, size.x as u32 - 1), world.rng.range(1, size.y as u32 - 1));
+    let (wall, rubble) = (content.glyphs.color("wall"), content.glyphs.color("rubble"));
+    for index in 0..world.map.len() {
+        let pos = world.map[index].pos;
+        let edge = pos.x == 0.0 || pos.y == 0.0 || pos.x == size.x - 1.0 || pos.y == size.y - 1.0;
+        let taken = pos.distance(player) < 2.0 || world.entities.iter().any(|entity| entity.pos == pos);
+        if edge || taken || pos.distance(center) > radius as f32 || world.rng.range(0, 100) >= QUAKE_CHANCE {
+            continue;
+        }
+        let tile = &mut world.map[index];
+        let (glyph, color) = match tile.glyph {
+            '#' => (':', rubble),
+            '.' | ':' | ',' | '&' | '"' => ('#', wall),
+            _ => continue,
+        };
+        tile.glyph = glyph;
+        tile.color = color;
+        tile.animation = None;
+        tile.light_radius = 0;
+    }
+}
+
+/// A happening set to come about on a level once some turns have gone by, and
+/// every so often after that if it comes again. Special levels schedule theirs in
+/// `static/specials.json`, and anything else can with `World::schedule`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Scheduled {
+    /// The happening of this name from the happening file.
+    pub happening: String,
+    /// How many turns are left until it comes about.
+    pub turns: usize,
+    /// How many turns go by before it comes about again, if it does.
+    #[serde(default)]
+    pub every: Option<usize>,
+}
+
+/// The definition of the happening called `name`.
+pub fn find<'a>(happenings: &'a [HappeningDef], name: &str) -> Option<&'a HappeningDef> {
+    happenings.iter().find(|happening| happening.name == name)
+}
+
+/// Read the happenings, so they can be added or changed by editing the file or in
+/// a mod.
+pub fn load(problems: &mut Vec<data::Problem>) -> Vec<HappeningDef> {
+    data::load_defs(HAPPENINGS_FILE, BUILT_IN, problems)
+}
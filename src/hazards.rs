@@ -0,0 +1,39 @@
+/// Water too deep to wade, which has to be swum. Drawn as `~`, darker than
+/// shallow water, unless the glyphs file says otherwise.
+pub const DEEP_WATER: char = '≈';
+
+/// Molten rock, which burns whatever is in it. Drawn as a glowing `~` unless the
+/// glyphs file says otherwise.
+pub const LAVA: char = '≋';
+
+/// How far lava lights up its surroundings, in tiles.
+pub const LAVA_LIGHT_RADIUS: u32 = 2;
+
+/// How much lava hurts whoever ends a turn in it.
+pub const LAVA_DAMAGE: i32 = 8;
+
+/// How many turns in a row the player can swim before they start to drown.
+pub const BREATH_TURNS: u32 = 6;
+
+/// How much drowning hurts each turn past the player's breath.
+pub const DROWN_DAMAGE: i32 = 2;
+
+/// The chance each turn of swimming that something the player carries slips away,
+/// in percent.
+pub const SINK_CHANCE: u32 = 10;
+
+/// What a tile of deep water costs creatures finding their way, against 1 for
+/// anywhere else they can go.
+pub const DEEP_WATER_COST: u32 = 5;
+
+/// Ice, which whatever steps onto slides across until it comes off the other side
+/// or hits something. Drawn as a pale `.` unless the glyphs file says otherwise.
+pub const ICE: char = '░';
+
+/// What a tile of ice costs creatures finding their way, who would rather not go
+/// skidding off somewhere they didn't mean to.
+pub const ICE_COST: u32 = 3;
+
+/// The most tiles anything slides in one go, in case ice and portals ever send it
+/// round in a loop.
+pub const MAX_SLIDE: u32 = 32;
@@ -0,0 +1,54 @@
+use crate::{
+    items::{self, ItemDef, ItemEffect},
+    world::World,
+};
+
+/// How many turns a full stomach lasts, which the player starts the run with.
+pub const FULL_TURNS: u32 = 2000;
+
+/// The most turns the player can eat their way up to.
+pub const MAX_FULL_TURNS: u32 = 3000;
+
+/// How many turns of food the player has left when they get hungry.
+pub const HUNGRY_TURNS: u32 = 300;
+
+/// How the player's stomach is doing, which slows their healing or stops it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hunger {
+    Fed,
+    /// Heals at half the rate.
+    Hungry,
+    /// Doesn't heal at all.
+    Starving,
+}
+
+impl Hunger {
+    /// How hungry the player is with `turns` turns of food left in them.
+    pub fn of(turns: u32) -> Hunger {
+        match turns {
+            0 => Hunger::Starving,
+            turns if turns <= HUNGRY_TURNS => Hunger::Hungry,
+            _ => Hunger::Fed,
+        }
+    }
+
+    /// How many turns it takes to heal a hp when it would take `turns` on a full
+    /// stomach, or `None` if the player can't heal at all.
+    pub fn heal_turns(self, turns: usize) -> Option<usize> {
+        match self {
+            Hunger::Fed => Some(turns),
+            Hunger::Hungry => Some(turns * 2),
+            Hunger::Starving => None,
+        }
+    }
+}
+
+/// The first thing in the player's pack that would feed them, by its index in
+/// their inventory, along with what it is.
+pub fn meal<'a>(world: &World, items: &'a [ItemDef]) -> Option<(usize, &'a ItemDef)> {
+    world.player().inventory.iter().enumerate().find_map(|(index, name)| {
+        let item = items::find(items, name)?;
+        let food = item.effects.iter().any(|effect| matches!(effect, ItemEffect::Nourish(_)));
+        food.then_some((index, item))
+    })
+}
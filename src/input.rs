@@ -0,0 +1,642 @@
+use crate::{config::KeyRepeatConfig, strings::Strings};
+use quicksilver::{
+    geom::Vector,
+    input::{ButtonState, Gamepad, GamepadAxis, GamepadButton, Key, Keyboard},
+};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::VecDeque;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::South,
+        Direction::SouthWest,
+        Direction::West,
+        Direction::NorthWest,
+    ];
+
+    pub fn delta(self) -> Vector {
+        let (x, y) = match self {
+            Direction::North => (0, -1),
+            Direction::NorthEast => (1, -1),
+            Direction::East => (1, 0),
+            Direction::SouthEast => (1, 1),
+            Direction::South => (0, 1),
+            Direction::SouthWest => (-1, 1),
+            Direction::West => (-1, 0),
+            Direction::NorthWest => (-1, -1),
+        };
+        Vector::new(x, y)
+    }
+
+    pub fn name(self, strings: &Strings) -> &str {
+        strings.get(match self {
+            Direction::North => "direction.north",
+            Direction::NorthEast => "direction.northeast",
+            Direction::East => "direction.east",
+            Direction::SouthEast => "direction.southeast",
+            Direction::South => "direction.south",
+            Direction::SouthWest => "direction.southwest",
+            Direction::West => "direction.west",
+            Direction::NorthWest => "direction.northwest",
+        })
+    }
+}
+
+/// Everything the player can ask for from the keyboard or a gamepad. Input code checks actions,
+/// never literal keys, so that every key can be rebound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Move(Direction),
+    Wait,
+    Rest,
+    Throw,
+    Cast,
+    TravelToStairs,
+    UseStairs,
+    PickUp,
+    Pray,
+    Craft,
+    UseItem,
+    Mix,
+    Repeat,
+    CharacterSheet,
+    QuestLog,
+    CommandPalette,
+    OpenSettings,
+    ToggleFullscreen,
+    ToggleMute,
+    Screenshot,
+    WatchReplay,
+    Quit,
+    Confirm,
+    Cancel,
+}
+
+impl Action {
+    pub fn all() -> Vec<Action> {
+        let mut actions: Vec<Action> = Direction::ALL.iter().map(|&dir| Action::Move(dir)).collect();
+        actions.extend([
+            Action::Wait,
+            Action::Rest,
+            Action::Throw,
+            Action::Cast,
+            Action::TravelToStairs,
+            Action::UseStairs,
+            Action::PickUp,
+            Action::Pray,
+            Action::Craft,
+            Action::UseItem,
+            Action::Mix,
+            Action::Repeat,
+            Action::CharacterSheet,
+            Action::QuestLog,
+            Action::CommandPalette,
+            Action::OpenSettings,
+            Action::ToggleFullscreen,
+            Action::ToggleMute,
+            Action::Screenshot,
+            Action::WatchReplay,
+            Action::Quit,
+            Action::Confirm,
+            Action::Cancel,
+        ]);
+        actions
+    }
+
+    pub fn label(self, strings: &Strings) -> String {
+        let id = match self {
+            Action::Move(dir) => return strings.format("action.move", &[("direction", &dir.name(strings))]),
+            Action::Wait => "action.wait",
+            Action::Rest => "action.rest",
+            Action::Throw => "action.throw",
+            Action::Cast => "action.cast",
+            Action::TravelToStairs => "action.travel_to_stairs",
+            Action::UseStairs => "action.use_stairs",
+            Action::PickUp => "action.pick_up",
+            Action::Pray => "action.pray",
+            Action::Craft => "action.craft",
+            Action::UseItem => "action.use_item",
+            Action::Mix => "action.mix",
+            Action::Repeat => "action.repeat",
+            Action::CharacterSheet => "action.character_sheet",
+            Action::QuestLog => "action.quest_log",
+            Action::CommandPalette => "action.command_palette",
+            Action::OpenSettings => "action.open_settings",
+            Action::ToggleFullscreen => "action.toggle_fullscreen",
+            Action::ToggleMute => "action.toggle_mute",
+            Action::Screenshot => "action.screenshot",
+            Action::WatchReplay => "action.watch_replay",
+            Action::Quit => "action.quit",
+            Action::Confirm => "action.confirm",
+            Action::Cancel => "action.cancel",
+        };
+        strings.get(id).to_string()
+    }
+
+    fn default_keys(self, preset: Preset) -> Vec<KeyCombo> {
+        let keys = match self {
+            Action::Move(dir) => preset.move_keys(dir),
+            Action::Wait => vec![Key::Period, Key::Numpad5, Key::Key5],
+            Action::Rest => vec![Key::R],
+            Action::Throw => vec![Key::T],
+            Action::Cast => vec![Key::X],
+            Action::TravelToStairs => return vec![KeyCombo::shifted(Key::Period)],
+            Action::UseStairs => return vec![KeyCombo::shifted(Key::Comma)],
+            Action::PickUp => vec![Key::Comma],
+            Action::Pray => vec![Key::P],
+            Action::Craft => vec![Key::F],
+            Action::UseItem => vec![Key::I],
+            Action::Mix => return vec![KeyCombo::shifted(Key::I)],
+            // A is taken by WASD movement
+            Action::Repeat if preset == Preset::Wasd => vec![Key::G],
+            Action::Repeat => vec![Key::A],
+            Action::CharacterSheet => return vec![KeyCombo::shifted(Key::C)],
+            Action::QuestLog => return vec![KeyCombo::shifted(Key::Q)],
+            Action::CommandPalette => return vec![KeyCombo::shifted(Key::Semicolon)],
+            Action::OpenSettings => vec![Key::O],
+            Action::ToggleFullscreen => return vec![KeyCombo::plain(Key::F11), KeyCombo::alt(Key::Return)],
+            Action::ToggleMute => vec![Key::M],
+            Action::Screenshot => vec![Key::F12],
+            Action::WatchReplay => vec![Key::V],
+            Action::Quit => vec![Key::Escape],
+            Action::Confirm => vec![Key::Y, Key::Return],
+            Action::Cancel => vec![Key::N, Key::Escape],
+        };
+        keys.into_iter().map(KeyCombo::plain).collect()
+    }
+}
+
+/// A ready-made set of movement keys, picked in the settings menu as a starting
+/// point for the player's own bindings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Preset {
+    /// Arrows, numpad and vi keys all at once.
+    #[default]
+    Classic,
+    /// Arrows, with Home/PageUp/End/PageDown for diagonals.
+    Arrows,
+    Wasd,
+    Vi,
+    Numpad,
+}
+
+impl Preset {
+    const ALL: [Preset; 5] = [Preset::Classic, Preset::Arrows, Preset::Wasd, Preset::Vi, Preset::Numpad];
+
+    pub fn next(self) -> Preset {
+        let index = Preset::ALL.iter().position(|&preset| preset == self).unwrap_or(0);
+        Preset::ALL[(index + 1) % Preset::ALL.len()]
+    }
+
+    pub fn name(self, strings: &Strings) -> &str {
+        strings.get(match self {
+            Preset::Classic => "preset.classic",
+            Preset::Arrows => "preset.arrows",
+            Preset::Wasd => "preset.wasd",
+            Preset::Vi => "preset.vi",
+            Preset::Numpad => "preset.numpad",
+        })
+    }
+
+    fn move_keys(self, dir: Direction) -> Vec<Key> {
+        use Direction::*;
+        let (arrow, numpad, vi, wasd) = match dir {
+            North => (Key::Up, Key::Numpad8, Key::K, Key::W),
+            NorthEast => (Key::PageUp, Key::Numpad9, Key::U, Key::E),
+            East => (Key::Right, Key::Numpad6, Key::L, Key::D),
+            SouthEast => (Key::PageDown, Key::Numpad3, Key::N, Key::C),
+            South => (Key::Down, Key::Numpad2, Key::J, Key::S),
+            SouthWest => (Key::End, Key::Numpad1, Key::B, Key::Z),
+            West => (Key::Left, Key::Numpad4, Key::H, Key::A),
+            NorthWest => (Key::Home, Key::Numpad7, Key::Y, Key::Q),
+        };
+        let diagonal = !matches!(dir, North | East | South | West);
+        match self {
+            Preset::Classic if diagonal => vec![numpad, vi],
+            Preset::Classic => vec![arrow, numpad, vi],
+            Preset::Arrows => vec![arrow],
+            Preset::Wasd => vec![wasd],
+            Preset::Vi => vec![vi],
+            Preset::Numpad => vec![numpad],
+        }
+    }
+}
+
+/// Keys that can be bound to actions, and the names they're stored under in the config.
+pub const BINDABLE_KEYS: &[Key] = &[
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K,
+    Key::L, Key::M, Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V,
+    Key::W, Key::X, Key::Y, Key::Z, Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4,
+    Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9, Key::F1, Key::F2, Key::F3, Key::F4,
+    Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12, Key::Left,
+    Key::Right, Key::Up, Key::Down, Key::Numpad0, Key::Numpad1, Key::Numpad2, Key::Numpad3,
+    Key::Numpad4, Key::Numpad5, Key::Numpad6, Key::Numpad7, Key::Numpad8, Key::Numpad9,
+    Key::NumpadEnter, Key::Add, Key::Subtract, Key::Multiply, Key::Divide, Key::Decimal,
+    Key::Escape, Key::Return, Key::Space, Key::Tab, Key::Back, Key::Insert, Key::Delete,
+    Key::Home, Key::End, Key::PageUp, Key::PageDown, Key::Comma, Key::Period, Key::Slash,
+    Key::Semicolon, Key::Apostrophe, Key::LBracket, Key::RBracket, Key::Minus, Key::Equals,
+    Key::Grave, Key::Backslash,
+];
+
+/// The digit just typed on the number row or numpad, for count prompts.
+pub fn typed_digit(keyboard: &Keyboard) -> Option<u32> {
+    const DIGITS: [(Key, Key); 10] = [
+        (Key::Key0, Key::Numpad0),
+        (Key::Key1, Key::Numpad1),
+        (Key::Key2, Key::Numpad2),
+        (Key::Key3, Key::Numpad3),
+        (Key::Key4, Key::Numpad4),
+        (Key::Key5, Key::Numpad5),
+        (Key::Key6, Key::Numpad6),
+        (Key::Key7, Key::Numpad7),
+        (Key::Key8, Key::Numpad8),
+        (Key::Key9, Key::Numpad9),
+    ];
+    DIGITS
+        .iter()
+        .position(|&(row, pad)| keyboard[row] == ButtonState::Pressed || keyboard[pad] == ButtonState::Pressed)
+        .map(|digit| digit as u32)
+}
+
+/// The letter, digit or space just typed, for text fields.
+pub fn typed_char(keyboard: &Keyboard) -> Option<char> {
+    const LETTERS: [Key; 26] = [
+        Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M,
+        Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    ];
+    if keyboard[Key::Space] == ButtonState::Pressed {
+        return Some(' ');
+    }
+    LETTERS
+        .iter()
+        .position(|&key| keyboard[key] == ButtonState::Pressed)
+        .map(|index| (b'a' + index as u8) as char)
+        .or_else(|| typed_digit(keyboard).and_then(|digit| std::char::from_digit(digit, 10)))
+}
+
+fn shift_down(keyboard: &Keyboard) -> bool {
+    keyboard[Key::LShift].is_down() || keyboard[Key::RShift].is_down()
+}
+
+fn alt_down(keyboard: &Keyboard) -> bool {
+    keyboard[Key::LAlt].is_down() || keyboard[Key::RAlt].is_down()
+}
+
+/// A key plus the modifiers that have to be held with it, so `>` can be bound as
+/// Shift+Period.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyCombo {
+    pub key: Key,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl KeyCombo {
+    pub fn plain(key: Key) -> Self {
+        KeyCombo {
+            key,
+            shift: false,
+            alt: false,
+        }
+    }
+
+    pub fn shifted(key: Key) -> Self {
+        KeyCombo {
+            shift: true,
+            ..KeyCombo::plain(key)
+        }
+    }
+
+    pub fn alt(key: Key) -> Self {
+        KeyCombo {
+            alt: true,
+            ..KeyCombo::plain(key)
+        }
+    }
+
+    /// The combo whose key was just pressed on `keyboard`, if it's a bindable one.
+    pub fn just_pressed(keyboard: &Keyboard) -> Option<KeyCombo> {
+        let key = BINDABLE_KEYS.iter().copied().find(|&key| keyboard[key] == ButtonState::Pressed)?;
+        Some(KeyCombo {
+            key,
+            shift: shift_down(keyboard),
+            alt: alt_down(keyboard),
+        })
+    }
+
+    pub fn name(self) -> String {
+        let alt = if self.alt { "Alt+" } else { "" };
+        let shift = if self.shift { "Shift+" } else { "" };
+        format!("{}{}{:?}", alt, shift, self.key)
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        let (alt, name) = match name.strip_prefix("Alt+") {
+            Some(rest) => (true, rest),
+            None => (false, name),
+        };
+        let (shift, key_name) = match name.strip_prefix("Shift+") {
+            Some(key_name) => (true, key_name),
+            None => (false, name),
+        };
+        let key = BINDABLE_KEYS.iter().copied().find(|&key| format!("{:?}", key) == key_name)?;
+        Some(KeyCombo { key, shift, alt })
+    }
+
+    fn modifiers_match(self, keyboard: &Keyboard) -> bool {
+        shift_down(keyboard) == self.shift && alt_down(keyboard) == self.alt
+    }
+
+    pub fn pressed(self, keyboard: &Keyboard) -> bool {
+        keyboard[self.key] == ButtonState::Pressed && self.modifiers_match(keyboard)
+    }
+
+    pub fn is_down(self, keyboard: &Keyboard) -> bool {
+        keyboard[self.key].is_down() && self.modifiers_match(keyboard)
+    }
+}
+
+fn serialize_keys<S: Serializer>(keys: &[KeyCombo], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(keys.iter().map(|&combo| combo.name()))
+}
+
+fn deserialize_keys<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<KeyCombo>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|name| KeyCombo::from_name(name).ok_or_else(|| D::Error::custom(format!("unknown key {:?}", name))))
+        .collect()
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Binding {
+    pub action: Action,
+    #[serde(serialize_with = "serialize_keys", deserialize_with = "deserialize_keys")]
+    pub keys: Vec<KeyCombo>,
+}
+
+/// The keys bound to each action.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(from = "Vec<Binding>", into = "Vec<Binding>")]
+pub struct KeyBindings {
+    bindings: Vec<Binding>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings::for_preset(Preset::default())
+    }
+}
+
+impl From<Vec<Binding>> for KeyBindings {
+    fn from(bindings: Vec<Binding>) -> Self {
+        KeyBindings { bindings }
+    }
+}
+
+impl From<KeyBindings> for Vec<Binding> {
+    fn from(bindings: KeyBindings) -> Self {
+        bindings.bindings
+    }
+}
+
+impl KeyBindings {
+    pub fn for_preset(preset: Preset) -> Self {
+        let mut bindings = KeyBindings { bindings: Vec::new() };
+        bindings.fill_missing(preset);
+        bindings
+    }
+
+    /// Give any action without a binding, such as one added since the config was
+    /// saved, the preset's keys for it.
+    pub fn fill_missing(&mut self, preset: Preset) {
+        for action in Action::all() {
+            if !self.bindings.iter().any(|binding| binding.action == action) {
+                self.bindings.push(Binding {
+                    action,
+                    keys: action.default_keys(preset),
+                });
+            }
+        }
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCombo] {
+        self.bindings
+            .iter()
+            .find(|binding| binding.action == action)
+            .map_or(&[], |binding| &binding.keys)
+    }
+
+    /// Name of the first key bound to `action`, for showing in prompts.
+    pub fn first_key_name(&self, action: Action) -> String {
+        self.keys(action).first().map_or(String::new(), |&combo| combo.name())
+    }
+
+    /// Bind `key` to `action`, or unbind it if it already was.
+    pub fn toggle(&mut self, action: Action, key: KeyCombo) {
+        if let Some(binding) = self.bindings.iter_mut().find(|binding| binding.action == action) {
+            if let Some(index) = binding.keys.iter().position(|&bound| bound == key) {
+                binding.keys.remove(index);
+            } else {
+                binding.keys.push(key);
+            }
+        }
+    }
+
+    pub fn pressed(&self, keyboard: &Keyboard, action: Action) -> bool {
+        self.keys(action).iter().any(|&combo| combo.pressed(keyboard))
+    }
+
+    pub fn move_keys(&self) -> Vec<KeyCombo> {
+        Direction::ALL
+            .iter()
+            .flat_map(|&dir| self.keys(Action::Move(dir)).iter().copied())
+            .collect()
+    }
+
+    pub fn move_direction(&self, key: KeyCombo) -> Option<Direction> {
+        Direction::ALL
+            .iter()
+            .copied()
+            .find(|&dir| self.keys(Action::Move(dir)).contains(&key))
+    }
+}
+
+/// Fixed gamepad buttons for the non-movement actions.
+const GAMEPAD_BUTTONS: &[(GamepadButton, Action)] = &[
+    (GamepadButton::FaceDown, Action::Confirm),
+    (GamepadButton::FaceRight, Action::Cancel),
+    (GamepadButton::FaceLeft, Action::Wait),
+    (GamepadButton::FaceUp, Action::Rest),
+    (GamepadButton::ShoulderLeft, Action::PickUp),
+    (GamepadButton::ShoulderRight, Action::UseItem),
+    (GamepadButton::Start, Action::Quit),
+    (GamepadButton::Select, Action::OpenSettings),
+    (GamepadButton::DpadUp, Action::Move(Direction::North)),
+    (GamepadButton::DpadDown, Action::Move(Direction::South)),
+    (GamepadButton::DpadLeft, Action::Move(Direction::West)),
+    (GamepadButton::DpadRight, Action::Move(Direction::East)),
+];
+
+/// How far a stick has to be pushed before it counts as a direction.
+const STICK_DEADZONE: f32 = 0.5;
+
+/// The direction any gamepad is pointing with its d-pad or left stick, if any.
+/// Pressing two d-pad buttons together gives a diagonal.
+pub fn gamepad_direction(gamepads: &[Gamepad]) -> Option<Direction> {
+    gamepads.iter().find_map(|pad| {
+        let dpad_x = pad[GamepadButton::DpadRight].is_down() as i32 - pad[GamepadButton::DpadLeft].is_down() as i32;
+        let dpad_y = pad[GamepadButton::DpadDown].is_down() as i32 - pad[GamepadButton::DpadUp].is_down() as i32;
+        let (x, y) = (pad[GamepadAxis::LeftStickX], -pad[GamepadAxis::LeftStickY]);
+        let (x, y) = if dpad_x != 0 || dpad_y != 0 {
+            (dpad_x as f32, dpad_y as f32)
+        } else if x.hypot(y) >= STICK_DEADZONE {
+            (x, y)
+        } else {
+            return None;
+        };
+        // Snap the angle to the nearest of the eight compass directions
+        let octant = (y.atan2(x) / std::f32::consts::FRAC_PI_4).round() as i32;
+        Some(match octant.rem_euclid(8) {
+            0 => Direction::East,
+            1 => Direction::SouthEast,
+            2 => Direction::South,
+            3 => Direction::SouthWest,
+            4 => Direction::West,
+            5 => Direction::NorthWest,
+            6 => Direction::North,
+            _ => Direction::NorthEast,
+        })
+    })
+}
+
+/// One update's keyboard and gamepad state, queried by action.
+pub struct Controls<'a> {
+    keyboard: &'a Keyboard,
+    bindings: &'a KeyBindings,
+    gamepads: &'a [Gamepad],
+}
+
+impl<'a> Controls<'a> {
+    pub fn new(keyboard: &'a Keyboard, bindings: &'a KeyBindings, gamepads: &'a [Gamepad]) -> Self {
+        Controls {
+            keyboard,
+            bindings,
+            gamepads,
+        }
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.bindings.pressed(self.keyboard, action)
+            || GAMEPAD_BUTTONS.iter().any(|&(button, bound)| {
+                bound == action && self.gamepads.iter().any(|pad| pad[button] == ButtonState::Pressed)
+            })
+    }
+}
+
+/// Turn actions pressed while the player can't act yet, played back in order once
+/// their turn comes round. It only holds a few so a long burst can't queue up
+/// moves the player has stopped expecting.
+#[derive(Clone, Debug, Default)]
+pub struct InputBuffer {
+    queue: VecDeque<Action>,
+}
+
+impl InputBuffer {
+    const CAPACITY: usize = 3;
+
+    pub fn push(&mut self, action: Action) {
+        if self.queue.len() < Self::CAPACITY {
+            self.queue.push_back(action);
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<Action> {
+        self.queue.pop_front()
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+}
+
+/// Turns a held key (or any other button) into a stream of repeated presses: one
+/// immediately, another after the initial delay, then one per interval until it's
+/// released.
+#[derive(Clone, Debug)]
+pub struct KeyRepeat<T> {
+    held: Option<T>,
+    held_ms: f64,
+}
+
+impl<T> Default for KeyRepeat<T> {
+    fn default() -> Self {
+        KeyRepeat {
+            held: None,
+            held_ms: 0.0,
+        }
+    }
+}
+
+impl<T: Copy + PartialEq> KeyRepeat<T> {
+    /// Return the button that should fire this update, if any, given the one
+    /// `pressed` this update and whether a button is still down.
+    pub fn poll(
+        &mut self,
+        pressed: Option<T>,
+        is_down: impl Fn(T) -> bool,
+        elapsed_ms: f64,
+        config: &KeyRepeatConfig,
+    ) -> Option<T> {
+        if let Some(button) = pressed {
+            self.held = Some(button);
+            self.held_ms = 0.0;
+            return Some(button);
+        }
+
+        self.held = self.held.filter(|&button| is_down(button));
+        let button = self.held?;
+        self.held_ms += elapsed_ms;
+        if self.held_ms >= config.delay_ms {
+            self.held_ms -= config.interval_ms.max(1.0);
+            Some(button)
+        } else {
+            None
+        }
+    }
+
+    /// Like `poll`, for inputs that only report what's held right now: a change
+    /// counts as a fresh press.
+    pub fn poll_held(&mut self, current: Option<T>, elapsed_ms: f64, config: &KeyRepeatConfig) -> Option<T> {
+        let pressed = current.filter(|&button| self.held != Some(button));
+        self.poll(pressed, |button| current == Some(button), elapsed_ms, config)
+    }
+}
+
+impl KeyRepeat<KeyCombo> {
+    pub fn poll_keys(
+        &mut self,
+        keyboard: &Keyboard,
+        keys: &[KeyCombo],
+        elapsed_ms: f64,
+        config: &KeyRepeatConfig,
+    ) -> Option<KeyCombo> {
+        let pressed = keys.iter().copied().find(|&combo| combo.pressed(keyboard));
+        self.poll(pressed, |combo| combo.is_down(keyboard), elapsed_ms, config)
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{
+    hunger::{self, Hunger},
+    items::ItemDef,
+    light::TORCH_LOW_TURNS,
+    spells::Status,
+    strings::Strings,
+    world::World,
+};
+use quicksilver::geom::Vector;
+
+/// Below this share of max hp, getting hurt gets a more urgent warning.
+const LOW_HP_PERCENT: i32 = 50;
+
+/// Why an auto action stopped before it was finished.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interruption {
+    Hurt,
+    LowHp,
+    HostileSpotted,
+    SteppedOn(char),
+    /// A status the player didn't have when they started came over them.
+    Afflicted(Status),
+    /// The player's torch burned low or went out.
+    TorchLow,
+    /// The player got hungry resting and has nothing left to eat.
+    OutOfFood,
+    Rested,
+    Blocked,
+}
+
+impl Interruption {
+    /// What the player is told when the auto action stops.
+    pub fn message(self, strings: &Strings) -> String {
+        let id = match self {
+            Interruption::Hurt => "interruption.hurt",
+            Interruption::LowHp => "interruption.low_hp",
+            Interruption::HostileSpotted => "interruption.hostile_spotted",
+            Interruption::SteppedOn('%') => "interruption.stepped_on_remains",
+            Interruption::SteppedOn(glyph) => {
+                return strings.format("interruption.stepped_on", &[("glyph", &glyph)]);
+            }
+            Interruption::Afflicted(status) => {
+                return strings.format("interruption.afflicted", &[("status", &status.name(strings))]);
+            }
+            Interruption::TorchLow => "interruption.torch_low",
+            Interruption::OutOfFood => "interruption.out_of_food",
+            Interruption::Rested => "interruption.rested",
+            Interruption::Blocked => "interruption.blocked",
+        };
+        strings.get(id).to_string()
+    }
+}
+
+/// What an auto action keeps an eye on so it can tell when something has changed
+/// that the player should see: their hp, position, statuses and torch, and the
+/// hostiles in view. A rest also stops once the player is healed, or is hungry
+/// with nothing to eat.
+#[derive(Clone, Debug)]
+pub struct Watch {
+    resting: bool,
+    hp: i32,
+    pos: Vector,
+    statuses: Vec<Status>,
+    torch: u32,
+    known_hostiles: Vec<usize>,
+}
+
+impl Watch {
+    pub fn new(world: &World, resting: bool) -> Watch {
+        let player = world.player();
+        Watch {
+            resting,
+            hp: player.hp,
+            pos: player.pos,
+            statuses: player.statuses.iter().map(|effect| effect.status).collect(),
+            torch: world.torch,
+            known_hostiles: world.visible_hostiles(),
+        }
+    }
+
+    /// Why the auto action should stop now, if it should, with `items` being what
+    /// the player's food could be.
+    pub fn check(&mut self, world: &World, items: &[ItemDef]) -> Option<Interruption> {
+        let player = world.player();
+        let last_hp = std::mem::replace(&mut self.hp, player.hp);
+        let last_pos = std::mem::replace(&mut self.pos, player.pos);
+        let last_torch = std::mem::replace(&mut self.torch, world.torch);
+
+        if player.hp < last_hp {
+            return Some(if player.hp * 100 < player.max_hp * LOW_HP_PERCENT {
+                Interruption::LowHp
+            } else {
+                Interruption::Hurt
+            });
+        }
+        if world.visible_hostiles().iter().any(|id| !self.known_hostiles.contains(id)) {
+            return Some(Interruption::HostileSpotted);
+        }
+        let mut statuses = player.statuses.iter().map(|effect| effect.status);
+        if let Some(status) = statuses.find(|status| !self.statuses.contains(status)) {
+            self.statuses.push(status);
+            return Some(Interruption::Afflicted(status));
+        }
+        if (last_torch > TORCH_LOW_TURNS && world.torch <= TORCH_LOW_TURNS) || (last_torch > 0 && world.torch == 0) {
+            return Some(Interruption::TorchLow);
+        }
+        if player.pos != last_pos {
+            let underfoot = world
+                .entities
+                .iter()
+                .enumerate()
+                .find(|&(id, entity)| id != world.player_id && entity.pos == player.pos);
+            if let Some((_, entity)) = underfoot {
+                return Some(Interruption::SteppedOn(entity.glyph));
+            }
+        }
+        if !self.resting {
+            return None;
+        }
+        if player.hp >= player.max_hp && player.mana >= player.max_mana {
+            return Some(Interruption::Rested);
+        }
+        let hungry = Hunger::of(world.satiety) != Hunger::Fed;
+        if hungry && hunger::meal(world, items).is_none() {
+            return Some(Interruption::OutOfFood);
+        }
+        None
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{alchemy::Essence, data, spells::StatusEffect, strings::Strings, timers::Timed, world::Entity};
+use quicksilver::{geom::Vector, graphics::Color};
+use serde::{Deserialize, Serialize};
+
+pub const ITEMS_FILE: &str = "items.json";
+
+/// The definitions the game was built with, for when the file can't be read.
+const BUILT_IN: &str = include_str!("../static/items.json");
+
+/// Where an item is worn or held, for items that aren't used up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum Slot {
+    Weapon,
+    Body,
+    Feet,
+}
+
+/// What an item does for whoever uses or equips it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ItemEffect {
+    Heal(i32),
+    Attack(i32),
+    Defense(i32),
+    Mana(i32),
+    /// Puts a status on whoever uses it, for better or worse.
+    Afflict(StatusEffect),
+    /// Takes every status off whoever uses it.
+    Cure,
+    /// Puts `effect` on whoever uses it for `turns` turns.
+    Lasting { effect: Timed, turns: u32 },
+    /// Keeps the player's torch burning this many turns longer.
+    Fuel(u32),
+    /// Keeps the player from starving this many turns longer.
+    Nourish(u32),
+}
+
+/// One kind of item, as written in `static/items.json`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ItemDef {
+    pub name: String,
+    pub glyph: char,
+    pub color: Color,
+    #[serde(default)]
+    pub slot: Option<Slot>,
+    #[serde(default)]
+    pub effects: Vec<ItemEffect>,
+    pub value: u32,
+    pub weight: u32,
+    /// Legendary items never turn up by chance, only as named one-offs.
+    #[serde(default)]
+    pub legendary: bool,
+    /// The spell, by name from the spell file, that whoever carries the item can
+    /// cast without mana. Items with a power are artifacts, which turn up at most
+    /// once a run.
+    #[serde(default)]
+    pub power: Option<String>,
+    /// Potions look alike until the player drinks one of the kind.
+    #[serde(default)]
+    pub potion: bool,
+    /// What the item brings to a mixture, for potions and anything else that can
+    /// be mixed.
+    #[serde(default)]
+    pub essences: Vec<Essence>,
+    /// Keys open the locked doors prefabs draw with their name, and are used up
+    /// doing it.
+    #[serde(default)]
+    pub key: bool,
+}
+
+impl ItemDef {
+    pub fn entity(&self, pos: Vector) -> Entity {
+        Entity {
+            item: Some(self.name.clone()),
+            ..Entity::new(pos, self.glyph, self.color, 0)
+        }
+    }
+
+    pub fn is_artifact(&self) -> bool {
+        self.power.is_some()
+    }
+
+    /// Whether the item is used up for its effects, rather than worn or held.
+    pub fn is_usable(&self) -> bool {
+        self.slot.is_none() && !self.effects.is_empty()
+    }
+
+    /// A one-line summary, such as "blade (weapon, +2 attack), weight 3, worth 200".
+    pub fn describe(&self, strings: &Strings) -> String {
+        let slot = self.slot.map(|slot| match slot {
+            Slot::Weapon => "slot.weapon",
+            Slot::Body => "slot.body",
+            Slot::Feet => "slot.feet",
+        });
+        let mut details: Vec<String> = slot.iter().map(|&id| strings.get(id).to_string()).collect();
+        details.extend(self.effects.iter().map(|effect| {
+            let (id, amount) = match *effect {
+                ItemEffect::Heal(hp) => ("effect.heal", hp),
+                ItemEffect::Attack(bonus) => ("effect.attack", bonus),
+                ItemEffect::Defense(bonus) => ("effect.defense", bonus),
+                ItemEffect::Mana(mana) => ("effect.mana", mana),
+                ItemEffect::Afflict(effect) => {
+                    let status = effect.status.name(strings);
+                    return strings.format("spell.status", &[("status", &status), ("turns", &effect.turns)]);
+                }
+                ItemEffect::Cure => return strings.get("effect.cure").to_string(),
+                ItemEffect::Lasting { effect, turns } => {
+                    let effect = effect.describe(strings);
+                    return strings.format("effect.lasting", &[("effect", &effect), ("turns", &turns)]);
+                }
+                ItemEffect::Fuel(turns) => return strings.format("effect.fuel", &[("turns", &turns)]),
+                ItemEffect::Nourish(turns) => return strings.format("effect.nourish", &[("turns", &turns)]),
+            };
+            strings.format(id, &[("amount", &amount)])
+        }));
+        if let Some(spell) = &self.power {
+            details.push(strings.format("effect.power", &[("spell", &strings.name("spell", spell))]));
+        }
+        let details = if details.is_empty() {
+            String::new()
+        } else {
+            strings.format("item.details", &[("details", &details.join(", "))])
+        };
+        strings.format(
+            "item.description",
+            &[
+                ("item", &strings.name("item", &self.name)),
+                ("details", &details),
+                ("weight", &self.weight),
+                ("value", &self.value),
+            ],
+        )
+    }
+}
+
+impl data::Named for ItemDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// The definition an item entity was made from.
+pub fn find<'a>(items: &'a [ItemDef], name: &str) -> Option<&'a ItemDef> {
+    items.iter().find(|item| item.name == name)
+}
+
+/// Read the item definitions, so new items can be added by editing the file or in
+/// a mod.
+pub fn load(problems: &mut Vec<data::Problem>) -> Vec<ItemDef> {
+    data::load_defs(ITEMS_FILE, BUILT_IN, problems)
+}
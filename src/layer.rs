@@ -0,0 +1,33 @@
+use crate::world::Entity;
+
+/// What gets drawn over what, from the bottom up. Everything on a higher layer
+/// covers everything on a lower one, whatever order it was drawn in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Layer {
+    Terrain,
+    /// Marks on the floor, such as the remains of slain creatures.
+    Decals,
+    /// Things lying around to be picked up.
+    Items,
+    Actors,
+    /// Short-lived visual effects like hit flashes.
+    Effects,
+    Ui,
+}
+
+impl Layer {
+    pub fn for_entity(entity: &Entity) -> Layer {
+        if entity.is_alive() || entity.boulder {
+            Layer::Actors
+        } else if entity.max_hp > 0 {
+            Layer::Decals
+        } else {
+            Layer::Items
+        }
+    }
+
+    /// The depth quicksilver sorts triangles by before drawing them.
+    pub fn z(self) -> f32 {
+        self as u8 as f32
+    }
+}
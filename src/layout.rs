@@ -0,0 +1,63 @@
+use quicksilver::geom::{Rectangle, Vector};
+
+/// Room kept above the map for the title.
+const TOP_PX: f32 = 80.0;
+/// Room kept below the map for the message line and font credits.
+const BOTTOM_PX: f32 = 100.0;
+/// Room kept to the right of the map for the health bar.
+const RIGHT_PX: f32 = 120.0;
+const MARGIN_PX: f32 = 20.0;
+/// The screen height tiles are drawn at their natural size on; taller screens
+/// scale them up in quarter steps.
+const BASE_HEIGHT_PX: f32 = 600.0;
+
+/// Where everything goes on a screen of a given size, worked out again whenever
+/// the window is resized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Layout {
+    pub screen_size: Vector,
+    pub tile_size_px: Vector,
+    pub title_center: Vector,
+    pub credits_pos: [Vector; 2],
+    pub panel: Rectangle,
+    map_area: Rectangle,
+}
+
+impl Layout {
+    pub fn new(screen_size: Vector, base_tile_size_px: Vector) -> Layout {
+        let scale = ((screen_size.y / BASE_HEIGHT_PX * 4.0).floor() / 4.0).clamp(0.75, 3.0);
+        let map_area = Rectangle::new(
+            (MARGIN_PX, TOP_PX),
+            (
+                (screen_size.x - MARGIN_PX - RIGHT_PX).max(0.0),
+                (screen_size.y - TOP_PX - BOTTOM_PX).max(0.0),
+            ),
+        );
+        Layout {
+            screen_size,
+            tile_size_px: base_tile_size_px * scale,
+            title_center: Vector::new(screen_size.x / 2.0, 40.0),
+            credits_pos: [
+                Vector::new(2.0, screen_size.y - 60.0),
+                Vector::new(2.0, screen_size.y - 30.0),
+            ],
+            panel: Rectangle::new((MARGIN_PX, TOP_PX), (screen_size.x - 2.0 * MARGIN_PX, 0.0)),
+            map_area,
+        }
+    }
+
+    /// How many whole tiles fit in the space for the map.
+    pub fn view_size(&self) -> Vector {
+        let size = self.map_area.size();
+        Vector::new(
+            (size.x / self.tile_size_px.x).floor().max(1.0),
+            (size.y / self.tile_size_px.y).floor().max(1.0),
+        )
+    }
+
+    /// The top left of a map view of `view_size_px`, centered in the space for the map.
+    pub fn map_offset(&self, view_size_px: Vector) -> Vector {
+        let pos = self.map_area.top_left() + (self.map_area.size() - view_size_px) / 2.0;
+        Vector::new(pos.x.floor(), pos.y.floor())
+    }
+}
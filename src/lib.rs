@@ -0,0 +1,55 @@
+//! The game itself, apart from the window: the world and its rules, level
+//! generation and the data files. The binary draws it and takes input; keeping
+//! the two apart lets tests build a world and play it without a window.
+
+pub mod alchemy;
+pub mod animation;
+pub mod attributes;
+pub mod boulders;
+pub mod branches;
+pub mod classes;
+pub mod crafting;
+pub mod data;
+pub mod decorate;
+pub mod destructibles;
+pub mod dialogue;
+pub mod dungeon;
+#[cfg(any(test, feature = "test-fixtures"))]
+pub mod fixture;
+pub mod foliage;
+pub mod fov;
+pub mod glyphs;
+pub mod gods;
+pub mod happenings;
+pub mod hazards;
+pub mod hunger;
+pub mod interrupt;
+pub mod items;
+pub mod light;
+pub mod locks;
+pub mod loot;
+pub mod mechanisms;
+pub mod monsters;
+pub mod names;
+pub mod path;
+pub mod perks;
+pub mod portals;
+pub mod prefab;
+pub mod profile;
+pub mod quests;
+pub mod races;
+pub mod replay;
+pub mod rng;
+pub mod save;
+pub mod shops;
+pub mod shrines;
+pub mod snapshot;
+pub mod spawn;
+pub mod specials;
+pub mod spells;
+pub mod strings;
+pub mod timers;
+pub mod validate;
+pub mod weather;
+pub mod wizard;
+pub mod world;
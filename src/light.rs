@@ -0,0 +1,73 @@
+use crate::{
+    fov,
+    timers::Timed,
+    world::{Tile, World},
+};
+use quicksilver::{geom::Vector, graphics::Color};
+
+/// How far the light the player carries reaches, in tiles.
+pub const PLAYER_LIGHT_RADIUS: i32 = 5;
+
+/// How many turns the torch the player starts out with burns for.
+pub const TORCH_TURNS: u32 = 1500;
+
+/// The most turns a torch can be topped up to.
+pub const MAX_TORCH_TURNS: u32 = 3000;
+
+/// How many turns the torch has left when the player is warned it's burning low.
+pub const TORCH_LOW_TURNS: u32 = 100;
+
+/// How far the player can see with no light of their own.
+pub const DARK_SIGHT_RADIUS: i32 = 2;
+
+/// The light level of a tile no light source reaches. Still above zero, so dark
+/// tiles in view can be made out.
+const AMBIENT_LIGHT: f32 = 0.3;
+
+/// The light level of every tile, from `AMBIENT_LIGHT` to 1, indexed like the map.
+///
+/// The player while their torch burns, any tile with a `light_radius` and anything
+/// with a timed light on it give off light that fades with distance and is blocked
+/// by walls like sight is.
+pub fn compute(world: &World) -> Vec<f32> {
+    let lights = world.timers.iter().filter_map(|timer| match timer.effect {
+        Timed::Light(radius) if world.entities[timer.entity].is_alive() => {
+            Some((world.entities[timer.entity].pos, radius as i32))
+        }
+        _ => None,
+    });
+    let sources = world
+        .map
+        .iter()
+        .filter(|tile| tile.light_radius > 0)
+        .map(|tile| (tile.pos, tile.light_radius as i32))
+        .chain((world.torch > 0).then(|| (world.player().pos, PLAYER_LIGHT_RADIUS)))
+        .chain(lights);
+
+    let height = world.map_size.y as usize;
+    let mut light = vec![0.0_f32; world.map.len()];
+    for (origin, radius) in sources {
+        let lit = fov::compute(origin, radius, world.map_size, |pos| {
+            world.tile_at(pos).is_some_and(Tile::is_solid)
+        });
+        for (index, _) in lit.iter().enumerate().filter(|&(_, &lit)| lit) {
+            let pos = Vector::new((index / height) as f32, (index % height) as f32);
+            let level = 1.0 - pos.distance(origin) / (radius + 1) as f32;
+            light[index] = light[index].max(level);
+        }
+    }
+    light
+        .into_iter()
+        .map(|level| AMBIENT_LIGHT + (1.0 - AMBIENT_LIGHT) * level)
+        .collect()
+}
+
+/// Darken `color` to how it looks under `level` light.
+pub fn tint(color: Color, level: f32) -> Color {
+    Color {
+        r: color.r * level,
+        g: color.g * level,
+        b: color.b * level,
+        a: color.a,
+    }
+}
@@ -0,0 +1,45 @@
+use crate::world::{Entity, World};
+use log::debug;
+use quicksilver::geom::Vector;
+use serde::{Deserialize, Serialize};
+
+/// A locked door on a level, drawn as `+`, which stops creatures, sight and light
+/// until it's opened.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Lock {
+    pub pos: Vector,
+    /// The item of this name from the item file opens it.
+    pub key: String,
+}
+
+/// Put `key` somewhere on `world` the player can walk to from where they are
+/// without going through a locked door, so every key can be found before the door
+/// it opens. It goes at the player's feet if there's nowhere else.
+pub fn place_key(world: &mut World, key: Entity) {
+    let start = world.player().pos;
+    let mut reached = vec![start];
+    let mut next = 0;
+    while next < reached.len() {
+        let pos = reached[next];
+        next += 1;
+        for delta in [Vector::new(1, 0), Vector::new(-1, 0), Vector::new(0, 1), Vector::new(0, -1)] {
+            let step = pos + delta;
+            if world.is_walkable(step) && !reached.contains(&step) {
+                reached.push(step);
+            }
+        }
+    }
+    let free = |pos: &Vector| {
+        *pos != start
+            && world.tile_at(*pos).is_some_and(|tile| tile.glyph == '.')
+            && world.entities.iter().all(|entity| entity.pos != *pos)
+    };
+    let spots: Vec<Vector> = reached.into_iter().filter(free).collect();
+    let pos = if spots.is_empty() {
+        start
+    } else {
+        spots[world.rng.range(0, spots.len() as u32) as usize]
+    };
+    debug!(target: "generate", item = key.item.as_deref().unwrap_or(""), x = pos.x, y = pos.y; "placed a key");
+    world.entities.push(Entity { pos, ..key });
+}
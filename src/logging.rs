@@ -0,0 +1,121 @@
+use log::{
+    kv::{self, Key, Value, VisitSource},
+    LevelFilter, Log, Metadata, Record,
+};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    str::FromStr,
+    sync::Mutex,
+    time::Instant,
+};
+
+/// Turns logging on, with a level for everything like `debug`, or levels for some
+/// targets like `ai=trace,combat=debug`, or both. The targets are `generate` for
+/// level generation, `ai` for what monsters decide and `combat` for hits and deaths.
+const LEVEL_VAR: &str = "ROGUE_LOG";
+
+/// Where the log is written instead of `LOG_FILE`.
+const FILE_VAR: &str = "ROGUE_LOG_FILE";
+
+/// Where the log is written, relative to where the game was started.
+const LOG_FILE: &str = "rogue-like.log";
+
+/// Writes log records to a file as one line each: the time since the game started,
+/// the level, the target and message, then the record's fields as `key=value` so
+/// the file can be searched and filtered.
+struct FileLogger {
+    start: Instant,
+    default: LevelFilter,
+    targets: Vec<(String, LevelFilter)>,
+    file: Mutex<BufWriter<File>>,
+}
+
+impl FileLogger {
+    fn level(&self, target: &str) -> LevelFilter {
+        self.targets
+            .iter()
+            .find(|(name, _)| target.starts_with(name.as_str()))
+            .map_or(self.default, |&(_, level)| level)
+    }
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let mut line = format!(
+            "{:>9.3} {:<5} {}: {}",
+            self.start.elapsed().as_secs_f64(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        let _ = record.key_values().visit(&mut Fields(&mut line));
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(file, "{}", line);
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// Appends a record's fields to its line.
+struct Fields<'a>(&'a mut String);
+
+impl<'kvs> VisitSource<'kvs> for Fields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        self.0.push_str(&format!(" {}={}", key, value));
+        Ok(())
+    }
+}
+
+/// Start logging if `ROGUE_LOG` asks for it. Without it nothing is logged and no
+/// file is made.
+pub fn init() {
+    let spec = match std::env::var(LEVEL_VAR) {
+        Ok(spec) => spec,
+        Err(_) => return,
+    };
+    let mut default = LevelFilter::Off;
+    let mut targets = Vec::new();
+    for directive in spec.split(',').map(str::trim).filter(|directive| !directive.is_empty()) {
+        let (target, level) = match directive.split_once('=') {
+            Some((target, level)) => (Some(target), level),
+            None => (None, directive),
+        };
+        match (target, LevelFilter::from_str(level)) {
+            (Some(target), Ok(level)) => targets.push((target.to_string(), level)),
+            (None, Ok(level)) => default = level,
+            (_, Err(_)) => eprintln!("{}: unknown log level '{}', so it's left out", LEVEL_VAR, level),
+        }
+    }
+    let max = targets.iter().map(|&(_, level)| level).fold(default, std::cmp::max);
+
+    let path = std::env::var(FILE_VAR).unwrap_or_else(|_| LOG_FILE.to_string());
+    let file = match File::create(&path) {
+        Ok(file) => file,
+        Err(err) => {
+            eprintln!("Couldn't create the log file {}: {}", path, err);
+            return;
+        }
+    };
+    let logger = FileLogger {
+        start: Instant::now(),
+        default,
+        targets,
+        file: Mutex::new(BufWriter::new(file)),
+    };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(max);
+    }
+}
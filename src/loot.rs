@@ -0,0 +1,166 @@
+use crate::{
+    data::{self, Problem, SpawnRange},
+    items::{self, ItemDef},
+    monsters::{self, MonsterDef},
+    rng::Rng,
+    world::Biome,
+};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+pub const LOOT_FILE: &str = "loot.json";
+
+/// The tables the game was built with, for when the file can't be read.
+const BUILT_IN: &str = include_str!("../static/loot.json");
+
+/// A monster or item by name, and the depths it turns up on.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SpawnEntry {
+    pub id: String,
+    pub spawns: Vec<SpawnRange>,
+}
+
+impl data::Named for SpawnEntry {
+    fn name(&self) -> &str {
+        &self.id
+    }
+}
+
+/// An item a monster may be carrying, and how likely it is to have one.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Drop {
+    pub item: String,
+    pub chance: f32,
+}
+
+/// What spawns where and what monsters drop, as written in `static/loot.json`.
+/// Balancing the game is a matter of editing this file. A mod's copy only needs
+/// the entries it changes or adds.
+#[derive(Clone, Debug, Deserialize)]
+pub struct LootTables {
+    #[serde(default)]
+    pub monsters: Vec<SpawnEntry>,
+    /// Legendary items can't go in here; they only ever turn up as named one-offs.
+    #[serde(default)]
+    pub items: Vec<SpawnEntry>,
+    /// Each monster's possible drops, tried in order until one comes up.
+    #[serde(default)]
+    pub drops: BTreeMap<String, Vec<Drop>>,
+    /// How many turns go by between monsters coming back to a level of each biome.
+    /// Monsters never come back to a biome left out.
+    #[serde(default)]
+    pub respawns: HashMap<Biome, usize>,
+}
+
+impl LootTables {
+    /// Take a mod's tables on top of these. Its spawn entries replace any for the
+    /// same monster or item, and its drops replace a monster's whole drop list.
+    fn merge(&mut self, tables: LootTables) {
+        data::merge(&mut self.monsters, tables.monsters);
+        data::merge(&mut self.items, tables.items);
+        self.drops.extend(tables.drops);
+        self.respawns.extend(tables.respawns);
+    }
+
+    /// The monsters that can spawn at `depth`, each with how likely it is.
+    pub fn monsters_at<'a>(&self, monsters: &'a [MonsterDef], depth: u32) -> Vec<(&'a MonsterDef, u32)> {
+        self.monsters
+            .iter()
+            .filter_map(|entry| Some((monsters::find(monsters, &entry.id)?, data::weight_at(&entry.spawns, depth))))
+            .collect()
+    }
+
+    /// The items that can turn up lying around at `depth`, each with how likely it is.
+    pub fn items_at<'a>(&self, items: &'a [ItemDef], depth: u32) -> Vec<(&'a ItemDef, u32)> {
+        self.items
+            .iter()
+            .filter_map(|entry| Some((items::find(items, &entry.id)?, data::weight_at(&entry.spawns, depth))))
+            .collect()
+    }
+
+    /// What a newly spawned `monster` is carrying, if anything.
+    pub fn roll_drop<'a>(&self, monster: &str, items: &'a [ItemDef], rng: &mut Rng) -> Option<&'a ItemDef> {
+        let drop = self.drops.get(monster)?.iter().find(|drop| rng.next_f32() < drop.chance)?;
+        items::find(items, &drop.item)
+    }
+
+    /// Check that every name in the tables is a monster or item that exists, so a
+    /// typo shows up at startup instead of as a monster that never spawns.
+    pub fn validate(&self, monsters: &[MonsterDef], items: &[ItemDef]) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        let about = |section: &str, name: &str, message: String| Problem::about(LOOT_FILE, section, name, message);
+        for entry in &self.monsters {
+            if monsters::find(monsters, &entry.id).is_none() {
+                problems.push(about("monsters", &entry.id, format!("unknown monster '{}'", entry.id)));
+            }
+            problems.extend(check_spawns(&entry.id, &entry.spawns).map(|message| about("monsters", &entry.id, message)));
+        }
+        for entry in &self.items {
+            match items::find(items, &entry.id) {
+                None => problems.push(about("items", &entry.id, format!("unknown item '{}'", entry.id))),
+                Some(item) if item.legendary => problems.push(about(
+                    "items",
+                    &entry.id,
+                    format!("'{}' is legendary and can't spawn by chance", entry.id),
+                )),
+                Some(_) => (),
+            }
+            problems.extend(check_spawns(&entry.id, &entry.spawns).map(|message| about("items", &entry.id, message)));
+        }
+        for (monster, drops) in &self.drops {
+            if monsters::find(monsters, monster).is_none() {
+                problems.push(about("drops", monster, format!("drops for unknown monster '{}'", monster)));
+            }
+            for drop in drops {
+                if items::find(items, &drop.item).is_none() {
+                    problems.push(about("drops", monster, format!("{} drops unknown item '{}'", monster, drop.item)));
+                }
+                if !(0.0..=1.0).contains(&drop.chance) {
+                    let message = format!("{} drops {} with chance {}", monster, drop.item, drop.chance);
+                    problems.push(about("drops", monster, message));
+                }
+            }
+        }
+        for (biome, &turns) in &self.respawns {
+            if turns == 0 {
+                let message = format!("monsters come back to the {:?} every 0 turns; leave it out instead", biome);
+                problems.push(about("respawns", &format!("{:?}", biome), message));
+            }
+        }
+        problems
+    }
+}
+
+fn check_spawns(id: &str, spawns: &[SpawnRange]) -> Option<String> {
+    spawns
+        .iter()
+        .find(|range| range.max_depth.is_some_and(|max| max < range.min_depth))
+        .map(|range| format!("{} spawns from depth {} to {:?}", id, range.min_depth, range.max_depth))
+}
+
+/// Read the loot tables along with every mod's, without falling back on failure.
+#[cfg(feature = "hot-reload")]
+pub fn try_load() -> Result<LootTables, Problem> {
+    let mut tables: LootTables = data::try_load(LOOT_FILE)?;
+    for path in data::mod_files(LOOT_FILE) {
+        tables.merge(data::try_load(&path)?);
+    }
+    Ok(tables)
+}
+
+/// Like `try_load`, falling back to the built-in tables if the file is missing or
+/// broken and skipping broken mods. Anything the tables name that doesn't exist
+/// just never turns up, so that's left for `validate` to point out.
+pub fn load(problems: &mut Vec<Problem>) -> LootTables {
+    let mut tables: LootTables = data::load(LOOT_FILE, BUILT_IN, problems);
+    for path in data::mod_files(LOOT_FILE) {
+        match data::try_load(&path) {
+            Ok(mod_tables) => tables.merge(mod_tables),
+            Err(mut problem) => {
+                problem.message += ", so the mod's copy is skipped";
+                problems.push(problem);
+            }
+        }
+    }
+    tables
+}
@@ -1,34 +1,169 @@
 use quicksilver::prelude::*;
-use std::collections::HashMap;
+use std::collections::BTreeSet;
 
-#[derive(Clone, Debug, PartialEq)]
-struct Tile {
-    pos: Vector,
-    glyph: char,
-    color: Color,
+use crate::{
+    effect::Effect,
+    input::Action,
+    layer::Layer,
+    world::{Entity, World},
+};
+use rogue_like::{
+    alchemy, animation, attributes, branches, classes, crafting, data, dialogue, dungeon, glyphs, gods, happenings,
+    hazards, hunger, interrupt, items, light, names, path, perks, prefab, profile, quests, races, replay, rng, save,
+    shops, shrines, snapshot, spawn, specials, spells, strings, timers, weather, wizard, world,
+};
+
+mod audio;
+mod auto;
+mod batch;
+mod bench;
+mod camera;
+mod config;
+mod debug;
+mod editor;
+mod effect;
+mod feeling;
+mod input;
+mod layer;
+mod layout;
+mod logging;
+mod morgue;
+mod palette;
+mod particles;
+mod projectile;
+mod quest_log;
+mod screenshot;
+mod settings;
+mod sheet;
+mod theme;
+mod tileset;
+
+
+/// The most times a single repeat command will redo an action.
+const MAX_REPEAT_COUNT: u32 = 999;
+
+/// How long monsters take to respond after the player acts, so both can be seen.
+const MONSTER_TURN_MS: f64 = 60.0;
+
+/// How long a notice such as "Sound off" stays on screen.
+const NOTICE_MS: f64 = 1500.0;
+
+/// Which screen currently owns the keyboard.
+enum Mode {
+    Playing,
+    ConfirmExit,
+    Settings(settings::SettingsMenu),
+    /// Typing how many times to repeat the last action.
+    RepeatCount(u32),
+    Palette(palette::CommandPalette),
+    GameOver,
+    /// Typing the seed for the next run; `None` picks one at random.
+    NewRun(Option<u64>),
+    /// Picking a class for a run from the seed, with the one under the cursor.
+    ChooseClass(u64, usize),
+    /// Picking a race to go with the class, with the one under the cursor.
+    ChooseRace(u64, Option<String>, usize),
+    /// Picking which of the profile's boons to start with, with those picked so far
+    /// and the line under the cursor; the line after the boons starts the run.
+    ChooseBoons(u64, Option<String>, Option<String>, BTreeSet<profile::Unlock>, usize),
+    /// Looking over the player's race, class, stats and equipment.
+    CharacterSheet,
+    /// Looking over the quests the player has taken on.
+    QuestLog,
+    /// Picking a perk after levelling up, with the one under the cursor.
+    ChoosePerk(usize),
+    /// Spending attribute points, with the attribute under the cursor.
+    RaiseAttribute(usize),
+    /// Watching the run that just ended play out again.
+    Replay(replay::Replay),
+    /// Picking a spell to cast, with the one under the cursor.
+    Spellbook(usize),
+    /// Moving the cursor onto where to cast the spell.
+    Aim(Box<spells::SpellDef>, Vector),
+    /// Trading at the counter of the shop with this index, with the trade under
+    /// the cursor.
+    Shop(usize, usize),
+    /// Talking to the NPC with this id, at the dialogue node with this id, with the
+    /// answer under the cursor.
+    Talk(usize, String, usize),
+    /// Making an offering or praying, with the rite under the cursor.
+    Rites(usize),
+    /// Picking something to craft, with the recipe under the cursor.
+    Craft(usize),
+    /// Picking an item to use up, with the one under the cursor.
+    UseItem(usize),
+    /// Picking two things to mix, with the inventory index of the first once it's
+    /// picked and the one under the cursor.
+    Mix(Option<usize>, usize),
+    /// Typing the name of an item to wish for, in wizard mode.
+    Wish(String),
+    /// Drawing a prefab, in wizard mode.
+    Editor(editor::Editor),
 }
 
-#[derive(Clone, Debug, PartialEq)]
-struct Entity {
-    pos: Vector,
-    glyph: char,
-    color: Color,
-    hp: i32,
-    max_hp: i32,
+/// Everything the cached terrain layer depends on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct TerrainKey {
+    camera: camera::Camera,
+    offset_px: Vector,
+    tile_size_px: Vector,
+    theme: theme::Theme,
+    revision: u64,
 }
 
 struct Game {
     title: Asset<Image>,
     mononoki_font_info: Asset<Image>,
     square_font_info: Asset<Image>,
-    map_size: Vector,
-    map: Vec<Tile>,
-    entities: Vec<Entity>,
-    player_id: usize,
-    tileset: Asset<HashMap<char, Image>>,
+    world: World,
+    /// What the player has earned across runs, which decides what new runs can start with.
+    profile: profile::Profile,
+    /// Monster, item, loot and prefab definitions, loaded from data files at startup.
+    content: data::Content,
+    #[cfg(feature = "hot-reload")]
+    data_watcher: data::Watcher,
+    tileset: Asset<tileset::Tileset>,
+    /// The map's still tiles, kept between frames while `terrain_key` stays the same.
+    terrain_batch: batch::GlyphBatch,
+    terrain_key: Option<TerrainKey>,
+    glyph_batch: batch::GlyphBatch,
+    effects: effect::Effects,
+    audio: audio::Audio,
+    weather: particles::WeatherOverlay,
     tile_size_px: Vector,
-    confirming_exit: bool,
-    confirm_exit_font: Asset<Font>,
+    layout: layout::Layout,
+    auto: Option<auto::AutoAction>,
+    /// What the run that just ended unlocked, to tell the player when it's over.
+    unlocked: Vec<profile::Unlock>,
+    /// Drives tile and entity animations; advanced once per frame.
+    animation_clock_ms: f64,
+    /// A one-line note under the map, such as why an auto action stopped.
+    message: Option<String>,
+    /// A short-lived note in the corner of the map, and how long it has left in ms.
+    notice: Option<(String, f64)>,
+    /// Time spent so far in the monster phase, while the player waits for their turn.
+    monster_turn_ms: Option<f64>,
+    /// Time the player has had to act this turn, which in real time mode runs out
+    /// and waits a turn for them.
+    idle_ms: f64,
+    /// A thrown dart or cast spell still in flight; the turn ends when it lands.
+    projectile: Option<projectile::Projectile>,
+    input_buffer: input::InputBuffer,
+    last_action: Option<Action>,
+    mode: Mode,
+    ui_font: Asset<Font>,
+    config: config::Config,
+    move_repeat: input::KeyRepeat<input::KeyCombo>,
+    pad_repeat: input::KeyRepeat<input::Direction>,
+    /// Whether wizard mode's cheats are on offer this session.
+    wizard: bool,
+    debug: debug::DebugOverlay,
+    /// The world at each of the last few turns, kept in wizard mode for the debug
+    /// overlay's turn diff.
+    history: snapshot::History,
+    /// A screenshot was asked for; it's taken at the end of the next draw, once
+    /// the whole frame is there.
+    screenshot: bool,
 }
 
 impl State for Game {
@@ -36,265 +171,2385 @@ impl State for Game {
         let font_mononoki = "mononoki-Regular.ttf";
         let font_square = "square.ttf";
         
-        let confirm_exit_font = Asset::new(Font::load(font_mononoki));
-
-        let map_size = Vector::new(20, 15);
-        let map = generate_map(map_size);
-        let mut entities = generate_entities();
-        let player_id = entities.len();
-        entities.push(Entity {
-            pos: Vector::new(5,3),
-            glyph: '@',
-            color: Color::BLUE,
-            hp: 3,
-            max_hp: 5,
+        let ui_font = Asset::new(Font::load(font_mononoki));
+
+        let config = config::Config::load();
+        let content = data::Content::load(config.language.as_deref(), config.cp437_tileset.is_some());
+        let saved = save::load().map_err(|err| match err {
+            save::SaveError::NotFound => {}
+            err => eprintln!("Starting a new game: {}", err),
         });
-        
-        let game_glyphs = "#@g.%";
+        // Without a save, a level from the run's seed sits behind the class menu
+        // until the player has picked a class and the run really starts
+        let (world, mode) = match saved {
+            Ok(world) => (world, Mode::Playing),
+            Err(()) => {
+                let seed = rng::random_seed();
+                let backdrop = generate_run(seed, &BTreeSet::new(), None, None, &content);
+                (backdrop, Mode::ChooseClass(seed, 0))
+            }
+        };
+        // Only a level the player hasn't done anything on yet gets its hint
+        let message = world.log.is_empty().then(|| feeling::describe(&world, &content.strings)).flatten();
+
+        // The most common glyphs; anything else is rendered the first time it is drawn
+        let game_glyphs = "#@g.%<>~";
         let tile_size_px = Vector::new(24,24);
         
-        let tileset = Asset::new(Font::load(font_square).and_then(move |font| {
-            let tiles = font.render(game_glyphs, &FontStyle::new(tile_size_px.y, Color::WHITE))?;
-            let mut tileset = HashMap::new();
-            for (index, glyph) in game_glyphs.chars().enumerate() {
-                let pos = (index as i32 * tile_size_px.x as i32, 0);
-                let tile = tiles.subimage(Rectangle::new(pos, tile_size_px));
-                tileset.insert(glyph, tile);
-            }
-            Ok(tileset)
-        }));
+        let tileset = match &config.cp437_tileset {
+            Some(path) => tileset::from_cp437(path.clone()),
+            None => tileset::from_font(font_square, game_glyphs, tile_size_px),
+        };
 
-        let title = Asset::new(Font::load(font_mononoki).and_then(|font| {
-            font.render("Rogue Like", &FontStyle::new(72.0, Color::BLACK))
+        let strings = &content.strings;
+        let title_text = strings.get("title").to_string();
+        let title = Asset::new(Font::load(font_mononoki).and_then(move |font| {
+            font.render(&title_text, &FontStyle::new(72.0, Color::WHITE))
         }));
 
-        let mononoki_font_info = Asset::new(Font::load(font_mononoki).and_then(|font| {
-            font.render(
-                "Mononoki font by Matthias Tellen, terms: SIL Open Font License 1.1",
-                &FontStyle::new(20.0, Color::BLACK),
-            )
+        let mononoki_credits = strings.get("credits.mononoki").to_string();
+        let mononoki_font_info = Asset::new(Font::load(font_mononoki).and_then(move |font| {
+            font.render(&mononoki_credits, &FontStyle::new(20.0, Color::WHITE))
         }));
 
+        let square_credits = strings.get("credits.square").to_string();
         let square_font_info = Asset::new(Font::load(font_square).and_then(move |font| {
-            font.render(
-                "Square font by Wouter Van Oortmerssen, terms: CC BY 3.0",
-                &FontStyle::new(20.0, Color::BLACK),
-            )
+            font.render(&square_credits, &FontStyle::new(20.0, Color::WHITE))
         }));
 
         Ok(Self {
             title,
             mononoki_font_info,
             square_font_info,
-            map_size,
-            map,
-            entities,
-            player_id,
+            world,
+            profile: profile::Profile::load(),
+            #[cfg(feature = "hot-reload")]
+            data_watcher: data::Watcher::new(content.files()),
+            content,
             tileset,
+            terrain_batch: batch::GlyphBatch::new(),
+            terrain_key: None,
+            glyph_batch: batch::GlyphBatch::new(),
+            effects: effect::Effects::default(),
+            audio: audio::Audio::new(),
+            weather: particles::WeatherOverlay::new(),
             tile_size_px,
-            confirming_exit: false,
-            confirm_exit_font,
+            layout: layout::Layout::new(Vector::ZERO, tile_size_px),
+            auto: None,
+            unlocked: Vec::new(),
+            animation_clock_ms: 0.0,
+            message,
+            notice: None,
+            monster_turn_ms: None,
+            idle_ms: 0.0,
+            projectile: None,
+            input_buffer: input::InputBuffer::default(),
+            last_action: None,
+            mode,
+            ui_font,
+            config,
+            move_repeat: input::KeyRepeat::default(),
+            pad_repeat: input::KeyRepeat::default(),
+            wizard: wizard::enabled(),
+            debug: debug::DebugOverlay::default(),
+            history: snapshot::History::default(),
+            screenshot: false,
         })
     }
 
     fn update(&mut self, window: &mut Window) -> Result<()> {
-        // Handle exit confirmation
-        use ButtonState::*;
+        self.update_hotkeys(window);
+        self.refresh_layout(window);
+        if self.wizard && matches!(self.mode, Mode::Playing) {
+            self.history.record(&self.world);
+        }
+        #[cfg(feature = "hot-reload")]
+        self.reload_data(window.update_rate());
+        let keyboard = window.keyboard();
+        let bindings = &self.config.bindings;
+        let controls = input::Controls::new(keyboard, bindings, window.gamepads());
+        let mouse = window.mouse();
+        let mouse_tile = self.screen_to_map(mouse.pos());
 
-        if self.confirming_exit {
-            if window.keyboard()[Key::Y] == Pressed {
-                window.close();
-            } else if window.keyboard()[Key::N] == Pressed || window.keyboard()[Key::Escape] == Pressed {
-                self.confirming_exit = false;
+        match &mut self.mode {
+            // Handle exit confirmation
+            Mode::ConfirmExit => {
+                if controls.pressed(Action::Confirm) {
+                    if let Err(err) = save::save(&self.world) {
+                        eprintln!("Failed to save the game: {}", err);
+                    }
+                    window.close();
+                } else if controls.pressed(Action::Cancel) {
+                    self.mode = Mode::Playing;
+                }
+            }
+            Mode::Settings(menu) => {
+                let selected = menu.selected();
+                if !menu.update(keyboard, window.gamepads(), &mut self.config) {
+                    self.mode = Mode::Playing;
+                } else if menu.selected() != selected {
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                }
+            }
+            Mode::RepeatCount(count) => {
+                if let Some(digit) = input::typed_digit(keyboard) {
+                    *count = (*count * 10 + digit).min(MAX_REPEAT_COUNT);
+                } else if keyboard[Key::Back] == ButtonState::Pressed {
+                    *count /= 10;
+                } else if controls.pressed(Action::Confirm) {
+                    let times = (*count).max(1);
+                    self.mode = Mode::Playing;
+                    if let Some(action) = self.last_action {
+                        self.start_auto(auto::Plan::Repeat {
+                            action,
+                            times_left: times,
+                        });
+                    }
+                } else if controls.pressed(Action::Cancel) {
+                    self.mode = Mode::Playing;
+                }
+            }
+            Mode::Palette(palette) => {
+                let selected = palette.selected();
+                match palette.update(keyboard, &self.content.strings) {
+                    palette::PaletteResult::Open => {
+                        if palette.selected() != selected {
+                            self.audio.play(audio::SoundEvent::MenuMove);
+                        }
+                    }
+                    palette::PaletteResult::Closed => self.mode = Mode::Playing,
+                    palette::PaletteResult::Chosen(action) => {
+                        self.mode = Mode::Playing;
+                        self.run_command(action);
+                    }
+                }
+            }
+            Mode::Spellbook(selected) => {
+                let castable = spells::castable(&self.content.spells, &self.content.items, self.world.player());
+                let last = castable.len().saturating_sub(1);
+                if controls.pressed(Action::Move(input::Direction::North)) && *selected > 0 {
+                    *selected -= 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Move(input::Direction::South)) && *selected < last {
+                    *selected += 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Confirm) {
+                    let index = *selected;
+                    self.mode = Mode::Playing;
+                    self.cast(index);
+                } else if controls.pressed(Action::Cancel) {
+                    self.mode = Mode::Playing;
+                }
+            }
+            Mode::Aim(spell, target) => {
+                let step = input::Direction::ALL.into_iter().find(|&dir| controls.pressed(Action::Move(dir)));
+                if let Some(dir) = step {
+                    let next = *target + dir.delta();
+                    if self.world.tile_at(next).is_some() {
+                        *target = next;
+                    }
+                } else if controls.pressed(Action::Confirm) || controls.pressed(Action::Cast) {
+                    let (spell, target) = (spell.clone(), *target);
+                    self.mode = Mode::Playing;
+                    self.release(*spell, target);
+                } else if controls.pressed(Action::Cancel) {
+                    self.mode = Mode::Playing;
+                }
+            }
+            Mode::Shop(shop, selected) => {
+                let trades = shops::trades(&self.world, *shop, &self.content.items);
+                let last = trades.len().saturating_sub(1);
+                *selected = (*selected).min(last);
+                if controls.pressed(Action::Move(input::Direction::North)) && *selected > 0 {
+                    *selected -= 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Move(input::Direction::South)) && *selected < last {
+                    *selected += 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Confirm) {
+                    if let Some(&trade) = trades.get(*selected) {
+                        if !self.world.trade(*shop, trade) {
+                            self.message = Some(self.content.strings.get("message.cant_afford").to_string());
+                        }
+                    }
+                } else if controls.pressed(Action::Cancel) {
+                    self.mode = Mode::Playing;
+                }
+            }
+            Mode::Rites(selected) => {
+                let rites = gods::rites(&self.world, &self.content.gods, &self.content.items);
+                let last = rites.len().saturating_sub(1);
+                *selected = (*selected).min(last);
+                if controls.pressed(Action::Move(input::Direction::North)) && *selected > 0 {
+                    *selected -= 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Move(input::Direction::South)) && *selected < last {
+                    *selected += 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Confirm) {
+                    let rite = rites.get(*selected).copied();
+                    self.mode = Mode::Playing;
+                    if let Some(rite) = rite {
+                        self.perform_rite(rite);
+                    }
+                } else if controls.pressed(Action::Cancel) {
+                    self.mode = Mode::Playing;
+                }
+            }
+            Mode::Craft(selected) => {
+                let last = self.content.recipes.len().saturating_sub(1);
+                if controls.pressed(Action::Move(input::Direction::North)) && *selected > 0 {
+                    *selected -= 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Move(input::Direction::South)) && *selected < last {
+                    *selected += 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Confirm) {
+                    let index = *selected;
+                    self.mode = Mode::Playing;
+                    self.craft(index);
+                } else if controls.pressed(Action::Cancel) {
+                    self.mode = Mode::Playing;
+                }
+            }
+            Mode::UseItem(selected) => {
+                let usable = alchemy::usable(self.world.player(), &self.content.items);
+                let last = usable.len().saturating_sub(1);
+                *selected = (*selected).min(last);
+                if controls.pressed(Action::Move(input::Direction::North)) && *selected > 0 {
+                    *selected -= 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Move(input::Direction::South)) && *selected < last {
+                    *selected += 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Confirm) {
+                    let index = usable.get(*selected).copied();
+                    self.mode = Mode::Playing;
+                    if let Some(index) = index {
+                        self.use_item(index);
+                    }
+                } else if controls.pressed(Action::Cancel) {
+                    self.mode = Mode::Playing;
+                }
+            }
+            Mode::Mix(first, selected) => {
+                let mixable = alchemy::mixable(self.world.player(), &self.content.items, *first);
+                let last = mixable.len().saturating_sub(1);
+                *selected = (*selected).min(last);
+                if controls.pressed(Action::Move(input::Direction::North)) && *selected > 0 {
+                    *selected -= 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Move(input::Direction::South)) && *selected < last {
+                    *selected += 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Confirm) {
+                    match (*first, mixable.get(*selected).copied()) {
+                        (None, Some(index)) => self.mode = Mode::Mix(Some(index), 0),
+                        (Some(first), Some(second)) => {
+                            self.mode = Mode::Playing;
+                            self.mix(first, second);
+                        }
+                        (_, None) => self.mode = Mode::Playing,
+                    }
+                } else if controls.pressed(Action::Cancel) {
+                    self.mode = Mode::Playing;
+                }
+            }
+            Mode::Talk(speaker, node, selected) => {
+                let node = dialogue::node(&self.content.dialogue, &self.world.entities[*speaker], node);
+                let choices = node.map_or(&[][..], |node| &node.choices);
+                let last = choices.len().saturating_sub(1);
+                // Answers are numbered from 1, so one can be picked by its number
+                let typed = input::typed_digit(keyboard).filter(|&digit| digit >= 1 && digit as usize <= choices.len());
+                if let Some(digit) = typed {
+                    let (speaker, choice) = (*speaker, choices[digit as usize - 1].clone());
+                    self.answer(speaker, choice);
+                } else if controls.pressed(Action::Move(input::Direction::North)) && *selected > 0 {
+                    *selected -= 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Move(input::Direction::South)) && *selected < last {
+                    *selected += 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Confirm) {
+                    match choices.get(*selected) {
+                        Some(choice) => {
+                            let (speaker, choice) = (*speaker, choice.clone());
+                            self.answer(speaker, choice);
+                        }
+                        None => self.mode = Mode::Playing,
+                    }
+                } else if controls.pressed(Action::Cancel) {
+                    self.mode = Mode::Playing;
+                }
+            }
+            Mode::Wish(query) => {
+                // A text field, so it reads keys directly instead of going through the bindings
+                let pressed = |key| keyboard[key] == ButtonState::Pressed;
+                if pressed(Key::Escape) {
+                    self.mode = Mode::Playing;
+                } else if pressed(Key::Return) || pressed(Key::NumpadEnter) {
+                    let query = std::mem::take(query);
+                    self.mode = Mode::Playing;
+                    self.wish(&query);
+                } else if pressed(Key::Back) {
+                    query.pop();
+                } else if let Some(c) = input::typed_char(keyboard) {
+                    query.push(c);
+                }
+            }
+            Mode::Editor(editor) => {
+                // A drawing tool, so it reads keys directly instead of going through the bindings
+                let pressed = |key| keyboard[key] == ButtonState::Pressed;
+                let shift = keyboard[Key::LShift].is_down() || keyboard[Key::RShift].is_down();
+                if pressed(Key::Escape) {
+                    self.close_editor();
+                } else if pressed(Key::Return) || pressed(Key::NumpadEnter) {
+                    let strings = &self.content.strings;
+                    self.message = Some(match editor.save(&self.world) {
+                        Ok(file) => strings.format("editor.saved", &[("file", &file)]),
+                        Err(err) => strings.format("editor.not_saved", &[("error", &err)]),
+                    });
+                    let mut problems = Vec::new();
+                    self.content.prefabs = prefab::load(&mut problems);
+                    data::report(&problems);
+                } else if pressed(Key::Tab) {
+                    editor.cycle_brush(shift);
+                } else if let Some(pos) = mouse_tile {
+                    if mouse[MouseButton::Left].is_down() {
+                        editor.paint(&mut self.world, pos, &self.content);
+                    } else if mouse[MouseButton::Right].is_down() {
+                        editor.erase(&mut self.world, pos, &self.content);
+                    }
+                }
+            }
+            Mode::GameOver => {
+                if controls.pressed(Action::Confirm) {
+                    self.mode = Mode::NewRun(None);
+                } else if controls.pressed(Action::WatchReplay) {
+                    self.start_replay();
+                }
+            }
+            Mode::Replay(replay) => {
+                if controls.pressed(Action::Wait) {
+                    replay.paused = !replay.paused;
+                } else if controls.pressed(Action::Move(input::Direction::East)) {
+                    replay.paused = true;
+                    replay.step(&mut self.world);
+                } else if controls.pressed(Action::Move(input::Direction::North)) {
+                    replay.faster();
+                } else if controls.pressed(Action::Move(input::Direction::South)) {
+                    replay.slower();
+                }
+                // A paused replay stays up at the end, so the last step can be looked at
+                let ended = replay.advance(&mut self.world, window.update_rate());
+                if ended || controls.pressed(Action::Cancel) {
+                    self.end_replay();
+                }
+            }
+            Mode::NewRun(seed) => {
+                if let Some(digit) = input::typed_digit(keyboard) {
+                    let typed = seed.unwrap_or(0).checked_mul(10).and_then(|seed| seed.checked_add(digit.into()));
+                    *seed = typed.or(*seed);
+                } else if keyboard[Key::Back] == ButtonState::Pressed {
+                    *seed = seed.map(|seed| seed / 10).filter(|&seed| seed > 0);
+                } else if controls.pressed(Action::Confirm) {
+                    let seed = seed.unwrap_or_else(rng::random_seed);
+                    if classes::available(&self.content.classes, &self.profile.unlocks).is_empty() {
+                        self.choose_race(seed, None);
+                    } else {
+                        self.mode = Mode::ChooseClass(seed, 0);
+                    }
+                } else if controls.pressed(Action::Cancel) && self.world.is_over() {
+                    // Before the first run there's no game over to go back to
+                    self.mode = Mode::GameOver;
+                }
+            }
+            Mode::ChooseClass(seed, selected) => {
+                let classes = classes::available(&self.content.classes, &self.profile.unlocks);
+                let last = classes.len().saturating_sub(1);
+                if controls.pressed(Action::Move(input::Direction::North)) && *selected > 0 {
+                    *selected -= 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Move(input::Direction::South)) && *selected < last {
+                    *selected += 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Confirm) {
+                    let (seed, class) = (*seed, classes.get(*selected).map(|class| class.name.clone()));
+                    self.choose_race(seed, class);
+                } else if controls.pressed(Action::Cancel) {
+                    self.mode = Mode::NewRun(Some(*seed));
+                }
+            }
+            Mode::ChooseRace(seed, class, selected) => {
+                let last = self.content.races.len().saturating_sub(1);
+                if controls.pressed(Action::Move(input::Direction::North)) && *selected > 0 {
+                    *selected -= 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Move(input::Direction::South)) && *selected < last {
+                    *selected += 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Confirm) {
+                    let (seed, class) = (*seed, class.take());
+                    let race = self.content.races.get(*selected).map(|race| race.name.clone());
+                    self.choose_boons(seed, class, race);
+                } else if controls.pressed(Action::Cancel) {
+                    let (seed, class) = (*seed, class.take());
+                    self.choose_class_again(seed, class.as_deref());
+                }
+            }
+            Mode::ChooseBoons(seed, class, race, chosen, selected) => {
+                let boons = self.profile.boons();
+                if controls.pressed(Action::Move(input::Direction::North)) && *selected > 0 {
+                    *selected -= 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Move(input::Direction::South)) && *selected < boons.len() {
+                    *selected += 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Confirm) {
+                    match boons.iter().nth(*selected) {
+                        Some(boon) => {
+                            if !chosen.remove(boon) {
+                                chosen.insert(*boon);
+                            }
+                        }
+                        None => {
+                            let (seed, class, race) = (*seed, class.take(), race.take());
+                            let chosen = std::mem::take(chosen);
+                            self.start_run(seed, class.as_deref(), race.as_deref(), &chosen);
+                        }
+                    }
+                } else if controls.pressed(Action::Cancel) {
+                    let (seed, class, race) = (*seed, class.take(), race.take());
+                    let races = &self.content.races;
+                    match races.iter().position(|def| Some(&def.name) == race.as_ref()) {
+                        Some(index) => self.mode = Mode::ChooseRace(seed, class, index),
+                        None => self.choose_class_again(seed, class.as_deref()),
+                    }
+                }
             }
-        } 
-        // Handle normal game controls
-        else {
-            let player = &mut self.entities[self.player_id];
-            
-            // Movement controls (using was_pressed for single moves)
-            if window.keyboard()[Key::Left] == Pressed {
-                player.pos.x = (player.pos.x - 1.0).max(0.0);
+            Mode::ChoosePerk(selected) => {
+                let open = perks::open_to(&self.content.perks, self.world.player());
+                let last = open.len().saturating_sub(1);
+                *selected = (*selected).min(last);
+                if controls.pressed(Action::Move(input::Direction::North)) && *selected > 0 {
+                    *selected -= 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Move(input::Direction::South)) && *selected < last {
+                    *selected += 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Confirm) {
+                    if let Some(perk) = open.get(*selected) {
+                        self.world.take_perk(perk);
+                    }
+                    self.mode = Mode::Playing;
+                }
             }
-            if window.keyboard()[Key::Right] == Pressed {
-                player.pos.x = (player.pos.x + 1.0).min(self.map_size.x - 1.0);
+            Mode::RaiseAttribute(selected) => {
+                let last = attributes::Attribute::ALL.len() - 1;
+                if controls.pressed(Action::Move(input::Direction::North)) && *selected > 0 {
+                    *selected -= 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Move(input::Direction::South)) && *selected < last {
+                    *selected += 1;
+                    self.audio.play(audio::SoundEvent::MenuMove);
+                } else if controls.pressed(Action::Confirm) {
+                    self.world.raise(attributes::Attribute::ALL[*selected]);
+                    if self.world.attribute_points == 0 {
+                        self.mode = Mode::Playing;
+                    }
+                }
             }
-            if window.keyboard()[Key::Up] == Pressed {
-                player.pos.y = (player.pos.y - 1.0).max(0.0);
+            Mode::CharacterSheet => {
+                let close = [Action::CharacterSheet, Action::Confirm, Action::Cancel];
+                if close.into_iter().any(|action| controls.pressed(action)) {
+                    self.mode = Mode::Playing;
+                }
             }
-            if window.keyboard()[Key::Down] == Pressed {
-                player.pos.y = (player.pos.y + 1.0).min(self.map_size.y - 1.0);
+            Mode::QuestLog => {
+                let close = [Action::QuestLog, Action::Confirm, Action::Cancel];
+                if close.into_iter().any(|action| controls.pressed(action)) {
+                    self.mode = Mode::Playing;
+                }
             }
-            
-            // Open exit confirmation
-            if window.keyboard()[Key::Escape] == Pressed {
-                self.confirming_exit = true;
+            // Handle normal game controls
+            Mode::Playing => {
+                let menu_commands = [
+                    Action::OpenSettings,
+                    Action::CharacterSheet,
+                    Action::QuestLog,
+                    Action::CommandPalette,
+                    Action::Repeat,
+                    Action::Quit,
+                ];
+                let menu_command = menu_commands.into_iter().find(|&action| controls.pressed(action));
+                if let Some(action) = menu_command {
+                    self.run_command(action);
+                    return Ok(());
+                }
+
+                // Movement controls, repeating while a movement key or stick is held
+                let elapsed_ms = window.update_rate();
+                let key_repeat = &self.config.key_repeat;
+                let key_dir = self
+                    .move_repeat
+                    .poll_keys(keyboard, &bindings.move_keys(), elapsed_ms, key_repeat)
+                    .and_then(|key| bindings.move_direction(key));
+                let pad_dir = self.pad_repeat.poll_held(
+                    input::gamepad_direction(window.gamepads()),
+                    elapsed_ms,
+                    key_repeat,
+                );
+                let turn_action = if let Some(dir) = key_dir.or(pad_dir) {
+                    Some(Action::Move(dir))
+                } else {
+                    let turn_actions = [
+                        Action::Wait,
+                        Action::Rest,
+                        Action::Throw,
+                        Action::Cast,
+                        Action::TravelToStairs,
+                        Action::UseStairs,
+                        Action::PickUp,
+                        Action::Pray,
+                        Action::Craft,
+                        Action::UseItem,
+                        Action::Mix,
+                    ];
+                    turn_actions.into_iter().find(|&action| controls.pressed(action))
+                };
+                if let Some(action) = turn_action {
+                    self.run_command(action);
+                }
+
+                // Click a visible floor tile to walk there
+                if mouse[MouseButton::Left] == ButtonState::Pressed {
+                    if let Some(target) = mouse_tile {
+                        self.start_travel(target);
+                    }
+                }
+
+                if self.wizard {
+                    self.update_wizard(window);
+                }
+
+                if self.projectile.as_mut().is_some_and(|projectile| projectile.advance(elapsed_ms)) {
+                    self.land_projectile();
+                }
+                if let Some(waited_ms) = self.monster_turn_ms.as_mut() {
+                    *waited_ms += elapsed_ms;
+                    if *waited_ms >= MONSTER_TURN_MS {
+                        self.monster_turn_ms = None;
+                        self.world.monster_turns();
+                        if !self.world.is_over() && self.world.turn().is_multiple_of(happenings::HAPPENING_TURNS) {
+                            self.roll_happening();
+                        }
+                        self.bring_due_happenings();
+                        if !self.world.is_over() {
+                            spawn::repopulate(&mut self.world, &self.content);
+                        }
+                    }
+                }
+                if self.monster_turn_ms.is_none() && self.projectile.is_none() {
+                    if let Some(action) = self.input_buffer.pop() {
+                        self.perform(action);
+                    } else if self.auto.is_some() {
+                        self.step_auto(elapsed_ms);
+                    } else {
+                        self.tick_real_time(elapsed_ms);
+                    }
+                }
+
+                let settled = self.monster_turn_ms.is_none() && self.projectile.is_none();
+                let perk_ready = settled
+                    && self.world.perk_points > 0
+                    && !perks::open_to(&self.content.perks, self.world.player()).is_empty();
+                if self.world.is_over() {
+                    self.auto = None;
+                    self.input_buffer.clear();
+                    self.unlocked = self.profile.record_run_end(&self.world);
+                    self.profile.save();
+                    self.write_morgue();
+                    self.mode = Mode::GameOver;
+                } else if perk_ready {
+                    self.auto = None;
+                    self.input_buffer.clear();
+                    self.mode = Mode::ChoosePerk(0);
+                } else if settled && self.world.attribute_points > 0 {
+                    self.auto = None;
+                    self.input_buffer.clear();
+                    self.mode = Mode::RaiseAttribute(0);
+                }
             }
         }
+
+        let paused = !matches!(self.mode, Mode::Playing | Mode::GameOver);
+        self.audio.set_volume(self.config.volume);
+        self.audio.update_background(self.world.biome, paused, window.update_rate());
         Ok(())
     }
     // ... keep your existing draw() implementation exactly the same ...
     fn draw(&mut self, window: &mut Window) -> Result<()> {
-        window.clear(Color::WHITE)?;
+        self.dispatch_events();
+        self.refresh_layout(window);
+        let layout = self.layout;
+        let theme = self.config.theme;
+        let ui_z = Layer::Ui.z();
+        window.clear(theme.background())?;
         self.title.execute(|image| {
-            window.draw(
+            window.draw_ex(
                 &image
                     .area()
-                    .with_center((window.screen_size().x as i32 / 2, 40)),
-                Img(&image),
+                    .with_center(layout.title_center),
+                Blended(image, theme.text()),
+                Transform::IDENTITY,
+                ui_z,
             );
             Ok(())
         })?;
 
         self.mononoki_font_info.execute(|image| {
-            window.draw(
+            window.draw_ex(
                 &image
                     .area()
-                    .translate((2, window.screen_size().y as i32 - 60)),
-                Img(&image),
+                    .translate(layout.credits_pos[0]),
+                Blended(image, theme.text()),
+                Transform::IDENTITY,
+                ui_z,
             );
             Ok(())
         })?;
 
         self.square_font_info.execute(|image| {
-            window.draw(
+            window.draw_ex(
                 &image
                     .area()
-                    .translate((2, window.screen_size().y as i32 - 30)),
-                Img(&image),
+                    .translate(layout.credits_pos[1]),
+                Blended(image, theme.text()),
+                Transform::IDENTITY,
+                ui_z,
             );
             Ok(())
         })?;
 
-        let tile_size_px = self.tile_size_px;
-        let offset_px = Vector::new(175, 120);
+        let tile_size_px = layout.tile_size_px;
+        let camera = self.camera();
+        let map_size_px = camera.size.times(tile_size_px);
+        let offset_px = layout.map_offset(map_size_px);
+        self.animation_clock_ms += window.draw_rate();
+        let clock_ms = self.animation_clock_ms;
+        self.effects.advance(window.draw_rate());
 
-        let (tileset, map) = (&mut self.tileset, &self.map);
+        // Still terrain only changes when the view moves or the player sees something
+        // new, so it's kept in its own batch and rebuilt only then. Animated tiles and
+        // entities go in a second batch every frame. Remembered tiles outside the
+        // field of view are faded; the rest are darkened by how little light reaches them.
+        let terrain_key = TerrainKey {
+            camera,
+            offset_px,
+            tile_size_px,
+            theme,
+            revision: self.world.revision,
+        };
+        let rebuild_terrain = self.terrain_key != Some(terrain_key);
+        let (tileset, world, glyphs) = (&mut self.tileset, &self.world, &self.content.glyphs);
+        let (terrain_batch, batch, cached_key) = (&mut self.terrain_batch, &mut self.glyph_batch, &mut self.terrain_key);
+        batch.clear();
         tileset.execute(|tileset| {
-            for tile in map.iter() {
-                if let Some(image) = tileset.get(&tile.glyph) {
-                    let pos_px = tile.pos.times(tile_size_px);
-                    window.draw(
-                        &Rectangle::new(pos_px + offset_px, image.area().size()),
-                        Blended(&image, tile.color),
-                    );
+            if rebuild_terrain {
+                terrain_batch.clear();
+                *cached_key = Some(terrain_key);
+            }
+            for pos in camera.positions() {
+                let tile = match world.tile_at(pos) {
+                    Some(tile) => tile,
+                    None => continue,
+                };
+                let animated = tile.animation.is_some();
+                if !animated && !rebuild_terrain {
+                    continue;
+                }
+                let frame = tile.appearance(clock_ms);
+                let color = if world.is_visible(pos) {
+                    light::tint(theme.glyph(frame.color), world.light_at(pos))
+                } else if world.is_explored(pos) {
+                    theme.glyph(frame.color).with_alpha(0.4)
+                } else {
+                    continue;
+                };
+                if let Some(image) = tileset.get(glyphs.glyph(frame.glyph)) {
+                    let pos_px = offset_px + (camera.to_view(pos) + frame.offset).times(tile_size_px);
+                    let target = if animated { &mut *batch } else { &mut *terrain_batch };
+                    target.push(image, Rectangle::new(pos_px, tile_size_px), color, Layer::Terrain);
+                }
+            }
+
+            let effects = &self.effects;
+            // Creatures sensed by infravision show as a dull red glow where they stand
+            let sensed = |entity: &Entity| entity.is_alive() && world.is_sensed(entity.pos);
+            let shown = |&(id, entity): &(usize, &Entity)| {
+                camera.contains(entity.pos) && (world.is_visible(entity.pos) || sensed(entity)) && !effects.hides(id)
+            };
+            for (_, entity) in world.entities.iter().enumerate().filter(shown) {
+                let frame = entity.appearance(clock_ms);
+                if let Some(image) = tileset.get(glyphs.glyph(frame.glyph)) {
+                    let pos_px = offset_px + (camera.to_view(entity.pos) + frame.offset).times(tile_size_px);
+                    let area = Rectangle::new(pos_px, tile_size_px);
+                    let color = if world.is_visible(entity.pos) {
+                        light::tint(theme.glyph(frame.color), world.light_at(entity.pos))
+                    } else {
+                        theme.glyph(Color::RED).with_alpha(0.5)
+                    };
+                    batch.push(image, area, color, Layer::for_entity(entity));
+                }
+            }
+
+            // A hit flashes the entity white; a death fades the creature out as it
+            // sinks, and only then do its remains show; coming out of a portal leaves
+            // a magenta shimmer rising off the entity
+            for (effect, progress) in effects.iter() {
+                let (target, glyph, color, sink) = match *effect {
+                    Effect::Hit { target } => {
+                        let glyph = world.entities[target].appearance(clock_ms).glyph;
+                        (target, glyph, Color::WHITE, 0.0)
+                    }
+                    Effect::Death { target, glyph, color } => (target, glyph, theme.glyph(color), 0.3 * progress),
+                    Effect::Warp { target } => {
+                        let glyph = world.entities[target].appearance(clock_ms).glyph;
+                        (target, glyph, theme.glyph(Color::MAGENTA), -0.3 * progress)
+                    }
+                };
+                let pos = world.entities[target].pos;
+                if !camera.contains(pos) || !world.is_visible(pos) {
+                    continue;
+                }
+                if let Some(image) = tileset.get(glyphs.glyph(glyph)) {
+                    let pos_px = offset_px + (camera.to_view(pos) + Vector::new(0.0, sink)).times(tile_size_px);
+                    let area = Rectangle::new(pos_px, tile_size_px);
+                    batch.push(image, area, color.with_alpha(1.0 - progress), Layer::Effects);
+                }
+            }
+
+            if let Some(projectile) = &self.projectile {
+                let pos = projectile.pos().filter(|&pos| camera.contains(pos));
+                if let (Some(pos), Some(image)) = (pos, tileset.get(glyphs.glyph(projectile.glyph))) {
+                    let pos_px = offset_px + camera.to_view(pos).times(tile_size_px);
+                    let color = theme.glyph(projectile.color);
+                    batch.push(image, Rectangle::new(pos_px, tile_size_px), color, Layer::Effects);
                 }
             }
             Ok(())
         })?;
+        self.terrain_batch.draw(window);
+        self.glyph_batch.draw(window);
+        if self.debug.shown {
+            self.debug.draw(window, &self.world, camera, offset_px, tile_size_px);
+        }
 
-        let (tileset, entities) = (&mut self.tileset, &self.entities);
-        tileset.execute(|tileset| {
-            for entity in entities.iter() {
-                if let Some(image) = tileset.get(&entity.glyph) {
-                    let pos_px = offset_px + entity.pos.times(tile_size_px);
-                    window.draw(
-                        &Rectangle::new(pos_px, image.area().size()),
-                        Blended(&image, entity.color)
-                    )
+        // The way a spell being aimed would fly, and the tile it's aimed at
+        if let Mode::Aim(spell, target) = &self.mode {
+            let mut fill = |pos: Vector, color: Color| {
+                let pos_px = offset_px + camera.to_view(pos).times(tile_size_px);
+                window.draw_ex(&Rectangle::new(pos_px, tile_size_px), Col(color), Transform::IDENTITY, ui_z);
+            };
+            for pos in spells::flight(&self.world, spell, *target) {
+                if camera.contains(pos) {
+                    fill(pos, spell.color.with_alpha(0.3));
                 }
             }
+            if camera.contains(*target) {
+                fill(*target, Color::YELLOW.with_alpha(0.4));
+            }
+        }
+
+        if !self.config.hide_weather {
+            let map_area = Rectangle::new(offset_px, map_size_px);
+            self.weather.update(self.world.weather, map_area, window.draw_rate());
+            self.weather.draw(window, map_area, Layer::Effects.z());
+        }
+
+        let player = self.world.player();
+        let full_bar_width_px = 100.0;
+        let health_bar_pos_px = offset_px + Vector::new(map_size_px.x + 8.0, 0.0);
+        let mut bar = |pos_px: Vector, fraction: f32, color: Color| {
+            let full = Rectangle::new(pos_px, (full_bar_width_px, tile_size_px.y));
+            window.draw_ex(&full, Col(color.with_alpha(0.5)), Transform::IDENTITY, ui_z);
+            let current = Rectangle::new(pos_px, (fraction.clamp(0.0, 1.0) * full_bar_width_px, tile_size_px.y));
+            window.draw_ex(&current, Col(color), Transform::IDENTITY, ui_z);
+        };
+        bar(health_bar_pos_px, player.hp as f32 / player.max_hp as f32, Color::RED);
+        // Only players with mana to spend get a mana bar, under the health bar
+        let mut hud_pos_px = health_bar_pos_px + Vector::new(0.0, tile_size_px.y + 8.0);
+        if player.max_mana > 0 {
+            bar(hud_pos_px, player.mana as f32 / player.max_mana as f32, Color::BLUE);
+            hud_pos_px.y += tile_size_px.y + 8.0;
+        }
+
+        let strings = &self.content.strings;
+        let mut hud_lines = vec![self.player_name()];
+        if let Some(class) = &self.world.class {
+            hud_lines.push(strings.format("hud.class", &[("class", &strings.name("class", class))]));
+        }
+        hud_lines.push(strings.format("hud.level", &[("level", &self.world.player().level)]));
+        hud_lines.push(strings.format("hud.gold", &[("gold", &self.world.player().gold)]));
+        hud_lines.push(match &self.world.branch {
+            Some(branch) => {
+                let branch = strings.name("branch", branch);
+                strings.format("hud.branch_depth", &[("depth", &self.world.depth), ("branch", &branch)])
+            }
+            None => strings.format("hud.depth", &[("depth", &self.world.depth)]),
+        });
+        let statuses = self.world.player().statuses.iter().map(|effect| effect.status.name(strings));
+        let statuses: Vec<&str> = statuses.collect();
+        if !statuses.is_empty() {
+            hud_lines.push(strings.format("hud.statuses", &[("statuses", &statuses.join(", "))]));
+        }
+        hud_lines.push(match self.world.torch {
+            0 => strings.get("hud.torch_out").to_string(),
+            turns => strings.format("hud.torch", &[("turns", &turns)]),
+        });
+        match hunger::Hunger::of(self.world.satiety) {
+            hunger::Hunger::Fed => {}
+            hunger::Hunger::Hungry => hud_lines.push(strings.get("hud.hungry").to_string()),
+            hunger::Hunger::Starving => hud_lines.push(strings.get("hud.starving").to_string()),
+        }
+        let (day, hour, minute) = self.world.clock();
+        let time = format!("{:02}:{:02}", hour, minute);
+        hud_lines.push(strings.format("hud.turn", &[("turn", &self.world.turn()), ("day", &day), ("time", &time)]));
+        hud_lines.push(strings.format("hud.seed", &[("seed", &self.world.seed)]));
+        if self.world.wizard {
+            hud_lines.push(strings.get("hud.wizard").to_string());
+        }
+        if self.debug.shown {
+            hud_lines.extend(self.debug.lines(&self.world, &self.history, window.average_fps(), strings));
+        }
+        self.ui_font.execute(|font| {
+            let style = FontStyle::new(16.0, theme.text());
+            for (index, line) in hud_lines.iter().enumerate() {
+                let text = font.render(line, &style)?;
+                let pos_px = hud_pos_px + Vector::new(0.0, index as f32 * 20.0);
+                window.draw_ex(&text.area().translate(pos_px), Img(&text), Transform::IDENTITY, ui_z);
+            }
             Ok(())
         })?;
 
-        let player = &self.entities[self.player_id];
-        let full_health_width_px = 100.0;
-        let current_health_width_px =
-            (player.hp as f32 / player.max_hp as f32) * full_health_width_px;
-        let map_size_px = self.map_size.times(tile_size_px);
-        let health_bar_pos_px = offset_px + Vector::new(map_size_px.x, 0.0);
+        if let Some(message) = &self.message {
+            self.ui_font.execute(|font| {
+                let text = font.render(message, &FontStyle::new(24.0, theme.text()))?;
+                let pos_px = offset_px + Vector::new(0.0, map_size_px.y + 8.0);
+                window.draw_ex(&text.area().translate(pos_px), Img(&text), Transform::IDENTITY, ui_z);
+                Ok(())
+            })?;
+        }
 
-        window.draw(
-            &Rectangle::new(health_bar_pos_px, (full_health_width_px, tile_size_px.y)),
-            Col(Color::RED.with_alpha(0.5)),
-        );
-        window.draw(
-            &Rectangle::new(health_bar_pos_px, (current_health_width_px, tile_size_px.y)),
-            Col(Color::RED),
-        );
+        if let Some((notice, remaining_ms)) = &mut self.notice {
+            *remaining_ms -= window.draw_rate();
+            self.ui_font.execute(|font| {
+                let text = font.render(notice, &FontStyle::new(24.0, theme.text()))?;
+                let pos_px = offset_px + Vector::new(map_size_px.x - text.area().width() - 8.0, 8.0);
+                window.draw_ex(&text.area().translate(pos_px), Img(&text), Transform::IDENTITY, ui_z);
+                Ok(())
+            })?;
+        }
+        if self.notice.as_ref().is_some_and(|&(_, remaining_ms)| remaining_ms <= 0.0) {
+            self.notice = None;
+        }
 
         // Add confirmation dialog drawing
-        if self.confirming_exit {
-            self.confirm_exit_font.execute(|font| {
-                let text = font.render(
-                    "Are you sure you want to quit? (Y/N)",
-                    &FontStyle::new(32.0, Color::BLACK),
-                )?;
-                let pos = Vector::new(100.0, 100.0);
-                window.draw(&text.area().translate(pos), Img(&text));
+        let lines = match &self.mode {
+            Mode::Playing => Vec::new(),
+            Mode::ConfirmExit => {
+                let bindings = &self.config.bindings;
+                vec![strings.format(
+                    "confirm_quit",
+                    &[
+                        ("yes", &bindings.first_key_name(Action::Confirm)),
+                        ("no", &bindings.first_key_name(Action::Cancel)),
+                    ],
+                )]
+            }
+            Mode::Settings(menu) => menu.lines(&self.config, strings),
+            Mode::RepeatCount(count) => {
+                let action = self.last_action.map_or(String::new(), |action| action.label(strings).to_lowercase());
+                let count = if *count == 0 { String::new() } else { count.to_string() };
+                vec![strings.format("repeat.prompt", &[("action", &action), ("count", &count)])]
+            }
+            Mode::Palette(palette) => palette.lines(&self.config.bindings, strings),
+            Mode::GameOver => {
+                let key = self.config.bindings.first_key_name(Action::Confirm);
+                let replay_key = self.config.bindings.first_key_name(Action::WatchReplay);
+                let mut lines = vec![self.ending()];
+                if self.world.wizard {
+                    lines.push(strings.get("game_over.wizard").to_string());
+                }
+                lines.extend(unlocked_messages(&self.unlocked, strings));
+                lines.push(strings.format("game_over.prompt", &[("new_run", &key), ("replay", &replay_key)]));
+                lines
+            }
+            Mode::Replay(replay) => {
+                let bindings = &self.config.bindings;
+                let (done, total) = replay.progress();
+                let id = if replay.paused { "replay.paused" } else { "replay.playing" };
+                vec![
+                    strings.format(id, &[("done", &done), ("total", &total), ("speed", &replay.speed)]),
+                    strings.format(
+                        "replay.controls",
+                        &[
+                            ("pause", &bindings.first_key_name(Action::Wait)),
+                            ("step", &bindings.first_key_name(Action::Move(input::Direction::East))),
+                            ("faster", &bindings.first_key_name(Action::Move(input::Direction::North))),
+                            ("slower", &bindings.first_key_name(Action::Move(input::Direction::South))),
+                            ("stop", &bindings.first_key_name(Action::Cancel)),
+                        ],
+                    ),
+                ]
+            }
+            Mode::Spellbook(selected) => {
+                let bindings = &self.config.bindings;
+                let mut lines = vec![strings.format(
+                    "spellbook.prompt",
+                    &[
+                        ("cast", &bindings.first_key_name(Action::Confirm)),
+                        ("back", &bindings.first_key_name(Action::Cancel)),
+                    ],
+                )];
+                for (index, spell) in self.castable().iter().enumerate() {
+                    let marker = if index == *selected { "> " } else { "  " };
+                    let cooldown = self.world.cooldown(&spell.name);
+                    let short = spell.mana > self.world.player().mana;
+                    let mut spell = spell.describe(strings);
+                    if cooldown > 0 {
+                        spell = strings.format("spellbook.cooldown", &[("spell", &spell), ("turns", &cooldown)]);
+                    } else if short {
+                        spell = strings.format("spellbook.no_mana", &[("spell", &spell)]);
+                    }
+                    lines.push(format!("{}{}", marker, spell));
+                }
+                lines
+            }
+            Mode::Aim(spell, _) => {
+                let bindings = &self.config.bindings;
+                let name = strings.name("spell", &spell.name);
+                vec![strings.format(
+                    "aim.prompt",
+                    &[
+                        ("spell", &name),
+                        ("cast", &bindings.first_key_name(Action::Confirm)),
+                        ("back", &bindings.first_key_name(Action::Cancel)),
+                    ],
+                )]
+            }
+            Mode::Shop(shop, selected) => self.shop_lines(*shop, *selected),
+            Mode::Talk(speaker, node, selected) => self.talk_lines(*speaker, node, *selected),
+            Mode::Rites(selected) => self.rite_lines(*selected),
+            Mode::Craft(selected) => self.craft_lines(*selected),
+            Mode::UseItem(selected) => self.use_lines(*selected),
+            Mode::Mix(first, selected) => self.mix_lines(*first, *selected),
+            Mode::Wish(query) => vec![strings.format("wish.prompt", &[("item", query)])],
+            Mode::Editor(editor) => editor.lines(strings),
+            Mode::NewRun(seed) => {
+                let bindings = &self.config.bindings;
+                let seed = seed.map_or(String::new(), |seed| seed.to_string());
+                vec![
+                    strings.format("new_run.seed", &[("seed", &seed)]),
+                    strings.format(
+                        "new_run.hint",
+                        &[
+                            ("start", &bindings.first_key_name(Action::Confirm)),
+                            ("back", &bindings.first_key_name(Action::Cancel)),
+                        ],
+                    ),
+                ]
+            }
+            Mode::ChooseClass(_, selected) => {
+                let bindings = &self.config.bindings;
+                let mut lines = vec![strings.format(
+                    "class_select.prompt",
+                    &[
+                        ("start", &bindings.first_key_name(Action::Confirm)),
+                        ("back", &bindings.first_key_name(Action::Cancel)),
+                    ],
+                )];
+                let classes = classes::available(&self.content.classes, &self.profile.unlocks);
+                for (index, class) in classes.iter().enumerate() {
+                    let marker = if index == *selected { "> " } else { "  " };
+                    lines.push(format!("{}{}", marker, class.describe(strings)));
+                }
+                lines
+            }
+            Mode::ChooseRace(_, _, selected) => {
+                let bindings = &self.config.bindings;
+                let mut lines = vec![strings.format(
+                    "race_select.prompt",
+                    &[
+                        ("start", &bindings.first_key_name(Action::Confirm)),
+                        ("back", &bindings.first_key_name(Action::Cancel)),
+                    ],
+                )];
+                for (index, race) in self.content.races.iter().enumerate() {
+                    let marker = if index == *selected { "> " } else { "  " };
+                    lines.push(format!("{}{}", marker, race.describe(strings)));
+                }
+                lines
+            }
+            Mode::ChooseBoons(_, _, _, chosen, selected) => {
+                let bindings = &self.config.bindings;
+                let mut lines = vec![strings.format(
+                    "boon_select.prompt",
+                    &[
+                        ("start", &bindings.first_key_name(Action::Confirm)),
+                        ("back", &bindings.first_key_name(Action::Cancel)),
+                    ],
+                )];
+                let boons = self.profile.boons();
+                for (index, boon) in boons.iter().enumerate() {
+                    let marker = if index == *selected { "> " } else { "  " };
+                    let id = if chosen.contains(boon) { "boon_select.chosen" } else { "boon_select.unchosen" };
+                    lines.push(format!("{}{}", marker, strings.format(id, &[("boon", &boon.describe(strings))])));
+                }
+                let marker = if *selected == boons.len() { "> " } else { "  " };
+                lines.push(format!("{}{}", marker, strings.get("boon_select.start")));
+                lines
+            }
+            Mode::ChoosePerk(selected) => {
+                let key = self.config.bindings.first_key_name(Action::Confirm);
+                let mut lines = vec![strings.format("perk_select.prompt", &[("pick", &key)])];
+                for (index, perk) in perks::open_to(&self.content.perks, self.world.player()).iter().enumerate() {
+                    let marker = if index == *selected { "> " } else { "  " };
+                    lines.push(format!("{}{}", marker, perk.describe(strings)));
+                }
+                lines
+            }
+            Mode::RaiseAttribute(selected) => {
+                let key = self.config.bindings.first_key_name(Action::Confirm);
+                let points = self.world.attribute_points;
+                let prompt = strings.format("attribute_select.prompt", &[("points", &points), ("raise", &key)]);
+                let mut lines = vec![prompt];
+                let current = self.world.player().attributes;
+                for (index, attribute) in attributes::Attribute::ALL.into_iter().enumerate() {
+                    let marker = if index == *selected { "> " } else { "  " };
+                    let line = strings.format(
+                        "attribute_select.attribute",
+                        &[
+                            ("attribute", &attribute.name(strings)),
+                            ("value", &current.get(attribute)),
+                            ("effect", &attribute.effect(strings)),
+                        ],
+                    );
+                    lines.push(format!("{}{}", marker, line));
+                }
+                lines
+            }
+            Mode::CharacterSheet => {
+                let mut lines = sheet::lines(&self.world, &self.player_name(), &self.content.items, strings);
+                let close = self.config.bindings.first_key_name(Action::Cancel);
+                lines.push(strings.format("sheet.close", &[("close", &close)]));
+                lines
+            }
+            Mode::QuestLog => {
+                let mut lines = quest_log::lines(&self.world, strings);
+                let close = self.config.bindings.first_key_name(Action::Cancel);
+                lines.push(strings.format("sheet.close", &[("close", &close)]));
+                lines
+            }
+        };
+        if !lines.is_empty() {
+            self.ui_font.execute(|font| {
+                let style = FontStyle::new(24.0, theme.text());
+                let line_height = 28.0;
+                let pos = layout.panel.top_left();
+                let panel_size = Vector::new(layout.panel.width(), lines.len() as f32 * line_height);
+                let panel = Rectangle::new(pos, panel_size);
+                window.draw_ex(&panel, Col(theme.background()), Transform::IDENTITY, ui_z);
+                for (index, line) in lines.iter().enumerate() {
+                    let text = font.render(line, &style)?;
+                    let line_pos = pos + Vector::new(0.0, index as f32 * line_height);
+                    window.draw_ex(&text.area().translate(line_pos), Img(&text), Transform::IDENTITY, ui_z);
+                }
                 Ok(())
             })?;
         }
 
+        if std::mem::take(&mut self.screenshot) {
+            window.flush()?;
+            let strings = &self.content.strings;
+            let note = match screenshot::save(window) {
+                Ok(file) => strings.format("notice.screenshot", &[("file", &file)]),
+                Err(err) => {
+                    eprintln!("Couldn't save a screenshot: {}", err);
+                    strings.get("notice.screenshot_failed").to_string()
+                }
+            };
+            self.notice = Some((note, NOTICE_MS));
+        }
+
         Ok(())
     }
 }
 
-// ... keep your existing generate_map(), generate_entities(), and main() functions ...
-fn generate_map(size:Vector) -> Vec<Tile> {
-    let width = size.x as usize;
-    let height = size.y as usize;
-    let mut map = Vec::with_capacity(width * height);
-    for x in 0..width {
-        for y in 0..height {
-            let mut tile = Tile {
-                pos: Vector::new(x as f32, y as f32),
-                glyph: '.',
-                color: Color::BLACK,
+impl Game {
+    /// Pass what happened in the world on to the effects and sounds that show it.
+    fn dispatch_events(&mut self) {
+        let player_pos = self.world.player().pos;
+        for event in std::mem::take(&mut self.world.events) {
+            self.effects.start(effect::Effect::for_event(&event));
+            if let world::Event::Moved { entity } = event {
+                if entity == self.world.player_id {
+                    let (strings, pos) = (&self.content.strings, self.world.player().pos);
+                    if let Some(description) = self.describe_item_at(pos) {
+                        self.message = Some(strings.format("message.see_here", &[("item", &description)]));
+                    } else if let Some(altar) = self.world.altar_at(pos) {
+                        let god = strings.name("god", &self.world.altars[altar].god);
+                        let pray = self.config.bindings.first_key_name(Action::Pray);
+                        self.message = Some(strings.format("message.altar", &[("god", &god), ("pray", &pray)]));
+                    } else if let Some(entrance) = self.world.entrance_at(pos) {
+                        let branch = strings.name("branch", &self.world.entrances[entrance].branch);
+                        self.message = Some(strings.format("message.branch_entrance", &[("branch", &branch)]));
+                    } else if self.world.tile_at(pos).is_some_and(|tile| tile.glyph == crafting::WORKBENCH) {
+                        let craft = self.config.bindings.first_key_name(Action::Craft);
+                        self.message = Some(strings.format("message.workbench", &[("craft", &craft)]));
+                    }
+                }
+            }
+            if let world::Event::Robbed { keeper } = event {
+                let name = self.world.entities[keeper].name.clone().unwrap_or_default();
+                self.message = Some(self.content.strings.format("message.robbed", &[("keeper", &name)]));
+            }
+            if let world::Event::WoreOff { entity, effect } = event {
+                if entity == self.world.player_id || effect == timers::Timed::Summoned {
+                    self.message = Some(effect.wore_off(&self.content.strings).to_string());
+                }
+            }
+            if let world::Event::Unlocked { key, .. } = &event {
+                let strings = &self.content.strings;
+                self.message = Some(match key {
+                    Some(key) => strings.format("message.unlocked", &[("key", &strings.name("item", key))]),
+                    None => strings.get("message.forced").to_string(),
+                });
+            }
+            if let world::Event::DoorHeld { .. } = event {
+                self.message = Some(self.content.strings.get("message.door_held").to_string());
+            }
+            if let world::Event::Worked { .. } = event {
+                self.message = Some(self.content.strings.get("message.worked").to_string());
+            }
+            if let world::Event::Trapped { entity } = event {
+                if entity == self.world.player_id {
+                    self.message = Some(self.content.strings.get("message.trapped").to_string());
+                }
+            }
+            if let world::Event::Battered { entity } = event {
+                if entity == self.world.player_id {
+                    self.message = Some(self.content.strings.get("message.battered").to_string());
+                }
+            }
+            if let world::Event::Collapsed { entity } = event {
+                if entity == self.world.player_id {
+                    self.message = Some(self.content.strings.get("message.collapsed").to_string());
+                }
+            }
+            if let world::Event::Wedged { .. } = event {
+                self.message = Some(self.content.strings.get("message.wedged").to_string());
+            }
+            if let world::Event::Plugged { .. } = event {
+                self.message = Some(self.content.strings.get("message.plugged").to_string());
+            }
+            if let world::Event::Burned { .. } = event {
+                self.message = Some(self.content.strings.get("message.burned").to_string());
+            }
+            if let world::Event::Sank { item, .. } = &event {
+                let item = self.item_name(item);
+                self.message = Some(self.content.strings.format("message.sank", &[("item", &item)]));
+            }
+            if let world::Event::Drowning { .. } = event {
+                self.message = Some(self.content.strings.get("message.drowning").to_string());
+            }
+            if let world::Event::Warped { entity } = event {
+                if entity == self.world.player_id {
+                    self.message = Some(self.content.strings.get("message.warped").to_string());
+                }
+            }
+            if let world::Event::Visited { outcome, .. } = &event {
+                self.message = Some(self.content.strings.name("outcome", outcome).to_string());
+            }
+            if let world::Event::Slid { entity } = event {
+                if entity == self.world.player_id {
+                    self.message = Some(self.content.strings.get("message.slid").to_string());
+                }
+            }
+            if let world::Event::Kindled { .. } = event {
+                self.message = Some(self.content.strings.get("message.kindled").to_string());
+            }
+            if let world::Event::Spent { kind, .. } = event {
+                let id = match kind {
+                    shrines::ShrineKind::Fountain => "message.fountain_dry",
+                    shrines::ShrineKind::Shrine => "message.shrine_silent",
+                };
+                self.message = Some(self.content.strings.get(id).to_string());
+            }
+            if let world::Event::TorchLow { .. } = event {
+                self.message = Some(self.content.strings.get("message.torch_low").to_string());
+            }
+            if let world::Event::TorchOut { .. } = event {
+                self.message = Some(self.content.strings.get("message.torch_out").to_string());
+            }
+            if let world::Event::Hungry { .. } = event {
+                self.message = Some(self.content.strings.get("message.hungry").to_string());
+            }
+            if let world::Event::Starving { .. } = event {
+                self.message = Some(self.content.strings.get("message.starving").to_string());
+            }
+            if let world::Event::LevelUp { level, .. } = event {
+                self.message = Some(self.content.strings.format("message.level_up", &[("level", &level)]));
+            }
+            if let world::Event::Spotted { entity } = event {
+                let strings = &self.content.strings;
+                self.message = Some(match &self.world.entities[entity].name {
+                    Some(name) => strings.format("message.spotted", &[("name", name)]),
+                    None => strings.get("message.spotted_unnamed").to_string(),
+                });
+            }
+            if let Some(sound) = audio::SoundEvent::for_event(&event, self.world.player_id) {
+                let offset = self.world.entities[event.entity()].pos - player_pos;
+                self.audio.play_at(sound, offset);
+            }
+        }
+    }
+
+    /// Toggle fullscreen, sound or the debug overlay, or ask for a screenshot, from any
+    /// screen but the settings menu, where the keys may be getting rebound, then make
+    /// the window match the config.
+    fn update_hotkeys(&mut self, window: &mut Window) {
+        let controls = input::Controls::new(window.keyboard(), &self.config.bindings, window.gamepads());
+        let hotkeys = !matches!(self.mode, Mode::Settings(_));
+        let (fullscreen, mute) = (controls.pressed(Action::ToggleFullscreen), controls.pressed(Action::ToggleMute));
+        let screenshot = controls.pressed(Action::Screenshot);
+        if hotkeys && fullscreen {
+            self.toggle_fullscreen();
+        }
+        if hotkeys && mute {
+            self.toggle_mute();
+        }
+        if hotkeys && screenshot {
+            self.screenshot = true;
+        }
+        if hotkeys {
+            self.debug.update(window.keyboard());
+        }
+        if window.get_fullscreen() != self.config.fullscreen {
+            window.set_fullscreen(self.config.fullscreen);
+        }
+    }
+
+    fn toggle_fullscreen(&mut self) {
+        self.config.fullscreen = !self.config.fullscreen;
+        self.config.save();
+    }
+
+    fn toggle_mute(&mut self) {
+        let volume = &mut self.config.volume;
+        volume.muted = !volume.muted;
+        let note = self.content.strings.get(if volume.muted { "notice.sound_off" } else { "notice.sound_on" });
+        self.notice = Some((note.to_string(), NOTICE_MS));
+        self.audio.set_volume(self.config.volume);
+        self.config.save();
+    }
+
+    /// Lay the screen out again if the window has changed size since last frame,
+    /// keeping one unit of the view equal to one pixel so nothing gets stretched.
+    fn refresh_layout(&mut self, window: &mut Window) {
+        let screen_size = window.screen_size();
+        if screen_size != self.layout.screen_size {
+            self.layout = layout::Layout::new(screen_size, self.tile_size_px);
+            window.set_view(quicksilver::graphics::View::new(Rectangle::new_sized(screen_size)));
+        }
+    }
+
+    /// Pick up edits to the data files and prefabs. New definitions apply to levels
+    /// generated from now on; a file that fails to parse is reported and the old one
+    /// kept.
+    #[cfg(feature = "hot-reload")]
+    fn reload_data(&mut self, elapsed_ms: f64) {
+        let changed = self.data_watcher.changed(elapsed_ms);
+        for file in &changed {
+            let problems = self.content.reload(file);
+            if problems.is_empty() {
+                log::info!(target: "reload", file = file.as_str(); "reloaded");
+            } else {
+                data::report(&problems);
+            }
+        }
+        // Prefabs may have been added or removed, so watch the new set of files, and
+        // the terrain may be drawn with different glyphs now
+        if !changed.is_empty() {
+            self.data_watcher = data::Watcher::new(self.content.files());
+            self.terrain_key = None;
+        }
+    }
+
+    /// What the item lying at `pos` is, if there is one, and its price if it's in
+    /// a shop.
+    fn describe_item_at(&self, pos: Vector) -> Option<String> {
+        let entity = &self.world.entities[self.world.item_at(pos)?];
+        let def = items::find(&self.content.items, entity.item.as_deref()?)?;
+        let strings = &self.content.strings;
+        let description = match &entity.name {
+            _ if !alchemy::is_known(&self.world, def) => self.item_name(&def.name),
+            Some(name) => {
+                let description = def.describe(strings);
+                strings.format("item.legendary", &[("name", name), ("description", &description)])
+            }
+            None => def.describe(strings),
+        };
+        if self.world.open_shop_at(pos).is_none() {
+            return Some(description);
+        }
+        let price = shops::buy_price(def.value, &self.world.player().attributes);
+        Some(strings.format("item.for_sale", &[("description", &description), ("price", &price)]))
+    }
+
+    /// Pick up the item at the player's feet, saying what it costs if it's for
+    /// sale. Returns whether that used up the turn.
+    fn pick_up(&mut self) -> bool {
+        let strings = &self.content.strings;
+        let id = match self.world.pick_up() {
+            Some(id) => id,
+            None => {
+                self.message = Some(strings.get("message.nothing_here").to_string());
+                return false;
+            }
+        };
+        let name = self.world.entities[id].item.clone().unwrap_or_default();
+        let item = self.item_name(&name);
+        let shop = self.world.shops.iter().find(|shop| shop.unpaid.contains(&id));
+        let value = items::find(&self.content.items, &name).map(|def| def.value);
+        self.message = Some(match (shop, value) {
+            (Some(shop), Some(value)) => {
+                let price = shops::buy_price(value, &self.world.player().attributes);
+                let keeper = self.world.entities[shop.keeper].name.clone().unwrap_or_default();
+                strings.format("message.picked_up_unpaid", &[("item", &item), ("price", &price), ("keeper", &keeper)])
+            }
+            _ => strings.format("message.picked_up", &[("item", &item)]),
+        });
+        true
+    }
+
+    /// Start talking to `speaker`, from where the last conversation with them left
+    /// off if it said to. Any quest done for them is handed in first.
+    fn start_talk(&mut self, speaker: usize) {
+        let name = self.world.entities[speaker].name.clone().unwrap_or_default();
+        let mut rewarded = Vec::new();
+        for index in self.world.quests_to_reward(speaker) {
+            self.world.reward_quest(index);
+            let strings = &self.content.strings;
+            let quest = strings.name("quest", &self.world.quests[index].def.name);
+            rewarded.push(strings.format("message.quest_rewarded", &[("giver", &name), ("quest", &quest)]));
+        }
+        if !rewarded.is_empty() {
+            self.message = Some(rewarded.join(" "));
+        }
+        let entity = &self.world.entities[speaker];
+        let def = entity.dialogue.as_deref().and_then(|name| dialogue::find(&self.content.dialogue, name));
+        let resume = self.world.dialogue_starts.get(&speaker).cloned();
+        if let Some(node) = resume.or_else(|| def.and_then(|def| def.start()).map(|node| node.id.clone())) {
+            self.mode = Mode::Talk(speaker, node, 0);
+        }
+    }
+
+    /// Give `choice` as the answer to `speaker`, doing what it does, then go on to
+    /// the node it leads to or end the conversation.
+    fn answer(&mut self, speaker: usize, choice: dialogue::Choice) {
+        if !choice.effects.is_empty() {
+            self.world.talk(speaker, &choice.effects);
+        }
+        let strings = &self.content.strings;
+        let name = self.world.entities[speaker].name.clone().unwrap_or_default();
+        let mut messages = Vec::new();
+        for effect in &choice.effects {
+            match effect {
+                dialogue::DialogueEffect::Give(item) => {
+                    let item = strings.name("item", item);
+                    messages.push(strings.format("message.given", &[("speaker", &name), ("item", &item)]));
+                }
+                dialogue::DialogueEffect::Gold(gold) => {
+                    messages.push(strings.format("message.given_gold", &[("speaker", &name), ("gold", gold)]));
+                }
+                dialogue::DialogueEffect::Resume(_) => {}
+                dialogue::DialogueEffect::StartQuest(quest) => {
+                    if let Some(def) = quests::find(&self.content.quests, quest) {
+                        let item = quests::item_at(def, self.world.depth, &self.content.items);
+                        let taken = self.world.quests.len();
+                        self.world.start_quest(speaker, def, item.as_ref());
+                        if self.world.quests.len() > taken {
+                            let quest = strings.name("quest", quest);
+                            messages.push(strings.format("message.quest_started", &[("quest", &quest)]));
+                        }
+                    }
+                }
+            }
+        }
+        if !messages.is_empty() {
+            self.message = Some(messages.join(" "));
+        }
+        self.mode = match choice.next {
+            Some(next) => Mode::Talk(speaker, next, 0),
+            None => Mode::Playing,
+        };
+    }
+
+    /// What `speaker` is saying at `node`, then each answer the player can give.
+    fn talk_lines(&self, speaker: usize, node: &str, selected: usize) -> Vec<String> {
+        let (strings, bindings) = (&self.content.strings, &self.config.bindings);
+        let entity = &self.world.entities[speaker];
+        let node = match dialogue::node(&self.content.dialogue, entity, node) {
+            Some(node) => node,
+            None => return Vec::new(),
+        };
+        let name = entity.name.clone().unwrap_or_default();
+        let text = strings.name("dialogue", &node.text);
+        let mut lines = vec![strings.format("dialogue.line", &[("speaker", &name), ("text", &text)])];
+        for (index, choice) in node.choices.iter().enumerate() {
+            let marker = if index == selected { "> " } else { "  " };
+            let text = strings.name("dialogue", &choice.text);
+            let choice = strings.format("dialogue.choice", &[("number", &(index + 1)), ("text", &text)]);
+            lines.push(format!("{}{}", marker, choice));
+        }
+        lines.push(strings.format(
+            "dialogue.hint",
+            &[
+                ("answer", &bindings.first_key_name(Action::Confirm)),
+                ("back", &bindings.first_key_name(Action::Cancel)),
+            ],
+        ));
+        lines
+    }
+
+    /// The shop counter: the player's gold, then each trade they can make.
+    fn shop_lines(&self, shop: usize, selected: usize) -> Vec<String> {
+        let (strings, bindings, world) = (&self.content.strings, &self.config.bindings, &self.world);
+        let keeper = world.entities[world.shops[shop].keeper].name.clone().unwrap_or_default();
+        let mut lines = vec![strings.format(
+            "shop.prompt",
+            &[
+                ("keeper", &keeper),
+                ("gold", &world.player().gold),
+                ("trade", &bindings.first_key_name(Action::Confirm)),
+                ("back", &bindings.first_key_name(Action::Cancel)),
+            ],
+        )];
+        let trades = shops::trades(world, shop, &self.content.items);
+        if trades.is_empty() {
+            lines.push(strings.get("shop.nothing").to_string());
+        }
+        let unpaid = |item: usize| world.entities[world.shops[shop].unpaid[item]].item.as_deref().unwrap_or("");
+        for (index, trade) in trades.into_iter().enumerate() {
+            let marker = if index == selected { "> " } else { "  " };
+            let line = match trade {
+                shops::Trade::Buy { item, price } => {
+                    let item = self.item_name(unpaid(item));
+                    strings.format("shop.buy", &[("item", &item), ("price", &price)])
+                }
+                shops::Trade::Return { item } => {
+                    strings.format("shop.return", &[("item", &self.item_name(unpaid(item)))])
+                }
+                shops::Trade::Sell { item, price } => {
+                    let item = self.item_name(&world.player().inventory[item]);
+                    strings.format("shop.sell", &[("item", &item), ("price", &price)])
+                }
+            };
+            lines.push(format!("{}{}", marker, line));
+        }
+        lines
+    }
+
+    /// The offerings the player can make and the prayers they can say, with the
+    /// piety each earns or costs.
+    fn rite_lines(&self, selected: usize) -> Vec<String> {
+        let (strings, bindings, world) = (&self.content.strings, &self.config.bindings, &self.world);
+        let mut lines = vec![strings.format(
+            "rites.prompt",
+            &[
+                ("choose", &bindings.first_key_name(Action::Confirm)),
+                ("back", &bindings.first_key_name(Action::Cancel)),
+            ],
+        )];
+        let gods = &self.content.gods;
+        for (index, rite) in gods::rites(world, gods, &self.content.items).into_iter().enumerate() {
+            let marker = if index == selected { "> " } else { "  " };
+            let line = match rite {
+                gods::Rite::Offer(altar, offering) => {
+                    let name = &world.altars[altar].god;
+                    let piety = gods::find(gods, name).map_or(0, |god| god.piety(offering));
+                    let offering = match offering {
+                        gods::Offering::Item { index, .. } => {
+                            strings.name("item", &world.player().inventory[index]).to_string()
+                        }
+                        gods::Offering::Corpse(id) => {
+                            let monster = world.entities[id].kind.as_deref().unwrap_or_default();
+                            strings.format("rites.corpse", &[("monster", &strings.name("monster", monster))])
+                        }
+                    };
+                    let god = strings.name("god", name);
+                    strings.format("rites.offer", &[("offering", &offering), ("god", &god), ("piety", &piety)])
+                }
+                gods::Rite::Pray(god, prayer) => {
+                    let (god, prayer) = (&gods[god], &gods[god].prayers[prayer]);
+                    let piety = world.devotion.get(&god.name).map_or(0, |devotion| devotion.piety);
+                    strings.format(
+                        "rites.pray",
+                        &[
+                            ("god", &strings.name("god", &god.name)),
+                            ("prayer", &strings.name("prayer", &prayer.name)),
+                            ("cost", &prayer.piety),
+                            ("piety", &piety),
+                        ],
+                    )
+                }
             };
+            lines.push(format!("{}{}", marker, line));
+        }
+        lines
+    }
+
+    /// Make an offering or say a prayer, which takes the turn if the god accepts it.
+    fn perform_rite(&mut self, rite: gods::Rite) {
+        let strings = &self.content.strings;
+        match rite {
+            gods::Rite::Offer(altar, offering) => {
+                let god = match gods::find(&self.content.gods, &self.world.altars[altar].god) {
+                    Some(god) => god,
+                    None => return,
+                };
+                let name = strings.name("god", &god.name);
+                let mut messages = vec![strings.format("message.sacrificed", &[("god", &name)])];
+                for gift in self.world.sacrifice(god, offering) {
+                    let item = strings.name("item", &gift);
+                    messages.push(strings.format("message.gift", &[("god", &name), ("item", &item)]));
+                }
+                self.message = Some(messages.join(" "));
+            }
+            gods::Rite::Pray(god, prayer) => {
+                let god = &self.content.gods[god];
+                let name = strings.name("god", &god.name);
+                if !self.world.pray(&god.name, &god.prayers[prayer]) {
+                    self.message = Some(strings.format("message.prayer_unanswered", &[("god", &name)]));
+                    return;
+                }
+                self.message = Some(strings.format("message.prayer_answered", &[("god", &name)]));
+            }
+        }
+        self.end_player_turn();
+    }
+
+    /// Every recipe, with what it takes and whether the player can make it here.
+    fn craft_lines(&self, selected: usize) -> Vec<String> {
+        let (strings, bindings, world) = (&self.content.strings, &self.config.bindings, &self.world);
+        let mut lines = vec![strings.format(
+            "craft.prompt",
+            &[
+                ("craft", &bindings.first_key_name(Action::Confirm)),
+                ("back", &bindings.first_key_name(Action::Cancel)),
+            ],
+        )];
+        let ingredients = |ingredients: &[crafting::Ingredient]| -> String {
+            let ingredients = ingredients.iter().map(|ingredient| {
+                let item = strings.name("item", &ingredient.item);
+                strings.format("craft.ingredient", &[("count", &ingredient.count), ("item", &item)])
+            });
+            ingredients.collect::<Vec<String>>().join(", ")
+        };
+        let at_workbench = crafting::near_workbench(world, world.player().pos);
+        for (index, recipe) in self.content.recipes.iter().enumerate() {
+            let marker = if index == selected { "> " } else { "  " };
+            let item = strings.name("item", &recipe.makes);
+            let mut line =
+                strings.format("craft.recipe", &[("item", &item), ("ingredients", &ingredients(&recipe.ingredients))]);
+            let missing = recipe.missing(world.player());
+            if !missing.is_empty() {
+                line = strings.format("craft.missing", &[("recipe", &line), ("missing", &ingredients(&missing))]);
+            } else if recipe.workbench && !at_workbench {
+                line = strings.format("craft.needs_workbench", &[("recipe", &line)]);
+            }
+            lines.push(format!("{}{}", marker, line));
+        }
+        lines
+    }
+
+    /// Make the recipe at `index` of the recipe file, which takes the turn, or say
+    /// why it can't be made.
+    fn craft(&mut self, index: usize) {
+        let recipe = match self.content.recipes.get(index) {
+            Some(recipe) => recipe,
+            None => return,
+        };
+        let strings = &self.content.strings;
+        let item = strings.name("item", &recipe.makes);
+        if self.world.craft(recipe) {
+            self.message = Some(strings.format("message.crafted", &[("item", &item)]));
+            self.end_player_turn();
+        } else if !recipe.missing(self.world.player()).is_empty() {
+            self.message = Some(strings.format("message.missing_components", &[("item", &item)]));
+        } else {
+            self.message = Some(strings.format("message.needs_workbench", &[("item", &item)]));
+        }
+    }
 
-            if x == 0 || x == width - 1 || y == 0 || y == height - 1 {
-                tile.glyph = '#';
+    /// What the player knows the item called `name` as.
+    fn item_name(&self, name: &str) -> String {
+        alchemy::name(&self.world, &self.content.items, &self.content.strings, name)
+    }
+
+    /// Everything the player can use up, with what it does if they know.
+    fn use_lines(&self, selected: usize) -> Vec<String> {
+        let (strings, bindings) = (&self.content.strings, &self.config.bindings);
+        let mut lines = vec![strings.format(
+            "use.prompt",
+            &[
+                ("use", &bindings.first_key_name(Action::Confirm)),
+                ("back", &bindings.first_key_name(Action::Cancel)),
+            ],
+        )];
+        let player = self.world.player();
+        for (row, index) in alchemy::usable(player, &self.content.items).into_iter().enumerate() {
+            let marker = if row == selected { "> " } else { "  " };
+            let name = &player.inventory[index];
+            let line = match items::find(&self.content.items, name) {
+                Some(item) if alchemy::is_known(&self.world, item) => item.describe(strings),
+                _ => self.item_name(name),
             };
-            map.push(tile);
+            lines.push(format!("{}{}", marker, line));
+        }
+        lines
+    }
+
+    /// Use up the inventory item at `index`, which takes the turn and tells the
+    /// player what it was if they didn't know.
+    fn use_item(&mut self, index: usize) {
+        let name = self.world.player().inventory[index].clone();
+        let item = match items::find(&self.content.items, &name) {
+            Some(item) => item,
+            None => return,
+        };
+        let known = alchemy::is_known(&self.world, item);
+        let was = self.item_name(&name);
+        self.world.use_item(index, &item.effects);
+        let strings = &self.content.strings;
+        let mut message = strings.format("message.used", &[("item", &was)]);
+        if !known {
+            let identified = strings.format("message.identified", &[("item", &strings.name("item", &name))]);
+            message = format!("{} {}", message, identified);
+        }
+        self.message = Some(message);
+        self.end_player_turn();
+    }
+
+    /// Everything the player can mix, leaving out the first pick once it's made.
+    fn mix_lines(&self, first: Option<usize>, selected: usize) -> Vec<String> {
+        let (strings, bindings) = (&self.content.strings, &self.config.bindings);
+        let player = self.world.player();
+        let (mix, back) = (bindings.first_key_name(Action::Confirm), bindings.first_key_name(Action::Cancel));
+        let mut lines = vec![match first {
+            Some(first) => {
+                let first = self.item_name(&player.inventory[first]);
+                strings.format("mix.second", &[("first", &first), ("mix", &mix), ("back", &back)])
+            }
+            None => strings.format("mix.first", &[("mix", &mix), ("back", &back)]),
+        }];
+        for (row, index) in alchemy::mixable(player, &self.content.items, first).into_iter().enumerate() {
+            let marker = if row == selected { "> " } else { "  " };
+            lines.push(format!("{}{}", marker, self.item_name(&player.inventory[index])));
         }
+        lines
     }
-    map
+
+    /// Mix the inventory items at `first` and `second`, which takes the turn.
+    fn mix(&mut self, first: usize, second: usize) {
+        let inventory = &self.world.player().inventory;
+        let defs = (
+            items::find(&self.content.items, &inventory[first]),
+            items::find(&self.content.items, &inventory[second]),
+        );
+        let mixture = match defs {
+            (Some(first), Some(second)) => alchemy::mix(first, second, &self.content.items),
+            _ => return,
+        };
+        let (first_name, second_name) = (self.item_name(&inventory[first]), self.item_name(&inventory[second]));
+        self.world.mix(first, second, &mixture);
+        let strings = &self.content.strings;
+        self.message = Some(match &mixture {
+            alchemy::Mixture::Potion(potion) => strings.format(
+                "message.mixed",
+                &[("first", &first_name), ("second", &second_name), ("potion", &self.item_name(potion))],
+            ),
+            alchemy::Mixture::Mishap { damage, .. } => strings.format(
+                "message.mishap",
+                &[("first", &first_name), ("second", &second_name), ("damage", damage)],
+            ),
+        });
+        self.end_player_turn();
+    }
+
+    /// Saves from before characters had names just get "You".
+    /// How the run ended, in a line.
+    fn ending(&self) -> String {
+        let id = if self.world.won { "game_over.won" } else { "game_over.died" };
+        self.content.strings.format(id, &[("name", &self.player_name()), ("seed", &self.world.seed)])
+    }
+
+    /// Write the finished run's morgue file, and say where it went.
+    fn write_morgue(&mut self) {
+        let strings = &self.content.strings;
+        let note = match morgue::write(&self.world, &self.player_name(), &self.ending(), &self.content) {
+            Ok(file) => strings.format("notice.morgue", &[("file", &file)]),
+            Err(err) => {
+                eprintln!("Couldn't write the morgue file: {}", err);
+                strings.get("notice.morgue_failed").to_string()
+            }
+        };
+        self.notice = Some((note, NOTICE_MS));
+    }
+
+    fn player_name(&self) -> String {
+        let unnamed = || self.content.strings.get("player.unnamed").to_string();
+        self.world.player().name.clone().unwrap_or_else(unnamed)
+    }
+
+    fn camera(&self) -> camera::Camera {
+        let view_size = self.layout.view_size();
+        camera::Camera::follow(self.world.player().pos, self.world.map_size, view_size)
+    }
+
+    /// The map position under a point on screen, if it's inside the view.
+    fn screen_to_map(&self, pos_px: Vector) -> Option<Vector> {
+        let camera = self.camera();
+        let tile_size_px = self.layout.tile_size_px;
+        let pos = pos_px - self.layout.map_offset(camera.size.times(tile_size_px));
+        let view_pos = Vector::new(
+            (pos.x / tile_size_px.x).floor(),
+            (pos.y / tile_size_px.y).floor(),
+        );
+        let map_pos = camera.to_map(view_pos);
+        camera.contains(map_pos).then_some(map_pos)
+    }
+
+    /// Respond to a command the player gave while playing, from a key or the palette.
+    /// Turn actions wait in the input buffer until the player's turn.
+    fn run_command(&mut self, action: Action) {
+        match action {
+            Action::OpenSettings => self.mode = Mode::Settings(settings::SettingsMenu::default()),
+            Action::CharacterSheet => self.mode = Mode::CharacterSheet,
+            Action::QuestLog => self.mode = Mode::QuestLog,
+            Action::ToggleFullscreen => self.toggle_fullscreen(),
+            Action::ToggleMute => self.toggle_mute(),
+            Action::Screenshot => self.screenshot = true,
+            Action::CommandPalette => self.mode = Mode::Palette(palette::CommandPalette::default()),
+            Action::Repeat => {
+                if self.last_action.is_some() {
+                    self.auto = None;
+                    self.mode = Mode::RepeatCount(0);
+                }
+            }
+            Action::Quit => {
+                // Settle the turn in progress so it isn't lost from the save
+                if self.projectile.is_some() {
+                    self.land_projectile();
+                }
+                if self.monster_turn_ms.take().is_some() {
+                    self.world.monster_turns();
+                }
+                self.mode = if self.world.is_over() {
+                    Mode::GameOver
+                } else {
+                    Mode::ConfirmExit
+                };
+            }
+            Action::Move(_)
+            | Action::Wait
+            | Action::Rest
+            | Action::Throw
+            | Action::Cast
+            | Action::TravelToStairs
+            | Action::UseStairs
+            | Action::PickUp
+            | Action::Pray
+            | Action::Craft
+            | Action::UseItem
+            | Action::Mix => {
+                self.auto = None;
+                self.input_buffer.push(action);
+            }
+            Action::Confirm | Action::Cancel | Action::WatchReplay => {}
+        }
+    }
+
+    /// Carry out a turn action from the input buffer.
+    fn perform(&mut self, action: Action) {
+        self.message = None;
+        // Walking into a shopkeeper goes up to their counter, and into a friendly
+        // NPC strikes up a conversation
+        if let Action::Move(dir) = action {
+            let next = self.world.player().pos + dir.delta();
+            if let Some(shop) = self.world.shop_kept_at(next) {
+                self.mode = Mode::Shop(shop, 0);
+                return;
+            }
+            if let Some(speaker) = self.world.talker_at(next) {
+                self.start_talk(speaker);
+                return;
+            }
+        }
+        let acted = match action {
+            Action::Move(_) | Action::Wait => {
+                self.last_action = Some(action);
+                self.act(action)
+            }
+            Action::Rest => {
+                self.start_rest();
+                false
+            }
+            Action::Throw => {
+                self.throw();
+                false
+            }
+            Action::Cast => {
+                if self.castable().is_empty() {
+                    self.message = Some(self.content.strings.get("message.no_spells").to_string());
+                } else {
+                    self.mode = Mode::Spellbook(0);
+                }
+                false
+            }
+            Action::TravelToStairs => {
+                self.start_travel_to_stairs();
+                false
+            }
+            Action::PickUp => self.pick_up(),
+            Action::UseStairs => self.take_stairs(),
+            Action::Pray => {
+                if gods::rites(&self.world, &self.content.gods, &self.content.items).is_empty() {
+                    self.message = Some(self.content.strings.get("message.no_rites").to_string());
+                } else {
+                    self.mode = Mode::Rites(0);
+                }
+                false
+            }
+            Action::Craft => {
+                if self.content.recipes.is_empty() {
+                    self.message = Some(self.content.strings.get("message.no_recipes").to_string());
+                } else {
+                    self.mode = Mode::Craft(0);
+                }
+                false
+            }
+            Action::UseItem => {
+                if alchemy::usable(self.world.player(), &self.content.items).is_empty() {
+                    self.message = Some(self.content.strings.get("message.nothing_to_use").to_string());
+                } else {
+                    self.mode = Mode::UseItem(0);
+                }
+                false
+            }
+            Action::Mix => {
+                if alchemy::mixable(self.world.player(), &self.content.items, None).len() < 2 {
+                    self.message = Some(self.content.strings.get("message.nothing_to_mix").to_string());
+                } else {
+                    self.mode = Mode::Mix(None, 0);
+                }
+                false
+            }
+            _ => false,
+        };
+        if acted {
+            self.end_player_turn();
+        }
+    }
+
+    /// Do a single-turn action in the world, returning whether it used up the turn.
+    fn act(&mut self, action: Action) -> bool {
+        match action {
+            Action::Move(dir) => self.world.move_player(dir.delta()),
+            Action::Wait => true,
+            _ => false,
+        }
+    }
+
+    /// Throw a dart at the nearest monster in view. The turn ends once it lands.
+    fn throw(&mut self) {
+        let target = match self.world.nearest_visible_hostile() {
+            Some(target) => target,
+            None => {
+                self.message = Some(self.content.strings.get("message.nothing_to_throw").to_string());
+                return;
+            }
+        };
+        let (path, hit) = self.world.throw_path(self.world.entities[target].pos);
+        let color = Color::from_rgba(120, 120, 120, 1.0);
+        let payload = projectile::Payload::Dart { target: hit };
+        self.projectile = Some(projectile::Projectile::new('*', color, path, payload));
+    }
+
+    /// Everything in the player's spellbook, artifacts' powers included.
+    fn castable(&self) -> Vec<spells::SpellDef> {
+        spells::castable(&self.content.spells, &self.content.items, self.world.player())
+    }
+
+    /// Cast the spell at `index` in the player's spellbook, if it's ready and they
+    /// have the mana. Aimed spells are aimed first, starting on the nearest monster
+    /// in view; the rest go off at once.
+    fn cast(&mut self, index: usize) {
+        let spell = match self.castable().into_iter().nth(index) {
+            Some(spell) => spell,
+            None => return,
+        };
+        let strings = &self.content.strings;
+        let name = strings.name("spell", &spell.name);
+        let cooldown = self.world.cooldown(&spell.name);
+        if cooldown > 0 {
+            self.message = Some(strings.format("message.cooldown", &[("spell", &name), ("turns", &cooldown)]));
+            return;
+        }
+        if spell.mana > self.world.player().mana {
+            self.message = Some(strings.format("message.no_mana", &[("spell", &name)]));
+            return;
+        }
+        if spell.targeting == spells::Targeting::Blink && self.world.no_teleport {
+            self.message = Some(strings.get("message.held_in_place").to_string());
+            return;
+        }
+        if spell.is_aimed() {
+            let nearest = self.world.nearest_visible_hostile().map(|target| self.world.entities[target].pos);
+            let start = nearest.unwrap_or(self.world.player().pos);
+            self.mode = Mode::Aim(Box::new(spell), start);
+        } else {
+            self.release(spell, self.world.player().pos);
+        }
+    }
+
+    /// Let `spell` go at `target`. Aimed spells fly there and the turn ends once
+    /// they land.
+    fn release(&mut self, spell: spells::SpellDef, target: Vector) {
+        let strings = &self.content.strings;
+        let player_pos = self.world.player().pos;
+        if spell.is_aimed() && target == player_pos {
+            self.message = Some(strings.get("message.nothing_to_aim").to_string());
+            return;
+        }
+        self.message = Some(strings.format("message.cast", &[("spell", &strings.name("spell", &spell.name))]));
+        if !spell.is_aimed() {
+            self.world.cast(&spell, player_pos);
+            self.end_player_turn();
+            return;
+        }
+        let path = spells::flight(&self.world, &spell, target);
+        let (glyph, color) = (spell.glyph, spell.color);
+        let payload = projectile::Payload::Spell { spell, target };
+        self.projectile = Some(projectile::Projectile::new(glyph, color, path, payload));
+    }
+
+    fn land_projectile(&mut self) {
+        if let Some(projectile) = self.projectile.take() {
+            match projectile.payload {
+                projectile::Payload::Dart { target: Some(target) } => self.world.ranged_attack(target),
+                projectile::Payload::Dart { target: None } => {}
+                projectile::Payload::Spell { spell, target } => self.world.cast(&spell, target),
+            }
+            self.end_player_turn();
+        }
+    }
+
+    /// Wizard mode's cheats: reveal the map, toggle invincibility or wish for an
+    /// item from their keys, and right-click a tile to teleport there.
+    fn update_wizard(&mut self, window: &Window) {
+        let keyboard = window.keyboard();
+        if keyboard[wizard::REVEAL_KEY] == ButtonState::Pressed {
+            self.world.cheat(wizard::Cheat::RevealMap);
+        } else if keyboard[wizard::INVINCIBLE_KEY] == ButtonState::Pressed {
+            self.world.cheat(wizard::Cheat::ToggleInvincible);
+            let note = if self.world.invincible { "notice.invincible_on" } else { "notice.invincible_off" };
+            self.notice = Some((self.content.strings.get(note).to_string(), NOTICE_MS));
+        } else if keyboard[wizard::WISH_KEY] == ButtonState::Pressed {
+            self.auto = None;
+            self.mode = Mode::Wish(String::new());
+        } else if keyboard[wizard::EDITOR_KEY] == ButtonState::Pressed {
+            self.open_editor();
+        }
+
+        let mouse = window.mouse();
+        if mouse[MouseButton::Right] == ButtonState::Pressed {
+            let world = &self.world;
+            let free = |&pos: &Vector| world.is_walkable(pos) && world.creature_at(pos).is_none();
+            if let Some(target) = self.screen_to_map(mouse.pos()).filter(free) {
+                self.auto = None;
+                self.world.cheat(wizard::Cheat::Teleport(target));
+            }
+        }
+    }
+
+    /// Set the run aside and start drawing a prefab on a blank canvas.
+    fn open_editor(&mut self) {
+        self.auto = None;
+        self.input_buffer.clear();
+        let editor = editor::Editor::open(&mut self.world, &self.content);
+        self.terrain_key = None;
+        self.effects.clear();
+        self.message = None;
+        self.mode = Mode::Editor(editor);
+    }
+
+    fn close_editor(&mut self) {
+        if let Mode::Editor(editor) = std::mem::replace(&mut self.mode, Mode::Playing) {
+            self.world = *editor.previous;
+            self.terrain_key = None;
+            self.effects.clear();
+        }
+    }
+
+    /// Put the item called `name`, in the data files or the current language, at
+    /// the player's feet.
+    fn wish(&mut self, name: &str) {
+        let strings = &self.content.strings;
+        let name = name.trim();
+        let def = self.content.items.iter().find(|def| {
+            def.name.eq_ignore_ascii_case(name) || strings.name("item", &def.name).eq_ignore_ascii_case(name)
+        });
+        match def {
+            Some(def) => {
+                let item = def.entity(self.world.player().pos);
+                let shown = strings.name("item", &def.name);
+                self.message = Some(strings.format("message.wished", &[("item", &shown)]));
+                self.world.cheat(wizard::Cheat::Wish(Box::new(item)));
+            }
+            None => self.message = Some(strings.format("message.no_such_item", &[("item", &name)])),
+        }
+    }
+
+    /// Hand over to the monsters, who act once `MONSTER_TURN_MS` has passed.
+    fn end_player_turn(&mut self) {
+        self.monster_turn_ms = Some(0.0);
+        self.idle_ms = 0.0;
+    }
+
+    /// In real time mode, wait a turn for the player once they've let one go by
+    /// without acting.
+    fn tick_real_time(&mut self, elapsed_ms: f64) {
+        if !self.config.real_time.enabled {
+            return;
+        }
+        self.idle_ms += elapsed_ms;
+        if self.idle_ms >= self.config.real_time.tick_ms && self.act(Action::Wait) {
+            self.end_player_turn();
+        }
+    }
+
+    /// Go on from picking a class to picking a race, or straight into the run if
+    /// there are no races to pick from.
+    fn choose_race(&mut self, seed: u64, class: Option<String>) {
+        if self.content.races.is_empty() {
+            self.choose_boons(seed, class, None);
+        } else {
+            self.mode = Mode::ChooseRace(seed, class, 0);
+        }
+    }
+
+    /// Go back to picking a class, with `class` under the cursor.
+    fn choose_class_again(&mut self, seed: u64, class: Option<&str>) {
+        let classes = classes::available(&self.content.classes, &self.profile.unlocks);
+        let index = classes.iter().position(|def| Some(def.name.as_str()) == class);
+        self.mode = Mode::ChooseClass(seed, index.unwrap_or(0));
+    }
+
+    /// Go on to picking boons, all of them picked to begin with, or straight into
+    /// the run if none have been unlocked.
+    fn choose_boons(&mut self, seed: u64, class: Option<String>, race: Option<String>) {
+        let boons = self.profile.boons();
+        if boons.is_empty() {
+            self.start_run(seed, class.as_deref(), race.as_deref(), &boons);
+        } else {
+            self.mode = Mode::ChooseBoons(seed, class, race, boons, 0);
+        }
+    }
+
+    /// Throw away the current run and start a fresh one from `seed`, playing as
+    /// `class` and `race` if they were picked, with the `unlocks` picked for it.
+    fn start_run(&mut self, seed: u64, class: Option<&str>, race: Option<&str>, unlocks: &BTreeSet<profile::Unlock>) {
+        let (world, unlocked) = new_run(seed, class, race, unlocks, &mut self.profile, &self.content);
+        self.world = world;
+        self.unlocked.clear();
+        self.terrain_key = None;
+        self.effects.clear();
+        self.input_buffer.clear();
+        let strings = &self.content.strings;
+        let messages: Vec<String> = feeling::describe(&self.world, strings)
+            .into_iter()
+            .chain(unlocked_messages(&unlocked, strings))
+            .collect();
+        self.message = (!messages.is_empty()).then(|| messages.join(" "));
+        self.mode = Mode::Playing;
+    }
+
+    /// Swap the finished run out for a fresh copy of its first level, which the
+    /// replay then plays the run's log back onto.
+    fn start_replay(&mut self) {
+        let (class, race) = (self.world.class.as_deref(), self.world.race.as_deref());
+        let start = generate_run(self.world.seed, &self.world.unlocks, class, race, &self.content);
+        let finished = std::mem::replace(&mut self.world, start);
+        self.terrain_key = None;
+        self.effects.clear();
+        self.mode = Mode::Replay(replay::Replay::new(finished));
+    }
+
+    fn end_replay(&mut self) {
+        if let Mode::Replay(replay) = std::mem::replace(&mut self.mode, Mode::GameOver) {
+            self.world = *replay.finished;
+            self.terrain_key = None;
+            self.effects.clear();
+        }
+    }
+
+    fn start_auto(&mut self, plan: auto::Plan) {
+        self.message = None;
+        self.auto = Some(auto::AutoAction::new(plan, &self.world));
+    }
+
+    /// Plan a walk to a visible floor tile; clicks anywhere else are ignored.
+    fn start_travel(&mut self, target: Vector) {
+        let world = &self.world;
+        if !world.is_visible(target) || !world.is_walkable(target) {
+            return;
+        }
+        let path = path::find_path(world.player().pos, target, |pos| world.step_cost(pos));
+        match path {
+            Some(path) => self.start_auto(auto::Plan::Walk(path.into())),
+            None => self.auto = None,
+        }
+    }
+
+    /// Go up or down the stairs the player is standing on, to a new level, which
+    /// may be in a side branch. The first level's stairs up lead out, but only
+    /// with the amulet.
+    fn take_stairs(&mut self) -> bool {
+        let (world, strings) = (&self.world, &self.content.strings);
+        let going_up = match world.tile_at(world.player().pos).map(|tile| tile.glyph) {
+            Some('>') => false,
+            Some('<') => true,
+            _ => {
+                self.message = Some(strings.get("message.no_stairs").to_string());
+                return false;
+            }
+        };
+        if going_up && world.depth <= 1 && world.branch.is_none() {
+            if !self.world.escape() {
+                self.message = Some(strings.get("message.need_amulet").to_string());
+            }
+            return false;
+        }
+        let (depth, branch, arrival) = branches::destination(world, &self.content.branches, going_up);
+        let (player, rng) = (world.player().clone(), world.rng.clone());
+        let section = dungeon::Section::of(depth, branch, &world.specials, &self.content);
+        let level = match world.visited_level(depth, branch.map(|def| def.name.as_str())) {
+            Some(level) => dungeon::revisit(level, player, rng, &world.artifacts, arrival),
+            None => dungeon::generate(depth, section, player, rng, &world.artifacts, arrival, &self.content),
+        };
+        self.world.change_level(&level);
+        self.effects.clear();
+        let feeling = feeling::describe(&self.world, strings);
+        let arrived = match branch {
+            Some(branch) => {
+                let branch = strings.name("branch", &branch.name);
+                strings.format("message.arrived_branch", &[("depth", &depth), ("branch", &branch)])
+            }
+            None => strings.format("message.arrived", &[("depth", &depth)]),
+        };
+        self.message = Some(feeling.map_or(arrived.clone(), |feeling| format!("{} {}", arrived, feeling)));
+        self.roll_happening();
+        true
+    }
+
+    /// Give each happening its chance on the current level, and tell the player
+    /// about the one that comes up, if any.
+    fn roll_happening(&mut self) {
+        let mut rng = self.world.rng.clone();
+        if let Some(def) = happenings::roll(&mut rng, &self.world, &self.content.happenings) {
+            let happening = happenings::prepare(&self.world, def, rng, &self.content);
+            self.bring_about(&happening);
+        }
+    }
+
+    /// Bring about every scheduled happening whose turn has come.
+    fn bring_due_happenings(&mut self) {
+        for name in std::mem::take(&mut self.world.due) {
+            if self.world.is_over() {
+                return;
+            }
+            match happenings::find(&self.content.happenings, &name) {
+                Some(def) => {
+                    let happening = happenings::prepare(&self.world, def, self.world.rng.clone(), &self.content);
+                    self.bring_about(&happening);
+                }
+                None => log::warn!(target: "generate", "There's no {} in the happening file to bring about", name),
+            }
+        }
+    }
+
+    /// Make `happening` happen and tell the player about it.
+    fn bring_about(&mut self, happening: &happenings::Happening) {
+        self.world.happen(happening);
+        let news = self.content.strings.name("happening", &happening.name);
+        self.message = Some(match self.message.take() {
+            Some(message) => format!("{} {}", message, news),
+            None => news.to_string(),
+        });
+    }
+
+    /// Walk to the nearest stairs the player has already seen.
+    fn start_travel_to_stairs(&mut self) {
+        let world = &self.world;
+        let start = world.player().pos;
+        let path = world
+            .known_stairs()
+            .into_iter()
+            .filter(|&stairs| stairs != start)
+            .filter_map(|stairs| path::find_path(start, stairs, |pos| world.step_cost(pos)))
+            .min_by_key(|path| path.len());
+        match path {
+            Some(path) => self.start_auto(auto::Plan::Walk(path.into())),
+            None => self.auto = None,
+        }
+    }
+
+    /// Rest until healed, for as long as that takes at the rate the player regenerates.
+    fn start_rest(&mut self) {
+        self.start_auto(auto::Plan::Rest {
+            turns_left: auto::AutoAction::rest_turns(&self.world),
+        });
+    }
+
+    /// Take the next step of an ongoing auto action, stopping early and saying why
+    /// if anything happened that the player should see.
+    fn step_auto(&mut self, elapsed_ms: f64) {
+        let interval_ms = self.config.key_repeat.interval_ms;
+        let auto = match self.auto.as_mut() {
+            Some(auto) => auto,
+            None => return,
+        };
+        if let Some(interruption) = auto.interruption(&self.world, &self.content.items) {
+            self.interrupt(interruption);
+            return;
+        }
+
+        let acted = match auto.next_step(elapsed_ms, interval_ms) {
+            Some(auto::Step::MoveTo(next)) => {
+                // Ice or a portal may have carried the player off the path
+                let delta = next - self.world.player().pos;
+                let adjacent = delta.x.abs() <= 1.0 && delta.y.abs() <= 1.0;
+                adjacent && self.world.creature_at(next).is_none() && self.world.move_player(delta)
+            }
+            Some(auto::Step::Wait) => {
+                // Resting eats whenever the player gets hungry; the watch stops it once nothing's left
+                let hungry = hunger::Hunger::of(self.world.satiety) != hunger::Hunger::Fed;
+                if let Some((index, food)) = hunger::meal(&self.world, &self.content.items).filter(|_| hungry) {
+                    self.world.use_item(index, &food.effects);
+                }
+                true
+            }
+            Some(auto::Step::Act(action)) => self.act(action),
+            None => {
+                return;
+            }
+        };
+        if !acted {
+            self.interrupt(interrupt::Interruption::Blocked);
+            return;
+        }
+        self.end_player_turn();
+        if self.auto.as_ref().is_some_and(|auto| auto.is_finished()) {
+            self.auto = None;
+        }
+    }
+
+    fn interrupt(&mut self, interruption: interrupt::Interruption) {
+        self.auto = None;
+        self.message = Some(interruption.message(&self.content.strings));
+    }
+}
+
+/// Start a fresh run with `unlocks` from the player's profile applied, counting
+/// it in the profile. Along with it come whatever unlocks starting it earned.
+fn new_run(
+    seed: u64,
+    class: Option<&str>,
+    race: Option<&str>,
+    unlocks: &BTreeSet<profile::Unlock>,
+    profile: &mut profile::Profile,
+    content: &data::Content,
+) -> (World, Vec<profile::Unlock>) {
+    let unlocked = profile.record_run_start();
+    profile.save();
+    (generate_run(seed, unlocks, class, race, content), unlocked)
+}
+
+/// What the player is told about each of the `unlocks` they've just earned.
+fn unlocked_messages(unlocks: &[profile::Unlock], strings: &strings::Strings) -> Vec<String> {
+    unlocks
+        .iter()
+        .map(|unlock| strings.format("message.unlocked", &[("unlock", &unlock.describe(strings))]))
+        .collect()
 }
 
-fn generate_entities() -> Vec<Entity> {
-    vec![
-        Entity {
-            pos: Vector::new(9,6),
-            glyph: 'g',
-            color: Color::RED,
-            hp: 1,
-            max_hp: 1,
-        },
-        Entity {
-            pos: Vector::new(9,6),
-            glyph: 'g',
-            color: Color::RED,
-            hp:1,
-            max_hp: 1,
-        },
-        Entity {
-            pos: Vector::new(2,4),
-            glyph: 'g',
-            color: Color::RED,
-            hp:1,
-            max_hp: 1,
-        },
-        Entity {
-            pos: Vector::new(7,5),
-            glyph: '%',
-            color: Color::PURPLE,
-            hp:0,
-            max_hp: 0,
-        },
-        Entity {
-            pos: Vector::new(4,8),
-            glyph: '%',
-            color: Color::PURPLE,
-            hp:0,
-            max_hp: 0,
-        },
-    ]
+/// Build a run's first level. The same seed, class, race and unlocks always give
+/// the same world.
+fn generate_run(
+    seed: u64,
+    unlocks: &BTreeSet<profile::Unlock>,
+    class: Option<&str>,
+    race: Option<&str>,
+    content: &data::Content,
+) -> World {
+    log::debug!(target: "generate", seed = seed; "new run");
+    let mut rng = rng::Rng::new(seed);
+    let color = content.glyphs.color("player");
+    // Without a class the player knows every spell, as they did before there were classes
+    let mut player = Entity {
+        max_hp: 5,
+        max_mana: 5,
+        mana: 5,
+        gold: shops::STARTING_GOLD,
+        spells: content.spells.iter().map(|spell| spell.name.clone()).collect(),
+        animation: Some(animation::Animation::bob('@', color)),
+        name: Some(names::generate(&mut rng)),
+        ..Entity::new(Vector::new(5,3), '@', color, 3)
+    };
+    let class = class.and_then(|name| classes::find(&content.classes, name));
+    if let Some(class) = class {
+        class.apply(&mut player, &content.items);
+    }
+    let race = race.and_then(|name| races::find(&content.races, name));
+    if let Some(race) = race {
+        race.apply(&mut player);
+    }
+    let artifacts = BTreeSet::new();
+    let main = dungeon::Section::Main;
+    let mut world = dungeon::generate(1, main, player, rng, &artifacts, dungeon::Arrival::Descending, content);
+    world.specials = specials::plan(&mut world.rng, &content.specials);
+    world.seed = seed;
+    world.class = class.map(|class| class.name.clone());
+    world.race = race.map(|race| race.name.clone());
+    world.attribute_points = attributes::CREATION_POINTS;
+    profile::apply(unlocks, &mut world);
+    world
 }
+
 fn main() {
+    logging::init();
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(index) = args.iter().position(|arg| arg == "--bench") {
+        bench::run(&args[index + 1..]);
+        return;
+    }
     std::env::set_var("WINIT_HIDPI_FACTOR", "1.0");
     let settings = Settings {
         scale: quicksilver::graphics::ImageScaleStrategy::Blur,
+        resize: quicksilver::graphics::ResizeStrategy::Stretch,
+        min_size: Some(Vector::new(480, 360)),
         ..Default::default()
     };
     run::<Game>("Rogue Like", Vector::new(800,600), settings);
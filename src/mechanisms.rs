@@ -0,0 +1,48 @@
+use crate::world::Tile;
+use quicksilver::geom::Vector;
+use serde::{Deserialize, Serialize};
+
+/// A lever, worked by walking into it. Nothing can stand on one.
+pub const LEVER: char = '|';
+
+/// A pressure plate, worked by anything that steps onto it.
+pub const PLATE: char = '_';
+
+/// A portcullis that's down, which stops creatures and sight like a wall.
+pub const PORTCULLIS: char = '}';
+
+/// An armed trap, which hurts whatever steps onto it.
+pub const TRAP: char = '^';
+
+/// How much a trap hurts whatever steps onto it.
+pub const TRAP_DAMAGE: i32 = 3;
+
+/// How a mechanism is worked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Trigger {
+    Lever,
+    Plate,
+}
+
+impl Trigger {
+    /// The kind of mechanism drawn as `glyph`, if it's one at all.
+    pub fn drawn_as(glyph: char) -> Option<Trigger> {
+        match glyph {
+            LEVER => Some(Trigger::Lever),
+            PLATE => Some(Trigger::Plate),
+            _ => None,
+        }
+    }
+}
+
+/// A lever or plate on a level, and the tiles it switches each time it's worked,
+/// such as a portcullis going up, traps being disarmed or a wall opening onto a
+/// hidden passage. Working it again switches them back.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Mechanism {
+    pub pos: Vector,
+    pub trigger: Trigger,
+    /// How each tile it's linked to looks switched the other way, traded places
+    /// with the one on the map each time it's worked.
+    pub switches: Vec<Tile>,
+}
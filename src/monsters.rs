@@ -0,0 +1,116 @@
+use crate::{
+    animation::Animation,
+    data,
+    spells::DamageType,
+    world::{Entity, NORMAL_SPEED},
+};
+use quicksilver::{geom::Vector, graphics::Color};
+use serde::{Deserialize, Serialize};
+
+pub const MONSTERS_FILE: &str = "monsters.json";
+
+/// The definitions the game was built with, for when the file can't be read.
+const BUILT_IN: &str = include_str!("../static/monsters.json");
+
+/// How long a venomous monster's hit keeps its target poisoned.
+pub const VENOM_TURNS: u32 = 3;
+
+/// How long a slowing monster's hit keeps its target slowed.
+pub const CHILL_TURNS: u32 = 4;
+
+/// How a monster spends its turn once it has seen the player.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Ai {
+    /// Walks up to the player and attacks.
+    #[default]
+    Chase,
+    /// Holds its ground, attacking only when the player comes next to it.
+    Guard,
+    /// Minds a shop, trading with the player instead of fighting. Robbing the shop
+    /// or hurting the keeper turns it into a chaser.
+    Shopkeeper,
+    /// Keeps to itself and talks to a player who walks into it. Hurting it turns
+    /// it into a chaser.
+    Friendly,
+}
+
+impl Ai {
+    /// Whether it leaves the player alone until crossed.
+    pub fn is_peaceful(self) -> bool {
+        matches!(self, Ai::Shopkeeper | Ai::Friendly)
+    }
+}
+
+/// Something a monster can do beyond walking and hitting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Ability {
+    /// Heals one hp on every turn it doesn't attack.
+    Regenerate,
+    /// Its hits poison for `VENOM_TURNS` turns, unless the target resists poison.
+    Venomous,
+    /// Its hits slow for `CHILL_TURNS` turns.
+    Slowing,
+}
+
+/// One kind of monster, as written in `static/monsters.json`. Where it spawns
+/// and what it drops are in the loot tables, which refer to it by name.
+#[derive(Clone, Debug, Deserialize)]
+pub struct MonsterDef {
+    pub name: String,
+    pub glyph: char,
+    pub color: Color,
+    pub hp: i32,
+    #[serde(default)]
+    pub ai: Ai,
+    #[serde(default)]
+    pub abilities: Vec<Ability>,
+    #[serde(default)]
+    pub resists: Vec<DamageType>,
+    /// What it says when the player walks into it, from the dialogue file.
+    #[serde(default)]
+    pub dialogue: Option<String>,
+    /// A one-of-a-kind monster, placed by hand rather than spawned. Bosses are
+    /// always named, and count as elites.
+    #[serde(default)]
+    pub boss: bool,
+    /// How much energy it gets each tick; `NORMAL_SPEED` is as fast as the player.
+    #[serde(default = "normal_speed")]
+    pub speed: u32,
+}
+
+fn normal_speed() -> u32 {
+    NORMAL_SPEED
+}
+
+impl MonsterDef {
+    pub fn entity(&self, pos: Vector) -> Entity {
+        Entity {
+            animation: Some(Animation::bob(self.glyph, self.color)),
+            ai: self.ai,
+            abilities: self.abilities.clone(),
+            resists: self.resists.clone(),
+            dialogue: self.dialogue.clone(),
+            kind: Some(self.name.clone()),
+            elite: self.boss,
+            speed: self.speed,
+            ..Entity::new(pos, self.glyph, self.color, self.hp)
+        }
+    }
+}
+
+impl data::Named for MonsterDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// The definition of the monster called `name`.
+pub fn find<'a>(monsters: &'a [MonsterDef], name: &str) -> Option<&'a MonsterDef> {
+    monsters.iter().find(|monster| monster.name == name)
+}
+
+/// Read the monster definitions, so new monsters can be added by editing the file
+/// or in a mod.
+pub fn load(problems: &mut Vec<data::Problem>) -> Vec<MonsterDef> {
+    data::load_defs(MONSTERS_FILE, BUILT_IN, problems)
+}
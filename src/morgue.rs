@@ -0,0 +1,25 @@
+use crate::{data::Content, screenshot, sheet, world::World};
+
+/// Where morgue files are written, under `static/`.
+const MORGUE_DIR: &str = "morgue";
+
+/// Write an account of the finished run to a text file named for when it ended:
+/// how it ended, how deep and how long it went, then the character sheet. Returns
+/// the file's name.
+pub fn write(world: &World, name: &str, ended: &str, content: &Content) -> Result<String, String> {
+    let strings = &content.strings;
+    let (day, hour, minute) = world.clock();
+    let time = format!("{:02}:{:02}", hour, minute);
+    let mut lines = vec![
+        ended.to_string(),
+        strings.format("morgue.depth", &[("depth", &world.deepest())]),
+        strings.format("morgue.turns", &[("turns", &world.turn()), ("day", &day), ("time", &time)]),
+        String::new(),
+    ];
+    lines.extend(sheet::lines(world, name, &content.items, strings));
+
+    std::fs::create_dir_all(MORGUE_DIR).map_err(|err| format!("{}: {}", MORGUE_DIR, err))?;
+    let file = format!("{}/{}.txt", MORGUE_DIR, screenshot::timestamp());
+    std::fs::write(&file, lines.join("\n") + "\n").map_err(|err| format!("{}: {}", file, err))?;
+    Ok(file)
+}
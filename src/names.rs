@@ -0,0 +1,28 @@
+use crate::rng::Rng;
+
+const ONSETS: &[&str] = &[
+    "b", "d", "g", "k", "m", "n", "r", "s", "t", "v", "z", "br", "dr", "gr", "kr", "th", "sk", "vr",
+];
+const VOWELS: &[&str] = &["a", "e", "i", "o", "u", "ae", "au", "ei", "ou"];
+/// Empty codas are listed more than once so open syllables come up more often.
+const CODAS: &[&str] = &["", "", "", "n", "r", "l", "k", "th", "sh", "x", "g"];
+
+fn choose<'a>(options: &[&'a str], rng: &mut Rng) -> &'a str {
+    options[rng.range(0, options.len() as u32) as usize]
+}
+
+/// A made-up name of two or three syllables, such as "Grauthek" or "Voumi".
+pub fn generate(rng: &mut Rng) -> String {
+    let syllables = rng.range(2, 4);
+    let mut name = String::new();
+    for _ in 0..syllables {
+        name.push_str(choose(ONSETS, rng));
+        name.push_str(choose(VOWELS, rng));
+        name.push_str(choose(CODAS, rng));
+    }
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => name,
+    }
+}
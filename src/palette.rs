@@ -0,0 +1,101 @@
+use crate::{
+    input::{typed_char, Action, KeyBindings},
+    strings::Strings,
+};
+use quicksilver::input::{ButtonState, Key, Keyboard};
+
+/// What the palette wants done after an update.
+pub enum PaletteResult {
+    Open,
+    Closed,
+    Chosen(Action),
+}
+
+/// A searchable list of every command, for actions without a key or whose key the
+/// player has forgotten. It's a text field, so it reads keys directly instead of
+/// going through the bindings.
+#[derive(Clone, Debug, Default)]
+pub struct CommandPalette {
+    query: String,
+    selected: usize,
+}
+
+impl CommandPalette {
+    const MAX_SHOWN: usize = 10;
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn update(&mut self, keyboard: &Keyboard, strings: &Strings) -> PaletteResult {
+        let pressed = |key| keyboard[key] == ButtonState::Pressed;
+        if pressed(Key::Escape) {
+            return PaletteResult::Closed;
+        } else if pressed(Key::Return) || pressed(Key::NumpadEnter) {
+            return match self.matches(strings).get(self.selected) {
+                Some(&action) => PaletteResult::Chosen(action),
+                None => PaletteResult::Closed,
+            };
+        } else if pressed(Key::Up) {
+            self.selected = self.selected.saturating_sub(1);
+        } else if pressed(Key::Down) {
+            self.selected = (self.selected + 1).min(self.matches(strings).len().saturating_sub(1));
+        } else if pressed(Key::Back) {
+            self.query.pop();
+            self.selected = 0;
+        } else if let Some(c) = typed_char(keyboard) {
+            self.query.push(c);
+            self.selected = 0;
+        }
+        PaletteResult::Open
+    }
+
+    /// Commands matching the query, best match first.
+    pub fn matches(&self, strings: &Strings) -> Vec<Action> {
+        let mut scored: Vec<(i32, Action)> = Action::all()
+            .into_iter()
+            .filter(|action| !matches!(action, Action::Confirm | Action::Cancel | Action::CommandPalette | Action::WatchReplay))
+            .filter_map(|action| fuzzy_score(&self.query, &action.label(strings)).map(|score| (score, action)))
+            .collect();
+        // Stable, so equally good matches keep their usual order
+        scored.sort_by_key(|&(score, _)| -score);
+        scored.into_iter().map(|(_, action)| action).collect()
+    }
+
+    pub fn lines(&self, bindings: &KeyBindings, strings: &Strings) -> Vec<String> {
+        let mut lines = vec![format!(":{}_", self.query)];
+        for (index, action) in self.matches(strings).into_iter().enumerate().take(Self::MAX_SHOWN) {
+            let marker = if index == self.selected { "> " } else { "  " };
+            let key = bindings.first_key_name(action);
+            if key.is_empty() {
+                lines.push(format!("{}{}", marker, action.label(strings)));
+            } else {
+                lines.push(format!("{}{} ({})", marker, action.label(strings), key));
+            }
+        }
+        lines
+    }
+}
+
+/// Score how well `query` fuzzily matches `text`, or `None` if its letters don't all
+/// appear in order. Consecutive letters and letters starting a word score higher.
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut last_match: Option<usize> = None;
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = next + text[next..].iter().position(|&t| t == c)?;
+        score += 1;
+        if found == 0 || text[found - 1] == ' ' {
+            score += 3;
+        }
+        if last_match == Some(found.wrapping_sub(1)) {
+            score += 2;
+        }
+        score -= (found - next) as i32 / 4;
+        last_match = Some(found);
+        next = found + 1;
+    }
+    Some(score)
+}
@@ -0,0 +1,104 @@
+use crate::{rng::Rng, weather::Weather};
+use quicksilver::{
+    geom::{Circle, Rectangle, Transform, Vector},
+    graphics::{Background::Col, Color},
+    lifecycle::Window,
+};
+
+/// How many particles show `weather` at once.
+fn particle_count(weather: Weather) -> usize {
+    match weather {
+        Weather::Clear => 0,
+        Weather::Rain => 120,
+        Weather::Fog => 12,
+        Weather::Ash => 60,
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Particle {
+    /// Position within the overlay's area, in pixels.
+    pos: Vector,
+    /// Pixels per millisecond.
+    velocity: Vector,
+    size: f32,
+}
+
+/// Particles drifting over the map to show the weather. Purely visual, so it
+/// keeps its own random numbers rather than touching the run's.
+pub struct WeatherOverlay {
+    weather: Weather,
+    particles: Vec<Particle>,
+    rng: Rng,
+}
+
+impl WeatherOverlay {
+    pub fn new() -> WeatherOverlay {
+        WeatherOverlay {
+            weather: Weather::Clear,
+            particles: Vec::new(),
+            rng: Rng::new(0),
+        }
+    }
+
+    /// Move the particles on by `elapsed_ms`, wrapping them around inside `area`,
+    /// and start over with fresh ones if the weather has changed.
+    pub fn update(&mut self, weather: Weather, area: Rectangle, elapsed_ms: f64) {
+        if weather != self.weather {
+            self.weather = weather;
+            self.particles.clear();
+        }
+        while self.particles.len() < particle_count(weather) {
+            let particle = self.spawn(area.size);
+            self.particles.push(particle);
+        }
+        let size = area.size;
+        for particle in &mut self.particles {
+            particle.pos += particle.velocity * elapsed_ms as f32;
+            particle.pos.x = particle.pos.x.rem_euclid(size.x.max(1.0));
+            particle.pos.y = particle.pos.y.rem_euclid(size.y.max(1.0));
+        }
+    }
+
+    fn spawn(&mut self, size: Vector) -> Particle {
+        let rng = &mut self.rng;
+        let pos = Vector::new(rng.range_f32(0.0, size.x), rng.range_f32(0.0, size.y));
+        let (velocity, size) = match self.weather {
+            Weather::Clear => (Vector::ZERO, 0.0),
+            Weather::Rain => (Vector::new(-0.12, rng.range_f32(0.5, 0.7)), rng.range_f32(8.0, 14.0)),
+            Weather::Fog => (Vector::new(rng.range_f32(0.005, 0.02), 0.0), rng.range_f32(60.0, 120.0)),
+            Weather::Ash => (
+                Vector::new(rng.range_f32(-0.01, 0.01), rng.range_f32(0.02, 0.05)),
+                rng.range_f32(2.0, 4.0),
+            ),
+        };
+        Particle { pos, velocity, size }
+    }
+
+    pub fn draw(&self, window: &mut Window, area: Rectangle, z: f32) {
+        for particle in &self.particles {
+            let pos = area.pos + particle.pos;
+            match self.weather {
+                Weather::Clear => {}
+                Weather::Rain => window.draw_ex(
+                    &Rectangle::new(pos, (1.5, particle.size)),
+                    Col(Color::from_rgba(120, 140, 170, 0.5)),
+                    Transform::rotate(12),
+                    z,
+                ),
+                Weather::Fog => window.draw_ex(
+                    &Circle::new(pos, particle.size),
+                    Col(Color::WHITE.with_alpha(0.06)),
+                    Transform::IDENTITY,
+                    z,
+                ),
+                Weather::Ash => window.draw_ex(
+                    &Rectangle::new(pos, (particle.size, particle.size)),
+                    Col(Color::from_rgba(110, 110, 110, 0.7)),
+                    Transform::IDENTITY,
+                    z,
+                ),
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use quicksilver::geom::Vector;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+const NEIGHBORS: [(i32, i32); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+/// Find the cheapest 8-directional path from `start` to `goal` with A*.
+///
+/// `cost` gives the price of stepping onto a tile, or `None` if it can't be entered.
+/// Diagonal steps may not cut past a blocked corner, same as player movement.
+/// The returned path excludes `start` and ends at `goal`.
+pub fn find_path(start: Vector, goal: Vector, cost: impl Fn(Vector) -> Option<u32>) -> Option<Vec<Vector>> {
+    let start = (start.x as i32, start.y as i32);
+    let goal = (goal.x as i32, goal.y as i32);
+    let heuristic = |(x, y): (i32, i32)| ((x - goal.0).abs().max((y - goal.1).abs())) as u32;
+    let tile_cost = |(x, y): (i32, i32)| cost(Vector::new(x, y));
+
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<(i32, i32), (i32, i32)> = HashMap::new();
+    let mut best: HashMap<(i32, i32), u32> = HashMap::new();
+    best.insert(start, 0);
+    open.push(Reverse((heuristic(start), start)));
+
+    while let Some(Reverse((_, current))) = open.pop() {
+        if current == goal {
+            let mut path = vec![Vector::new(current.0, current.1)];
+            let mut node = current;
+            while let Some(&prev) = came_from.get(&node) {
+                if prev == start {
+                    break;
+                }
+                path.push(Vector::new(prev.0, prev.1));
+                node = prev;
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        let current_cost = best[&current];
+        for &(dx, dy) in NEIGHBORS.iter() {
+            let next = (current.0 + dx, current.1 + dy);
+            let step = match tile_cost(next) {
+                Some(step) => step,
+                None => continue,
+            };
+            let diagonal = dx != 0 && dy != 0;
+            if diagonal
+                && (tile_cost((current.0 + dx, current.1)).is_none()
+                    || tile_cost((current.0, current.1 + dy)).is_none())
+            {
+                continue;
+            }
+            let next_cost = current_cost + step;
+            if best.get(&next).is_none_or(|&known| next_cost < known) {
+                best.insert(next, next_cost);
+                came_from.insert(next, current);
+                open.push(Reverse((next_cost + heuristic(next), next)));
+            }
+        }
+    }
+    None
+}
+
+/// How many steps every reachable tile is from `start`, by the same rules as
+/// `find_path`: a Dijkstra map, for seeing how far things are for anything
+/// pathing across the level.
+pub fn distance_map(start: Vector, cost: impl Fn(Vector) -> Option<u32>) -> HashMap<(i32, i32), u32> {
+    let start = (start.x as i32, start.y as i32);
+    let tile_cost = |(x, y): (i32, i32)| cost(Vector::new(x, y));
+
+    let mut open = BinaryHeap::new();
+    let mut best: HashMap<(i32, i32), u32> = HashMap::new();
+    best.insert(start, 0);
+    open.push(Reverse((0, start)));
+
+    while let Some(Reverse((current_cost, current))) = open.pop() {
+        if current_cost > best[&current] {
+            continue;
+        }
+        for &(dx, dy) in NEIGHBORS.iter() {
+            let next = (current.0 + dx, current.1 + dy);
+            let step = match tile_cost(next) {
+                Some(step) => step,
+                None => continue,
+            };
+            let diagonal = dx != 0 && dy != 0;
+            if diagonal
+                && (tile_cost((current.0 + dx, current.1)).is_none()
+                    || tile_cost((current.0, current.1 + dy)).is_none())
+            {
+                continue;
+            }
+            let next_cost = current_cost + step;
+            if best.get(&next).is_none_or(|&known| next_cost < known) {
+                best.insert(next, next_cost);
+                open.push(Reverse((next_cost, next)));
+            }
+        }
+    }
+    best
+}
@@ -0,0 +1,111 @@
+use crate::{classes::Talent, data, races::Trait, strings::Strings, world::Entity};
+use serde::{Deserialize, Serialize};
+
+pub const PERKS_FILE: &str = "perks.json";
+
+/// The definitions the game was built with, for when the file can't be read.
+const BUILT_IN: &str = include_str!("../static/perks.json");
+
+/// The player gets to pick a perk on every level that's a multiple of this.
+pub const PERK_LEVELS: u32 = 2;
+
+/// What taking a perk does for the player.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PerkEffect {
+    MaxHp(i32),
+    MaxMana(i32),
+    Attack(i32),
+    Defense(i32),
+    Talent(Talent),
+    Trait(Trait),
+    /// Learn the spell of this name from the spell file.
+    Spell(String),
+}
+
+/// One perk, as written in `static/perks.json`. Perks that require others form a
+/// tree: a perk is only on offer once everything it requires has been taken.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PerkDef {
+    pub name: String,
+    #[serde(default)]
+    pub requires: Vec<String>,
+    pub effects: Vec<PerkEffect>,
+}
+
+impl PerkDef {
+    /// Give `player` the perk, and everything it does.
+    pub fn apply(&self, player: &mut Entity) {
+        for effect in &self.effects {
+            match effect {
+                PerkEffect::MaxHp(hp) => {
+                    player.max_hp += hp;
+                    player.hp += hp;
+                }
+                PerkEffect::MaxMana(mana) => {
+                    player.max_mana += mana;
+                    player.mana += mana;
+                }
+                PerkEffect::Attack(bonus) => player.attack += bonus,
+                PerkEffect::Defense(bonus) => player.defense += bonus,
+                PerkEffect::Talent(talent) => player.talents.push(*talent),
+                PerkEffect::Trait(race_trait) => player.traits.push(*race_trait),
+                PerkEffect::Spell(spell) => player.spells.push(spell.clone()),
+            }
+        }
+        player.perks.push(self.name.clone());
+    }
+
+    /// Whether `player` could take the perk: they haven't yet, they have what it
+    /// requires, and it would give them something they don't already have.
+    pub fn is_open_to(&self, player: &Entity) -> bool {
+        let taken = |name: &String| player.perks.contains(name);
+        let useful = self.effects.iter().any(|effect| match effect {
+            PerkEffect::Talent(talent) => !player.talents.contains(talent),
+            PerkEffect::Trait(race_trait) => !player.traits.contains(race_trait),
+            PerkEffect::Spell(spell) => !player.spells.contains(spell),
+            _ => true,
+        });
+        !taken(&self.name) && self.requires.iter().all(taken) && useful
+    }
+
+    /// A one-line summary for the perk menu, such as "iron skin: +1 defense".
+    pub fn describe(&self, strings: &Strings) -> String {
+        let details: Vec<String> = self
+            .effects
+            .iter()
+            .map(|effect| match effect {
+                PerkEffect::MaxHp(hp) => strings.format("effect.max_hp", &[("amount", hp)]),
+                PerkEffect::MaxMana(mana) => strings.format("effect.max_mana", &[("amount", mana)]),
+                PerkEffect::Attack(bonus) => strings.format("effect.attack", &[("amount", bonus)]),
+                PerkEffect::Defense(bonus) => strings.format("effect.defense", &[("amount", bonus)]),
+                PerkEffect::Talent(talent) => talent.describe(strings).to_string(),
+                PerkEffect::Trait(race_trait) => race_trait.describe(strings),
+                PerkEffect::Spell(spell) => strings.format("effect.spell", &[("spell", &strings.name("spell", spell))]),
+            })
+            .collect();
+        let name = strings.name("perk", &self.name);
+        strings.format("perk.description", &[("perk", &name), ("details", &details.join(", "))])
+    }
+}
+
+impl data::Named for PerkDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// The definition of the perk called `name`.
+pub fn find<'a>(perks: &'a [PerkDef], name: &str) -> Option<&'a PerkDef> {
+    perks.iter().find(|perk| perk.name == name)
+}
+
+/// The perks `player` could take now, in file order.
+pub fn open_to<'a>(perks: &'a [PerkDef], player: &Entity) -> Vec<&'a PerkDef> {
+    perks.iter().filter(|perk| perk.is_open_to(player)).collect()
+}
+
+/// Read the perk definitions, so the tree can be changed by editing the file or in
+/// a mod.
+pub fn load(problems: &mut Vec<data::Problem>) -> Vec<PerkDef> {
+    data::load_defs(PERKS_FILE, BUILT_IN, problems)
+}
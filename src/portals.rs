@@ -0,0 +1,66 @@
+use crate::{animation::Animation, glyphs::Glyphs, spawn, world::World};
+use log::debug;
+use quicksilver::geom::Vector;
+use serde::{Deserialize, Serialize};
+
+/// A portal, which sends whatever steps onto it out of the one it's linked to.
+/// Drawn as a shimmering `O` unless the glyphs file says otherwise.
+pub const PORTAL: char = 'Ω';
+
+/// The chance a level gets a pair of portals.
+const PORTAL_CHANCE: f32 = 0.3;
+
+/// How far apart the generator puts the two ends of a pair, at the least, so
+/// going through is worth it.
+const MIN_SPAN: f32 = 12.0;
+
+/// How many spots to try for the far end before leaving a level without portals.
+const PLACE_ATTEMPTS: u32 = 10;
+
+/// How far a portal lights up its surroundings, in tiles.
+const LIGHT_RADIUS: u32 = 1;
+
+/// One end of a pair of portals on a level.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Portal {
+    pub pos: Vector,
+    /// Where whatever steps onto it comes out, on the other end.
+    pub exit: Vector,
+}
+
+/// Link the portals at `a` and `b` on `world`, each sending whatever steps onto it
+/// out of the other.
+pub fn link(world: &mut World, a: Vector, b: Vector) {
+    world.portals.push(Portal { pos: a, exit: b });
+    world.portals.push(Portal { pos: b, exit: a });
+}
+
+/// Maybe open a linked pair of portals on `world`, drawn with `glyphs`, on floor
+/// away from the player and well apart from each other.
+pub fn place(world: &mut World, glyphs: &Glyphs) {
+    if world.rng.next_f32() >= PORTAL_CHANCE {
+        return;
+    }
+    let a = match spawn::free_spot(world) {
+        Some(pos) => pos,
+        None => return,
+    };
+    let b = (0..PLACE_ATTEMPTS)
+        .filter_map(|_| spawn::free_spot(world))
+        .find(|pos| pos.distance(a) >= MIN_SPAN);
+    let b = match b {
+        Some(pos) => pos,
+        None => return,
+    };
+    debug!(target: "generate", x = a.x, y = a.y, exit_x = b.x, exit_y = b.y; "placed portals");
+    let color = glyphs.color("portal");
+    for pos in [a, b] {
+        if let Some(tile) = world.tile_at_mut(pos) {
+            tile.glyph = PORTAL;
+            tile.color = color;
+            tile.animation = Some(Animation::flicker(PORTAL, color));
+            tile.light_radius = LIGHT_RADIUS;
+        }
+    }
+    link(world, a, b);
+}
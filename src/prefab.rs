@@ -0,0 +1,480 @@
+use crate::{
+    animation::Animation,
+    boulders::{self, BOULDER},
+    data::{self, Content, Problem},
+    glyphs::Glyphs,
+    hazards::{DEEP_WATER, ICE, LAVA, LAVA_LIGHT_RADIUS},
+    items,
+    locks::{self, Lock},
+    mechanisms::{self, Mechanism, Trigger},
+    monsters::{self, Ai},
+    names,
+    shops::Shop,
+    world::{Entity, Tile, World},
+};
+use log::{debug, warn};
+use std::path::Path;
+use quicksilver::geom::Vector;
+
+/// Where prefab files live, under `static/`.
+pub const PREFAB_DIR: &str = "prefabs";
+
+/// The prefabs the game was built with, for when the folder can't be read.
+const BUILT_IN: &[(&str, &str)] = &[
+    ("arena.txt", include_str!("../static/prefabs/arena.txt")),
+    ("caravan.txt", include_str!("../static/prefabs/caravan.txt")),
+    ("guardroom.txt", include_str!("../static/prefabs/guardroom.txt")),
+    ("hut.txt", include_str!("../static/prefabs/hut.txt")),
+    ("lair.txt", include_str!("../static/prefabs/lair.txt")),
+    ("pond.txt", include_str!("../static/prefabs/pond.txt")),
+    ("rockfall.txt", include_str!("../static/prefabs/rockfall.txt")),
+    ("shop.txt", include_str!("../static/prefabs/shop.txt")),
+    ("treasury.txt", include_str!("../static/prefabs/treasury.txt")),
+    ("vault.txt", include_str!("../static/prefabs/vault.txt")),
+];
+
+/// The prefab the last level is built around, which is never stamped anywhere else.
+pub const FINAL_PREFAB: &str = "lair.txt";
+
+/// The tiles a prefab can draw, and a link can switch one to.
+const TILES: &[char] = &[
+    '#',
+    '.',
+    '~',
+    DEEP_WATER,
+    LAVA,
+    ICE,
+    mechanisms::LEVER,
+    mechanisms::PLATE,
+    mechanisms::PORTCULLIS,
+    mechanisms::TRAP,
+];
+
+/// How many spots to try for a prefab before leaving a level without one.
+const PLACE_ATTEMPTS: u32 = 20;
+
+/// Whether a placement is a monster, an item or a locked door.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Monster,
+    Item,
+    /// A locked door, named after the key that opens it.
+    Door,
+}
+
+impl Kind {
+    pub fn keyword(self) -> &'static str {
+        match self {
+            Kind::Monster => "monster",
+            Kind::Item => "item",
+            Kind::Door => "door",
+        }
+    }
+}
+
+/// A monster or item a prefab puts in place, by name and position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Placement {
+    pub kind: Kind,
+    pub name: String,
+    pub pos: Vector,
+    /// The line of the file it's on, for pointing at it if the name is wrong.
+    pub line: usize,
+}
+
+/// A tile that a prefab's lever or plate switches each time it's worked, by the
+/// column and row of both in the drawing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Link {
+    pub trigger: Vector,
+    pub target: Vector,
+    /// What the tile is switched to, and back from.
+    pub glyph: char,
+    pub line: usize,
+}
+
+/// A room or vault drawn by hand as ASCII art, stamped onto generated levels.
+///
+/// `#` is wall, `.` is floor, `~` is water, `≈` deep water, `≋` lava and `░` ice,
+/// `|` a lever, `_` a pressure plate, `}` a portcullis, `^` a trap and `0` a
+/// boulder on floor. A space leaves whatever the generator put there, and lines
+/// starting with `;` are comments. Lines such as `monster 3 2 goblin` or `item 1 1
+/// leather armour` put a monster or item at that column and row of the drawing,
+/// and `door 4 8 iron key` a door there that the iron key opens, with the key
+/// somewhere on the level the player can get to first. A line such as `link 1 6 4
+/// 5 .` has the lever or plate at column 1, row 6 switch the tile at column 4, row
+/// 5 to floor and back.
+#[derive(Clone, Debug)]
+pub struct Prefab {
+    pub name: String,
+    rows: Vec<Vec<char>>,
+    pub placements: Vec<Placement>,
+    pub links: Vec<Link>,
+}
+
+impl Prefab {
+    pub fn new(name: &str, rows: Vec<Vec<char>>, placements: Vec<Placement>) -> Prefab {
+        Prefab {
+            name: name.to_string(),
+            rows,
+            placements,
+            links: Vec::new(),
+        }
+    }
+
+    /// Read the prefab in `text`, which came from the file `name`.
+    pub fn parse(name: &str, text: &str) -> Result<Prefab, Problem> {
+        let mut rows = Vec::new();
+        let mut placements = Vec::new();
+        let mut links = Vec::new();
+        for (index, line) in text.lines().enumerate().filter(|(_, line)| !line.starts_with(';')) {
+            let problem = |message: String| Problem::new(name, Some(index + 1), message);
+            if let Some(rest) = line.strip_prefix("link ") {
+                let words: Vec<&str> = rest.split_whitespace().collect();
+                let numbers: Vec<u32> = words.iter().filter_map(|word| word.parse().ok()).take(4).collect();
+                let glyph = words.get(4).and_then(|word| word.chars().next());
+                match (numbers.as_slice(), glyph) {
+                    (&[x, y, target_x, target_y], Some(glyph)) if words.len() == 5 => links.push(Link {
+                        trigger: Vector::new(x as f32, y as f32),
+                        target: Vector::new(target_x as f32, target_y as f32),
+                        glyph,
+                        line: index + 1,
+                    }),
+                    _ => return Err(problem("expected 'link <column> <row> <column> <row> <tile>'".to_string())),
+                }
+                continue;
+            }
+            let placement = [Kind::Monster, Kind::Item, Kind::Door]
+                .into_iter()
+                .find_map(|kind| Some((kind, line.strip_prefix(kind.keyword())?.strip_prefix(' ')?)));
+            if let Some((kind, rest)) = placement {
+                let mut words = rest.trim().splitn(3, ' ');
+                let mut coordinate = || words.next().and_then(|word| word.parse::<u32>().ok());
+                let (x, y) = match (coordinate(), coordinate()) {
+                    (Some(x), Some(y)) => (x, y),
+                    _ => return Err(problem(format!("expected '{} <column> <row> <name>'", kind.keyword()))),
+                };
+                let name = words.next().unwrap_or("").trim().to_string();
+                if name.is_empty() {
+                    return Err(problem(format!("no name for the {}", kind.keyword())));
+                }
+                let pos = Vector::new(x as f32, y as f32);
+                placements.push(Placement { kind, name, pos, line: index + 1 });
+                continue;
+            }
+            let row: Vec<char> = line.trim_end().chars().collect();
+            let known = |glyph: char| glyph == ' ' || glyph == BOULDER || TILES.contains(&glyph);
+            if let Some(glyph) = row.iter().find(|&&glyph| !known(glyph)) {
+                return Err(problem(format!("unknown tile '{}'", glyph)));
+            }
+            rows.push(row);
+        }
+        if rows.iter().all(|row| row.is_empty()) {
+            return Err(Problem::new(name, None, "no tiles".to_string()));
+        }
+        let mut prefab = Prefab::new(name, rows, placements);
+        prefab.links = links;
+        let size = prefab.size();
+        let outside = prefab.placements.iter().find(|placement| placement.pos.x >= size.x || placement.pos.y >= size.y);
+        if let Some(outside) = outside {
+            let message = format!("{} is outside the drawing", outside.name);
+            return Err(Problem::new(name, Some(outside.line), message));
+        }
+        for link in &prefab.links {
+            let problem = |message: &str| Err(Problem::new(name, Some(link.line), message.to_string()));
+            if prefab.glyph_at(link.trigger).and_then(Trigger::drawn_as).is_none() {
+                return problem("the link doesn't start from a lever or plate");
+            }
+            if prefab.glyph_at(link.target).is_none_or(|glyph| glyph == ' ') {
+                return problem("the link goes to a tile the drawing leaves alone");
+            }
+            if !TILES.contains(&link.glyph) {
+                return problem(&format!("the link switches to unknown tile '{}'", link.glyph));
+            }
+        }
+        Ok(prefab)
+    }
+
+    /// The prefab written out the way `parse` reads it.
+    pub fn text(&self) -> String {
+        let mut lines: Vec<String> = self.rows.iter().map(|row| row.iter().collect()).collect();
+        lines.extend(self.placements.iter().map(|placement| {
+            let Placement { kind, name, pos, .. } = placement;
+            format!("{} {} {} {}", kind.keyword(), pos.x, pos.y, name)
+        }));
+        lines.extend(self.links.iter().map(|link| {
+            let Link { trigger, target, glyph, .. } = link;
+            format!("link {} {} {} {} {}", trigger.x, trigger.y, target.x, target.y, glyph)
+        }));
+        lines.join("\n") + "\n"
+    }
+
+    /// Whether this came from a file called `file`, in whichever folder.
+    pub fn is(&self, file: &str) -> bool {
+        Path::new(&self.name).file_name().is_some_and(|name| name == file)
+    }
+
+    /// Whether this is the last level's prefab.
+    pub fn is_final(&self) -> bool {
+        self.is(FINAL_PREFAB)
+    }
+
+    fn size(&self) -> Vector {
+        let width = self.rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        Vector::new(width as f32, self.rows.len() as f32)
+    }
+
+    /// What the drawing has at `pos`, if it reaches that far.
+    fn glyph_at(&self, pos: Vector) -> Option<char> {
+        self.rows.get(pos.y as usize)?.get(pos.x as usize).copied()
+    }
+
+    /// Every tile the prefab sets, relative to its top left corner.
+    fn tiles(&self) -> impl Iterator<Item = (Vector, char)> + '_ {
+        self.rows.iter().enumerate().flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|&(_, &glyph)| glyph != ' ')
+                .map(move |(x, &glyph)| (Vector::new(x as f32, y as f32), glyph))
+        })
+    }
+}
+
+/// Read every prefab in the prefab folder and in each mod's, so rooms can be drawn
+/// without a rebuild. A mod's prefab replaces the one with the same file name. A
+/// broken file is left out, and the built-in prefabs are used if the folder is
+/// missing.
+pub fn load(problems: &mut Vec<Problem>) -> Vec<Prefab> {
+    let mut prefabs = read_folder(PREFAB_DIR, problems).unwrap_or_else(|mut problem| {
+        problem.message += ", so the built-in prefabs are used instead";
+        problems.push(problem);
+        BUILT_IN
+            .iter()
+            .map(|(name, text)| Prefab::parse(name, text).expect("the built-in prefabs are valid"))
+            .collect()
+    });
+    for folder in data::mod_files(PREFAB_DIR) {
+        let mod_prefabs = read_folder(&folder, problems).unwrap_or_else(|problem| {
+            problems.push(problem);
+            Vec::new()
+        });
+        for prefab in mod_prefabs {
+            let file_name = |prefab: &Prefab| Path::new(&prefab.name).file_name().map(|name| name.to_owned());
+            match prefabs.iter().position(|existing| file_name(existing) == file_name(&prefab)) {
+                Some(index) => prefabs[index] = prefab,
+                None => prefabs.push(prefab),
+            }
+        }
+    }
+    prefabs
+}
+
+/// The prefabs in `folder` that could be read, with a problem for each that couldn't.
+fn read_folder(folder: &str, problems: &mut Vec<Problem>) -> Result<Vec<Prefab>, Problem> {
+    let mut files: Vec<_> = std::fs::read_dir(folder)
+        .map_err(|err| Problem::new(folder, None, err.to_string()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "txt"))
+        .collect();
+    files.sort();
+    let mut prefabs = Vec::new();
+    for path in files {
+        let name = path.display().to_string();
+        let prefab = std::fs::read_to_string(&path)
+            .map_err(|err| Problem::new(&name, None, err.to_string()))
+            .and_then(|text| Prefab::parse(&name, &text));
+        match prefab {
+            Ok(prefab) => prefabs.push(prefab),
+            Err(mut problem) => {
+                problem.message += ", so the prefab is left out";
+                problems.push(problem);
+            }
+        }
+    }
+    Ok(prefabs)
+}
+
+/// Turn `tile` into the prefab tile drawn as `glyph`.
+pub fn paint(tile: &mut Tile, glyph: char, glyphs: &Glyphs) {
+    tile.glyph = glyph;
+    tile.color = glyphs.color(match glyph {
+        '#' => "wall",
+        '~' => "water",
+        DEEP_WATER => "deep_water",
+        LAVA => "lava",
+        ICE => "ice",
+        '+' => "door",
+        mechanisms::LEVER | mechanisms::PLATE => "mechanism",
+        mechanisms::PORTCULLIS => "portcullis",
+        mechanisms::TRAP => "trap",
+        _ => "floor",
+    });
+    tile.animation = match glyph {
+        '~' | DEEP_WATER => Some(Animation::ripple(glyph, tile.color)),
+        LAVA => Some(Animation::flicker(glyph, tile.color)),
+        _ => None,
+    };
+    tile.light_radius = if glyph == LAVA { LAVA_LIGHT_RADIUS } else { 0 };
+}
+
+/// The monster or item `placement` names, or the key to the door it locks,
+/// standing at `pos`; `None` if there's no such thing in the data files.
+pub fn entity(placement: &Placement, pos: Vector, content: &Content) -> Option<Entity> {
+    match placement.kind {
+        Kind::Monster => monsters::find(&content.monsters, &placement.name).map(|monster| monster.entity(pos)),
+        Kind::Item => items::find(&content.items, &placement.name).map(|item| item.entity(pos)),
+        Kind::Door => items::find(&content.items, &placement.name).filter(|item| item.key).map(|item| item.entity(pos)),
+    }
+}
+
+/// Stamp one of the prefabs somewhere on a freshly generated level, clear of the
+/// outer wall, the stairs and the player's starting spot, along with the monsters
+/// and items it places. The last level's prefab is left for the last level,
+/// special levels' prefabs for theirs, and happenings' for them.
+pub fn place(world: &mut World, content: &Content) {
+    let reserved = |prefab: &Prefab| {
+        prefab.is_final()
+            || content.specials.iter().any(|special| special.uses(prefab))
+            || content.happenings.iter().any(|happening| happening.uses(prefab))
+    };
+    let prefabs: Vec<&Prefab> = content.prefabs.iter().filter(|prefab| !reserved(prefab)).collect();
+    if prefabs.is_empty() {
+        return;
+    }
+    let prefab = prefabs[world.rng.range(0, prefabs.len() as u32) as usize];
+    place_anywhere(world, prefab, content);
+}
+
+/// Stamp the prefab from the file called `file` the way `place` would. Returns
+/// `false` if there's no such prefab or no room for it.
+pub fn place_named(world: &mut World, file: &str, content: &Content) -> bool {
+    match content.prefabs.iter().find(|prefab| prefab.is(file)) {
+        Some(prefab) => place_anywhere(world, prefab, content),
+        None => {
+            warn!(target: "generate", "There's no prefab called {}", file);
+            false
+        }
+    }
+}
+
+/// Stamp `prefab` somewhere clear on `world`, returning whether there was room.
+fn place_anywhere(world: &mut World, prefab: &Prefab, content: &Content) -> bool {
+    let size = prefab.size();
+    let free = world.map_size - size - Vector::new(2, 2);
+    if free.x < 0.0 || free.y < 0.0 {
+        warn!(target: "generate", "{} is too big for the level", prefab.name);
+        return false;
+    }
+
+    for _ in 0..PLACE_ATTEMPTS {
+        let x = world.rng.range(1, free.x as u32 + 2);
+        let y = world.rng.range(1, free.y as u32 + 2);
+        let origin = Vector::new(x as f32, y as f32);
+        let covers = |pos: Vector| {
+            pos.x >= origin.x && pos.y >= origin.y && pos.x < origin.x + size.x && pos.y < origin.y + size.y
+        };
+        let stairs = world.map.iter().any(|tile| matches!(tile.glyph, '>' | '<') && covers(tile.pos));
+        // The player, and anything else already on the level, stays where it is
+        if stairs || world.entities.iter().any(|entity| covers(entity.pos)) {
+            continue;
+        }
+        stamp(world, prefab, origin, content);
+        return true;
+    }
+    debug!(target: "generate", prefab = prefab.name.as_str(); "found no room for the prefab");
+    false
+}
+
+/// Build the last level around its prefab, against the right-hand wall, well away
+/// from the stairs up. Returns `false` if there's no such prefab or it won't fit,
+/// for the level to make do with an ordinary one.
+pub fn place_final(world: &mut World, content: &Content) -> bool {
+    let prefab = match content.prefabs.iter().find(|prefab| prefab.is_final()) {
+        Some(prefab) => prefab,
+        None => return false,
+    };
+    let size = prefab.size();
+    let free = world.map_size - size - Vector::new(2, 2);
+    if free.x < 0.0 || free.y < 0.0 {
+        warn!(target: "generate", "{} is too big for the level", prefab.name);
+        return false;
+    }
+    let origin = Vector::new(free.x + 1.0, (free.y / 2.0).floor() + 1.0);
+    stamp(world, prefab, origin, content);
+    true
+}
+
+/// Stamp `prefab` onto `world` with its top left corner at `origin`, along with
+/// its boulders, the monsters, items and locked doors it places, the keys to the
+/// doors and the mechanisms it links up.
+/// Peaceful monsters and bosses get names of their own. A prefab with a
+/// shopkeeper in it is a shop, and the items in it are for sale.
+fn stamp(world: &mut World, prefab: &Prefab, origin: Vector, content: &Content) {
+    let size = prefab.size();
+    for (offset, glyph) in prefab.tiles() {
+        if let Some(tile) = world.tile_at_mut(origin + offset) {
+            paint(tile, if glyph == BOULDER { '.' } else { glyph }, &content.glyphs);
+        }
+        if glyph == BOULDER {
+            world.entities.push(boulders::boulder(origin + offset, content.glyphs.color("boulder")));
+        }
+    }
+    for link in &prefab.links {
+        let (trigger, target) = (origin + link.trigger, origin + link.target);
+        let mut switched = match world.tile_at(target) {
+            Some(tile) => tile.clone(),
+            None => continue,
+        };
+        paint(&mut switched, link.glyph, &content.glyphs);
+        match world.mechanisms.iter_mut().find(|mechanism| mechanism.pos == trigger) {
+            Some(mechanism) => mechanism.switches.push(switched),
+            None => {
+                let glyph = world.tile_at(trigger).map_or(' ', |tile| tile.glyph);
+                if let Some(kind) = Trigger::drawn_as(glyph) {
+                    world.mechanisms.push(Mechanism {
+                        pos: trigger,
+                        trigger: kind,
+                        switches: vec![switched],
+                    });
+                }
+            }
+        }
+    }
+    let mut keys = Vec::new();
+    for placement in &prefab.placements {
+        let pos = origin + placement.pos;
+        let mut entity = match entity(placement, pos, content) {
+            Some(entity) => entity,
+            None => continue,
+        };
+        if placement.kind == Kind::Door {
+            if let Some(tile) = world.tile_at_mut(pos) {
+                paint(tile, '+', &content.glyphs);
+            }
+            world.locks.push(Lock {
+                pos,
+                key: placement.name.clone(),
+            });
+            keys.push(entity);
+            continue;
+        }
+        if entity.ai.is_peaceful() && placement.kind == Kind::Monster {
+            entity.name = Some(names::generate(&mut world.rng));
+        }
+        if entity.elite && placement.kind == Kind::Monster {
+            let (strings, name) = (&content.strings, names::generate(&mut world.rng));
+            let monster = strings.name("monster", &placement.name);
+            entity.name = Some(strings.format("name.elite", &[("name", &name), ("monster", &monster)]));
+        }
+        if entity.ai == Ai::Shopkeeper && placement.kind == Kind::Monster {
+            world.shops.push(Shop::new(world.entities.len(), origin, size));
+        }
+        world.entities.push(entity);
+    }
+    // Only once every door is in place can it be told where's on the near side of them
+    for key in keys {
+        locks::place_key(world, key);
+    }
+    debug!(target: "generate", prefab = prefab.name.as_str(), x = origin.x, y = origin.y; "stamped a prefab");
+    world.update_fov();
+}
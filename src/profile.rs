@@ -0,0 +1,177 @@
+use crate::{
+    save,
+    strings::Strings,
+    world::{Entity, World},
+};
+use log::info;
+use quicksilver::{geom::Vector, graphics::Color, saving};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+const PROFILE_NAME: &str = "profile";
+
+/// How much more gold a wealthy player starts with.
+const WEALTH: u32 = 100;
+
+/// Something earned across runs that changes how new runs start.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Unlock {
+    /// One extra point of max hp.
+    Hardy,
+    /// A ration waiting next to the player.
+    Provisioned,
+    /// The classes in the class file that name it can be picked.
+    Paladin,
+    /// A potion of healing in the player's pack.
+    Heirloom,
+    /// `WEALTH` more gold to start with.
+    Wealthy,
+}
+
+impl Unlock {
+    /// Whether it's a boon the player can choose to start a run with or without,
+    /// rather than something that's simply on offer from then on.
+    pub fn is_boon(self) -> bool {
+        !matches!(self, Unlock::Paladin)
+    }
+
+    pub fn describe(self, strings: &Strings) -> &str {
+        strings.get(match self {
+            Unlock::Hardy => "unlock.hardy",
+            Unlock::Provisioned => "unlock.provisioned",
+            Unlock::Paladin => "unlock.paladin",
+            Unlock::Heirloom => "unlock.heirloom",
+            Unlock::Wealthy => "unlock.wealthy",
+        })
+    }
+}
+
+/// An achievement and the unlock it grants once reached.
+struct Achievement {
+    name: &'static str,
+    unlock: Unlock,
+    reached: fn(&Profile) -> bool,
+}
+
+const ACHIEVEMENTS: &[Achievement] = &[
+    Achievement {
+        name: "Persistent",
+        unlock: Unlock::Hardy,
+        reached: |profile| profile.runs_started >= 3,
+    },
+    Achievement {
+        name: "Regular",
+        unlock: Unlock::Provisioned,
+        reached: |profile| profile.runs_started >= 10,
+    },
+    Achievement {
+        name: "Delver",
+        unlock: Unlock::Paladin,
+        reached: |profile| profile.deepest >= 5,
+    },
+    Achievement {
+        name: "Spelunker",
+        unlock: Unlock::Heirloom,
+        reached: |profile| profile.deepest >= 8,
+    },
+    Achievement {
+        name: "Victor",
+        unlock: Unlock::Wealthy,
+        reached: |profile| profile.wins >= 1,
+    },
+];
+
+/// Account-level progress that outlives any single run.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default)]
+    pub runs_started: u32,
+    /// The deepest any run has been.
+    #[serde(default)]
+    pub deepest: u32,
+    #[serde(default)]
+    pub wins: u32,
+    #[serde(default)]
+    pub unlocks: BTreeSet<Unlock>,
+}
+
+impl Profile {
+    pub fn load() -> Profile {
+        match saving::load::<Profile>(save::APP_NAME, PROFILE_NAME).map_err(save::SaveError::from) {
+            Ok(profile) => profile,
+            Err(save::SaveError::NotFound) => Profile::default(),
+            Err(err) => {
+                eprintln!("Using a fresh profile: {}", err);
+                Profile::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = saving::save(save::APP_NAME, PROFILE_NAME, self) {
+            eprintln!("Failed to save the profile: {}", err);
+        }
+    }
+
+    /// Count a new run and grant the unlocks of any achievements it completes,
+    /// returning them for the player to be told.
+    pub fn record_run_start(&mut self) -> Vec<Unlock> {
+        self.runs_started += 1;
+        self.grant()
+    }
+
+    /// Count how far the finished `run` got and grant the unlocks of any
+    /// achievements that completes, returning them for the player to be told. Runs
+    /// with wizard mode used don't count.
+    pub fn record_run_end(&mut self, run: &World) -> Vec<Unlock> {
+        if run.wizard {
+            return Vec::new();
+        }
+        self.deepest = self.deepest.max(run.deepest());
+        if run.won {
+            self.wins += 1;
+        }
+        self.grant()
+    }
+
+    /// Grant the unlocks of any achievements newly reached, returning them.
+    fn grant(&mut self) -> Vec<Unlock> {
+        let mut granted = Vec::new();
+        for achievement in ACHIEVEMENTS {
+            if (achievement.reached)(self) && self.unlocks.insert(achievement.unlock) {
+                let (name, unlock) = (achievement.name, achievement.unlock);
+                info!(target: "profile", achievement = name, unlock:? = unlock; "achievement reached");
+                granted.push(unlock);
+            }
+        }
+        granted
+    }
+
+    /// The boons unlocked so far, for the player to pick from for a new run.
+    pub fn boons(&self) -> BTreeSet<Unlock> {
+        self.unlocks.iter().copied().filter(|unlock| unlock.is_boon()).collect()
+    }
+}
+
+/// Start `run` with `unlocks`, the ones picked for it from the profile.
+pub fn apply(unlocks: &BTreeSet<Unlock>, run: &mut World) {
+    run.unlocks = unlocks.clone();
+    let player_pos = run.entities[run.player_id].pos;
+    for unlock in unlocks {
+        let player = &mut run.entities[run.player_id];
+        match unlock {
+            Unlock::Hardy => {
+                player.max_hp += 1;
+                player.hp += 1;
+            }
+            Unlock::Provisioned => run.entities.push(Entity {
+                item: Some("ration".to_string()),
+                ..Entity::new(player_pos + Vector::new(1, 0), '%', Color::PURPLE, 0)
+            }),
+            Unlock::Heirloom => player.inventory.push("potion of healing".to_string()),
+            Unlock::Wealthy => player.gold += WEALTH,
+            // Only opens up a class to pick
+            Unlock::Paladin => {}
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use crate::spells::SpellDef;
+use quicksilver::{geom::Vector, graphics::Color};
+
+/// How long a projectile takes to cross one tile.
+const STEP_MS: f64 = 30.0;
+
+/// What happens when a projectile lands.
+#[derive(Clone, Debug)]
+pub enum Payload {
+    /// A dart, hitting whoever it reached, if anyone.
+    Dart { target: Option<usize> },
+    /// A spell, going off the way it was aimed.
+    Spell { spell: SpellDef, target: Vector },
+}
+
+/// Something thrown or cast, in flight. The turn it was thrown on only finishes once it
+/// lands, so the player sees it travel before the damage shows up.
+#[derive(Clone, Debug)]
+pub struct Projectile {
+    pub glyph: char,
+    pub color: Color,
+    /// The tiles it passes over, ending where it lands.
+    path: Vec<Vector>,
+    pub payload: Payload,
+    elapsed_ms: f64,
+}
+
+impl Projectile {
+    pub fn new(glyph: char, color: Color, path: Vec<Vector>, payload: Payload) -> Projectile {
+        Projectile {
+            glyph,
+            color,
+            path,
+            payload,
+            elapsed_ms: 0.0,
+        }
+    }
+
+    /// Move the projectile along, returning whether it has landed.
+    pub fn advance(&mut self, elapsed_ms: f64) -> bool {
+        self.elapsed_ms += elapsed_ms;
+        self.elapsed_ms >= self.path.len() as f64 * STEP_MS
+    }
+
+    /// The tile the projectile is over right now.
+    pub fn pos(&self) -> Option<Vector> {
+        let step = (self.elapsed_ms / STEP_MS) as usize;
+        self.path.get(step.min(self.path.len().saturating_sub(1))).copied()
+    }
+}
@@ -0,0 +1,36 @@
+use crate::{quests::Goal, strings::Strings, world::World};
+
+/// The quest log: every quest the player has taken on, what it asks of them and
+/// how far along it is, as lines for the panel.
+pub fn lines(world: &World, strings: &Strings) -> Vec<String> {
+    let mut lines = vec![strings.get("quests.title").to_string()];
+    if world.quests.is_empty() {
+        lines.push(strings.get("quests.none").to_string());
+    }
+    for quest in &world.quests {
+        let name = strings.name("quest", &quest.def.name);
+        let goal = match &quest.def.goal {
+            Goal::Kill { monster, count } => {
+                let monster = strings.name("monster", monster);
+                let kills = quest.kills.min(*count);
+                strings.format(
+                    "quests.kill",
+                    &[("quest", &name), ("monster", &monster), ("kills", &kills), ("count", count)],
+                )
+            }
+            Goal::Fetch { item, depth } => {
+                let item = strings.name("item", item);
+                strings.format("quests.fetch", &[("quest", &name), ("item", &item), ("depth", depth)])
+            }
+        };
+        let giver = world.entities[quest.giver].name.clone().unwrap_or_default();
+        lines.push(if quest.rewarded {
+            strings.format("quests.rewarded", &[("goal", &goal)])
+        } else if quest.is_done(world.player()) {
+            strings.format("quests.done", &[("goal", &goal), ("giver", &giver)])
+        } else {
+            goal
+        });
+    }
+    lines
+}
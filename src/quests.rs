@@ -0,0 +1,112 @@
+use crate::{
+    data,
+    items::{self, ItemDef},
+    world::Entity,
+};
+use quicksilver::geom::Vector;
+use serde::{Deserialize, Serialize};
+
+pub const QUESTS_FILE: &str = "quests.json";
+
+/// The definitions the game was built with, for when the file can't be read.
+const BUILT_IN: &str = include_str!("../static/quests.json");
+
+/// What the player has to do to complete a quest.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Goal {
+    /// Kill this many of the monster of this name from the monster file.
+    Kill { monster: String, count: u32 },
+    /// Bring back the item of this name, which lies somewhere on the level at
+    /// this depth.
+    Fetch { item: String, depth: u32 },
+}
+
+/// What the quest giver hands over once the quest is done.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Reward {
+    Gold(u32),
+    /// The item of this name from the item file.
+    Item(String),
+    Xp(u32),
+}
+
+/// One quest, as written in `static/quests.json`. Quests are handed out by NPCs
+/// in dialogue, and rewarded by the same NPC when the player next talks to them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QuestDef {
+    pub name: String,
+    pub goal: Goal,
+    #[serde(default)]
+    pub rewards: Vec<Reward>,
+    /// The node the giver's dialogue picks up from once the quest is rewarded.
+    #[serde(default)]
+    pub after: Option<String>,
+}
+
+impl data::Named for QuestDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A quest the player has taken on. The definition is kept whole, so a save or
+/// replay doesn't depend on the quest file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Quest {
+    pub def: QuestDef,
+    /// The NPC who gave it, by entity on the level at `depth`.
+    pub giver: usize,
+    #[serde(default = "first_depth")]
+    pub depth: u32,
+    /// Monsters of the kind to kill killed since taking it on.
+    #[serde(default)]
+    pub kills: u32,
+    #[serde(default)]
+    pub rewarded: bool,
+}
+
+impl Quest {
+    pub fn new(def: QuestDef, giver: usize, depth: u32) -> Quest {
+        Quest {
+            def,
+            giver,
+            depth,
+            kills: 0,
+            rewarded: false,
+        }
+    }
+
+    /// Whether `player` has done what the quest asks, and can go back for the reward.
+    pub fn is_done(&self, player: &Entity) -> bool {
+        match &self.def.goal {
+            Goal::Kill { count, .. } => self.kills >= *count,
+            Goal::Fetch { item, .. } => player.inventory.contains(item),
+        }
+    }
+}
+
+fn first_depth() -> u32 {
+    1
+}
+
+/// The item to put down somewhere on a level at `depth` for `quest`, if that's
+/// where it's fetched from. Where exactly is up to whoever puts it down.
+pub fn item_at(quest: &QuestDef, depth: u32, items: &[ItemDef]) -> Option<Entity> {
+    match &quest.goal {
+        Goal::Fetch { item, depth: from } if *from == depth => {
+            items::find(items, item).map(|item| item.entity(Vector::new(0, 0)))
+        }
+        _ => None,
+    }
+}
+
+/// The definition of the quest called `name`.
+pub fn find<'a>(quests: &'a [QuestDef], name: &str) -> Option<&'a QuestDef> {
+    quests.iter().find(|quest| quest.name == name)
+}
+
+/// Read the quest definitions, so quests can be written or changed by editing the
+/// file or in a mod.
+pub fn load(problems: &mut Vec<data::Problem>) -> Vec<QuestDef> {
+    data::load_defs(QUESTS_FILE, BUILT_IN, problems)
+}
@@ -0,0 +1,91 @@
+use crate::{
+    data,
+    spells::DamageType,
+    strings::Strings,
+    world::{Entity, NORMAL_SPEED},
+};
+use serde::{Deserialize, Serialize};
+
+pub const RACES_FILE: &str = "races.json";
+
+/// The definitions the game was built with, for when the file can't be read.
+const BUILT_IN: &str = include_str!("../static/races.json");
+
+/// How far away infravision picks out creatures through walls and darkness, in tiles.
+pub const INFRAVISION_RADIUS: f32 = 6.0;
+
+/// How much more energy a quick player gets each tick.
+pub const QUICK_SPEED: u32 = 5;
+
+/// Something the player is born with, which the world checks for where it applies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Trait {
+    /// Creatures within `INFRAVISION_RADIUS` show up even out of sight.
+    Infravision,
+    /// Gets `QUICK_SPEED` more energy each tick, so acts more often.
+    Quick,
+}
+
+impl Trait {
+    pub fn describe(self, strings: &Strings) -> String {
+        match self {
+            Trait::Infravision => strings.get("trait.infravision").to_string(),
+            Trait::Quick => strings.format("trait.quick", &[("percent", &(QUICK_SPEED * 100 / NORMAL_SPEED))]),
+        }
+    }
+}
+
+/// One race the player can be, as written in `static/races.json`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct RaceDef {
+    pub name: String,
+    /// Added to the player's max hp, after their class has set it.
+    #[serde(default)]
+    pub hp: i32,
+    #[serde(default)]
+    pub resists: Vec<DamageType>,
+    #[serde(default)]
+    pub traits: Vec<Trait>,
+}
+
+impl RaceDef {
+    /// Make `player` one of this race, on top of whatever class they are.
+    pub fn apply(&self, player: &mut Entity) {
+        player.max_hp += self.hp;
+        player.hp += self.hp;
+        player.resists.extend(&self.resists);
+        player.traits.extend(&self.traits);
+    }
+
+    /// A one-line summary for the race menu, such as "dwarf: resists poison,
+    /// infravision".
+    pub fn describe(&self, strings: &Strings) -> String {
+        let mut details = Vec::new();
+        if self.hp != 0 {
+            details.push(strings.format("race.hp", &[("hp", &format!("{:+}", self.hp))]));
+        }
+        details.extend(self.resists.iter().map(|resist| {
+            strings.format("race.resists", &[("damage_type", &resist.name(strings))])
+        }));
+        details.extend(self.traits.iter().map(|race_trait| race_trait.describe(strings)));
+        let name = strings.name("race", &self.name);
+        strings.format("race.description", &[("race", &name), ("details", &details.join(", "))])
+    }
+}
+
+impl data::Named for RaceDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// The definition of the race called `name`.
+pub fn find<'a>(races: &'a [RaceDef], name: &str) -> Option<&'a RaceDef> {
+    races.iter().find(|race| race.name == name)
+}
+
+/// Read the race definitions, so races can be changed or added by editing the file
+/// or in a mod.
+pub fn load(problems: &mut Vec<data::Problem>) -> Vec<RaceDef> {
+    data::load_defs(RACES_FILE, BUILT_IN, problems)
+}
@@ -0,0 +1,180 @@
+use crate::{
+    alchemy::Mixture,
+    attributes::Attribute,
+    crafting::RecipeDef,
+    dialogue::DialogueEffect,
+    gods::{GodDef, Offering, Prayer},
+    happenings::{Happening, Scheduled},
+    items::ItemEffect,
+    perks::PerkDef,
+    quests::QuestDef,
+    rng::Rng,
+    shops::Trade,
+    spells::SpellDef,
+    wizard::Cheat,
+    world::{Entity, World},
+};
+use quicksilver::geom::Vector;
+use serde::{Deserialize, Serialize};
+
+/// How long a replay lingers on each command at normal speed.
+const STEP_MS: f64 = 120.0;
+
+/// How many times faster than normal a replay can go.
+const MAX_SPEED: u32 = 16;
+
+/// A change made to the world during a run. Generating the level from the run's
+/// seed and redoing these in order gets back to exactly the same world.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Command {
+    MovePlayer(Vector),
+    RangedAttack(usize),
+    /// The spell is kept whole, so replays don't depend on the spell file.
+    Cast(Box<SpellDef>, Vector),
+    MonsterTurns,
+    Cheat(Cheat),
+    /// The perk is kept whole, like spells are.
+    TakePerk(Box<PerkDef>),
+    Raise(Attribute),
+    PickUp,
+    /// A trade at the shop with this index.
+    Trade(usize, Trade),
+    /// An answer to the NPC with this id, kept as what it did.
+    Talk(usize, Vec<DialogueEffect>),
+    /// A quest from the NPC with this id, kept whole like perks, with the item to
+    /// fetch if it lies on this level.
+    StartQuest(usize, Box<QuestDef>, Option<Box<Entity>>),
+    /// Handing in the quest at this index.
+    RewardQuest(usize),
+    /// Going up or down to a new level, kept whole since generating it takes the
+    /// data files.
+    ChangeLevel(Box<World>),
+    Escape,
+    /// An offering to a god, kept whole like perks.
+    Sacrifice(Box<GodDef>, Offering),
+    /// A prayer to the god of this name, kept whole too.
+    Pray(String, Box<Prayer>),
+    /// The recipe is kept whole as well.
+    Craft(Box<RecipeDef>),
+    /// Using up the inventory item at this index, kept as what it did.
+    UseItem(usize, Vec<ItemEffect>),
+    /// Mixing the inventory items at these indices, kept as what came of it.
+    Mix(usize, usize, Mixture),
+    /// Something happening to the level, kept as everything it changed.
+    Happen(Box<Happening>),
+    /// A monster coming back to the level, kept whole along with where the level's
+    /// random choices carry on from.
+    Respawn(Box<Entity>, Rng),
+    /// A monster coming back to the level at this index of the visited ones, kept
+    /// the same way.
+    RespawnKept(usize, Box<Entity>, Rng),
+    Schedule(Scheduled),
+}
+
+impl Command {
+    pub fn apply(&self, world: &mut World) {
+        match *self {
+            Command::MovePlayer(delta) => {
+                world.move_player(delta);
+            }
+            Command::RangedAttack(target) => world.ranged_attack(target),
+            Command::Cast(ref spell, target) => world.cast(spell, target),
+            Command::MonsterTurns => world.monster_turns(),
+            Command::Cheat(ref cheat) => world.cheat(cheat.clone()),
+            Command::TakePerk(ref perk) => world.take_perk(perk),
+            Command::Raise(attribute) => world.raise(attribute),
+            Command::PickUp => {
+                world.pick_up();
+            }
+            Command::Trade(shop, trade) => {
+                world.trade(shop, trade);
+            }
+            Command::Talk(speaker, ref effects) => world.talk(speaker, effects),
+            Command::StartQuest(giver, ref quest, ref item) => world.start_quest(giver, quest, item.as_deref()),
+            Command::RewardQuest(quest) => world.reward_quest(quest),
+            Command::ChangeLevel(ref level) => world.change_level(level),
+            Command::Escape => {
+                world.escape();
+            }
+            Command::Sacrifice(ref god, offering) => {
+                world.sacrifice(god, offering);
+            }
+            Command::Pray(ref god, ref prayer) => {
+                world.pray(god, prayer);
+            }
+            Command::Craft(ref recipe) => {
+                world.craft(recipe);
+            }
+            Command::UseItem(index, ref effects) => world.use_item(index, effects),
+            Command::Mix(first, second, ref mixture) => world.mix(first, second, mixture),
+            Command::Happen(ref happening) => world.happen(happening),
+            Command::Respawn(ref monster, ref rng) => world.respawn(monster, rng),
+            Command::RespawnKept(level, ref monster, ref rng) => world.respawn_kept(level, monster, rng),
+            Command::Schedule(ref scheduled) => world.schedule(scheduled.clone()),
+        }
+    }
+}
+
+/// A finished run being played back one command at a time, on a world that
+/// started out fresh from the run's seed. It can be paused, stepped through one
+/// command at a time and sped up, to look closely at how something happened.
+pub struct Replay {
+    /// The run as it really ended, to go back to afterwards.
+    pub finished: Box<World>,
+    next: usize,
+    elapsed_ms: f64,
+    pub paused: bool,
+    /// How many times faster than normal it plays.
+    pub speed: u32,
+}
+
+impl Replay {
+    pub fn new(finished: World) -> Replay {
+        Replay {
+            finished: Box::new(finished),
+            next: 0,
+            elapsed_ms: 0.0,
+            paused: false,
+            speed: 1,
+        }
+    }
+
+    /// How many commands have been redone, and how many there are in all.
+    pub fn progress(&self) -> (usize, usize) {
+        (self.next, self.finished.log.len())
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed * 2).min(MAX_SPEED);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = (self.speed / 2).max(1);
+    }
+
+    /// Redo however many commands are due after `elapsed_ms` more, unless paused.
+    /// Returns whether the replay has reached the end of the run.
+    pub fn advance(&mut self, world: &mut World, elapsed_ms: f64) -> bool {
+        if self.paused {
+            return false;
+        }
+        self.elapsed_ms += elapsed_ms * self.speed as f64;
+        while self.elapsed_ms >= STEP_MS {
+            self.elapsed_ms -= STEP_MS;
+            if !self.step(world) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Redo just the next command, returning `false` if there are none left.
+    pub fn step(&mut self, world: &mut World) -> bool {
+        match self.finished.log.get(self.next) {
+            Some(command) => command.apply(world),
+            None => return false,
+        }
+        self.next += 1;
+        true
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A seed taken from the clock, for runs the player didn't pick a seed for.
+pub fn random_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos() as u64)
+}
+
+/// A small, fast xorshift random number generator. Not fit for anything secret,
+/// but cheap, seedable and serializable.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // Zero would get stuck at zero, so mix the seed first
+        let mut rng = Rng {
+            state: seed ^ 0x9E37_79B9_7F4A_7C15,
+        };
+        if rng.state == 0 {
+            rng.state = 1;
+        }
+        rng.next_u64();
+        rng
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// An integer in `low..high`.
+    pub fn range(&mut self, low: u32, high: u32) -> u32 {
+        low + (self.next_u64() % u64::from(high - low)) as u32
+    }
+
+    /// A float in `0.0..1.0`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// A float in `low..high`.
+    pub fn range_f32(&mut self, low: f32, high: f32) -> f32 {
+        low + (high - low) * self.next_f32()
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Rng::new(0)
+    }
+}
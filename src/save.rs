@@ -0,0 +1,112 @@
+use crate::world::World;
+use quicksilver::saving;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+pub const APP_NAME: &str = "rogue-like";
+const SAVE_PROFILE: &str = "save";
+
+/// The format version written into every save file.
+///
+/// Adding a field with a `#[serde(default)]` doesn't need a bump, but renaming or
+/// reshaping anything does, together with a new entry in `MIGRATIONS`. The save
+/// tests load a save of the first version to keep the first half of that true.
+pub const SAVE_VERSION: u32 = 1;
+
+/// Saves older than this can't be migrated and are rejected outright.
+const MIN_SUPPORTED_VERSION: u32 = 1;
+
+/// Entry `i` upgrades the raw JSON from `MIN_SUPPORTED_VERSION + i` to the next version.
+const MIGRATIONS: &[fn(&mut Value)] = &[];
+
+#[derive(Serialize)]
+struct SaveFile<'a> {
+    version: u32,
+    data: &'a World,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    NotFound,
+    Storage(saving::SaveError),
+    Corrupt(serde_json::Error),
+    Incompatible { found: u32 },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::NotFound => write!(f, "no save file found"),
+            SaveError::Storage(err) => write!(f, "could not access save file: {}", err),
+            SaveError::Corrupt(err) => write!(f, "save file is corrupt: {}", err),
+            SaveError::Incompatible { found } => write!(
+                f,
+                "save file version {} is not supported (expected {} to {})",
+                found, MIN_SUPPORTED_VERSION, SAVE_VERSION
+            ),
+        }
+    }
+}
+
+impl From<saving::SaveError> for SaveError {
+    fn from(err: saving::SaveError) -> SaveError {
+        match err {
+            saving::SaveError::IOError(ref io) if io.kind() == std::io::ErrorKind::NotFound => {
+                SaveError::NotFound
+            }
+            saving::SaveError::SaveNotFound(_) => SaveError::NotFound,
+            err => SaveError::Storage(err),
+        }
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(err: serde_json::Error) -> SaveError {
+        SaveError::Corrupt(err)
+    }
+}
+
+pub fn save(data: &World) -> Result<(), SaveError> {
+    saving::save_raw(APP_NAME, SAVE_PROFILE, &to_bytes(data)?)?;
+    Ok(())
+}
+
+pub fn load() -> Result<World, SaveError> {
+    from_bytes(&saving::load_raw(APP_NAME, SAVE_PROFILE)?)
+}
+
+/// The contents of a save file for `data`, at the current version.
+pub fn to_bytes(data: &World) -> Result<Vec<u8>, SaveError> {
+    let file = SaveFile {
+        version: SAVE_VERSION,
+        data,
+    };
+    Ok(serde_json::to_vec(&file)?)
+}
+
+/// Read a save file of any supported version, migrating it to the current one.
+pub fn from_bytes(bytes: &[u8]) -> Result<World, SaveError> {
+    let raw: Value = serde_json::from_slice(bytes)?;
+    let data = migrate(raw)?;
+    let mut world: World = serde_json::from_value(data)?;
+    world.restore();
+    Ok(world)
+}
+
+/// Check a raw save's version and run every migration between it and the current one.
+fn migrate(mut raw: Value) -> Result<Value, SaveError> {
+    let version = raw
+        .get("version")
+        .and_then(Value::as_u64)
+        .map_or(0, |version| version as u32);
+    if !(MIN_SUPPORTED_VERSION..=SAVE_VERSION).contains(&version) {
+        return Err(SaveError::Incompatible { found: version });
+    }
+
+    let mut data = raw.get_mut("data").map(Value::take).unwrap_or(Value::Null);
+    for step in &MIGRATIONS[(version - MIN_SUPPORTED_VERSION) as usize..] {
+        step(&mut data);
+    }
+    Ok(data)
+}
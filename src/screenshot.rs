@@ -0,0 +1,47 @@
+use quicksilver::{graphics::PixelFormat, lifecycle::Window};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where screenshots are written, under `static/`.
+const SCREENSHOT_DIR: &str = "screenshots";
+
+/// Save what's in the window as a PNG named for the time it was taken, and
+/// return the file's name. Everything drawn so far this frame must have been
+/// flushed to the window first.
+pub fn save(window: &mut Window) -> Result<String, String> {
+    std::fs::create_dir_all(SCREENSHOT_DIR).map_err(|err| format!("{}: {}", SCREENSHOT_DIR, err))?;
+    let stamp = timestamp();
+    // Two in the same second get numbered rather than overwriting each other
+    let file = std::iter::once(format!("{}/{}.png", SCREENSHOT_DIR, stamp))
+        .chain((2..).map(|number| format!("{}/{}-{}.png", SCREENSHOT_DIR, stamp, number)))
+        .find(|file| !std::path::Path::new(file).exists())
+        .expect("there is always a free file name");
+    let image = window.screenshot(PixelFormat::RGB);
+    image.save(&file).map_err(|err| format!("{}: {}", file, err))?;
+    Ok(file)
+}
+
+/// The current time in UTC as `2024-03-09_14-05-59`, which sorts in the order
+/// the screenshots were taken and is safe in file names everywhere.
+pub fn timestamp() -> String {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs());
+    let (days, time) = (seconds / 86_400, seconds % 86_400);
+    // Howard Hinnant's days-to-civil-date conversion, with eras of 400 years
+    let days = days as i64 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
@@ -0,0 +1,139 @@
+use crate::{
+    audio::Channel,
+    config::Config,
+    input::{Action, Controls, Direction, KeyBindings, KeyCombo},
+    strings::Strings,
+};
+use quicksilver::input::{Gamepad, Keyboard};
+
+/// One line of the settings menu.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Row {
+    Theme,
+    Fullscreen,
+    Weather,
+    Volume(Channel),
+    RealTime,
+    Preset,
+    Binding(Action),
+}
+
+fn rows() -> Vec<Row> {
+    let mut rows = vec![Row::Theme, Row::Fullscreen, Row::Weather];
+    rows.extend(Channel::ALL.iter().map(|&channel| Row::Volume(channel)));
+    rows.extend([Row::RealTime, Row::Preset]);
+    rows.extend(Action::all().into_iter().map(Row::Binding));
+    rows
+}
+
+/// The in-game settings screen. Confirming on the theme, fullscreen, weather or
+/// real time row switches it. Left and right turn a volume down and up, and make
+/// real time turns shorter and longer. Confirming on the preset cycles through the
+/// ready-made key sets, replacing the current bindings. Confirming on an action
+/// waits for a key press and binds that key, or unbinds it if it was bound.
+#[derive(Clone, Debug, Default)]
+pub struct SettingsMenu {
+    selected: usize,
+    capturing: bool,
+}
+
+impl SettingsMenu {
+    /// How many rows fit on screen at once; the list scrolls to keep the selection in view.
+    const VISIBLE_ROWS: usize = 14;
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Handle one update's input, returning `false` once the menu has been closed.
+    pub fn update(&mut self, keyboard: &Keyboard, gamepads: &[Gamepad], config: &mut Config) -> bool {
+        let rows = rows();
+
+        if self.capturing {
+            if let Some(combo) = KeyCombo::just_pressed(keyboard) {
+                if let Row::Binding(action) = rows[self.selected] {
+                    config.bindings.toggle(action, combo);
+                }
+                self.capturing = false;
+            }
+            return true;
+        }
+
+        let controls = Controls::new(keyboard, &config.bindings, gamepads);
+        if controls.pressed(Action::Move(Direction::North)) {
+            self.selected = (self.selected + rows.len() - 1) % rows.len();
+        } else if controls.pressed(Action::Move(Direction::South)) {
+            self.selected = (self.selected + 1) % rows.len();
+        } else if controls.pressed(Action::Move(Direction::West)) {
+            match rows[self.selected] {
+                Row::Volume(channel) => config.volume.lower(channel),
+                Row::RealTime => config.real_time.faster(),
+                _ => {}
+            }
+        } else if controls.pressed(Action::Move(Direction::East)) {
+            match rows[self.selected] {
+                Row::Volume(channel) => config.volume.raise(channel),
+                Row::RealTime => config.real_time.slower(),
+                _ => {}
+            }
+        } else if controls.pressed(Action::Confirm) {
+            match rows[self.selected] {
+                Row::Theme => config.theme = config.theme.next(),
+                Row::Fullscreen => config.fullscreen = !config.fullscreen,
+                Row::Weather => config.hide_weather = !config.hide_weather,
+                Row::Volume(_) => {}
+                Row::RealTime => config.real_time.enabled = !config.real_time.enabled,
+                Row::Preset => {
+                    config.preset = config.preset.next();
+                    config.bindings = KeyBindings::for_preset(config.preset);
+                }
+                Row::Binding(_) => self.capturing = true,
+            }
+        } else if controls.pressed(Action::Cancel) {
+            config.save();
+            return false;
+        }
+        true
+    }
+
+    pub fn lines(&self, config: &Config, strings: &Strings) -> Vec<String> {
+        let rows = rows();
+        let first = self
+            .selected
+            .saturating_sub(Self::VISIBLE_ROWS / 2)
+            .min(rows.len().saturating_sub(Self::VISIBLE_ROWS));
+
+        let on_off = |on: bool| strings.get(if on { "settings.on" } else { "settings.off" });
+        let mut lines = vec![strings.get("settings.title").to_string()];
+        for (index, row) in rows.into_iter().enumerate().skip(first).take(Self::VISIBLE_ROWS) {
+            let marker = if index != self.selected {
+                "  "
+            } else if self.capturing {
+                "? "
+            } else {
+                "> "
+            };
+            let line = match row {
+                Row::Theme => strings.format("settings.theme", &[("theme", &config.theme.name(strings))]),
+                Row::Fullscreen => strings.format("settings.fullscreen", &[("state", &on_off(config.fullscreen))]),
+                Row::Weather => strings.format("settings.weather", &[("state", &on_off(!config.hide_weather))]),
+                Row::Volume(channel) => strings.format(
+                    "settings.volume",
+                    &[("channel", &channel.name(strings)), ("percent", &config.volume.get(channel))],
+                ),
+                Row::RealTime => {
+                    let (state, tick_ms) = (on_off(config.real_time.enabled), config.real_time.tick_ms);
+                    strings.format("settings.real_time", &[("state", &state), ("ms", &tick_ms)])
+                }
+                Row::Preset => strings.format("settings.preset", &[("preset", &config.preset.name(strings))]),
+                Row::Binding(action) => {
+                    let keys: Vec<String> = config.bindings.keys(action).iter().map(|&combo| combo.name()).collect();
+                    let (action, keys) = (action.label(strings), keys.join(", "));
+                    strings.format("settings.binding", &[("action", &action), ("keys", &keys)])
+                }
+            };
+            lines.push(format!("{}{}", marker, line));
+        }
+        lines
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{
+    alchemy,
+    attributes::Attribute,
+    items::ItemDef,
+    strings::Strings,
+    world::{self, World},
+};
+
+/// The character sheet: who the player is, their race and class, and everything
+/// those gave them, as lines for the panel.
+pub fn lines(world: &World, name: &str, items: &[ItemDef], strings: &Strings) -> Vec<String> {
+    let player = world.player();
+    let mut lines = vec![name.to_string()];
+    let race = world.race.as_deref().map(|race| strings.name("race", race));
+    let class = world.class.as_deref().map(|class| strings.name("class", class));
+    if race.is_some() || class.is_some() {
+        let (race, class) = (race.unwrap_or_default(), class.unwrap_or_default());
+        lines.push(strings.format("sheet.race_class", &[("race", &race), ("class", &class)]).trim().to_string());
+    }
+    let needed = world::xp_needed(player.level);
+    lines.push(strings.format("sheet.level", &[("level", &player.level), ("xp", &player.xp), ("needed", &needed)]));
+    lines.push(strings.format(
+        "sheet.stats",
+        &[
+            ("hp", &player.hp),
+            ("max_hp", &player.max_hp),
+            ("attack", &player.attack),
+            ("defense", &player.defense),
+        ],
+    ));
+    let attributes: Vec<String> = Attribute::ALL
+        .into_iter()
+        .map(|attribute| {
+            let (name, value) = (attribute.name(strings), player.attributes.get(attribute));
+            strings.format("sheet.attribute", &[("attribute", &name), ("value", &value)])
+        })
+        .collect();
+    lines.push(attributes.join(", "));
+    if player.max_mana > 0 {
+        lines.push(strings.format("sheet.mana", &[("mana", &player.mana), ("max_mana", &player.max_mana)]));
+    }
+    lines.push(strings.format("sheet.gold", &[("gold", &player.gold)]));
+
+    // Each of these is left out when there's nothing in it
+    let mut list = |id: &str, key: &str, items: Vec<String>| {
+        if !items.is_empty() {
+            lines.push(strings.format(id, &[(key, &items.join(", "))]));
+        }
+    };
+    let talents = player.talents.iter().map(|talent| talent.describe(strings).to_string());
+    list("sheet.talents", "talents", talents.collect());
+    list("sheet.traits", "traits", player.traits.iter().map(|race_trait| race_trait.describe(strings)).collect());
+    list("sheet.resists", "resists", player.resists.iter().map(|resist| resist.name(strings).to_string()).collect());
+    let spells = player.spells.iter().map(|spell| strings.name("spell", spell).to_string());
+    list("sheet.spells", "spells", spells.collect());
+    let perks = player.perks.iter().map(|perk| strings.name("perk", perk).to_string());
+    list("sheet.perks", "perks", perks.collect());
+    let equipment = player.equipment.iter().map(|item| strings.name("item", item).to_string());
+    list("sheet.equipment", "equipment", equipment.collect());
+    let inventory = player.inventory.iter().map(|item| alchemy::name(world, items, strings, item));
+    list("sheet.inventory", "items", inventory.collect());
+    let statuses = player.statuses.iter().map(|effect| {
+        strings.format("spell.status", &[("status", &effect.status.name(strings)), ("turns", &effect.turns)])
+    });
+    list("sheet.statuses", "statuses", statuses.collect());
+    let lasting = world.timers.iter().filter(|timer| timer.entity == world.player_id).map(|timer| {
+        strings.format("sheet.timer", &[("effect", &timer.effect.describe(strings)), ("turns", &timer.turns)])
+    });
+    list("sheet.lasting", "effects", lasting.collect());
+    let piety = world.devotion.iter().map(|(god, devotion)| {
+        strings.format("sheet.god", &[("god", &strings.name("god", god)), ("piety", &devotion.piety)])
+    });
+    list("sheet.piety", "gods", piety.collect());
+    lines
+}
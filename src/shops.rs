@@ -0,0 +1,108 @@
+use crate::{
+    attributes::Attributes,
+    dungeon,
+    items::{self, ItemDef},
+    world::World,
+};
+use quicksilver::geom::Vector;
+use serde::{Deserialize, Serialize};
+
+/// How much gold the player starts a run with if their class doesn't say.
+pub const STARTING_GOLD: u32 = 30;
+
+/// What a shopkeeper pays for an item, in percent of what it's worth, before
+/// charisma is counted.
+const SELL_PERCENT: i32 = 50;
+
+/// A room on the level minded by a shopkeeper. Items lying in it are for sale:
+/// the player picks them up, then pays the keeper before leaving. Walking out
+/// without paying is theft, and turns the keeper on them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Shop {
+    pub keeper: usize,
+    /// The top left corner of the room.
+    pub origin: Vector,
+    pub size: Vector,
+    /// Items the player has picked up here and not paid for yet, by entity.
+    #[serde(default)]
+    pub unpaid: Vec<usize>,
+}
+
+impl Shop {
+    pub fn new(keeper: usize, origin: Vector, size: Vector) -> Shop {
+        Shop {
+            keeper,
+            origin,
+            size,
+            unpaid: Vec::new(),
+        }
+    }
+
+    pub fn contains(&self, pos: Vector) -> bool {
+        let end = self.origin + self.size;
+        pos.x >= self.origin.x && pos.y >= self.origin.y && pos.x < end.x && pos.y < end.y
+    }
+}
+
+/// Something the player can do at a shop's counter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Trade {
+    /// Pay for the item at this index of the shop's unpaid items.
+    Buy { item: usize, price: u32 },
+    /// Hand the item at this index of the shop's unpaid items back.
+    Return { item: usize },
+    /// Sell the item at this index of the player's inventory.
+    Sell { item: usize, price: u32 },
+}
+
+/// What an item worth `value` costs someone with `attributes`; never nothing.
+pub fn buy_price(value: u32, attributes: &Attributes) -> u32 {
+    let percent = (100 - attributes.discount()) as u32;
+    (value * percent / 100).max(1)
+}
+
+/// What a shopkeeper pays someone with `attributes` for an item worth `value`,
+/// which is never more than they'd sell it back for.
+pub fn sell_price(value: u32, attributes: &Attributes) -> u32 {
+    let percent = (SELL_PERCENT + attributes.discount() / 2) as u32;
+    (value * percent / 100).min(buy_price(value, attributes))
+}
+
+/// Everything the player can do at `shop`: pay for or hand back each item they've
+/// picked up there, then sell anything else they carry.
+pub fn trades(world: &World, shop: usize, items: &[ItemDef]) -> Vec<Trade> {
+    let player = world.player();
+    let value = |name: &str| items::find(items, name).map(|item| item.value);
+    let unpaid = &world.shops[shop].unpaid;
+    let mut trades = Vec::new();
+    let mut owed = Vec::new();
+    for (index, &id) in unpaid.iter().enumerate() {
+        let name = world.entities[id].item.as_deref().unwrap_or("");
+        if let Some(value) = value(name) {
+            trades.push(Trade::Buy {
+                item: index,
+                price: buy_price(value, &player.attributes),
+            });
+        }
+        trades.push(Trade::Return { item: index });
+        owed.push(name);
+    }
+    for (index, name) in player.inventory.iter().enumerate() {
+        // Each unpaid item is somewhere in the inventory too, and can't be sold
+        if let Some(position) = owed.iter().position(|owed| owed == name) {
+            owed.swap_remove(position);
+            continue;
+        }
+        // No shopkeeper could ever pay what the amulet is worth
+        if name == dungeon::AMULET {
+            continue;
+        }
+        if let Some(value) = value(name) {
+            trades.push(Trade::Sell {
+                item: index,
+                price: sell_price(value, &player.attributes),
+            });
+        }
+    }
+    trades
+}
@@ -0,0 +1,112 @@
+use crate::{data, items::ItemEffect, rng::Rng, spawn, world::World};
+use log::debug;
+use quicksilver::{geom::Vector, graphics::Color};
+use serde::{Deserialize, Serialize};
+
+pub const SHRINES_FILE: &str = "shrines.json";
+
+/// The definitions the game was built with, for when the file can't be read.
+const BUILT_IN: &str = include_str!("../static/shrines.json");
+
+/// The chance each level has of a fountain or shrine.
+const SHRINE_CHANCE: f32 = 0.4;
+
+/// Whether a shrine is drunk from or prayed at, which is all that sets a fountain
+/// apart from any other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShrineKind {
+    Fountain,
+    Shrine,
+}
+
+/// One of the things a visit can bring about, as likely as its weight against
+/// the rest.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Outcome {
+    /// What the player is told, from the string file as `outcome.<name>`.
+    pub name: String,
+    pub weight: u32,
+    /// What it does for the player, or to them, the same as an item would.
+    #[serde(default)]
+    pub effects: Vec<ItemEffect>,
+    /// How much it hurts the player.
+    #[serde(default)]
+    pub damage: i32,
+}
+
+/// One kind of fountain or shrine, as written in `static/shrines.json`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ShrineDef {
+    pub name: String,
+    pub kind: ShrineKind,
+    pub glyph: char,
+    pub color: Color,
+    /// How many visits it has in it before it runs dry or falls silent.
+    pub uses: u32,
+    pub outcomes: Vec<Outcome>,
+}
+
+impl ShrineDef {
+    /// One of its outcomes, picked by weight with `rng`.
+    pub fn roll(&self, rng: &mut Rng) -> Option<&Outcome> {
+        let total: u32 = self.outcomes.iter().map(|outcome| outcome.weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = rng.range(0, total);
+        self.outcomes.iter().find(|outcome| {
+            let picked = roll < outcome.weight;
+            roll = roll.saturating_sub(outcome.weight);
+            picked
+        })
+    }
+}
+
+impl data::Named for ShrineDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A fountain or shrine on a level, which blocks the way like a lever does and is
+/// visited by walking into it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Shrine {
+    pub pos: Vector,
+    /// Kept whole, so the level never depends on the shrine file changing.
+    pub def: ShrineDef,
+    /// Visits left before it's spent.
+    pub uses: u32,
+}
+
+/// Now and then put a fountain or shrine picked at random somewhere open on `world`.
+pub fn place(world: &mut World, shrines: &[ShrineDef]) {
+    if shrines.is_empty() || world.rng.next_f32() >= SHRINE_CHANCE {
+        return;
+    }
+    let def = &shrines[world.rng.range(0, shrines.len() as u32) as usize];
+    if let Some(pos) = spawn::free_spot(world) {
+        debug!(target: "generate", shrine = def.name.as_str(), x = pos.x, y = pos.y; "placed a shrine");
+        if let Some(tile) = world.tile_at_mut(pos) {
+            tile.glyph = def.glyph;
+            tile.color = def.color;
+            tile.animation = None;
+        }
+        world.shrines.push(Shrine {
+            pos,
+            def: def.clone(),
+            uses: def.uses,
+        });
+    }
+}
+
+/// The definition of the shrine called `name`.
+pub fn find<'a>(shrines: &'a [ShrineDef], name: &str) -> Option<&'a ShrineDef> {
+    shrines.iter().find(|shrine| shrine.name == name)
+}
+
+/// Read the fountain and shrine definitions, so new ones can be added by editing
+/// the file or in a mod.
+pub fn load(problems: &mut Vec<data::Problem>) -> Vec<ShrineDef> {
+    data::load_defs(SHRINES_FILE, BUILT_IN, problems)
+}
@@ -0,0 +1,115 @@
+use crate::world::World;
+use serde_json::Value;
+use std::collections::VecDeque;
+
+/// How many turns back the history goes.
+pub const HISTORY_TURNS: usize = 32;
+
+/// The longest a value gets in a diff before it's cut short.
+const MAX_VALUE_CHARS: usize = 48;
+
+/// The world as it was at the start of one turn, kept as JSON.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub turn: usize,
+    json: String,
+}
+
+impl Snapshot {
+    pub fn take(world: &World) -> Snapshot {
+        Snapshot {
+            turn: world.turn(),
+            json: serde_json::to_string(world).expect("the world can always be written as JSON"),
+        }
+    }
+
+    fn value(&self) -> Value {
+        serde_json::from_str(&self.json).expect("snapshots are valid JSON")
+    }
+}
+
+/// The world at each of the last `HISTORY_TURNS` turns, oldest first, for finding
+/// out what a turn changed when something happens that shouldn't have.
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    snapshots: VecDeque<Snapshot>,
+}
+
+impl History {
+    /// Take a snapshot of `world` if its turn has moved on since the last one,
+    /// dropping the oldest once the history is full.
+    pub fn record(&mut self, world: &World) {
+        let turn = world.turn();
+        if self.snapshots.back().is_some_and(|last| last.turn == turn) {
+            return;
+        }
+        if self.snapshots.len() == HISTORY_TURNS {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(Snapshot::take(world));
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Two snapshots in a row, the later one `back` snapshots before the newest;
+    /// `None` if there aren't that many.
+    pub fn pair(&self, back: usize) -> Option<(&Snapshot, &Snapshot)> {
+        let after = self.snapshots.len().checked_sub(back + 1)?;
+        let before = after.checked_sub(1)?;
+        Some((&self.snapshots[before], &self.snapshots[after]))
+    }
+}
+
+/// Every value that differs between two snapshots, one line each, such as
+/// `entities[0].hp: 5 -> 4`. Lists that grew or shrank show what was added or
+/// removed at the end.
+pub fn diff(before: &Snapshot, after: &Snapshot) -> Vec<String> {
+    let mut changes = Vec::new();
+    compare(String::new(), &before.value(), &after.value(), &mut changes);
+    changes
+}
+
+fn compare(path: String, before: &Value, after: &Value, changes: &mut Vec<String>) {
+    let field = |key: &str| if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) => {
+            for (key, old) in before {
+                match after.get(key) {
+                    Some(new) => compare(field(key), old, new, changes),
+                    None => changes.push(format!("{}: removed {}", field(key), short(old))),
+                }
+            }
+            for (key, new) in after.iter().filter(|(key, _)| !before.contains_key(*key)) {
+                changes.push(format!("{}: added {}", field(key), short(new)));
+            }
+        }
+        (Value::Array(before), Value::Array(after)) => {
+            for (index, (old, new)) in before.iter().zip(after).enumerate() {
+                compare(format!("{}[{}]", path, index), old, new, changes);
+            }
+            for (index, old) in before.iter().enumerate().skip(after.len()) {
+                changes.push(format!("{}[{}]: removed {}", path, index, short(old)));
+            }
+            for (index, new) in after.iter().enumerate().skip(before.len()) {
+                changes.push(format!("{}[{}]: added {}", path, index, short(new)));
+            }
+        }
+        _ if before != after => changes.push(format!("{}: {} -> {}", path, short(before), short(after))),
+        _ => {}
+    }
+}
+
+/// `value` as JSON, cut short if it's long.
+fn short(value: &Value) -> String {
+    let text = value.to_string();
+    match text.char_indices().nth(MAX_VALUE_CHARS) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text,
+    }
+}
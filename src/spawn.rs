@@ -0,0 +1,213 @@
+use crate::{
+    animation::Animation,
+    data::Content,
+    dungeon,
+    items::ItemDef,
+    names, quests,
+    rng::Rng,
+    strings::Strings,
+    world::{Entity, World},
+};
+use log::debug;
+use quicksilver::geom::Vector;
+
+/// How close to the player's starting spot nothing is allowed to spawn.
+const SAFE_DISTANCE: f32 = 6.0;
+
+/// How many items lie around on each level.
+const ITEMS_PER_LEVEL: u32 = 2;
+
+/// The chance each level has of a named elite from deeper down.
+const ELITE_CHANCE: f32 = 0.15;
+
+/// How many levels deeper than the current one elites come from.
+const ELITE_DEPTH: u32 = 3;
+
+/// The chance each level has of a named legendary item.
+const LEGENDARY_CHANCE: f32 = 0.1;
+
+/// The chance each level has of an artifact not yet found this run.
+const ARTIFACT_CHANCE: f32 = 0.08;
+
+/// Pick one of `options` at random, each as likely as its weight.
+fn pick<T: Copy>(options: &[(T, u32)], rng: &mut Rng) -> Option<T> {
+    let total: u32 = options.iter().map(|&(_, weight)| weight).sum();
+    if total == 0 {
+        return None;
+    }
+    let mut roll = rng.range(0, total);
+    for &(option, weight) in options {
+        if roll < weight {
+            return Some(option);
+        }
+        roll -= weight;
+    }
+    None
+}
+
+/// A random empty floor tile away from the player, or `None` if none turns up
+/// after a fair number of tries.
+pub fn free_spot(world: &mut World) -> Option<Vector> {
+    let mut rng = std::mem::take(&mut world.rng);
+    let spot = open_spot(world, &mut rng);
+    world.rng = rng;
+    spot
+}
+
+/// Like `free_spot`, with the choices made by `rng` instead of the level's own.
+fn open_spot(world: &World, rng: &mut Rng) -> Option<Vector> {
+    let player_pos = world.player().pos;
+    for _ in 0..100 {
+        let x = rng.range(0, world.map_size.x as u32);
+        let y = rng.range(0, world.map_size.y as u32);
+        let pos = Vector::new(x as f32, y as f32);
+        let floor = world.tile_at(pos).is_some_and(|tile| tile.glyph == '.');
+        let taken = world.entities.iter().any(|entity| entity.pos == pos);
+        if floor && !taken && pos.distance(player_pos) > SAFE_DISTANCE {
+            return Some(pos);
+        }
+    }
+    None
+}
+
+/// How many monsters a level at `world`'s depth starts out with, more on the way
+/// back up with the amulet.
+fn monster_count(world: &World) -> u32 {
+    // The dungeon doesn't let the amulet go easily
+    let hunters = if dungeon::has_amulet(world.player()) { world.depth } else { 0 };
+    2 + world.depth + hunters
+}
+
+/// Fill a new level with monsters and items for its depth, as the loot tables say.
+/// Deeper levels get more monsters, and now and then an elite, a legendary item or
+/// an artifact. Levels on the way back up with the amulet get twice as many monsters.
+pub fn populate(world: &mut World, content: &Content) {
+    let (loot, items, strings) = (&content.loot, &content.items, &content.strings);
+    let depth = world.depth;
+    let monster_options = loot.monsters_at(&content.monsters, depth);
+    for _ in 0..monster_count(world) {
+        let monster = pick(&monster_options, &mut world.rng);
+        if let (Some(monster), Some(pos)) = (monster, free_spot(world)) {
+            let mut entity = monster.entity(pos);
+            let drop = loot.roll_drop(&monster.name, items, &mut world.rng);
+            // An artifact only ever turns up once, however it comes
+            let drop = drop.filter(|item| !item.is_artifact() || world.artifacts.insert(item.name.clone()));
+            let item = drop.map_or("", |item| item.name.as_str());
+            debug!(target: "generate", monster = monster.name.as_str(), x = pos.x, y = pos.y, loot = item; "spawned");
+            entity.loot = drop.map(|item| Box::new(item.entity(pos)));
+            world.entities.push(entity);
+        }
+    }
+    let mut item_options = loot.items_at(items, depth);
+    for _ in 0..ITEMS_PER_LEVEL {
+        item_options.retain(|(item, _)| !world.artifacts.contains(&item.name));
+        let item = pick(&item_options, &mut world.rng);
+        if let (Some(item), Some(pos)) = (item, free_spot(world)) {
+            debug!(target: "generate", item = item.name.as_str(), x = pos.x, y = pos.y; "placed item");
+            if item.is_artifact() {
+                world.artifacts.insert(item.name.clone());
+            }
+            world.entities.push(item.entity(pos));
+        }
+    }
+
+    // Anything the player has been sent down here to fetch
+    let wanted: Vec<Entity> = world
+        .quests
+        .iter()
+        .filter(|quest| !quest.rewarded)
+        .filter_map(|quest| quests::item_at(&quest.def, depth, items))
+        .collect();
+    for item in wanted {
+        if let Some(pos) = free_spot(world) {
+            let name = item.item.as_deref().unwrap_or("");
+            debug!(target: "generate", item = name, x = pos.x, y = pos.y; "placed a quest item");
+            world.entities.push(Entity { pos, ..item });
+        }
+    }
+
+    if world.rng.next_f32() < ELITE_CHANCE {
+        let monster = pick(&loot.monsters_at(&content.monsters, depth + ELITE_DEPTH), &mut world.rng);
+        if let (Some(monster), Some(pos)) = (monster, free_spot(world)) {
+            let mut elite = monster.entity(pos);
+            elite.max_hp *= 2;
+            elite.hp = elite.max_hp;
+            // Elites stand out from the ordinary monsters of their kind
+            elite.color = content.glyphs.color("elite");
+            elite.animation = Some(Animation::bob(elite.glyph, elite.color));
+            let name = names::generate(&mut world.rng);
+            let monster = strings.name("monster", &monster.name);
+            elite.name = Some(strings.format("name.elite", &[("name", &name), ("monster", &monster)]));
+            elite.elite = true;
+            elite.loot = legendary_loot(pos, items, strings, &mut world.rng).map(Box::new);
+            debug!(target: "generate", monster = monster, x = pos.x, y = pos.y; "spawned an elite");
+            world.entities.push(elite);
+        }
+    }
+    if world.rng.next_f32() < LEGENDARY_CHANCE {
+        if let Some(loot) = free_spot(world).and_then(|pos| legendary_loot(pos, items, strings, &mut world.rng)) {
+            debug!(target: "generate", x = loot.pos.x, y = loot.pos.y; "placed a legendary item");
+            world.entities.push(loot);
+        }
+    }
+    if world.rng.next_f32() < ARTIFACT_CHANCE {
+        let unfound: Vec<(&ItemDef, u32)> = items
+            .iter()
+            .filter(|item| item.is_artifact() && !world.artifacts.contains(&item.name))
+            .map(|item| (item, 1))
+            .collect();
+        if let (Some(item), Some(pos)) = (pick(&unfound, &mut world.rng), free_spot(world)) {
+            debug!(target: "generate", item = item.name.as_str(), x = pos.x, y = pos.y; "placed an artifact");
+            world.artifacts.insert(item.name.clone());
+            world.entities.push(item.entity(pos));
+        }
+    }
+}
+
+/// Whether a monster comes back to `level` on `turn` of the run, and if so which,
+/// worked out without changing it: one for its depth turns up somewhere out of
+/// sight every so many turns, as the loot tables say for its biome, until there are
+/// as many as it started with. It comes back empty-handed, so a level can't be
+/// farmed for loot. Along with it comes where the level's random choices carry on
+/// from.
+pub fn respawn(level: &World, turn: usize, content: &Content) -> Option<(Entity, Rng)> {
+    let every = *content.loot.respawns.get(&level.biome)?;
+    if every == 0 || !turn.is_multiple_of(every) {
+        return None;
+    }
+    let player = level.player_id;
+    let living = level.entities.iter().enumerate().filter(|&(id, entity)| id != player && entity.is_alive());
+    if living.filter(|(_, entity)| !entity.ai.is_peaceful()).count() >= monster_count(level) as usize {
+        return None;
+    }
+    let mut rng = level.rng.clone();
+    let options = content.loot.monsters_at(&content.monsters, level.depth);
+    let monster = pick(&options, &mut rng)?;
+    let pos = (0..10).filter_map(|_| open_spot(level, &mut rng)).find(|&pos| !level.is_visible(pos))?;
+    Some((monster.entity(pos), rng))
+}
+
+/// Bring monsters back to `world` and to each level the player has left, as
+/// `respawn` works out for them on this turn of the run.
+pub fn repopulate(world: &mut World, content: &Content) {
+    let turn = world.turn();
+    if let Some((monster, rng)) = respawn(world, turn, content) {
+        world.respawn(&monster, &rng);
+    }
+    for index in 0..world.visited.len() {
+        if let Some((monster, rng)) = respawn(&world.visited[index], turn, content) {
+            world.respawn_kept(index, &monster, &rng);
+        }
+    }
+}
+
+/// A one-of-a-kind named item, found lying around now and then or dropped by elites.
+fn legendary_loot(pos: Vector, items: &[ItemDef], strings: &Strings, rng: &mut Rng) -> Option<Entity> {
+    let legendary: Vec<(&ItemDef, u32)> = items.iter().filter(|item| item.legendary).map(|item| (item, 1)).collect();
+    let item = pick(&legendary, rng)?;
+    let mut loot = item.entity(pos);
+    let name = names::generate(rng);
+    let item = strings.name("item", &item.name);
+    loot.name = Some(strings.format("name.legendary", &[("item", &item), ("name", &name)]));
+    Some(loot)
+}
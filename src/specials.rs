@@ -0,0 +1,102 @@
+use crate::{
+    data,
+    dungeon::DEEPEST_DEPTH,
+    glyphs::Glyphs,
+    happenings::Scheduled,
+    prefab,
+    rng::Rng,
+    world::World,
+};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const SPECIALS_FILE: &str = "specials.json";
+
+/// The definitions the game was built with, for when the file can't be read.
+const BUILT_IN: &str = include_str!("../static/specials.json");
+
+/// How close to where the player arrives floodwater never reaches.
+const DRY_DISTANCE: f32 = 2.0;
+
+fn yes() -> bool {
+    true
+}
+
+/// One special level, as written in `static/specials.json`. Every run has one of
+/// each, somewhere in the main dungeon between its depths.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpecialDef {
+    pub name: String,
+    pub min_depth: u32,
+    pub max_depth: u32,
+    /// The prefab the level is built around, by file name, which no other level gets.
+    #[serde(default)]
+    pub prefab: Option<String>,
+    /// How much of the floor is under water, in percent.
+    #[serde(default)]
+    pub flood: u32,
+    /// Whether the level gets its usual monsters and items as well.
+    #[serde(default = "yes")]
+    pub populate: bool,
+    /// Happenings set to come about on the level, counting from when it's made.
+    #[serde(default)]
+    pub schedule: Vec<Scheduled>,
+}
+
+impl SpecialDef {
+    /// Whether `prefab` is kept for this level.
+    pub fn uses(&self, prefab: &prefab::Prefab) -> bool {
+        self.prefab.as_deref().is_some_and(|name| prefab.is(name))
+    }
+}
+
+impl data::Named for SpecialDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Pick a depth for each special level in a new run, each at a different one
+/// between its own depths, never the first or the last. One with no depth left
+/// for it is left out.
+pub fn plan(rng: &mut Rng, specials: &[SpecialDef]) -> BTreeMap<u32, String> {
+    let mut planned = BTreeMap::new();
+    for special in specials {
+        let (min, max) = (special.min_depth.max(2), special.max_depth.min(DEEPEST_DEPTH - 1));
+        let free: Vec<u32> = (min..=max).filter(|depth| !planned.contains_key(depth)).collect();
+        if free.is_empty() {
+            warn!(target: "generate", "There's no depth left for the {} this run", special.name);
+            continue;
+        }
+        let depth = free[rng.range(0, free.len() as u32) as usize];
+        debug!(target: "generate", special = special.name.as_str(), depth = depth; "planned a special level");
+        planned.insert(depth, special.name.clone());
+    }
+    planned
+}
+
+/// Put `percent` of the floor of `world` under water, away from the player.
+pub fn flood(world: &mut World, percent: u32, glyphs: &Glyphs) {
+    let player = world.player().pos;
+    for index in 0..world.map.len() {
+        let tile = &world.map[index];
+        if tile.glyph != '.' || tile.pos.distance(player) <= DRY_DISTANCE {
+            continue;
+        }
+        if world.rng.range(0, 100) < percent {
+            prefab::paint(&mut world.map[index], '~', glyphs);
+        }
+    }
+}
+
+/// The definition of the special level called `name`.
+pub fn find<'a>(specials: &'a [SpecialDef], name: &str) -> Option<&'a SpecialDef> {
+    specials.iter().find(|special| special.name == name)
+}
+
+/// Read the special level definitions, so they can be added or changed by editing
+/// the file or in a mod.
+pub fn load(problems: &mut Vec<data::Problem>) -> Vec<SpecialDef> {
+    data::load_defs(SPECIALS_FILE, BUILT_IN, problems)
+}
@@ -0,0 +1,241 @@
+use crate::{
+    data, fov,
+    items::{self, ItemDef},
+    strings::Strings,
+    world::{Entity, World},
+};
+use quicksilver::{geom::Vector, graphics::Color};
+use serde::{Deserialize, Serialize};
+
+pub const SPELLS_FILE: &str = "spells.json";
+
+/// The definitions the game was built with, for when the file can't be read.
+const BUILT_IN: &str = include_str!("../static/spells.json");
+
+/// The player gets a point of mana back every this many turns.
+pub const MANA_REGEN_TURNS: usize = 3;
+
+/// Which tiles a spell reaches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Targeting {
+    /// Flies at the target and hits the first creature in the way.
+    Bolt,
+    /// Goes straight through every creature on the way to the target.
+    Beam,
+    /// Flies like a bolt, then bursts over everything within `radius` of where it lands.
+    Ball { radius: u32 },
+    /// Bursts out from the caster over everything within `radius`.
+    Nova { radius: u32 },
+    /// Carries the caster as far towards the target as it can, stopping short of
+    /// walls and creatures.
+    Blink,
+}
+
+/// What kind of harm a spell or bite does. Creatures that resist a type take half
+/// damage from it, and can't be poisoned if they resist poison.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DamageType {
+    Physical,
+    Fire,
+    Cold,
+    Lightning,
+    Poison,
+}
+
+impl DamageType {
+    pub fn name(self, strings: &Strings) -> &str {
+        strings.get(match self {
+            DamageType::Physical => "damage_type.physical",
+            DamageType::Fire => "damage_type.fire",
+            DamageType::Cold => "damage_type.cold",
+            DamageType::Lightning => "damage_type.lightning",
+            DamageType::Poison => "damage_type.poison",
+        })
+    }
+}
+
+/// A lasting condition, ticking down once per monster turn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Status {
+    /// Takes one damage every turn.
+    Burning,
+    /// Can't move or attack.
+    Frozen,
+    /// Takes one damage every turn, like burning.
+    Poisoned,
+    /// Gets twice as much energy each tick, so acts twice as often.
+    Hasted,
+    /// Gets half as much energy each tick, so acts half as often.
+    Slowed,
+}
+
+impl Status {
+    pub fn name(self, strings: &Strings) -> &str {
+        strings.get(match self {
+            Status::Burning => "status.burning",
+            Status::Frozen => "status.frozen",
+            Status::Poisoned => "status.poisoned",
+            Status::Hasted => "status.hasted",
+            Status::Slowed => "status.slowed",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusEffect {
+    pub status: Status,
+    pub turns: u32,
+}
+
+/// One spell, as written in `static/spells.json`. The world works out what it does
+/// from these fields alone, so most new spells need no code.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpellDef {
+    pub name: String,
+    /// How it looks in flight.
+    pub glyph: char,
+    pub color: Color,
+    pub targeting: Targeting,
+    /// How far it reaches from the caster, in tiles.
+    pub range: u32,
+    pub damage: i32,
+    pub damage_type: DamageType,
+    /// A condition put on every creature it hits that survives.
+    #[serde(default)]
+    pub status: Option<StatusEffect>,
+    /// How many turns before it can be cast again.
+    #[serde(default)]
+    pub cooldown: u32,
+    /// How much mana casting it takes.
+    #[serde(default)]
+    pub mana: i32,
+}
+
+impl SpellDef {
+    /// A one-line summary for the spellbook, such as "firebolt: 2 mana, bolt, 2 fire,
+    /// burning 3".
+    pub fn describe(&self, strings: &Strings) -> String {
+        let shape = match self.targeting {
+            Targeting::Bolt => strings.get("targeting.bolt").to_string(),
+            Targeting::Beam => strings.get("targeting.beam").to_string(),
+            Targeting::Ball { radius } => strings.format("targeting.ball", &[("radius", &radius)]),
+            Targeting::Nova { radius } => strings.format("targeting.nova", &[("radius", &radius)]),
+            Targeting::Blink => strings.format("targeting.blink", &[("range", &self.range)]),
+        };
+        let mut details = vec![shape];
+        if self.damage > 0 {
+            let damage_type = self.damage_type.name(strings);
+            details.push(strings.format("spell.damage", &[("damage", &self.damage), ("damage_type", &damage_type)]));
+        }
+        if self.mana > 0 {
+            details.insert(0, strings.format("spell.mana", &[("mana", &self.mana)]));
+        }
+        if let Some(effect) = self.status {
+            let status = effect.status.name(strings);
+            details.push(strings.format("spell.status", &[("status", &status), ("turns", &effect.turns)]));
+        }
+        let name = strings.name("spell", &self.name);
+        strings.format("spell.description", &[("spell", &name), ("details", &details.join(", "))])
+    }
+
+    /// Whether the spell needs something to aim at, rather than going off around the caster.
+    pub fn is_aimed(&self) -> bool {
+        !matches!(self.targeting, Targeting::Nova { .. })
+    }
+}
+
+impl data::Named for SpellDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// The definition of the spell called `name`.
+pub fn find<'a>(spells: &'a [SpellDef], name: &str) -> Option<&'a SpellDef> {
+    spells.iter().find(|spell| spell.name == name)
+}
+
+/// The spells `caster` knows, in the order they're in the file.
+pub fn known<'a>(spells: &'a [SpellDef], caster: &Entity) -> Vec<&'a SpellDef> {
+    spells.iter().filter(|spell| caster.spells.contains(&spell.name)).collect()
+}
+
+/// Everything `caster` can cast: the spells they know, then the powers of the
+/// artifacts they carry that they don't know already, which cost no mana.
+pub fn castable(spells: &[SpellDef], items: &[ItemDef], caster: &Entity) -> Vec<SpellDef> {
+    let mut castable: Vec<SpellDef> = known(spells, caster).into_iter().cloned().collect();
+    let carried = caster.inventory.iter().chain(&caster.equipment);
+    for power in carried.filter_map(|name| items::find(items, name)?.power.as_deref()) {
+        if let Some(spell) = find(spells, power).filter(|spell| castable.iter().all(|known| known.name != spell.name)) {
+            castable.push(SpellDef {
+                mana: 0,
+                ..spell.clone()
+            });
+        }
+    }
+    castable
+}
+
+/// The tiles a spell cast by the player towards `target` flies over, ending where
+/// it lands. Every kind stops at walls and at its range; all but beams stop at the
+/// first creature too, and blinks just short of it.
+pub fn flight(world: &World, spell: &SpellDef, target: Vector) -> Vec<Vector> {
+    if !spell.is_aimed() {
+        return Vec::new();
+    }
+    let origin = world.player().pos;
+    let mut path = Vec::new();
+    for pos in fov::line(origin, target).into_iter().skip(1).take(spell.range as usize) {
+        if !world.is_walkable(pos) || (spell.targeting == Targeting::Blink && world.creature_at(pos).is_some()) {
+            break;
+        }
+        path.push(pos);
+        if spell.targeting != Targeting::Beam && world.creature_at(pos).is_some() {
+            break;
+        }
+    }
+    path
+}
+
+/// The tiles a spell affects once its `flight` is over.
+pub fn area(world: &World, spell: &SpellDef, flight: &[Vector]) -> Vec<Vector> {
+    match spell.targeting {
+        Targeting::Bolt | Targeting::Blink => flight.last().copied().into_iter().collect(),
+        Targeting::Beam => flight.to_vec(),
+        Targeting::Ball { .. } | Targeting::Nova { .. } => match blast(world, spell, flight) {
+            Some((center, radius)) => burst(world, center, radius),
+            None => Vec::new(),
+        },
+    }
+}
+
+/// Where a spell that explodes bursts once its `flight` is over, and how far it
+/// reaches; `None` for one that doesn't.
+pub fn blast(world: &World, spell: &SpellDef, flight: &[Vector]) -> Option<(Vector, u32)> {
+    match spell.targeting {
+        Targeting::Ball { radius } => flight.last().map(|&center| (center, radius)),
+        Targeting::Nova { radius } => Some((world.player().pos, radius)),
+        Targeting::Bolt | Targeting::Blink | Targeting::Beam => None,
+    }
+}
+
+/// Open tiles within `radius` of `center`, including the center itself.
+fn burst(world: &World, center: Vector, radius: u32) -> Vec<Vector> {
+    let radius = radius as i32;
+    let mut tiles = Vec::new();
+    for dx in -radius..=radius {
+        for dy in -radius..=radius {
+            let pos = center + Vector::new(dx, dy);
+            if pos.distance(center) <= radius as f32 + 0.5 && world.is_walkable(pos) {
+                tiles.push(pos);
+            }
+        }
+    }
+    tiles
+}
+
+/// Read the spell definitions, so new spells can be added by editing the file or
+/// in a mod.
+pub fn load(problems: &mut Vec<data::Problem>) -> Vec<SpellDef> {
+    data::load_defs(SPELLS_FILE, BUILT_IN, problems)
+}
@@ -0,0 +1,103 @@
+use crate::data::{self, Problem};
+use std::{collections::HashMap, fmt};
+
+/// Where the string files live, under `static/`, one per language.
+pub const LANG_DIR: &str = "lang";
+
+/// The language every other one falls back to.
+const ENGLISH: &str = "en";
+
+/// The English strings the game was built with, for when the file can't be read.
+const BUILT_IN: &str = include_str!("../static/lang/en.json");
+
+/// Every piece of text the player sees, looked up by id such as `"message.cast"`.
+/// Text with blanks to fill in marks them `{like_this}`. Anything a translation
+/// leaves out is shown in English instead.
+#[derive(Clone, Debug)]
+pub struct Strings {
+    /// The language code, such as "fr"; `None` for English.
+    pub language: Option<String>,
+    table: HashMap<String, String>,
+    english: HashMap<String, String>,
+}
+
+impl Strings {
+    /// Load the strings for `language`, with each mod's strings for it on top.
+    pub fn load(language: Option<&str>, problems: &mut Vec<Problem>) -> Strings {
+        let mut english: HashMap<String, String> = data::load(&file(ENGLISH), BUILT_IN, problems);
+        add_mods(&mut english, ENGLISH, problems);
+        let table = match language.filter(|&language| language != ENGLISH) {
+            Some(language) => {
+                let mut table = data::try_load(&file(language)).unwrap_or_else(|mut problem| {
+                    problem.message += ", so the text is shown in English";
+                    problems.push(problem);
+                    HashMap::new()
+                });
+                add_mods(&mut table, language, problems);
+                table
+            }
+            None => HashMap::new(),
+        };
+        Strings {
+            language: language.map(str::to_string),
+            table,
+            english,
+        }
+    }
+
+    /// The text for `id`, or the id itself if no language has it, so a missing
+    /// string is easy to spot.
+    pub fn get<'a>(&'a self, id: &'a str) -> &'a str {
+        self.table
+            .get(id)
+            .or_else(|| self.english.get(id))
+            .map_or(id, String::as_str)
+    }
+
+    /// The text for `id` with each `{name}` in it filled in from `args`.
+    pub fn format(&self, id: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        let mut text = self.get(id).to_string();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+        text
+    }
+
+    /// What to call a monster, item or spell from the data files: its translation
+    /// under `kind.name` if there is one, or else the name it was defined with.
+    pub fn name<'a>(&'a self, kind: &str, name: &'a str) -> &'a str {
+        let id = format!("{}.{}", kind, name);
+        self.table
+            .get(&id)
+            .or_else(|| self.english.get(&id))
+            .map_or(name, String::as_str)
+    }
+
+    /// The string files in use, for the hot-reload watcher.
+    #[cfg(feature = "hot-reload")]
+    pub fn files(&self) -> Vec<String> {
+        let mut files = vec![file(ENGLISH)];
+        files.extend(self.language.as_deref().filter(|&language| language != ENGLISH).map(file));
+        let mod_files: Vec<String> = files.iter().flat_map(|file| data::mod_files(file)).collect();
+        files.extend(mod_files);
+        files
+    }
+}
+
+/// Let each mod's strings for `language` replace or add to `table`. Mods that
+/// don't have any are skipped.
+fn add_mods(table: &mut HashMap<String, String>, language: &str, problems: &mut Vec<Problem>) {
+    for path in data::mod_files(&file(language)) {
+        match data::try_load::<HashMap<String, String>>(&path) {
+            Ok(strings) => table.extend(strings),
+            Err(mut problem) => {
+                problem.message += ", so the mod's copy is skipped";
+                problems.push(problem);
+            }
+        }
+    }
+}
+
+fn file(language: &str) -> String {
+    format!("{}/{}.json", LANG_DIR, language)
+}
@@ -0,0 +1,60 @@
+use crate::strings::Strings;
+use quicksilver::graphics::Color;
+use serde::{Deserialize, Serialize};
+
+/// Colors that tiles darker than this get lightened on a dark background.
+const DARK_LUMINANCE: f32 = 0.15;
+
+/// The screen's color scheme. Tile and entity colors are written for the light
+/// scheme; `Dark` lightens the ones that would vanish against black.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Theme {
+    #[default]
+    Light,
+    /// Black background and light text, like a terminal.
+    Dark,
+}
+
+impl Theme {
+    pub fn next(self) -> Theme {
+        match self {
+            Theme::Light => Theme::Dark,
+            Theme::Dark => Theme::Light,
+        }
+    }
+
+    pub fn name(self, strings: &Strings) -> &str {
+        strings.get(match self {
+            Theme::Light => "theme.light",
+            Theme::Dark => "theme.dark",
+        })
+    }
+
+    pub fn background(self) -> Color {
+        match self {
+            Theme::Light => Color::WHITE,
+            Theme::Dark => Color::BLACK,
+        }
+    }
+
+    pub fn text(self) -> Color {
+        match self {
+            Theme::Light => Color::BLACK,
+            Theme::Dark => Color::from_rgba(204, 204, 204, 1.0),
+        }
+    }
+
+    /// The color to draw a tile or entity of the given color with.
+    pub fn glyph(self, color: Color) -> Color {
+        let luminance = 0.2126 * color.r + 0.7152 * color.g + 0.0722 * color.b;
+        match self {
+            Theme::Dark if luminance < DARK_LUMINANCE => Color {
+                r: color.r + (1.0 - color.r) * 0.7,
+                g: color.g + (1.0 - color.g) * 0.7,
+                b: color.b + (1.0 - color.b) * 0.7,
+                a: color.a,
+            },
+            _ => color,
+        }
+    }
+}
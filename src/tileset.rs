@@ -0,0 +1,88 @@
+use crate::glyphs;
+use quicksilver::{
+    geom::{Rectangle, Vector},
+    graphics::{Color, Font, FontStyle, Image},
+    lifecycle::Asset,
+    Future, Result,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Drawn in place of any glyph the tileset has no image for.
+const FALLBACK_GLYPH: char = '?';
+
+/// One image per glyph the map can show. Tilesets built from a font render any
+/// other character the first time it's asked for and keep it.
+pub struct Tileset {
+    glyphs: HashMap<char, Image>,
+    font: Option<(Font, FontStyle)>,
+    missing: HashSet<char>,
+}
+
+impl Tileset {
+    fn new(glyphs: HashMap<char, Image>, font: Option<(Font, FontStyle)>) -> Tileset {
+        Tileset {
+            glyphs,
+            font,
+            missing: HashSet::new(),
+        }
+    }
+
+    /// The image for `glyph`, or a `?` if it can't be had so content using it still
+    /// shows up. Each missing glyph is reported once.
+    pub fn get(&mut self, glyph: char) -> Option<&Image> {
+        if !self.glyphs.contains_key(&glyph) && !self.missing.contains(&glyph) {
+            let rendered = self.font.as_ref().map(|(font, style)| font.render(&glyph.to_string(), style));
+            match rendered {
+                Some(Ok(image)) => {
+                    self.glyphs.insert(glyph, image);
+                }
+                Some(Err(err)) => {
+                    eprintln!("Could not render glyph {:?}, drawing {:?} instead: {}", glyph, FALLBACK_GLYPH, err);
+                    self.missing.insert(glyph);
+                }
+                None => {
+                    eprintln!("No tile for glyph {:?}, drawing {:?} instead", glyph, FALLBACK_GLYPH);
+                    self.missing.insert(glyph);
+                }
+            }
+        }
+        self.glyphs.get(&glyph).or_else(|| self.glyphs.get(&FALLBACK_GLYPH))
+    }
+}
+
+/// Render glyphs from a TrueType font into tiles of `tile_size_px`. The common
+/// `glyphs` are rendered up front onto one shared image so they batch well; the
+/// rest are rendered as they turn up.
+pub fn from_font(path: &'static str, glyphs: &'static str, tile_size_px: Vector) -> Asset<Tileset> {
+    Asset::new(Font::load(path).and_then(move |font| {
+        let style = FontStyle::new(tile_size_px.y, Color::WHITE);
+        let glyphs: String = glyphs.chars().chain(Some(FALLBACK_GLYPH)).collect();
+        let tiles = font.render(&glyphs, &style)?;
+        let mut tileset = HashMap::new();
+        for (index, glyph) in glyphs.chars().enumerate() {
+            let pos = (index as i32 * tile_size_px.x as i32, 0);
+            let tile = tiles.subimage(Rectangle::new(pos, tile_size_px));
+            tileset.insert(glyph, tile);
+        }
+        Ok(Tileset::new(tileset, Some((font, style))))
+    }))
+}
+
+/// Cut a classic 16x16 grid bitmap font, laid out in code page 437 order, into one
+/// tile per character. Glyphs should be white on a transparent background so they
+/// can be tinted.
+pub fn from_cp437(path: String) -> Asset<Tileset> {
+    Asset::new(Image::load(path).and_then(|sheet| -> Result<Tileset> {
+        let cell = sheet.area().size() / 16.0;
+        let tileset = glyphs::CP437
+            .chars()
+            .enumerate()
+            .skip(1)
+            .map(|(index, glyph)| {
+                let pos = Vector::new((index % 16) as f32, (index / 16) as f32).times(cell);
+                (glyph, sheet.subimage(Rectangle::new(pos, cell)))
+            })
+            .collect();
+        Ok(Tileset::new(tileset, None))
+    }))
+}
@@ -0,0 +1,47 @@
+use crate::strings::Strings;
+use serde::{Deserialize, Serialize};
+
+/// Something that lasts a number of turns and then wears off, undoing itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Timed {
+    /// This much more attack, or less if it's negative.
+    Attack(i32),
+    /// This much more defense, or less if it's negative.
+    Defense(i32),
+    /// Gives off light this many tiles around.
+    Light(u32),
+    /// Was brought in for a while, and vanishes once its time is up.
+    Summoned,
+}
+
+impl Timed {
+    /// What it does, such as "+2 attack", for item descriptions and the sheet.
+    pub fn describe(self, strings: &Strings) -> String {
+        match self {
+            Timed::Attack(amount) => strings.format("timed.attack", &[("amount", &format!("{:+}", amount))]),
+            Timed::Defense(amount) => strings.format("timed.defense", &[("amount", &format!("{:+}", amount))]),
+            Timed::Light(radius) => strings.format("timed.light", &[("radius", &radius)]),
+            Timed::Summoned => strings.get("timed.summoned").to_string(),
+        }
+    }
+
+    /// What the player is told when it wears off them, or when something summoned goes.
+    pub fn wore_off(self, strings: &Strings) -> &str {
+        strings.get(match self {
+            Timed::Attack(amount) if amount < 0 => "wore_off.weakness",
+            Timed::Attack(_) => "wore_off.strength",
+            Timed::Defense(amount) if amount < 0 => "wore_off.exposure",
+            Timed::Defense(_) => "wore_off.protection",
+            Timed::Light(_) => "wore_off.light",
+            Timed::Summoned => "wore_off.summoned",
+        })
+    }
+}
+
+/// A `Timed` effect on an entity, and how many more turns it has to run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timer {
+    pub entity: usize,
+    pub effect: Timed,
+    pub turns: u32,
+}
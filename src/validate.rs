@@ -0,0 +1,329 @@
+use crate::{
+    alchemy::{self, APPEARANCES},
+    branches::{self, BRANCHES_FILE},
+    classes::{ClassDef, CLASSES_FILE},
+    crafting::RECIPES_FILE,
+    data::{Content, Problem},
+    dialogue::{self, DialogueEffect, DIALOGUE_FILE},
+    dungeon,
+    glyphs::{self, GLYPHS_FILE},
+    gods::GODS_FILE,
+    happenings::{self, HappeningEffect, HAPPENINGS_FILE},
+    items::{self, ItemEffect, ITEMS_FILE},
+    monsters::{self, MONSTERS_FILE},
+    perks::{self, PerkEffect, PERKS_FILE},
+    prefab::{self, Kind, Placement},
+    specials::SPECIALS_FILE,
+    quests::{self, Goal, Reward, QUESTS_FILE},
+    races::RACES_FILE,
+    shrines::SHRINES_FILE,
+    spells::{self, SPELLS_FILE},
+    timers::Timed,
+};
+
+/// Look over loaded content for anything that parses but can't work: names that
+/// point at nothing, numbers out of range and glyphs the tileset can't draw.
+/// Everything wrong is returned at once rather than stopping at the first.
+pub fn check(content: &Content) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    if items::find(&content.items, dungeon::AMULET).is_none() {
+        let message = format!("there's no '{}' to win the game with", dungeon::AMULET);
+        problems.push(Problem::new(ITEMS_FILE, None, message));
+    }
+    for item in content.items.iter().filter(|item| item.is_artifact()) {
+        if item.legendary {
+            let message = format!("{} can't be both an artifact and legendary", item.name);
+            problems.push(about(ITEMS_FILE, &item.name, message));
+        }
+        if let Some(power) = item.power.as_ref().filter(|power| spells::find(&content.spells, power).is_none()) {
+            let message = format!("{} has the power of '{}', which isn't a spell", item.name, power);
+            problems.push(about(ITEMS_FILE, &item.name, message));
+        }
+    }
+    for item in &content.items {
+        for effect in &item.effects {
+            let message = match *effect {
+                ItemEffect::Lasting { turns: 0, .. } => format!("{} wears off before it does anything", item.name),
+                // Whoever used it would vanish when it wore off
+                ItemEffect::Lasting {
+                    effect: Timed::Summoned,
+                    ..
+                } => format!("{} summons whoever uses it", item.name),
+                ItemEffect::Fuel(0) => format!("{} doesn't keep a torch burning any longer", item.name),
+                ItemEffect::Nourish(0) => format!("{} doesn't keep anyone from starving", item.name),
+                _ => continue,
+            };
+            problems.push(about(ITEMS_FILE, &item.name, message));
+        }
+    }
+    let potions: Vec<_> = content.items.iter().filter(|item| item.potion).collect();
+    if potions.len() > APPEARANCES.len() {
+        let (count, looks) = (potions.len(), APPEARANCES.len());
+        let message = format!("there are {} potions but only {} ways for them to look", count, looks);
+        problems.push(Problem::new(ITEMS_FILE, None, message));
+    }
+    for (index, potion) in potions.iter().enumerate() {
+        if potion.essences.is_empty() {
+            let message = format!("{} is a potion with no essences, so it can't be mixed", potion.name);
+            problems.push(about(ITEMS_FILE, &potion.name, message));
+        }
+        for other in potions[..index].iter().filter(|other| alchemy::same_essences(potion, other)) {
+            let message = format!("{} has the same essences as {}", potion.name, other.name);
+            problems.push(about(ITEMS_FILE, &potion.name, message));
+        }
+    }
+    for monster in &content.monsters {
+        if monster.hp <= 0 {
+            problems.push(about(MONSTERS_FILE, &monster.name, format!("{} has {} hp", monster.name, monster.hp)));
+        }
+        if monster.speed == 0 {
+            problems.push(about(MONSTERS_FILE, &monster.name, format!("{} never gets to act", monster.name)));
+        }
+        if let Some(name) = monster.dialogue.as_ref().filter(|name| dialogue::find(&content.dialogue, name).is_none()) {
+            let message = format!("{} says '{}', which isn't a dialogue", monster.name, name);
+            problems.push(about(MONSTERS_FILE, &monster.name, message));
+        }
+    }
+    for spell in &content.spells {
+        if spell.is_aimed() && spell.range == 0 {
+            problems.push(about(SPELLS_FILE, &spell.name, format!("{} is aimed but has no range", spell.name)));
+        }
+        if spell.damage < 0 {
+            problems.push(about(SPELLS_FILE, &spell.name, format!("{} does {} damage", spell.name, spell.damage)));
+        }
+    }
+    if !content.classes.is_empty() && content.classes.iter().all(|class| class.unlock.is_some()) {
+        problems.push(Problem::new(CLASSES_FILE, None, "every class has to be unlocked first".to_string()));
+    }
+    for class in &content.classes {
+        let hp = class.hp + class.attributes.hp();
+        if hp <= 0 {
+            problems.push(about(CLASSES_FILE, &class.name, format!("{} has {} hp", class.name, hp)));
+        }
+        for name in &class.equipment {
+            let message = match items::find(&content.items, name) {
+                None => format!("{} starts with '{}', which isn't an item", class.name, name),
+                Some(item) if item.slot.is_none() => format!("{} can't be worn or held", name),
+                Some(_) => continue,
+            };
+            problems.push(about(CLASSES_FILE, &class.name, message));
+        }
+        for name in class.spells.iter().filter(|name| spells::find(&content.spells, name).is_none()) {
+            let message = format!("{} starts knowing '{}', which isn't a spell", class.name, name);
+            problems.push(about(CLASSES_FILE, &class.name, message));
+        }
+    }
+    for race in &content.races {
+        let hp = |class: &ClassDef| class.hp + class.attributes.hp() + race.hp;
+        for class in content.classes.iter().filter(|class| hp(class) <= 0) {
+            let message = format!("a {} {} would start with {} hp", race.name, class.name, hp(class));
+            problems.push(about(RACES_FILE, &race.name, message));
+        }
+    }
+    for perk in &content.perks {
+        for name in perk.requires.iter().filter(|name| perks::find(&content.perks, name).is_none()) {
+            let message = format!("{} requires '{}', which isn't a perk", perk.name, name);
+            problems.push(about(PERKS_FILE, &perk.name, message));
+        }
+        for effect in &perk.effects {
+            if let PerkEffect::Spell(name) = effect {
+                if spells::find(&content.spells, name).is_none() {
+                    let message = format!("{} teaches '{}', which isn't a spell", perk.name, name);
+                    problems.push(about(PERKS_FILE, &perk.name, message));
+                }
+            }
+        }
+    }
+    for def in &content.dialogue {
+        let mut problem = |message: String| problems.push(about(DIALOGUE_FILE, &def.name, message));
+        if def.nodes.is_empty() {
+            problem(format!("{} has nothing to say", def.name));
+        }
+        for node in &def.nodes {
+            if node.choices.is_empty() {
+                problem(format!("{} gives no way to answer '{}'", def.name, node.id));
+            }
+            for choice in &node.choices {
+                let nodes = choice.next.iter().chain(choice.effects.iter().filter_map(|effect| match effect {
+                    DialogueEffect::Resume(node) => Some(node),
+                    _ => None,
+                }));
+                for next in nodes.filter(|next| def.node(next).is_none()) {
+                    problem(format!("{} goes on to '{}', which isn't one of its nodes", def.name, next));
+                }
+                for effect in &choice.effects {
+                    match effect {
+                        DialogueEffect::Give(item) if items::find(&content.items, item).is_none() => {
+                            problem(format!("{} gives '{}', which isn't an item", def.name, item));
+                        }
+                        DialogueEffect::StartQuest(name) => match quests::find(&content.quests, name) {
+                            None => problem(format!("{} starts '{}', which isn't a quest", def.name, name)),
+                            Some(quest) => {
+                                for after in quest.after.iter().filter(|after| def.node(after).is_none()) {
+                                    problem(format!("{} goes on to '{}', which isn't in {}", name, after, def.name));
+                                }
+                            }
+                        },
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+    for quest in &content.quests {
+        let mut problem = |message: String| problems.push(about(QUESTS_FILE, &quest.name, message));
+        match &quest.goal {
+            Goal::Kill { monster, .. } if monsters::find(&content.monsters, monster).is_none() => {
+                problem(format!("{} asks for '{}' to be killed, which isn't a monster", quest.name, monster));
+            }
+            Goal::Kill { count: 0, .. } => problem(format!("{} asks for nothing to be killed", quest.name)),
+            Goal::Fetch { item, .. } if items::find(&content.items, item).is_none() => {
+                problem(format!("{} asks for '{}', which isn't an item", quest.name, item));
+            }
+            _ => {}
+        }
+        for reward in &quest.rewards {
+            if let Reward::Item(item) = reward {
+                if items::find(&content.items, item).is_none() {
+                    problem(format!("{} rewards '{}', which isn't an item", quest.name, item));
+                }
+            }
+        }
+    }
+    for god in &content.gods {
+        let mut problem = |message: String| problems.push(about(GODS_FILE, &god.name, message));
+        if god.corpse_piety == 0 && god.item_piety == 0 {
+            problem(format!("{} takes no offerings", god.name));
+        }
+        for gift in god.gifts.iter().filter(|gift| items::find(&content.items, &gift.item).is_none()) {
+            problem(format!("{} gives '{}', which isn't an item", god.name, gift.item));
+        }
+        if god.gifts.windows(2).any(|pair| pair[1].piety < pair[0].piety) {
+            problem(format!("{}'s gifts aren't in order of piety", god.name));
+        }
+    }
+    for shrine in &content.shrines {
+        let mut problem = |message: String| problems.push(about(SHRINES_FILE, &shrine.name, message));
+        if shrine.uses == 0 {
+            problem(format!("{} is spent before anyone visits it", shrine.name));
+        }
+        if shrine.outcomes.iter().all(|outcome| outcome.weight == 0) {
+            problem(format!("{} has nothing that can come of a visit", shrine.name));
+        }
+    }
+    for recipe in &content.recipes {
+        let mut problem = |message: String| problems.push(about(RECIPES_FILE, &recipe.name, message));
+        if recipe.ingredients.iter().all(|ingredient| ingredient.count == 0) {
+            problem(format!("{} is made out of nothing", recipe.name));
+        }
+        let items = recipe.ingredients.iter().map(|ingredient| &ingredient.item).chain(Some(&recipe.makes));
+        for item in items.filter(|item| items::find(&content.items, item).is_none()) {
+            problem(format!("{} needs '{}', which isn't an item", recipe.name, item));
+        }
+    }
+    for branch in &content.branches {
+        let mut problem = |message: String| problems.push(about(BRANCHES_FILE, &branch.name, message));
+        if branch.levels == 0 {
+            problem(format!("{} has no levels", branch.name));
+        }
+        // Entrances always lead deeper, so no branch can end up leading back into itself
+        let parent = branch.parent.as_ref().map(|name| (name, branches::find(&content.branches, name)));
+        let (top, bottom) = match parent {
+            None => (1, dungeon::DEEPEST_DEPTH - 1),
+            Some((_, Some(parent))) => (parent.depth + 1, parent.bottom()),
+            Some((name, None)) => {
+                problem(format!("{} leads off '{}', which isn't a branch", branch.name, name));
+                continue;
+            }
+        };
+        if branch.depth < top || branch.depth > bottom {
+            let parent = branch.parent.as_deref().unwrap_or("the dungeon");
+            problem(format!("{} starts at depth {}, which isn't in {}", branch.name, branch.depth, parent));
+        }
+        if items::find(&content.items, &branch.reward).is_none() {
+            problem(format!("{} rewards '{}', which isn't an item", branch.name, branch.reward));
+        }
+    }
+    for special in &content.specials {
+        let mut problem = |message: String| problems.push(about(SPECIALS_FILE, &special.name, message));
+        // Neither the first level nor the last can be special
+        if special.min_depth.max(2) > special.max_depth.min(dungeon::DEEPEST_DEPTH - 1) {
+            problem(format!("{} has no depth it can be at", special.name));
+        }
+        if special.flood > 100 {
+            problem(format!("{} is {}% flooded", special.name, special.flood));
+        }
+        let missing = |file: &&String| !content.prefabs.iter().any(|prefab| prefab.is(file));
+        if let Some(file) = special.prefab.as_ref().filter(missing) {
+            problem(format!("{} is built around '{}', which isn't a prefab", special.name, file));
+        }
+        for scheduled in &special.schedule {
+            let happening = &scheduled.happening;
+            if happenings::find(&content.happenings, happening).is_none() {
+                problem(format!("{} schedules '{}', which isn't a happening", special.name, happening));
+            }
+            if scheduled.turns == 0 || scheduled.every == Some(0) {
+                problem(format!("{} schedules {} for no turns from now", special.name, happening));
+            }
+        }
+    }
+    for happening in &content.happenings {
+        let mut problem = |message: String| problems.push(about(HAPPENINGS_FILE, &happening.name, message));
+        if happening.chance > 100 {
+            problem(format!("{} has a {}% chance", happening.name, happening.chance));
+        }
+        match &happening.effect {
+            HappeningEffect::Earthquake(0) => problem(format!("{} reaches nowhere", happening.name)),
+            HappeningEffect::Flood(percent) if *percent > 100 => {
+                problem(format!("{} floods {}% of the floor", happening.name, percent));
+            }
+            HappeningEffect::Camp(file) if !content.prefabs.iter().any(|prefab| prefab.is(file)) => {
+                problem(format!("{} camps '{}', which isn't a prefab", happening.name, file));
+            }
+            HappeningEffect::Migration { monster, .. } if monsters::find(&content.monsters, monster).is_none() => {
+                problem(format!("{} brings '{}', which isn't a monster", happening.name, monster));
+            }
+            _ => {}
+        }
+    }
+    problems.extend(content.loot.validate(&content.monsters, &content.items));
+    for prefab in &content.prefabs {
+        let unknown = |placement: &&Placement| prefab::entity(placement, placement.pos, content).is_none();
+        for placement in prefab.placements.iter().filter(unknown) {
+            let message = match placement.kind {
+                Kind::Door => format!("door locked with '{}', which isn't a key", placement.name),
+                kind => format!("unknown {} '{}'", kind.keyword(), placement.name),
+            };
+            problems.push(Problem::new(&prefab.name, Some(placement.line), message));
+        }
+    }
+    for name in content.glyphs.unknown_colors() {
+        problems.push(Problem::about(GLYPHS_FILE, "colors", name, format!("unknown color '{}'", name)));
+    }
+
+    if content.cp437 {
+        let glyphs = content
+            .monsters
+            .iter()
+            .map(|monster| (MONSTERS_FILE, &monster.name, monster.glyph))
+            .chain(content.items.iter().map(|item| (ITEMS_FILE, &item.name, item.glyph)))
+            .chain(content.spells.iter().map(|spell| (SPELLS_FILE, &spell.name, spell.glyph)))
+            .chain(content.shrines.iter().map(|shrine| (SHRINES_FILE, &shrine.name, shrine.glyph)));
+        for (file, name, glyph) in glyphs {
+            let glyph = content.glyphs.glyph(glyph);
+            if !glyphs::in_cp437(glyph) {
+                problems.push(about(file, name, format!("{} uses '{}', which the tileset doesn't have", name, glyph)));
+            }
+        }
+        for glyph in content.glyphs.drawn().filter(|&glyph| !glyphs::in_cp437(glyph)) {
+            let message = format!("'{}' is drawn in place of another glyph, but the tileset doesn't have it", glyph);
+            problems.push(Problem::about(GLYPHS_FILE, "glyphs", &glyph.to_string(), message));
+        }
+    }
+    problems
+}
+
+/// A problem with the definition called `name` in `file`.
+fn about(file: &str, name: &str, message: String) -> Problem {
+    Problem::about(file, "name", name, message)
+}
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+/// The weather over a level. Only levels open to the sky get any.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Weather {
+    #[default]
+    Clear,
+    Rain,
+    Fog,
+    Ash,
+}
@@ -0,0 +1,34 @@
+use crate::world::Entity;
+use quicksilver::{geom::Vector, input::Key};
+use serde::{Deserialize, Serialize};
+
+/// Reveals the whole level.
+pub const REVEAL_KEY: Key = Key::F5;
+
+/// Turns the player's invincibility on and off.
+pub const INVINCIBLE_KEY: Key = Key::F6;
+
+/// Asks for an item by name and puts one at the player's feet.
+pub const WISH_KEY: Key = Key::F7;
+
+/// Opens the prefab editor.
+pub const EDITOR_KEY: Key = Key::F8;
+
+/// Whether wizard mode's cheats are on offer: always in debug builds, and in
+/// release builds when the game is started with `--wizard`. Using any of them
+/// marks the run, so it can never count for high scores. While it's on,
+/// right-clicking a tile teleports the player there.
+pub fn enabled() -> bool {
+    cfg!(debug_assertions) || std::env::args().any(|arg| arg == "--wizard")
+}
+
+/// A wizard mode cheat, kept in the run's log like any other change so replays
+/// still come out the same.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Cheat {
+    Teleport(Vector),
+    RevealMap,
+    ToggleInvincible,
+    /// The item is kept whole, so replays don't depend on the item file.
+    Wish(Box<Entity>),
+}
@@ -0,0 +1,2142 @@
+use crate::{
+    alchemy::Mixture,
+    animation::{Animation, Frame},
+    attributes::{Attribute, Attributes, ATTRIBUTE_LEVELS},
+    boulders::CRUSH_DAMAGE,
+    branches::Entrance,
+    classes::Talent,
+    crafting::{self, RecipeDef},
+    destructibles::{Destructible, DEBRIS_CHANCE, HEAVY_DAMAGE},
+    dialogue::DialogueEffect,
+    dungeon,
+    foliage::{GRASS_FIRE_TURNS, TALL_GRASS},
+    fov,
+    glyphs::Glyphs,
+    gods::{Altar, Devotion, GodDef, Offering, Prayer, PrayerEffect},
+    happenings::{Happening, Scheduled},
+    hunger::{self, Hunger},
+    hazards::{self, BREATH_TURNS, DEEP_WATER, DROWN_DAMAGE, ICE, LAVA, LAVA_DAMAGE, MAX_SLIDE, SINK_CHANCE},
+    items::ItemEffect,
+    light,
+    locks::Lock,
+    mechanisms::{self, Mechanism, Trigger},
+    monsters::{Ability, Ai, CHILL_TURNS, VENOM_TURNS},
+    path,
+    perks::{PerkDef, PERK_LEVELS},
+    portals::{Portal, PORTAL},
+    profile::Unlock,
+    quests::{Goal, Quest, QuestDef, Reward},
+    races::{Trait, INFRAVISION_RADIUS, QUICK_SPEED},
+    replay::Command,
+    rng::Rng,
+    shops::{Shop, Trade},
+    shrines::{Shrine, ShrineKind},
+    spawn,
+    spells::{self, DamageType, SpellDef, Status, StatusEffect, Targeting, MANA_REGEN_TURNS},
+    timers::{Timed, Timer},
+    weather::Weather,
+    wizard::Cheat,
+};
+use log::{debug, trace};
+use quicksilver::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Tile {
+    pub pos: Vector,
+    pub glyph: char,
+    pub color: Color,
+    #[serde(default)]
+    pub animation: Option<Animation>,
+    /// How far the tile lights up its surroundings, in tiles; 0 for no light.
+    #[serde(default)]
+    pub light_radius: u32,
+}
+
+impl Tile {
+    /// Whether the tile stops creatures, sight and light: a wall, a locked door or
+    /// a portcullis that's down.
+    pub fn is_solid(&self) -> bool {
+        matches!(self.glyph, '#' | '+' | mechanisms::PORTCULLIS)
+    }
+
+    /// Whether the tile stops sight, for creatures on either side of it: anything
+    /// solid, and tall grass.
+    pub fn blocks_sight(&self) -> bool {
+        self.is_solid() || self.glyph == TALL_GRASS
+    }
+
+    pub fn appearance(&self, clock_ms: f64) -> Frame {
+        appearance(self.glyph, self.color, &self.animation, clock_ms)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entity {
+    pub pos: Vector,
+    pub glyph: char,
+    pub color: Color,
+    pub hp: i32,
+    pub max_hp: i32,
+    #[serde(default)]
+    pub animation: Option<Animation>,
+    /// Only the player, champions and legendary items have a name of their own.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Out-of-depth monsters are tougher, and announced when first seen.
+    #[serde(default)]
+    pub elite: bool,
+    #[serde(default)]
+    pub ai: Ai,
+    #[serde(default)]
+    pub abilities: Vec<Ability>,
+    /// Damage types it only takes half damage from.
+    #[serde(default)]
+    pub resists: Vec<DamageType>,
+    /// Conditions it's under, such as burning, and for how many more turns.
+    #[serde(default)]
+    pub statuses: Vec<StatusEffect>,
+    /// The item definition it was made from, if it's an item.
+    #[serde(default)]
+    pub item: Option<String>,
+    /// What it leaves behind when killed.
+    #[serde(default)]
+    pub loot: Option<Box<Entity>>,
+    /// How much damage its melee attacks do, before strength.
+    #[serde(default = "base_attack")]
+    pub attack: i32,
+    /// Each point gives a melee attack on it a `BLOCK_CHANCE` chance of missing.
+    #[serde(default)]
+    pub defense: i32,
+    /// What it's wearing or holding, by item name. Their bonuses are already
+    /// counted in its attack and defense.
+    #[serde(default)]
+    pub equipment: Vec<String>,
+    #[serde(default)]
+    pub talents: Vec<Talent>,
+    #[serde(default)]
+    pub traits: Vec<Trait>,
+    #[serde(default)]
+    pub attributes: Attributes,
+    #[serde(default)]
+    pub mana: i32,
+    #[serde(default)]
+    pub max_mana: i32,
+    /// The spells it knows, by name.
+    #[serde(default)]
+    pub spells: Vec<String>,
+    /// Experience towards the next level, from killing monsters.
+    #[serde(default)]
+    pub xp: u32,
+    #[serde(default = "first_level")]
+    pub level: u32,
+    /// The perks taken so far, by name. What they do is already counted in the
+    /// rest of the entity.
+    #[serde(default)]
+    pub perks: Vec<String>,
+    #[serde(default)]
+    pub gold: u32,
+    /// What it's carrying, by item name.
+    #[serde(default)]
+    pub inventory: Vec<String>,
+    /// What it says when the player talks to it, by dialogue name.
+    #[serde(default)]
+    pub dialogue: Option<String>,
+    /// The kind of monster it is, by name from the monster file.
+    #[serde(default)]
+    pub kind: Option<String>,
+    /// How much energy it gets each tick, before anything that hurries it along.
+    #[serde(default = "normal_speed")]
+    pub speed: u32,
+    /// Energy saved up towards its next action, which costs `ACTION_COST`.
+    #[serde(default)]
+    pub energy: u32,
+    /// Whether it's a boulder, which stands in the way until it's pushed.
+    #[serde(default)]
+    pub boulder: bool,
+}
+
+impl Entity {
+    /// A plain entity with full hp; anything else is filled in afterwards.
+    pub fn new(pos: Vector, glyph: char, color: Color, hp: i32) -> Entity {
+        Entity {
+            pos,
+            glyph,
+            color,
+            hp,
+            max_hp: hp,
+            animation: None,
+            name: None,
+            elite: false,
+            ai: Ai::default(),
+            abilities: Vec::new(),
+            resists: Vec::new(),
+            statuses: Vec::new(),
+            item: None,
+            loot: None,
+            attack: base_attack(),
+            defense: 0,
+            equipment: Vec::new(),
+            talents: Vec::new(),
+            traits: Vec::new(),
+            attributes: Attributes::default(),
+            mana: 0,
+            max_mana: 0,
+            spells: Vec::new(),
+            xp: 0,
+            level: first_level(),
+            perks: Vec::new(),
+            gold: 0,
+            inventory: Vec::new(),
+            dialogue: None,
+            kind: None,
+            speed: NORMAL_SPEED,
+            energy: 0,
+            boulder: false,
+        }
+    }
+
+    /// How much energy it gets each tick, never so little that it stops acting.
+    pub fn energy_per_tick(&self) -> u32 {
+        let quick = if self.traits.contains(&Trait::Quick) { QUICK_SPEED } else { 0 };
+        let mut speed = self.speed + quick;
+        if self.has_status(Status::Hasted) {
+            speed *= 2;
+        }
+        if self.has_status(Status::Slowed) {
+            speed /= 2;
+        }
+        speed.max(1)
+    }
+
+    pub fn appearance(&self, clock_ms: f64) -> Frame {
+        appearance(self.glyph, self.color, &self.animation, clock_ms)
+    }
+
+    /// Items and corpses have no hp, so anything alive is a creature.
+    pub fn is_alive(&self) -> bool {
+        self.hp > 0
+    }
+
+    pub fn has_status(&self, status: Status) -> bool {
+        self.statuses.iter().any(|effect| effect.status == status)
+    }
+
+    /// Put `effect` on the entity. One it already has lasts for whichever is longer.
+    fn afflict(&mut self, effect: StatusEffect) {
+        match self.statuses.iter_mut().find(|existing| existing.status == effect.status) {
+            Some(existing) => existing.turns = existing.turns.max(effect.turns),
+            None => self.statuses.push(effect),
+        }
+    }
+}
+
+fn appearance(glyph: char, color: Color, animation: &Option<Animation>, clock_ms: f64) -> Frame {
+    animation
+        .as_ref()
+        .and_then(|animation| animation.frame(clock_ms))
+        .unwrap_or_else(|| Frame::still(glyph, color))
+}
+
+/// The kind of place a level is, which sets its music and ambience.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Biome {
+    #[default]
+    Surface,
+    Caves,
+    Crypt,
+    Swamp,
+    /// The last level, where the amulet is kept.
+    Abyss,
+}
+
+fn first_depth() -> u32 {
+    1
+}
+
+fn base_attack() -> i32 {
+    1
+}
+
+fn first_level() -> u32 {
+    1
+}
+
+fn normal_speed() -> u32 {
+    NORMAL_SPEED
+}
+
+fn full_torch() -> u32 {
+    light::TORCH_TURNS
+}
+
+fn full_stomach() -> u32 {
+    hunger::FULL_TURNS
+}
+
+/// How long a turn takes in the dungeon, in seconds.
+pub const TURN_SECONDS: usize = 6;
+
+/// The hour of the first day that runs start at.
+const START_HOUR: usize = 8;
+
+/// How much energy most creatures get each tick.
+pub const NORMAL_SPEED: u32 = 20;
+
+/// How much energy it takes to act. A creature at `NORMAL_SPEED` acts every five
+/// ticks, and one twice as fast every two and a half.
+pub const ACTION_COST: u32 = 100;
+
+/// How many ticks make a turn of the world's time, however fast the player is.
+pub const TICKS_PER_TURN: usize = (ACTION_COST / NORMAL_SPEED) as usize;
+
+/// How much more experience each level takes than the one before.
+const LEVEL_XP: u32 = 5;
+
+/// How much experience it takes to get from `level` to the next.
+pub fn xp_needed(level: u32) -> u32 {
+    LEVEL_XP * level
+}
+
+/// How likely each point of defense makes a melee attack miss.
+const BLOCK_CHANCE: f32 = 0.2;
+
+/// Where picked up items go. Entities are referred to by index, so they're never
+/// taken out of the list, only off the map.
+const OFF_MAP: Vector = Vector { x: -1.0, y: -1.0 };
+
+/// Something that happened in the world that the player should see or hear.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    Moved { entity: usize },
+    Hit { target: usize },
+    Died { target: usize, glyph: char, color: Color },
+    /// An elite came into view for the first time.
+    Spotted { entity: usize },
+    LevelUp { entity: usize, level: u32 },
+    /// The player left a shop without paying, and its keeper is after them.
+    Robbed { keeper: usize },
+    /// A timed effect on an entity ran out.
+    WoreOff { entity: usize, effect: Timed },
+    /// A locked door gave way to an entity, opened with the key of this name or
+    /// forced if `None`.
+    Unlocked { entity: usize, key: Option<String> },
+    /// A locked door held against an entity trying to force it.
+    DoorHeld { entity: usize },
+    /// An entity worked a lever or pressure plate.
+    Worked { entity: usize },
+    /// An entity stepped onto a trap.
+    Trapped { entity: usize },
+    /// The player's torch has only `light::TORCH_LOW_TURNS` turns left.
+    TorchLow { entity: usize },
+    /// The player's torch burned out.
+    TorchOut { entity: usize },
+    /// The player has only `hunger::HUNGRY_TURNS` turns of food left in them.
+    Hungry { entity: usize },
+    /// The player has nothing left in them, and has stopped healing.
+    Starving { entity: usize },
+    /// An entity knocked at a wall, pillar or piece of furniture without bringing
+    /// it down.
+    Battered { entity: usize },
+    /// An entity knocked a wall, pillar or piece of furniture down into rubble.
+    Collapsed { entity: usize },
+    /// A boulder was pushed somewhere it can't go.
+    Wedged { entity: usize },
+    /// A boulder was pushed into a trap, deep water or lava and filled it.
+    Plugged { entity: usize },
+    /// An entity ended its turn in lava.
+    Burned { entity: usize },
+    /// Something an entity carried slipped away as it swam, the item of this name.
+    Sank { entity: usize, item: String },
+    /// An entity swam until it ran out of breath, and is drowning.
+    Drowning { entity: usize },
+    /// An entity stepped into a portal and came out of the other end.
+    Warped { entity: usize },
+    /// An entity drank from a fountain or prayed at a shrine, and the outcome of
+    /// this name came of it.
+    Visited { entity: usize, kind: ShrineKind, outcome: String },
+    /// An entity went to a fountain that had run dry or a shrine fallen silent.
+    Spent { entity: usize, kind: ShrineKind },
+    /// An entity set tall grass alight.
+    Kindled { entity: usize },
+    /// An entity slid across ice.
+    Slid { entity: usize },
+    /// An entity picked up an item.
+    PickedUp { entity: usize },
+}
+
+impl Event {
+    /// The entity the event happened to.
+    pub fn entity(&self) -> usize {
+        match *self {
+            Event::Moved { entity }
+            | Event::Spotted { entity }
+            | Event::LevelUp { entity, .. }
+            | Event::WoreOff { entity, .. }
+            | Event::Unlocked { entity, .. }
+            | Event::DoorHeld { entity }
+            | Event::Worked { entity }
+            | Event::Trapped { entity }
+            | Event::TorchLow { entity }
+            | Event::TorchOut { entity }
+            | Event::Hungry { entity }
+            | Event::Starving { entity }
+            | Event::Battered { entity }
+            | Event::Collapsed { entity }
+            | Event::Wedged { entity }
+            | Event::Plugged { entity }
+            | Event::Burned { entity }
+            | Event::Sank { entity, .. }
+            | Event::Drowning { entity }
+            | Event::Warped { entity }
+            | Event::Visited { entity, .. }
+            | Event::Spent { entity, .. }
+            | Event::Kindled { entity }
+            | Event::Slid { entity }
+            | Event::PickedUp { entity } => entity,
+            Event::Robbed { keeper } => keeper,
+            Event::Hit { target } | Event::Died { target, .. } => target,
+        }
+    }
+}
+
+/// Everything about a run that gets saved: the map, what's on it, and what the
+/// player has seen so far.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct World {
+    pub map_size: Vector,
+    pub map: Vec<Tile>,
+    pub entities: Vec<Entity>,
+    pub player_id: usize,
+    #[serde(default)]
+    pub explored: Vec<bool>,
+    /// What the run was started from, so it can be shared and played again.
+    #[serde(default)]
+    pub seed: u64,
+    /// Every random choice in the run comes from here, in order.
+    #[serde(default)]
+    pub rng: Rng,
+    /// The profile unlocks the run started with, which shape its first level.
+    #[serde(default)]
+    pub unlocks: BTreeSet<Unlock>,
+    /// The class the player picked for the run, by name; `None` for runs from
+    /// before there were classes.
+    #[serde(default)]
+    pub class: Option<String>,
+    /// The race the player picked for the run, by name, the same way.
+    #[serde(default)]
+    pub race: Option<String>,
+    /// Perks the player has earned by levelling up but not picked yet.
+    #[serde(default)]
+    pub perk_points: u32,
+    /// Points the player has to raise their attributes with but hasn't spent.
+    #[serde(default)]
+    pub attribute_points: u32,
+    #[serde(default)]
+    pub shops: Vec<Shop>,
+    /// The node each NPC the player has talked to picks up from next time, where
+    /// it isn't the start.
+    #[serde(default)]
+    pub dialogue_starts: BTreeMap<usize, String>,
+    /// Every quest the player has taken on, in the order they took them.
+    #[serde(default)]
+    pub quests: Vec<Quest>,
+    /// Kinds of item the player has used, and so knows, by name.
+    #[serde(default)]
+    pub identified: BTreeSet<String>,
+    #[serde(default)]
+    pub altars: Vec<Altar>,
+    /// The fountains and shrines on the level, with the visits they have left.
+    #[serde(default)]
+    pub shrines: Vec<Shrine>,
+    /// How the player stands with each god they've made an offering to, by name.
+    #[serde(default)]
+    pub devotion: BTreeMap<String, Devotion>,
+    /// Elites the player has already been warned about.
+    #[serde(default)]
+    pub spotted_elites: BTreeSet<usize>,
+    /// Turns left before each spell the player has cast can be cast again.
+    #[serde(default)]
+    pub cooldowns: BTreeMap<String, u32>,
+    /// Effects running out turn by turn. The player's go with them from level to
+    /// level; the rest stay behind with the level.
+    #[serde(default)]
+    pub timers: Vec<Timer>,
+    /// Happenings set to come about on the level, counting down turn by turn.
+    #[serde(default)]
+    pub schedule: Vec<Scheduled>,
+    /// The happenings whose turn came during the last monster turns, by name, for
+    /// the game to bring about.
+    #[serde(skip)]
+    pub due: Vec<String>,
+    /// How many more turns the player's torch burns for.
+    #[serde(default = "full_torch")]
+    pub torch: u32,
+    /// How many more turns the player can go on what they've eaten before they
+    /// starve.
+    #[serde(default = "full_stomach")]
+    pub satiety: u32,
+    /// How many turns in a row the player has been swimming in deep water.
+    #[serde(default)]
+    pub swum: u32,
+    /// Whether any wizard mode cheat was used, which rules the run out of high scores.
+    #[serde(default)]
+    pub wizard: bool,
+    /// The player takes no damage, thanks to a wizard mode cheat.
+    #[serde(default)]
+    pub invincible: bool,
+    /// Every change made since the level was generated, for replays.
+    #[serde(default)]
+    pub log: Vec<Command>,
+    /// How many turns have gone by in the run, each the player acting and the
+    /// monsters answering.
+    #[serde(default)]
+    pub turns: usize,
+    /// How many ticks of time have gone by in the run, `TICKS_PER_TURN` to a turn
+    /// of the world's time.
+    #[serde(default)]
+    pub ticks: usize,
+    /// The player made it out of the dungeon with the amulet.
+    #[serde(default)]
+    pub won: bool,
+    /// How many levels down this one is, starting from 1.
+    #[serde(default = "first_depth")]
+    pub depth: u32,
+    /// The side branch this level is in, by name, or `None` in the main dungeon.
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// The special level this is, by name, if it's one of them.
+    #[serde(default)]
+    pub special: Option<String>,
+    /// The special levels planned for this run, by name, at the depths of the
+    /// main dungeon they're at.
+    #[serde(default)]
+    pub specials: BTreeMap<u32, String>,
+    /// Stairs on this level down into side branches.
+    #[serde(default)]
+    pub entrances: Vec<Entrance>,
+    /// The locked doors on the level.
+    #[serde(default)]
+    pub locks: Vec<Lock>,
+    /// The levers and pressure plates on the level.
+    #[serde(default)]
+    pub mechanisms: Vec<Mechanism>,
+    /// The walls, pillars and furniture on the level that can be knocked down.
+    #[serde(default)]
+    pub destructibles: Vec<Destructible>,
+    /// The floor under each tile of tall grass on the level, which is all that's
+    /// left once the grass burns.
+    #[serde(default)]
+    pub beneath_grass: Vec<Tile>,
+    /// Both ends of every pair of portals on the level.
+    #[serde(default)]
+    pub portals: Vec<Portal>,
+    #[serde(default)]
+    pub biome: Biome,
+    #[serde(default)]
+    pub weather: Weather,
+    /// Nothing can teleport on this level.
+    #[serde(default)]
+    pub no_teleport: bool,
+    /// Artifacts and branch rewards that have turned up this run, by name, which
+    /// never turn up again.
+    #[serde(default)]
+    pub artifacts: BTreeSet<String>,
+    /// The levels the player has left this run, just as they left them, to come
+    /// back to.
+    #[serde(default)]
+    pub visited: Vec<World>,
+    #[serde(skip)]
+    pub visible: Vec<bool>,
+    #[serde(skip)]
+    pub light: Vec<f32>,
+    /// What happened since the game last looked, for the renderer and sound.
+    #[serde(skip)]
+    pub events: Vec<Event>,
+    /// Bumped whenever the map or what's known of it changes, so the renderer can
+    /// tell when its cached map layer is stale.
+    #[serde(skip)]
+    pub revision: u64,
+}
+
+impl World {
+    pub fn new(map_size: Vector, map: Vec<Tile>, entities: Vec<Entity>, player_id: usize) -> World {
+        let mut world = World {
+            map_size,
+            map,
+            entities,
+            player_id,
+            explored: Vec::new(),
+            seed: 0,
+            rng: Rng::default(),
+            unlocks: BTreeSet::new(),
+            class: None,
+            race: None,
+            perk_points: 0,
+            attribute_points: 0,
+            shops: Vec::new(),
+            dialogue_starts: BTreeMap::new(),
+            quests: Vec::new(),
+            identified: BTreeSet::new(),
+            altars: Vec::new(),
+            shrines: Vec::new(),
+            devotion: BTreeMap::new(),
+            spotted_elites: BTreeSet::new(),
+            cooldowns: BTreeMap::new(),
+            timers: Vec::new(),
+            schedule: Vec::new(),
+            due: Vec::new(),
+            torch: light::TORCH_TURNS,
+            satiety: hunger::FULL_TURNS,
+            swum: 0,
+            wizard: false,
+            invincible: false,
+            log: Vec::new(),
+            turns: 0,
+            ticks: 0,
+            won: false,
+            depth: first_depth(),
+            branch: None,
+            special: None,
+            specials: BTreeMap::new(),
+            entrances: Vec::new(),
+            locks: Vec::new(),
+            mechanisms: Vec::new(),
+            destructibles: Vec::new(),
+            beneath_grass: Vec::new(),
+            portals: Vec::new(),
+            biome: Biome::Surface,
+            weather: Weather::Clear,
+            no_teleport: false,
+            artifacts: BTreeSet::new(),
+            visited: Vec::new(),
+            visible: Vec::new(),
+            light: Vec::new(),
+            events: Vec::new(),
+            revision: 0,
+        };
+        world.restore();
+        world
+    }
+
+    /// Rebuild the state that isn't saved, after creating or loading a world.
+    pub fn restore(&mut self) {
+        if self.explored.len() != self.map.len() {
+            self.explored = vec![false; self.map.len()];
+        }
+        self.update_fov();
+    }
+
+    pub fn player(&self) -> &Entity {
+        &self.entities[self.player_id]
+    }
+
+    /// Whether the run has ended, one way or the other.
+    pub fn is_over(&self) -> bool {
+        self.won || self.player_is_dead()
+    }
+
+    pub fn player_is_dead(&self) -> bool {
+        !self.player().is_alive()
+    }
+
+    fn index(&self, pos: Vector) -> Option<usize> {
+        if pos.x < 0.0 || pos.y < 0.0 || pos.x >= self.map_size.x || pos.y >= self.map_size.y {
+            return None;
+        }
+        Some(pos.x as usize * self.map_size.y as usize + pos.y as usize)
+    }
+
+    pub fn tile_at(&self, pos: Vector) -> Option<&Tile> {
+        self.index(pos).and_then(|index| self.map.get(index))
+    }
+
+    pub fn tile_at_mut(&mut self, pos: Vector) -> Option<&mut Tile> {
+        self.index(pos).and_then(move |index| self.map.get_mut(index))
+    }
+
+    /// Whether creatures can go to `pos`: nothing solid, no lever and no boulder.
+    pub fn is_walkable(&self, pos: Vector) -> bool {
+        let open = self.tile_at(pos).is_some_and(|tile| !tile.is_solid() && tile.glyph != mechanisms::LEVER);
+        open && self.boulder_at(pos).is_none() && self.shrine_at(pos).is_none()
+    }
+
+    pub fn is_visible(&self, pos: Vector) -> bool {
+        self.index(pos).is_some_and(|index| self.visible[index])
+    }
+
+    pub fn light_at(&self, pos: Vector) -> f32 {
+        self.index(pos).map_or(1.0, |index| self.light[index])
+    }
+
+    pub fn is_explored(&self, pos: Vector) -> bool {
+        self.index(pos).is_some_and(|index| self.explored[index])
+    }
+
+    /// Whether the player can make out a creature at `pos` by infravision though
+    /// it's out of sight.
+    pub fn is_sensed(&self, pos: Vector) -> bool {
+        let player = self.player();
+        player.traits.contains(&Trait::Infravision)
+            && !self.is_visible(pos)
+            && pos.distance(player.pos) <= INFRAVISION_RADIUS
+    }
+
+    /// Whether the player has a light of their own: a torch still burning, or a
+    /// timed light such as a flare's.
+    pub fn player_is_lit(&self) -> bool {
+        let timed = |timer: &Timer| timer.entity == self.player_id && matches!(timer.effect, Timed::Light(_));
+        self.torch > 0 || self.timers.iter().any(timed)
+    }
+
+    /// How far the player can see, a lot less far with no light of their own.
+    pub fn sight_radius(&self) -> i32 {
+        if self.player_is_lit() {
+            fov::FOV_RADIUS
+        } else {
+            light::DARK_SIGHT_RADIUS
+        }
+    }
+
+    pub fn update_fov(&mut self) {
+        let origin = self.player().pos;
+        self.visible = fov::compute(origin, self.sight_radius(), self.map_size, |pos| {
+            self.tile_at(pos).is_some_and(Tile::blocks_sight)
+        });
+        for (explored, &visible) in self.explored.iter_mut().zip(&self.visible) {
+            *explored |= visible;
+        }
+        self.light = light::compute(self);
+        self.revision += 1;
+
+        for id in self.visible_hostiles() {
+            if self.entities[id].elite && self.spotted_elites.insert(id) {
+                self.events.push(Event::Spotted { entity: id });
+            }
+        }
+    }
+
+    /// Positions of every stairs tile the player has seen, up or down.
+    pub fn known_stairs(&self) -> Vec<Vector> {
+        self.map
+            .iter()
+            .zip(&self.explored)
+            .filter(|&(tile, &explored)| explored && matches!(tile.glyph, '>' | '<'))
+            .map(|(tile, _)| tile.pos)
+            .collect()
+    }
+
+    /// Living non-player entities currently in view, apart from peaceful ones who
+    /// haven't been crossed.
+    pub fn visible_hostiles(&self) -> Vec<usize> {
+        self.entities
+            .iter()
+            .enumerate()
+            .filter(|&(id, entity)| id != self.player_id && entity.is_alive() && self.is_visible(entity.pos))
+            .filter(|(_, entity)| !entity.ai.is_peaceful())
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    pub fn creature_at(&self, pos: Vector) -> Option<usize> {
+        self.entities
+            .iter()
+            .position(|entity| entity.is_alive() && entity.pos == pos)
+    }
+
+    /// What it costs creatures to go through `pos` on their way somewhere: deep
+    /// water is slow going, ice treacherous and lava never worth it. `None` if they
+    /// can't go there.
+    pub fn step_cost(&self, pos: Vector) -> Option<u32> {
+        match self.tile_at(pos).map(|tile| tile.glyph) {
+            _ if !self.is_walkable(pos) => None,
+            Some(LAVA) => None,
+            Some(DEEP_WATER) => Some(hazards::DEEP_WATER_COST),
+            Some(ICE) => Some(hazards::ICE_COST),
+            _ => Some(1),
+        }
+    }
+
+    /// The boulder at `pos`, if there is one.
+    pub fn boulder_at(&self, pos: Vector) -> Option<usize> {
+        self.entities.iter().position(|entity| entity.boulder && entity.pos == pos)
+    }
+
+    /// An item lying on the map at `pos`.
+    pub fn item_at(&self, pos: Vector) -> Option<usize> {
+        self.entities.iter().position(|entity| entity.item.is_some() && entity.pos == pos)
+    }
+
+    /// The altar at `pos`, if there is one.
+    pub fn altar_at(&self, pos: Vector) -> Option<usize> {
+        self.altars.iter().position(|altar| altar.pos == pos)
+    }
+
+    /// The stairs into a branch at `pos`, if there are any.
+    pub fn entrance_at(&self, pos: Vector) -> Option<usize> {
+        self.entrances.iter().position(|entrance| entrance.pos == pos)
+    }
+
+    /// Corpses lying on or next to `pos`.
+    pub fn corpses_near(&self, pos: Vector) -> Vec<usize> {
+        self.entities
+            .iter()
+            .enumerate()
+            .filter(|&(id, entity)| id != self.player_id && !entity.is_alive())
+            .filter(|(_, entity)| entity.item.is_none() && !entity.boulder)
+            .filter(|(_, entity)| (entity.pos.x - pos.x).abs() <= 1.0 && (entity.pos.y - pos.y).abs() <= 1.0)
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// The shop whose keeper is standing at `pos`, if the keeper will still trade.
+    pub fn shop_kept_at(&self, pos: Vector) -> Option<usize> {
+        let keeper = self.creature_at(pos).filter(|&id| self.entities[id].ai == Ai::Shopkeeper)?;
+        self.shops.iter().position(|shop| shop.keeper == keeper)
+    }
+
+    /// The NPC standing at `pos` with something to say, if it's still friendly.
+    pub fn talker_at(&self, pos: Vector) -> Option<usize> {
+        let id = self.creature_at(pos)?;
+        let entity = &self.entities[id];
+        (entity.ai == Ai::Friendly && entity.dialogue.is_some()).then_some(id)
+    }
+
+    /// The shop `pos` is in, if its keeper will still trade.
+    pub fn open_shop_at(&self, pos: Vector) -> Option<usize> {
+        self.shops.iter().position(|shop| {
+            let keeper = &self.entities[shop.keeper];
+            shop.contains(pos) && keeper.is_alive() && keeper.ai == Ai::Shopkeeper
+        })
+    }
+
+    /// Whether a step by `delta` from `pos` stays on walkable ground. A diagonal step
+    /// can't cut past the corner of a wall, so it's blocked if either of the two
+    /// tiles beside it is.
+    fn can_step(&self, pos: Vector, delta: Vector) -> bool {
+        let diagonal = delta.x != 0.0 && delta.y != 0.0;
+        self.is_walkable(pos + delta)
+            && !(diagonal
+                && (!self.is_walkable(pos + Vector::new(delta.x, 0.0))
+                    || !self.is_walkable(pos + Vector::new(0.0, delta.y))))
+    }
+
+    /// Move the player by `delta`, attacking whatever stands there, pushing any
+    /// boulder, trying any locked door there, pulling any lever and visiting any
+    /// fountain or shrine. Returns whether that used up the player's turn; walking
+    /// into a wall, anything peaceful, a boulder that won't budge or a spent shrine
+    /// doesn't.
+    pub fn move_player(&mut self, delta: Vector) -> bool {
+        let pos = self.player().pos;
+        let creature = self.creature_at(pos + delta);
+        if creature.is_some_and(|id| self.entities[id].ai.is_peaceful()) {
+            return false;
+        } else if let Some(target) = creature {
+            self.attack(self.player_id, target);
+        } else if let Some(boulder) = self.boulder_at(pos + delta) {
+            if !self.push(boulder, delta) {
+                return false;
+            }
+        } else if let Some(index) = self.lock_at(pos + delta) {
+            self.try_lock(index);
+        } else if let Some(index) = self.mechanism_at(pos + delta, Trigger::Lever) {
+            self.work(self.player_id, index);
+        } else if let Some(index) = self.shrine_at(pos + delta) {
+            if !self.visit(index) {
+                return false;
+            }
+        } else if let Some(index) = self.smashable(pos + delta) {
+            self.batter(self.player_id, index, self.melee_damage(self.player_id));
+        } else if self.can_step(pos, delta) {
+            self.entities[self.player_id].pos = pos + delta;
+            self.events.push(Event::Moved { entity: self.player_id });
+            self.step_onto(self.player_id);
+            self.slide(self.player_id, delta);
+            self.update_fov();
+            self.check_theft();
+        } else {
+            return false;
+        }
+        self.log.push(Command::MovePlayer(delta));
+        true
+    }
+
+    /// The player pushes `boulder` on by `delta`, following it into where it was.
+    /// It rolls onto open ground and fills a trap, deep water or lava, and a
+    /// creature in its way is crushed, the boulder rolling on only if that kills it.
+    /// It can't be pushed diagonally, into anything else or into anyone peaceful.
+    /// Returns whether it took the player's turn.
+    fn push(&mut self, boulder: usize, delta: Vector) -> bool {
+        let pos = self.entities[boulder].pos;
+        let beyond = pos + delta;
+        let creature = self.creature_at(beyond);
+        let diagonal = delta.x != 0.0 && delta.y != 0.0;
+        if diagonal || creature.is_some_and(|id| self.entities[id].ai.is_peaceful()) || !self.is_walkable(beyond) {
+            debug!(target: "combat", id = boulder, x = beyond.x, y = beyond.y; "boulder wedged");
+            self.events.push(Event::Wedged { entity: boulder });
+            return false;
+        }
+        if let Some(target) = creature {
+            debug!(target: "combat", id = boulder, target = target; "boulder crushed");
+            self.hurt(target, CRUSH_DAMAGE);
+            if self.entities[target].is_alive() {
+                return true;
+            }
+        }
+        if self.tile_at(beyond).is_some_and(|tile| matches!(tile.glyph, mechanisms::TRAP | DEEP_WATER | LAVA)) {
+            debug!(target: "combat", id = boulder, x = beyond.x, y = beyond.y; "boulder filled a hole");
+            // It fills in level with whatever the boulder stood on
+            let filled = self.tile_at(pos).map(|tile| Tile { pos: beyond, ..tile.clone() });
+            if let Some((tile, filled)) = self.tile_at_mut(beyond).zip(filled) {
+                *tile = filled;
+            }
+            self.entities[boulder].pos = OFF_MAP;
+            self.events.push(Event::Plugged { entity: boulder });
+        } else {
+            self.entities[boulder].pos = beyond;
+            self.events.push(Event::Moved { entity: boulder });
+            if let Some(index) = self.mechanism_at(beyond, Trigger::Plate) {
+                self.work(boulder, index);
+            }
+        }
+        self.entities[self.player_id].pos = pos;
+        self.events.push(Event::Moved { entity: self.player_id });
+        self.step_onto(self.player_id);
+        self.update_fov();
+        true
+    }
+
+    /// `id` has just stepped by `delta`. While it's on ice it slides on the same
+    /// way, setting off whatever it slides onto, until it comes off the ice or
+    /// something stops it.
+    fn slide(&mut self, id: usize, delta: Vector) {
+        let mut slid = 0;
+        while slid < MAX_SLIDE && self.entities[id].is_alive() {
+            let pos = self.entities[id].pos;
+            let on_ice = self.tile_at(pos).is_some_and(|tile| tile.glyph == ICE);
+            if !on_ice || !self.can_step(pos, delta) || self.creature_at(pos + delta).is_some() {
+                break;
+            }
+            self.entities[id].pos = pos + delta;
+            self.events.push(Event::Moved { entity: id });
+            self.step_onto(id);
+            slid += 1;
+        }
+        if slid > 0 {
+            debug!(target: "combat", id = id, tiles = slid; "slid");
+            self.events.push(Event::Slid { entity: id });
+        }
+    }
+
+    /// Set the tall grass at `pos` alight, as `id` did, and let the fire run
+    /// through all of the patch it's part of. It leaves bare floor behind, and
+    /// whoever stood in it burning.
+    fn kindle(&mut self, id: usize, pos: Vector) {
+        let mut spreading = vec![pos];
+        let mut kindled = false;
+        while let Some(pos) = spreading.pop() {
+            let grass = self.tile_at(pos).is_some_and(|tile| tile.glyph == TALL_GRASS);
+            let index = match self.beneath_grass.iter().position(|floor| floor.pos == pos) {
+                Some(index) if grass => index,
+                _ => continue,
+            };
+            let floor = self.beneath_grass.remove(index);
+            if let Some(tile) = self.tile_at_mut(pos) {
+                *tile = floor;
+            }
+            if let Some(creature) = self.creature_at(pos) {
+                self.entities[creature].afflict(StatusEffect { status: Status::Burning, turns: GRASS_FIRE_TURNS });
+            }
+            kindled = true;
+            spreading.extend((-1..=1).flat_map(|dx| (-1..=1).map(move |dy| pos + Vector::new(dx, dy))));
+        }
+        if kindled {
+            debug!(target: "combat", id = id, x = pos.x, y = pos.y; "set the grass alight");
+            self.events.push(Event::Kindled { entity: id });
+        }
+    }
+
+    /// The fountain or shrine at `pos`, by index into `shrines`, as long as it's
+    /// still standing.
+    pub fn shrine_at(&self, pos: Vector) -> Option<usize> {
+        let index = self.shrines.iter().position(|shrine| shrine.pos == pos)?;
+        self.tile_at(pos).is_some_and(|tile| tile.glyph == self.shrines[index].def.glyph).then_some(index)
+    }
+
+    /// The player drinks from or prays at the fountain or shrine at `index` of
+    /// `shrines`, and one of its outcomes comes of it. Returns whether it had a
+    /// visit left to give.
+    fn visit(&mut self, index: usize) -> bool {
+        let (entity, kind) = (self.player_id, self.shrines[index].def.kind);
+        if self.shrines[index].uses == 0 {
+            self.events.push(Event::Spent { entity, kind });
+            return false;
+        }
+        self.shrines[index].uses -= 1;
+        let outcome = self.shrines[index].def.roll(&mut self.rng).cloned();
+        if let Some(outcome) = outcome {
+            let shrine = self.shrines[index].def.name.as_str();
+            debug!(target: "gods", shrine = shrine, outcome = outcome.name.as_str(); "visited");
+            self.take_effects(&outcome.effects);
+            if outcome.damage > 0 {
+                self.hurt(entity, outcome.damage);
+            }
+            self.events.push(Event::Visited { entity, kind, outcome: outcome.name });
+        }
+        true
+    }
+
+    /// The locked door at `pos`, by index into `locks`.
+    pub fn lock_at(&self, pos: Vector) -> Option<usize> {
+        self.locks.iter().position(|lock| lock.pos == pos)
+    }
+
+    /// Try the locked door at `index` of `locks`. The player's key for it opens it
+    /// and is used up; without one, they try to force it.
+    fn try_lock(&mut self, index: usize) {
+        let entity = self.player_id;
+        let key = self.locks[index].key.clone();
+        let player = &mut self.entities[entity];
+        let key = match player.inventory.iter().position(|item| *item == key) {
+            Some(slot) => Some(player.inventory.remove(slot)),
+            None if self.rng.range(0, 100) < player.attributes.force_chance() => None,
+            None => {
+                debug!(target: "combat", x = self.locks[index].pos.x, y = self.locks[index].pos.y; "door held");
+                self.events.push(Event::DoorHeld { entity });
+                return;
+            }
+        };
+        let lock = self.locks.remove(index);
+        debug!(target: "combat", x = lock.pos.x, y = lock.pos.y, key:? = key; "unlocked");
+        if let Some(tile) = self.tile_at_mut(lock.pos) {
+            tile.glyph = '\'';
+        }
+        self.events.push(Event::Unlocked { entity, key });
+        self.update_fov();
+    }
+
+    /// The mechanism worked by `trigger` at `pos`, by index into `mechanisms`.
+    pub fn mechanism_at(&self, pos: Vector, trigger: Trigger) -> Option<usize> {
+        let at = |mechanism: &Mechanism| mechanism.pos == pos && mechanism.trigger == trigger;
+        self.mechanisms.iter().position(at)
+    }
+
+    /// `id` works the mechanism at `index` of `mechanisms`, switching each tile
+    /// it's linked to, apart from any that would come down on a creature.
+    fn work(&mut self, id: usize, index: usize) {
+        let at = self.mechanisms[index].pos;
+        debug!(target: "generate", id = id, x = at.x, y = at.y; "worked");
+        for slot in 0..self.mechanisms[index].switches.len() {
+            let pos = self.mechanisms[index].switches[slot].pos;
+            let taken = self.creature_at(pos).is_some() || self.boulder_at(pos).is_some();
+            let crushing = self.mechanisms[index].switches[slot].is_solid() && taken;
+            if let (Some(map_index), false) = (self.index(pos), crushing) {
+                std::mem::swap(&mut self.map[map_index], &mut self.mechanisms[index].switches[slot]);
+            }
+        }
+        self.events.push(Event::Worked { entity: id });
+        self.update_fov();
+    }
+
+    /// Whatever there is at `id`'s feet goes off now it has stepped there: a
+    /// pressure plate is worked, a trap hurts it, tall grass catches from it if
+    /// it's burning and a portal sends it out of the other end.
+    fn step_onto(&mut self, id: usize) {
+        let pos = self.entities[id].pos;
+        if self.entities[id].has_status(Status::Burning) {
+            self.kindle(id, pos);
+        }
+        if let Some(index) = self.mechanism_at(pos, Trigger::Plate) {
+            self.work(id, index);
+        }
+        if self.tile_at(pos).is_some_and(|tile| tile.glyph == mechanisms::TRAP) {
+            debug!(target: "combat", id = id, x = pos.x, y = pos.y; "sprang a trap");
+            self.events.push(Event::Trapped { entity: id });
+            self.hurt(id, mechanisms::TRAP_DAMAGE);
+        }
+        if let Some(index) = self.portal_at(pos) {
+            self.warp(id, index);
+        }
+    }
+
+    /// The portal at `pos`, by index into `portals`, as long as it's still there.
+    pub fn portal_at(&self, pos: Vector) -> Option<usize> {
+        let open = self.tile_at(pos).is_some_and(|tile| tile.glyph == PORTAL);
+        self.portals.iter().position(|portal| portal.pos == pos).filter(|_| open)
+    }
+
+    /// Send `id` through the portal at `index` of `portals`, out of the other end,
+    /// unless something stands there or that end is gone.
+    fn warp(&mut self, id: usize, index: usize) {
+        let exit = self.portals[index].exit;
+        if self.portal_at(exit).is_none() || self.creature_at(exit).is_some() || self.boulder_at(exit).is_some() {
+            return;
+        }
+        debug!(target: "combat", id = id, x = exit.x, y = exit.y; "went through a portal");
+        self.entities[id].pos = exit;
+        self.events.push(Event::Warped { entity: id });
+    }
+
+    /// `attacker` hits `target` in melee, unless the target's defense blocks it,
+    /// with whatever talents the attacker has. Strength adds to the damage and
+    /// dexterity makes a block less likely. A venomous attacker's hits poison.
+    fn attack(&mut self, attacker: usize, target: usize) {
+        let defense = self.entities[target].defense;
+        let attributes = self.entities[attacker].attributes;
+        let block_chance = defense as f32 * BLOCK_CHANCE - attributes.accuracy();
+        if defense > 0 && self.rng.next_f32() < block_chance {
+            debug!(target: "combat", attacker = attacker, id = target; "blocked");
+            return;
+        }
+        let damage = self.melee_damage(attacker);
+        let talents = &self.entities[attacker].talents;
+        let (ambush, cleave) = (talents.contains(&Talent::Ambush), talents.contains(&Talent::Cleave));
+        let unhurt = self.entities[target].hp == self.entities[target].max_hp;
+        self.hurt(target, if ambush && unhurt { damage * 2 } else { damage });
+        let venomous = self.entities[attacker].abilities.contains(&Ability::Venomous);
+        if venomous && self.entities[target].is_alive() {
+            self.poison(target);
+        }
+        let slowing = self.entities[attacker].abilities.contains(&Ability::Slowing);
+        if slowing && self.entities[target].is_alive() {
+            self.entities[target].afflict(StatusEffect {
+                status: Status::Slowed,
+                turns: CHILL_TURNS,
+            });
+        }
+        if cleave {
+            let pos = self.entities[attacker].pos;
+            let next_to = |id: usize| {
+                let entity = &self.entities[id];
+                let bystander = id != attacker && id != target && id != self.player_id;
+                bystander && entity.is_alive() && entity.pos.distance(pos) < 1.5
+            };
+            if let Some(other) = (0..self.entities.len()).find(|&id| next_to(id)) {
+                debug!(target: "combat", attacker = attacker, id = other; "cleave");
+                self.hurt(other, damage);
+            }
+        }
+    }
+
+    /// How much damage a melee attack by `id` does, before talents.
+    fn melee_damage(&self, id: usize) -> i32 {
+        (self.entities[id].attack + self.entities[id].attributes.melee_damage()).max(1)
+    }
+
+    /// The wall, pillar or piece of furniture at `pos`, by index into
+    /// `destructibles`.
+    pub fn destructible_at(&self, pos: Vector) -> Option<usize> {
+        self.destructibles.iter().position(|destructible| destructible.pos == pos)
+    }
+
+    /// The destructible at `pos` the player knocks at by moving into it: one in
+    /// their way, when their melee attacks are heavy enough to.
+    fn smashable(&self, pos: Vector) -> Option<usize> {
+        let heavy = self.melee_damage(self.player_id) >= HEAVY_DAMAGE;
+        self.destructible_at(pos).filter(|_| heavy && !self.is_walkable(pos))
+    }
+
+    /// `id` does `damage` to the destructible at `index` of `destructibles`, which
+    /// comes down into rubble once its hp runs out, strewing debris over the bare
+    /// floor around it.
+    fn batter(&mut self, id: usize, index: usize, damage: i32) {
+        self.destructibles[index].hp -= damage;
+        if self.destructibles[index].hp > 0 {
+            debug!(target: "combat", id = id, hp = self.destructibles[index].hp; "battered");
+            self.events.push(Event::Battered { entity: id });
+            return;
+        }
+        let destructible = self.destructibles.remove(index);
+        let pos = destructible.pos;
+        debug!(target: "combat", id = id, x = pos.x, y = pos.y; "knocked down");
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let at = pos + Vector::new(dx, dy);
+                let bare = self.tile_at(at).is_some_and(|tile| tile.glyph == '.');
+                if bare && self.rng.range(0, 100) < DEBRIS_CHANCE {
+                    if let Some(tile) = self.tile_at_mut(at) {
+                        *tile = Tile {
+                            pos: at,
+                            ..destructible.rubble.clone()
+                        };
+                    }
+                }
+            }
+        }
+        if let Some(tile) = self.tile_at_mut(pos) {
+            *tile = destructible.rubble;
+        }
+        self.events.push(Event::Collapsed { entity: id });
+        self.update_fov();
+    }
+
+    /// Pick up the item at the player's feet, returning it. One lying in a shop
+    /// has to be paid for before the player leaves.
+    pub fn pick_up(&mut self) -> Option<usize> {
+        let pos = self.player().pos;
+        let id = self.item_at(pos)?;
+        self.log.push(Command::PickUp);
+        let item = &mut self.entities[id];
+        item.pos = OFF_MAP;
+        let name = item.item.clone().unwrap_or_default();
+        debug!(target: "items", id = id, item = name.as_str(); "picked up");
+        self.entities[self.player_id].inventory.push(name);
+        self.events.push(Event::PickedUp { entity: self.player_id });
+        if let Some(shop) = self.open_shop_at(pos) {
+            self.shops[shop].unpaid.push(id);
+        }
+        Some(id)
+    }
+
+    /// Make `trade` at `shop`, returning whether it went through; the player can't
+    /// pay more than they have.
+    pub fn trade(&mut self, shop: usize, trade: Trade) -> bool {
+        let unpaid = &mut self.shops[shop].unpaid;
+        let player = &mut self.entities[self.player_id];
+        match trade {
+            Trade::Buy { item, price } => {
+                if item >= unpaid.len() || price > player.gold {
+                    return false;
+                }
+                player.gold -= price;
+                unpaid.remove(item);
+            }
+            Trade::Return { item } => {
+                if item >= unpaid.len() {
+                    return false;
+                }
+                let id = unpaid.remove(item);
+                let (pos, entity) = (player.pos, &self.entities[id]);
+                let name = entity.item.clone().unwrap_or_default();
+                let player = &mut self.entities[self.player_id];
+                if let Some(index) = player.inventory.iter().rposition(|carried| *carried == name) {
+                    player.inventory.remove(index);
+                }
+                self.entities[id].pos = pos;
+            }
+            Trade::Sell { item, price } => {
+                if item >= player.inventory.len() {
+                    return false;
+                }
+                player.inventory.remove(item);
+                player.gold += price;
+            }
+        }
+        self.log.push(Command::Trade(shop, trade));
+        true
+    }
+
+    /// Whether the player has walked out of a shop with things they haven't paid
+    /// for. If so they keep them, but the keeper comes after them.
+    fn check_theft(&mut self) {
+        let pos = self.player().pos;
+        for index in 0..self.shops.len() {
+            let shop = &self.shops[index];
+            let keeper = &self.entities[shop.keeper];
+            if shop.unpaid.is_empty() || shop.contains(pos) || !keeper.is_alive() || keeper.ai != Ai::Shopkeeper {
+                continue;
+            }
+            debug!(target: "items", keeper = shop.keeper, items = shop.unpaid.len(); "stolen from a shop");
+            self.events.push(Event::Robbed { keeper: shop.keeper });
+            self.anger(shop.keeper);
+        }
+    }
+
+    /// Turn `id` on the player if it's peaceful. A shopkeeper then won't trade any
+    /// more, and lets go of whatever is still owed.
+    fn anger(&mut self, id: usize) {
+        if !self.entities[id].ai.is_peaceful() {
+            return;
+        }
+        self.entities[id].ai = Ai::Chase;
+        for shop in self.shops.iter_mut().filter(|shop| shop.keeper == id) {
+            shop.unpaid.clear();
+        }
+    }
+
+    /// Answer `speaker` with a choice that does `effects`.
+    pub fn talk(&mut self, speaker: usize, effects: &[DialogueEffect]) {
+        self.log.push(Command::Talk(speaker, effects.to_vec()));
+        let player = &mut self.entities[self.player_id];
+        for effect in effects {
+            match effect {
+                DialogueEffect::Give(item) => player.inventory.push(item.clone()),
+                DialogueEffect::Gold(gold) => player.gold += gold,
+                DialogueEffect::Resume(node) => {
+                    self.dialogue_starts.insert(speaker, node.clone());
+                }
+                // Quests are started on their own, with the whole definition
+                DialogueEffect::StartQuest(_) => {}
+            }
+        }
+    }
+
+    /// Move the player onto `level`, freshly generated or visited before, with
+    /// them standing where they arrive. The level they leave behind is kept as it
+    /// is for them to come back to, and the one they arrive on is no longer kept
+    /// apart, while what belongs to the run comes along.
+    pub fn change_level(&mut self, level: &World) {
+        self.leave_level();
+        self.visited.retain(|kept| kept.depth != level.depth || kept.branch != level.branch);
+        self.log.push(Command::ChangeLevel(Box::new(level.clone())));
+        let mut player = self.player().clone();
+        player.pos = level.player().pos;
+        debug!(target: "generate", depth = level.depth; "changed level");
+        self.map_size = level.map_size;
+        self.map = level.map.clone();
+        self.entities = level.entities.clone();
+        let (old_player, player_id) = (self.player_id, level.player_id);
+        self.timers.retain(|timer| timer.entity == old_player);
+        for timer in &mut self.timers {
+            timer.entity = player_id;
+        }
+        // A kept level's own timers pick up where they left off
+        let others = level.timers.iter().filter(|timer| timer.entity != player_id);
+        self.timers.extend(others.copied());
+        self.player_id = player_id;
+        self.entities[self.player_id] = player;
+        self.explored = level.explored.clone();
+        self.rng = level.rng.clone();
+        self.shops = level.shops.clone();
+        self.altars = level.altars.clone();
+        self.shrines = level.shrines.clone();
+        self.dialogue_starts = level.dialogue_starts.clone();
+        self.spotted_elites = level.spotted_elites.clone();
+        self.depth = level.depth;
+        self.branch = level.branch.clone();
+        self.special = level.special.clone();
+        self.entrances = level.entrances.clone();
+        self.locks = level.locks.clone();
+        self.mechanisms = level.mechanisms.clone();
+        self.destructibles = level.destructibles.clone();
+        self.beneath_grass = level.beneath_grass.clone();
+        self.portals = level.portals.clone();
+        self.schedule = level.schedule.clone();
+        self.biome = level.biome;
+        self.weather = level.weather;
+        self.no_teleport = level.no_teleport;
+        // The level was made knowing every artifact found so far, and adds its own
+        self.artifacts = level.artifacts.clone();
+        self.restore();
+    }
+
+    /// Keep the current level among the visited ones, in place of how it was the
+    /// last time the player left it.
+    fn leave_level(&mut self) {
+        let (log, visited) = (std::mem::take(&mut self.log), std::mem::take(&mut self.visited));
+        let left = self.clone();
+        self.log = log;
+        self.visited = visited;
+        self.visited.retain(|level| level.depth != left.depth || level.branch != left.branch);
+        self.visited.push(left);
+    }
+
+    /// The level at `depth` of `branch`, or of the main dungeon, as the player
+    /// last left it, if they've been there.
+    pub fn visited_level(&self, depth: u32, branch: Option<&str>) -> Option<&World> {
+        self.visited.iter().find(|level| level.depth == depth && level.branch.as_deref() == branch)
+    }
+
+    /// Move `id` straight to `pos` by magic, returning whether it went. It doesn't
+    /// on a level that holds teleportation back, or onto a wall or a creature.
+    pub fn teleport(&mut self, id: usize, pos: Vector) -> bool {
+        if self.no_teleport || !self.is_walkable(pos) || self.creature_at(pos).is_some() {
+            return false;
+        }
+        self.entities[id].pos = pos;
+        self.events.push(Event::Moved { entity: id });
+        if id == self.player_id {
+            self.check_theft();
+        }
+        true
+    }
+
+    /// Leave the dungeon by the first level's stairs up, which wins the run if the
+    /// player has the amulet. Returns whether they did.
+    pub fn escape(&mut self) -> bool {
+        if !dungeon::has_amulet(self.player()) {
+            return false;
+        }
+        self.log.push(Command::Escape);
+        debug!(target: "generate", depth = self.depth; "escaped with the amulet");
+        self.won = true;
+        true
+    }
+
+    /// Take on `quest` from `giver`, putting `item` down somewhere on the level if
+    /// it's the one to fetch from here. A quest already taken is left as it is.
+    pub fn start_quest(&mut self, giver: usize, quest: &QuestDef, item: Option<&Entity>) {
+        self.log.push(Command::StartQuest(giver, Box::new(quest.clone()), item.cloned().map(Box::new)));
+        if self.quests.iter().any(|taken| taken.def.name == quest.name) {
+            return;
+        }
+        debug!(target: "quests", quest = quest.name.as_str(), giver = giver; "started");
+        self.quests.push(Quest::new(quest.clone(), giver, self.depth));
+        if let Some(item) = item {
+            if let Some(pos) = spawn::free_spot(self) {
+                self.entities.push(Entity { pos, ..item.clone() });
+            }
+        }
+    }
+
+    /// The quests `giver`, on this level, handed out that are done but haven't been
+    /// rewarded yet.
+    pub fn quests_to_reward(&self, giver: usize) -> Vec<usize> {
+        let player = self.player();
+        (0..self.quests.len())
+            .filter(|&index| {
+                let quest = &self.quests[index];
+                quest.giver == giver && quest.depth == self.depth && !quest.rewarded && quest.is_done(player)
+            })
+            .collect()
+    }
+
+    /// Hand in the quest at `index` to its giver, who takes back anything fetched
+    /// and gives the rewards.
+    pub fn reward_quest(&mut self, index: usize) {
+        self.log.push(Command::RewardQuest(index));
+        let quest = &mut self.quests[index];
+        quest.rewarded = true;
+        let (def, giver) = (quest.def.clone(), quest.giver);
+        debug!(target: "quests", quest = def.name.as_str(); "rewarded");
+        let player = &mut self.entities[self.player_id];
+        if let Goal::Fetch { item, .. } = &def.goal {
+            if let Some(position) = player.inventory.iter().position(|carried| carried == item) {
+                player.inventory.remove(position);
+            }
+        }
+        for reward in &def.rewards {
+            match reward {
+                Reward::Gold(gold) => self.entities[self.player_id].gold += gold,
+                Reward::Item(item) => self.entities[self.player_id].inventory.push(item.clone()),
+                Reward::Xp(xp) => self.gain_xp(*xp),
+            }
+        }
+        if let Some(after) = def.after {
+            self.dialogue_starts.insert(giver, after);
+        }
+    }
+
+    /// Bring about `happening`, worked out ahead by `happenings::prepare`.
+    pub fn happen(&mut self, happening: &Happening) {
+        self.log.push(Command::Happen(Box::new(happening.clone())));
+        debug!(target: "generate", happening = happening.name.as_str(); "happened");
+        for tile in &happening.tiles {
+            if let Some(index) = self.index(tile.pos) {
+                self.map[index] = tile.clone();
+            }
+        }
+        self.entities.extend(happening.arrivals.iter().cloned());
+        self.shops.extend(happening.shops.iter().cloned());
+        self.locks.extend(happening.locks.iter().cloned());
+        self.mechanisms.extend(happening.mechanisms.iter().cloned());
+        // Whatever the happening changed is no longer what could be knocked down there
+        self.destructibles.retain(|destructible| happening.tiles.iter().all(|tile| tile.pos != destructible.pos));
+        self.destructibles.extend(happening.destructibles.iter().cloned());
+        self.rng = happening.rng.clone();
+        self.update_fov();
+    }
+
+    /// Bring `monster` back onto the level, as worked out by `spawn::respawn`, with
+    /// the level's random choices carrying on from `rng`.
+    pub fn respawn(&mut self, monster: &Entity, rng: &Rng) {
+        self.log.push(Command::Respawn(Box::new(monster.clone()), rng.clone()));
+        debug!(target: "generate", x = monster.pos.x, y = monster.pos.y; "respawned a monster");
+        self.entities.push(monster.clone());
+        self.rng = rng.clone();
+    }
+
+    /// Bring `monster` back onto the level at `index` of the visited ones, as
+    /// worked out by `spawn::respawn`, with that level's random choices carrying on
+    /// from `rng`.
+    pub fn respawn_kept(&mut self, index: usize, monster: &Entity, rng: &Rng) {
+        self.log.push(Command::RespawnKept(index, Box::new(monster.clone()), rng.clone()));
+        let level = &mut self.visited[index];
+        debug!(target: "generate", depth = level.depth, x = monster.pos.x, y = monster.pos.y; "respawned a monster");
+        level.entities.push(monster.clone());
+        level.rng = rng.clone();
+    }
+
+    /// Use up the item at `index` of the player's inventory for its `effects`,
+    /// learning what it is.
+    pub fn use_item(&mut self, index: usize, effects: &[ItemEffect]) {
+        self.log.push(Command::UseItem(index, effects.to_vec()));
+        let name = self.entities[self.player_id].inventory.remove(index);
+        debug!(target: "items", item = name.as_str(); "used");
+        self.take_effects(effects);
+        self.identified.insert(name);
+    }
+
+    /// Give the player `effects`, the way using an item would.
+    fn take_effects(&mut self, effects: &[ItemEffect]) {
+        for effect in effects {
+            let player = &mut self.entities[self.player_id];
+            match *effect {
+                ItemEffect::Heal(hp) => player.hp = (player.hp + hp).min(player.max_hp),
+                ItemEffect::Mana(mana) => player.mana = (player.mana + mana).min(player.max_mana),
+                ItemEffect::Afflict(effect) => player.afflict(effect),
+                ItemEffect::Cure => player.statuses.clear(),
+                ItemEffect::Lasting { effect, turns } => self.start_timer(self.player_id, effect, turns),
+                ItemEffect::Fuel(turns) => self.torch = (self.torch + turns).min(light::MAX_TORCH_TURNS),
+                ItemEffect::Nourish(turns) => self.satiety = (self.satiety + turns).min(hunger::MAX_FULL_TURNS),
+                // Only worn or held items give these
+                ItemEffect::Attack(_) | ItemEffect::Defense(_) => {}
+            }
+        }
+    }
+
+    /// Mix the items at `first` and `second` of the player's inventory, which
+    /// makes `mixture` out of them.
+    pub fn mix(&mut self, first: usize, second: usize, mixture: &Mixture) {
+        self.log.push(Command::Mix(first, second, mixture.clone()));
+        let inventory = &mut self.entities[self.player_id].inventory;
+        inventory.remove(first.max(second));
+        inventory.remove(first.min(second));
+        debug!(target: "items", mixture:? = mixture; "mixed");
+        match mixture {
+            Mixture::Potion(potion) => inventory.push(potion.clone()),
+            Mixture::Mishap { damage, statuses } => {
+                self.hurt(self.player_id, *damage);
+                for &effect in statuses {
+                    self.entities[self.player_id].afflict(effect);
+                }
+            }
+        }
+    }
+
+    /// Make `recipe` out of the components the player carries, returning whether
+    /// they could: they need every ingredient, and a workbench if it asks for one.
+    pub fn craft(&mut self, recipe: &RecipeDef) -> bool {
+        let pos = self.player().pos;
+        if !recipe.missing(self.player()).is_empty() || (recipe.workbench && !crafting::near_workbench(self, pos)) {
+            return false;
+        }
+        self.log.push(Command::Craft(Box::new(recipe.clone())));
+        let player = &mut self.entities[self.player_id];
+        for ingredient in &recipe.ingredients {
+            for _ in 0..ingredient.count {
+                if let Some(index) = player.inventory.iter().position(|item| *item == ingredient.item) {
+                    player.inventory.remove(index);
+                }
+            }
+        }
+        player.inventory.push(recipe.makes.clone());
+        debug!(target: "items", recipe = recipe.name.as_str(); "crafted");
+        true
+    }
+
+    /// Give up `offering` to `god`, earning piety with it, and receive any of its
+    /// gifts that earns. Returns the gifts, by item name.
+    pub fn sacrifice(&mut self, god: &GodDef, offering: Offering) -> Vec<String> {
+        self.log.push(Command::Sacrifice(Box::new(god.clone()), offering));
+        match offering {
+            Offering::Item { index, .. } => {
+                self.entities[self.player_id].inventory.remove(index);
+            }
+            Offering::Corpse(id) => self.entities[id].pos = OFF_MAP,
+        }
+        let devotion = self.devotion.entry(god.name.clone()).or_default();
+        devotion.piety += god.piety(offering);
+        debug!(target: "gods", god = god.name.as_str(), piety = devotion.piety; "sacrificed");
+        let mut gifts = Vec::new();
+        while let Some(gift) = god.gifts.get(devotion.gifts).filter(|gift| devotion.piety >= gift.piety) {
+            devotion.gifts += 1;
+            gifts.push(gift.item.clone());
+        }
+        self.entities[self.player_id].inventory.extend(gifts.iter().cloned());
+        gifts
+    }
+
+    /// Spend piety with `god` on `prayer`, returning whether it was answered. It
+    /// isn't without enough piety, or with nothing in view for a smiting to hit.
+    pub fn pray(&mut self, god: &str, prayer: &Prayer) -> bool {
+        let piety = self.devotion.get(god).map_or(0, |devotion| devotion.piety);
+        let target = self.nearest_visible_hostile();
+        if piety < prayer.piety || (matches!(prayer.effect, PrayerEffect::Smite(_)) && target.is_none()) {
+            return false;
+        }
+        self.log.push(Command::Pray(god.to_string(), Box::new(prayer.clone())));
+        if let Some(devotion) = self.devotion.get_mut(god) {
+            devotion.piety -= prayer.piety;
+        }
+        debug!(target: "gods", god = god, prayer = prayer.name.as_str(); "prayer answered");
+        match prayer.effect {
+            PrayerEffect::Heal(hp) => {
+                let player = &mut self.entities[self.player_id];
+                player.hp = (player.hp + hp).min(player.max_hp);
+            }
+            PrayerEffect::Smite(damage) => {
+                if let Some(target) = target {
+                    self.hurt(target, damage);
+                }
+            }
+        }
+        true
+    }
+
+    /// Poison `target` for `VENOM_TURNS` turns, unless it resists poison.
+    fn poison(&mut self, target: usize) {
+        let entity = &mut self.entities[target];
+        if entity.resists.contains(&DamageType::Poison) {
+            debug!(target: "combat", id = target; "resists the poison");
+            return;
+        }
+        entity.afflict(StatusEffect {
+            status: Status::Poisoned,
+            turns: VENOM_TURNS,
+        });
+    }
+
+    /// Take `damage` off `target`'s hp, leaving a corpse and dropping its loot if
+    /// that kills it.
+    fn hurt(&mut self, target: usize, damage: i32) {
+        let invincible = self.invincible && target == self.player_id;
+        self.anger(target);
+        let entity = &mut self.entities[target];
+        if !invincible {
+            entity.hp -= damage;
+        }
+        let (glyph, hp) = (entity.glyph, entity.hp);
+        debug!(
+            target: "combat",
+            id = target, glyph:% = glyph, damage = damage, hp = hp, invincible = invincible;
+            "hurt"
+        );
+        if !entity.is_alive() {
+            debug!(target: "combat", id = target, glyph:% = glyph; "killed");
+        }
+        if !entity.is_alive() && target != self.player_id {
+            self.events.push(Event::Died {
+                target,
+                glyph: entity.glyph,
+                color: entity.color,
+            });
+            entity.glyph = '%';
+            entity.hp = 0;
+            entity.animation = None;
+            let xp = entity.max_hp.max(1) as u32;
+            let kind = entity.kind.clone();
+            if let Some(mut loot) = entity.loot.take() {
+                loot.pos = entity.pos;
+                self.entities.push(*loot);
+            }
+            self.gain_xp(xp);
+            for quest in self.quests.iter_mut().filter(|quest| !quest.rewarded) {
+                if matches!(&quest.def.goal, Goal::Kill { monster, .. } if Some(monster) == kind.as_ref()) {
+                    quest.kills += 1;
+                }
+            }
+        } else {
+            self.events.push(Event::Hit { target });
+        }
+    }
+
+    /// Give the player `xp` experience, levelling them up as many times as it's
+    /// enough for. Each level adds a max hp, every `PERK_LEVELS` levels a perk to
+    /// pick and every `ATTRIBUTE_LEVELS` levels an attribute point.
+    fn gain_xp(&mut self, xp: u32) {
+        let player = &mut self.entities[self.player_id];
+        player.xp += xp;
+        while player.xp >= xp_needed(player.level) {
+            player.xp -= xp_needed(player.level);
+            player.level += 1;
+            player.max_hp += 1;
+            player.hp += 1;
+            debug!(target: "combat", level = player.level; "level up");
+            self.events.push(Event::LevelUp {
+                entity: self.player_id,
+                level: player.level,
+            });
+            if player.level.is_multiple_of(PERK_LEVELS) {
+                self.perk_points += 1;
+            }
+            if player.level.is_multiple_of(ATTRIBUTE_LEVELS) {
+                self.attribute_points += 1;
+            }
+        }
+    }
+
+    /// Spend an attribute point on raising `attribute`. Constitution raises max hp
+    /// at once; the rest count wherever they're used.
+    pub fn raise(&mut self, attribute: Attribute) {
+        self.log.push(Command::Raise(attribute));
+        self.attribute_points = self.attribute_points.saturating_sub(1);
+        let player = &mut self.entities[self.player_id];
+        let hp_before = player.attributes.hp();
+        player.attributes.raise(attribute);
+        let gained = player.attributes.hp() - hp_before;
+        player.max_hp += gained;
+        player.hp += gained;
+    }
+
+    /// Spend a perk point on `perk`.
+    pub fn take_perk(&mut self, perk: &PerkDef) {
+        self.log.push(Command::TakePerk(Box::new(perk.clone())));
+        self.perk_points = self.perk_points.saturating_sub(1);
+        perk.apply(&mut self.entities[self.player_id]);
+    }
+
+    /// The nearest living hostile in view, for actions that need a target.
+    pub fn nearest_visible_hostile(&self) -> Option<usize> {
+        let pos = self.player().pos;
+        self.visible_hostiles().into_iter().min_by(|&a, &b| {
+            let (a, b) = (self.entities[a].pos.distance(pos), self.entities[b].pos.distance(pos));
+            a.total_cmp(&b)
+        })
+    }
+
+    /// The flight of something thrown from the player towards `target`: the tiles
+    /// it crosses and the creature it hits, if any. It stops at the first creature
+    /// or wall in the way.
+    pub fn throw_path(&self, target: Vector) -> (Vec<Vector>, Option<usize>) {
+        let mut path = Vec::new();
+        for pos in fov::line(self.player().pos, target).into_iter().skip(1) {
+            if !self.is_walkable(pos) {
+                break;
+            }
+            path.push(pos);
+            if let Some(hit) = self.creature_at(pos) {
+                return (path, Some(hit));
+            }
+        }
+        (path, None)
+    }
+
+    /// Hit `target` from a distance. Darts do one damage whoever throws them.
+    pub fn ranged_attack(&mut self, target: usize) {
+        self.log.push(Command::RangedAttack(target));
+        self.hurt(target, 1);
+        self.update_fov();
+    }
+
+    /// Use a wizard mode cheat, marking the run as one that doesn't count.
+    pub fn cheat(&mut self, cheat: Cheat) {
+        self.wizard = true;
+        match &cheat {
+            Cheat::Teleport(pos) => {
+                self.entities[self.player_id].pos = *pos;
+                self.events.push(Event::Moved { entity: self.player_id });
+                self.check_theft();
+            }
+            Cheat::RevealMap => self.explored.iter_mut().for_each(|explored| *explored = true),
+            Cheat::ToggleInvincible => self.invincible = !self.invincible,
+            Cheat::Wish(item) => self.entities.push(Entity {
+                pos: self.player().pos,
+                ..(**item).clone()
+            }),
+        }
+        self.log.push(Command::Cheat(cheat));
+        self.update_fov();
+    }
+
+    /// Turns left before `spell` can be cast again; 0 if it's ready.
+    pub fn cooldown(&self, spell: &str) -> u32 {
+        self.cooldowns.get(spell).copied().unwrap_or(0)
+    }
+
+    /// Cast `spell` from the player towards `target`, spending its mana, hurting
+    /// every monster in its area and putting its status on the ones that survive.
+    pub fn cast(&mut self, spell: &SpellDef, target: Vector) {
+        self.log.push(Command::Cast(Box::new(spell.clone()), target));
+        let player = &mut self.entities[self.player_id];
+        player.mana = (player.mana - spell.mana).max(0);
+        if spell.cooldown > 0 {
+            self.cooldowns.insert(spell.name.clone(), spell.cooldown);
+        }
+        let spellpower = self.player().talents.contains(&Talent::Spellpower) && spell.damage > 0;
+        let mut base_damage = if spellpower { spell.damage + 1 } else { spell.damage };
+        if spell.damage > 0 {
+            base_damage = (base_damage + self.player().attributes.spell_damage()).max(1);
+        }
+        let flight = spells::flight(self, spell, target);
+        if spell.targeting == Targeting::Blink {
+            if let Some(&pos) = flight.last() {
+                self.teleport(self.player_id, pos);
+            }
+        }
+        let area = spells::area(self, spell, &flight);
+        for &pos in &area {
+            let id = match self.creature_at(pos) {
+                Some(id) if id != self.player_id => id,
+                _ => continue,
+            };
+            let resisted = self.entities[id].resists.contains(&spell.damage_type);
+            let damage = if resisted { base_damage / 2 } else { base_damage };
+            debug!(target: "combat", id = id, spell = spell.name.as_str(), resisted = resisted; "spell hit");
+            if damage > 0 {
+                self.hurt(id, damage);
+            }
+            if let Some(effect) = spell.status.filter(|_| self.entities[id].is_alive()) {
+                self.entities[id].afflict(effect);
+            }
+        }
+        if spell.damage_type == DamageType::Fire {
+            for &pos in &area {
+                self.kindle(self.player_id, pos);
+            }
+        }
+        // Explosions knock at the walls, pillars and furniture they reach as well
+        if let Some((center, radius)) = spells::blast(self, spell, &flight).filter(|_| base_damage > 0) {
+            for index in (0..self.destructibles.len()).rev() {
+                if self.destructibles[index].pos.distance(center) <= radius as f32 + 0.5 {
+                    self.batter(self.player_id, index, base_damage);
+                }
+            }
+        }
+        self.update_fov();
+    }
+
+    /// Run time on until the player can act again, with each monster acting as
+    /// often as its speed lets it and the player getting hungrier and healing as
+    /// the ticks go by, then let the player's statuses take their toll and refresh
+    /// what they can see.
+    pub fn monster_turns(&mut self) {
+        self.log.push(Command::MonsterTurns);
+        self.turns += 1;
+        for turns in self.cooldowns.values_mut() {
+            *turns -= 1;
+        }
+        self.cooldowns.retain(|_, &mut turns| turns > 0);
+        self.update_fov();
+        // The player has just spent their energy. Time runs on until they have enough
+        // again, with each monster acting whenever it has enough of its own
+        let player = &mut self.entities[self.player_id];
+        player.energy = player.energy.saturating_sub(ACTION_COST);
+        while self.player().energy < ACTION_COST && !self.player_is_dead() {
+            for id in 0..self.entities.len() {
+                if !self.entities[id].is_alive() {
+                    continue;
+                }
+                let entity = &mut self.entities[id];
+                entity.energy += entity.energy_per_tick();
+                let ready = entity.energy >= ACTION_COST;
+                if id != self.player_id && ready && !self.player_is_dead() {
+                    self.entities[id].energy -= ACTION_COST;
+                    self.monster_turn(id);
+                }
+            }
+            self.tick_player();
+        }
+        if !self.player_is_dead() {
+            self.suffer_statuses(self.player_id);
+        }
+        if !self.player_is_dead() {
+            self.wade();
+        }
+        self.run_timers();
+        self.run_schedule();
+        self.burn_torch();
+        self.update_fov();
+    }
+
+    /// Let a tick go by for the player. Each turn of the world's time they get
+    /// hungrier, and every so many turns they get back some mana and some hp, as
+    /// often as hunger and constitution allow. Being quicker doesn't heal them any
+    /// faster, since it goes by the world's time rather than their actions.
+    fn tick_player(&mut self) {
+        self.ticks += 1;
+        if !self.ticks.is_multiple_of(TICKS_PER_TURN) || self.player_is_dead() {
+            return;
+        }
+        let turn = self.ticks / TICKS_PER_TURN;
+        self.digest();
+        let heal = self.heal_turns().is_some_and(|turns| turn.is_multiple_of(turns));
+        let player = &mut self.entities[self.player_id];
+        if turn.is_multiple_of(MANA_REGEN_TURNS) && player.mana < player.max_mana {
+            player.mana += 1;
+        }
+        if heal && player.hp < player.max_hp {
+            player.hp += 1;
+        }
+    }
+
+    /// How many turns it takes the player to heal a hp on their own, fewer the
+    /// more constitution and more the hungrier they are, or `None` while they're
+    /// starving.
+    pub fn heal_turns(&self) -> Option<usize> {
+        Hunger::of(self.satiety).heal_turns(self.player().attributes.heal_turns())
+    }
+
+    /// Use up a turn's worth of what the player has eaten, warning them when they
+    /// get hungry and when they start to starve.
+    fn digest(&mut self) {
+        if self.satiety == 0 {
+            return;
+        }
+        self.satiety -= 1;
+        let entity = self.player_id;
+        match self.satiety {
+            0 => self.events.push(Event::Starving { entity }),
+            hunger::HUNGRY_TURNS => self.events.push(Event::Hungry { entity }),
+            _ => {}
+        }
+    }
+
+    /// Set `scheduled` to come about on the level.
+    pub fn schedule(&mut self, scheduled: Scheduled) {
+        self.log.push(Command::Schedule(scheduled.clone()));
+        debug!(target: "generate", happening = scheduled.happening.as_str(), turns = scheduled.turns; "scheduled");
+        self.schedule.push(scheduled);
+    }
+
+    /// Count the schedule down a turn, putting whatever's come round in `due` and
+    /// setting anything that comes again going from the start.
+    fn run_schedule(&mut self) {
+        self.due.clear();
+        for scheduled in &mut self.schedule {
+            scheduled.turns = scheduled.turns.saturating_sub(1);
+            if scheduled.turns == 0 {
+                self.due.push(scheduled.happening.clone());
+                scheduled.turns = scheduled.every.unwrap_or(0);
+            }
+        }
+        self.schedule.retain(|scheduled| scheduled.turns > 0);
+    }
+
+    /// Burn a turn's worth of the player's torch, warning them when it's low and
+    /// when it goes out.
+    fn burn_torch(&mut self) {
+        if self.torch == 0 {
+            return;
+        }
+        self.torch -= 1;
+        let entity = self.player_id;
+        match self.torch {
+            0 => self.events.push(Event::TorchOut { entity }),
+            light::TORCH_LOW_TURNS => self.events.push(Event::TorchLow { entity }),
+            _ => {}
+        }
+    }
+
+    /// Put `effect` on `id` for `turns` turns, after which it wears off.
+    pub fn start_timer(&mut self, id: usize, effect: Timed, turns: u32) {
+        debug!(target: "timers", id = id, effect:? = effect, turns = turns; "started");
+        let entity = &mut self.entities[id];
+        match effect {
+            Timed::Attack(amount) => entity.attack += amount,
+            Timed::Defense(amount) => entity.defense += amount,
+            // These only matter while the timer runs
+            Timed::Light(_) | Timed::Summoned => {}
+        }
+        self.timers.push(Timer { entity: id, effect, turns });
+    }
+
+    /// Bring `creature` onto the level for `turns` turns, returning its id.
+    pub fn summon(&mut self, creature: Entity, turns: u32) -> usize {
+        self.entities.push(creature);
+        let id = self.entities.len() - 1;
+        self.start_timer(id, Timed::Summoned, turns);
+        id
+    }
+
+    /// Count every timer down a turn, undoing whatever runs out.
+    fn run_timers(&mut self) {
+        for timer in &mut self.timers {
+            timer.turns = timer.turns.saturating_sub(1);
+        }
+        let (expired, running): (Vec<Timer>, Vec<Timer>) =
+            std::mem::take(&mut self.timers).into_iter().partition(|timer| timer.turns == 0);
+        self.timers = running;
+        for Timer { entity: id, effect, .. } in expired {
+            debug!(target: "timers", id = id, effect:? = effect; "wore off");
+            let entity = &mut self.entities[id];
+            match effect {
+                Timed::Attack(amount) => entity.attack -= amount,
+                Timed::Defense(amount) => entity.defense -= amount,
+                Timed::Light(_) => {}
+                // One that was killed already has nothing left to vanish
+                Timed::Summoned if !entity.is_alive() => continue,
+                Timed::Summoned => {
+                    entity.hp = 0;
+                    entity.pos = OFF_MAP;
+                }
+            }
+            self.events.push(Event::WoreOff { entity: id, effect });
+        }
+    }
+
+    /// Let one monster act unless it's frozen, heal it if it regenerates, then let
+    /// its statuses take their toll and wear off.
+    fn monster_turn(&mut self, id: usize) {
+        let frozen = self.entities[id].has_status(Status::Frozen);
+        if frozen {
+            debug!(target: "ai", id = id; "frozen, so it loses its turn");
+        }
+        let attacked = !frozen && self.monster_act(id);
+        let monster = &mut self.entities[id];
+        if !attacked && monster.abilities.contains(&Ability::Regenerate) && monster.hp < monster.max_hp {
+            monster.hp += 1;
+            debug!(target: "ai", id = id, hp = monster.hp; "regenerates");
+        }
+        self.suffer_statuses(id);
+    }
+
+    /// Take a hp off `id` for each of burning and poison, then count its statuses
+    /// down and drop any that have worn off.
+    fn suffer_statuses(&mut self, id: usize) {
+        for status in [Status::Burning, Status::Poisoned] {
+            if self.entities[id].has_status(status) && self.entities[id].is_alive() {
+                self.hurt(id, 1);
+            }
+        }
+        let statuses = &mut self.entities[id].statuses;
+        for effect in statuses.iter_mut() {
+            effect.turns = effect.turns.saturating_sub(1);
+        }
+        statuses.retain(|effect| effect.turns > 0);
+    }
+
+    /// Whatever the player ends their turn in takes its toll: lava burns them, and
+    /// in deep water they swim, with what they carry slipping away now and then and
+    /// drowning once they're out of breath.
+    fn wade(&mut self) {
+        let id = self.player_id;
+        let glyph = self.tile_at(self.player().pos).map(|tile| tile.glyph);
+        if glyph == Some(LAVA) {
+            let resisted = self.player().resists.contains(&DamageType::Fire);
+            debug!(target: "combat", id = id, resisted = resisted; "burned by lava");
+            self.events.push(Event::Burned { entity: id });
+            self.hurt(id, if resisted { LAVA_DAMAGE / 2 } else { LAVA_DAMAGE });
+        }
+        if glyph != Some(DEEP_WATER) {
+            self.swum = 0;
+            return;
+        }
+        self.swum += 1;
+        let carried = self.player().inventory.len() as u32;
+        if carried > 0 && self.rng.range(0, 100) < SINK_CHANCE {
+            let slot = self.rng.range(0, carried) as usize;
+            let item = self.entities[id].inventory.remove(slot);
+            debug!(target: "items", item = item.as_str(); "sank");
+            self.events.push(Event::Sank { entity: id, item });
+        }
+        if self.swum > BREATH_TURNS {
+            debug!(target: "combat", id = id, turns = self.swum; "drowning");
+            self.events.push(Event::Drowning { entity: id });
+            self.hurt(id, DROWN_DAMAGE);
+        }
+    }
+
+    /// The way monster `id` would go to reach the player, around other creatures
+    /// and into a portal where that's quicker, if it can see the player and get
+    /// there.
+    pub fn monster_path(&self, id: usize) -> Option<Vec<Vector>> {
+        let pos = self.entities[id].pos;
+        let target = self.player().pos;
+        if !self.is_visible(pos) {
+            return None;
+        }
+        let way = |from: Vector, to: Vector| {
+            path::find_path(from, to, |step| {
+                let free = step == target || self.creature_at(step).is_none();
+                let cost = if step == target { Some(1) } else { self.step_cost(step) };
+                cost.filter(|_| free)
+            })
+        };
+        let direct = way(pos, target);
+        // A portal is worth stepping into when it comes out nearer the player
+        let shortcut = self
+            .portals
+            .iter()
+            .filter(|portal| portal.pos != pos && self.portal_at(portal.pos).is_some())
+            .filter(|portal| self.portal_at(portal.exit).is_some())
+            .filter_map(|portal| Some((way(pos, portal.pos)?, way(portal.exit, target)?)))
+            .map(|(there, onward)| (there.len() + onward.len(), there))
+            .min_by_key(|(steps, _)| *steps);
+        match (direct, shortcut) {
+            (Some(direct), Some((steps, there))) if steps < direct.len() => Some(there),
+            (None, Some((_, there))) => Some(there),
+            (direct, _) => direct,
+        }
+    }
+
+    /// The deepest the run has been.
+    pub fn deepest(&self) -> u32 {
+        let levels = self.log.iter().filter_map(|command| match command {
+            Command::ChangeLevel(level) => Some(level.depth),
+            _ => None,
+        });
+        levels.fold(self.depth, u32::max)
+    }
+
+    /// How many turns have gone by in the run.
+    pub fn turn(&self) -> usize {
+        self.turns
+    }
+
+    /// The time in the dungeon, as the day of the run counting from 1, the hour
+    /// and the minute.
+    pub fn clock(&self) -> (usize, usize, usize) {
+        let minutes = START_HOUR * 60 + self.turns * TURN_SECONDS / 60;
+        (minutes / (24 * 60) + 1, minutes / 60 % 24, minutes % 60)
+    }
+
+    /// Monsters that can see the player attack once adjacent, and chasers close in
+    /// until then. Returns whether the monster attacked.
+    fn monster_act(&mut self, id: usize) -> bool {
+        let target = self.player().pos;
+        let ai = self.entities[id].ai;
+        if ai.is_peaceful() {
+            trace!(target: "ai", id = id, ai:? = ai; "leaves the player be");
+            return false;
+        }
+        match self.monster_path(id).and_then(|path| path.first().copied()) {
+            Some(step) if step == target => {
+                debug!(target: "ai", id = id, ai:? = ai; "attacks the player");
+                self.attack(id, self.player_id);
+                return true;
+            }
+            Some(step) if ai == Ai::Chase => {
+                debug!(target: "ai", id = id, ai:? = ai, x = step.x, y = step.y; "closes in");
+                let delta = step - self.entities[id].pos;
+                self.entities[id].pos = step;
+                self.events.push(Event::Moved { entity: id });
+                self.step_onto(id);
+                self.slide(id, delta);
+            }
+            Some(_) => debug!(target: "ai", id = id, ai:? = ai; "holds its ground"),
+            None => trace!(target: "ai", id = id, ai:? = ai; "can't see or reach the player"),
+        }
+        false
+    }
+}
+
+pub fn generate_map(size:Vector, rng: &mut Rng, glyphs: &Glyphs) -> Vec<Tile> {
+    let width = size.x as usize;
+    let height = size.y as usize;
+    let pool_x = rng.range(2, size.x as u32 - 7) as usize;
+    let pool_y = rng.range(2, size.y as u32 - 4) as usize;
+    debug!(target: "generate", width = width, height = height, pool_x = pool_x, pool_y = pool_y; "map");
+    let mut map = Vec::with_capacity(width * height);
+    for x in 0..width {
+        for y in 0..height {
+            let mut tile = Tile {
+                pos: Vector::new(x as f32, y as f32),
+                glyph: '.',
+                color: glyphs.color("floor"),
+                animation: None,
+                light_radius: 0,
+            };
+
+            if x == 0 || x == width - 1 || y == 0 || y == height - 1 {
+                tile.glyph = '#';
+                tile.color = glyphs.color("wall");
+                if y == 0 && x % 6 == 3 {
+                    tile.color = glyphs.color("torch");
+                    tile.animation = Some(Animation::flicker('#', tile.color));
+                    tile.light_radius = 5;
+                }
+            } else if x == width - 3 && y == height - 3 {
+                tile.glyph = '>';
+                tile.color = glyphs.color("stairs");
+            } else if (pool_x..pool_x + 4).contains(&x) && (pool_y..pool_y + 2).contains(&y) {
+                tile.glyph = '~';
+                tile.color = glyphs.color("water");
+                tile.animation = Some(Animation::ripple('~', tile.color));
+            };
+            map.push(tile);
+        }
+    }
+    map
+}
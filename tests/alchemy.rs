@@ -0,0 +1,84 @@
+use rogue_like::{
+    alchemy::{self, Mixture},
+    data, fixture,
+    items::{self, ItemEffect},
+    replay::Command,
+    spells::{Status, StatusEffect},
+};
+
+#[test]
+fn mixing_makes_the_potion_with_both_essences() {
+    let content = data::Content::load(None, false);
+    let item = |name| items::find(&content.items, name).unwrap();
+    let mixture = alchemy::mix(item("herb"), item("potion of mana"), &content.items);
+    assert_eq!(mixture, Mixture::Potion("potion of vigor".to_string()));
+    let mixture = alchemy::mix(item("herb"), item("herb"), &content.items);
+    assert_eq!(mixture, Mixture::Potion("potion of healing".to_string()));
+}
+
+#[test]
+fn mixing_what_makes_no_potion_goes_wrong() {
+    let content = data::Content::load(None, false);
+    let item = |name| items::find(&content.items, name).unwrap();
+    let mixture = alchemy::mix(item("potion of fire"), item("potion of frost"), &content.items);
+    let statuses = vec![
+        StatusEffect {
+            status: Status::Burning,
+            turns: 3,
+        },
+        StatusEffect {
+            status: Status::Frozen,
+            turns: 2,
+        },
+    ];
+    assert_eq!(mixture, Mixture::Mishap { damage: 2, statuses });
+}
+
+#[test]
+fn using_a_potion_identifies_it() {
+    let content = data::Content::load(None, false);
+    let mut world = fixture::world("#@#");
+    world.entities[world.player_id].hp = 1;
+    fixture::carry(&mut world, &["ration", "potion of healing"]);
+    let unknown = alchemy::name(&world, &content.items, &content.strings, "potion of healing");
+    assert_ne!(unknown, "potion of healing");
+
+    fixture::play(&mut world, &[Command::UseItem(1, vec![ItemEffect::Heal(10)])]);
+    assert_eq!(world.player().hp, fixture::PLAYER_HP);
+    assert_eq!(world.player().inventory, ["ration"]);
+    let known = alchemy::name(&world, &content.items, &content.strings, "potion of healing");
+    assert_eq!(known, "potion of healing");
+}
+
+#[test]
+fn mixing_uses_up_both_and_mishaps_hurt() {
+    let mut world = fixture::world("#@#");
+    fixture::carry(&mut world, &["herb", "ration", "potion of mana"]);
+    let vigor = Mixture::Potion("potion of vigor".to_string());
+    fixture::play(&mut world, &[Command::Mix(2, 0, vigor)]);
+    assert_eq!(world.player().inventory, ["ration", "potion of vigor"]);
+
+    let burning = StatusEffect {
+        status: Status::Burning,
+        turns: 3,
+    };
+    let mishap = Mixture::Mishap {
+        damage: 2,
+        statuses: vec![burning],
+    };
+    fixture::play(&mut world, &[Command::Mix(0, 1, mishap)]);
+    assert!(world.player().inventory.is_empty());
+    assert_eq!(world.player().hp, fixture::PLAYER_HP - 2);
+    assert_eq!(world.player().statuses, [burning]);
+}
+
+#[test]
+fn each_potion_looks_different_within_a_run() {
+    let content = data::Content::load(None, false);
+    let potions = content.items.iter().filter(|item| item.potion);
+    let mut looks: Vec<&str> = potions.map(|item| alchemy::appearance(7, &item.name, &content.items)).collect();
+    let count = looks.len();
+    looks.sort();
+    looks.dedup();
+    assert_eq!(looks.len(), count);
+}
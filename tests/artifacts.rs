@@ -0,0 +1,90 @@
+use quicksilver::{geom::Vector, graphics::Color};
+use rogue_like::{
+    data,
+    dungeon::{self, Arrival, Section},
+    fixture,
+    replay::Command,
+    rng::Rng,
+    spells::{self, DamageType, SpellDef, Targeting},
+    world::Entity,
+};
+use std::collections::BTreeSet;
+
+fn blink() -> SpellDef {
+    SpellDef {
+        name: "blink".to_string(),
+        glyph: '@',
+        color: Color::WHITE,
+        targeting: Targeting::Blink,
+        range: 5,
+        damage: 0,
+        damage_type: DamageType::Physical,
+        status: None,
+        cooldown: 0,
+        mana: 0,
+    }
+}
+
+#[test]
+fn carrying_an_artifact_grants_its_power_for_free() {
+    let content = data::Content::load(None, false);
+    let mut player = Entity::new(Vector::new(0, 0), '@', Color::WHITE, 5);
+    assert!(spells::castable(&content.spells, &content.items, &player).is_empty());
+    player.inventory.push("stormcaller".to_string());
+    let castable = spells::castable(&content.spells, &content.items, &player);
+    assert_eq!(castable.len(), 1);
+    assert_eq!(castable[0].name, "lightning");
+    assert_eq!(castable[0].mana, 0);
+}
+
+#[test]
+fn blinking_stops_short_of_walls_and_creatures() {
+    let mut world = fixture::world("#@...G.#");
+    fixture::play(&mut world, &[Command::Cast(Box::new(blink()), Vector::new(6, 0))]);
+    assert_eq!(world.player().pos, Vector::new(4, 0));
+    assert_eq!(world.entities[1].hp, fixture::MONSTER_HP);
+
+    let mut world = fixture::world("#@.#");
+    fixture::play(&mut world, &[Command::Cast(Box::new(blink()), Vector::new(3, 0))]);
+    assert_eq!(world.player().pos, Vector::new(2, 0));
+}
+
+#[test]
+fn nothing_blinks_where_teleporting_is_held_back() {
+    let mut world = fixture::world("#@...#");
+    world.no_teleport = true;
+    fixture::play(&mut world, &[Command::Cast(Box::new(blink()), Vector::new(4, 0))]);
+    assert_eq!(world.player().pos, Vector::new(1, 0));
+}
+
+#[test]
+fn artifacts_turn_up_at_most_once_a_run() {
+    let content = data::Content::load(None, false);
+    let artifacts: Vec<&str> =
+        content.items.iter().filter(|item| item.is_artifact()).map(|item| item.name.as_str()).collect();
+    let found: BTreeSet<String> = artifacts.iter().map(|name| name.to_string()).collect();
+    for seed in 0..20 {
+        let player = Entity::new(Vector::new(0, 0), '@', Color::WHITE, 5);
+        let level = dungeon::generate(2, Section::Main, player, Rng::new(seed), &found, Arrival::Descending, &content);
+        let items = level.entities.iter().chain(level.entities.iter().filter_map(|entity| entity.loot.as_deref()));
+        assert!(items.filter_map(|entity| entity.item.as_deref()).all(|item| !artifacts.contains(&item)));
+    }
+}
+
+#[test]
+fn placing_an_artifact_notes_it_down_for_the_run() {
+    let content = data::Content::load(None, false);
+    let mut placed = 0;
+    for seed in 0..100 {
+        let player = Entity::new(Vector::new(0, 0), '@', Color::WHITE, 5);
+        let (rng, found) = (Rng::new(seed), BTreeSet::new());
+        let level = dungeon::generate(2, Section::Main, player, rng, &found, Arrival::Descending, &content);
+        for item in level.entities.iter().filter_map(|entity| entity.item.as_deref()) {
+            if content.items.iter().any(|def| def.name == item && def.is_artifact()) {
+                assert!(level.artifacts.contains(item));
+                placed += 1;
+            }
+        }
+    }
+    assert!(placed > 0);
+}
@@ -0,0 +1,75 @@
+use rogue_like::{
+    attributes::{Attribute, ATTRIBUTE_LEVELS},
+    fixture::{self, EAST},
+    replay::Command,
+    world,
+};
+
+/// A corridor with the player at one end and a monster next to them.
+const SIDE_BY_SIDE: &str = "
+    #####
+    #@g.#
+    #####
+";
+
+#[test]
+fn strength_adds_to_melee_damage() {
+    let mut world = fixture::world(SIDE_BY_SIDE);
+    world.entities[world.player_id].attributes.strength = 5;
+    fixture::play(&mut world, &[Command::MovePlayer(EAST)]);
+    assert_eq!(world.entities[1].hp, fixture::MONSTER_HP - 2);
+}
+
+#[test]
+fn enough_dexterity_gets_past_any_block() {
+    let mut world = fixture::world(SIDE_BY_SIDE);
+    world.entities[1].defense = 1;
+    world.entities[1].hp = 100;
+    world.entities[world.player_id].attributes.dexterity = 7;
+    let attacks = vec![Command::MovePlayer(EAST); 20];
+    fixture::play(&mut world, &attacks);
+    assert_eq!(world.entities[1].hp, 80);
+}
+
+#[test]
+fn raising_constitution_raises_max_hp() {
+    let mut world = fixture::world("#@#");
+    world.attribute_points = 1;
+    fixture::play(&mut world, &[Command::Raise(Attribute::Constitution)]);
+    assert_eq!(world.attribute_points, 0);
+    assert_eq!(world.player().attributes.constitution, 4);
+    assert_eq!(world.player().max_hp, fixture::PLAYER_HP + 1);
+    assert_eq!(world.player().hp, fixture::PLAYER_HP + 1);
+}
+
+#[test]
+fn levelling_up_earns_attribute_points() {
+    let mut world = fixture::world(SIDE_BY_SIDE);
+    let xp: u32 = (1..ATTRIBUTE_LEVELS).map(world::xp_needed).sum();
+    world.entities[1].max_hp = xp as i32;
+    world.entities[1].hp = 1;
+    fixture::play(&mut world, &[Command::MovePlayer(EAST)]);
+    assert_eq!(world.player().level, ATTRIBUTE_LEVELS);
+    assert_eq!(world.attribute_points, 1);
+}
+
+#[test]
+fn the_player_heals_slowly_on_their_own() {
+    let mut world = fixture::world("#@#");
+    world.entities[world.player_id].hp = 1;
+    let turns = world.player().attributes.heal_turns();
+    fixture::play(&mut world, &vec![Command::MonsterTurns; turns - 1]);
+    assert_eq!(world.player().hp, 1);
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert_eq!(world.player().hp, 2);
+    fixture::play(&mut world, &vec![Command::MonsterTurns; turns * 10]);
+    assert_eq!(world.player().hp, fixture::PLAYER_HP);
+}
+
+#[test]
+fn constitution_speeds_up_healing() {
+    let mut world = fixture::world("#@#");
+    let slow = world.player().attributes.heal_turns();
+    world.entities[world.player_id].attributes.constitution = 6;
+    assert!(world.player().attributes.heal_turns() < slow);
+}
@@ -0,0 +1,89 @@
+use quicksilver::geom::Vector;
+use rogue_like::{
+    boulders::CRUSH_DAMAGE,
+    fixture,
+    mechanisms,
+    monsters::Ai,
+    replay::Command,
+    world::{Event, World},
+};
+
+const MAP: &str = "
+    #######
+    #@0.^.#
+    #.0...#
+    #.#...#
+    #######
+";
+
+fn push(world: &mut World, delta: Vector) {
+    fixture::play(world, &[Command::MovePlayer(delta)]);
+}
+
+#[test]
+fn a_pushed_boulder_rolls_on_and_the_player_follows() {
+    let mut world = fixture::world(MAP);
+    assert!(!world.is_walkable(Vector::new(2, 1)));
+    push(&mut world, Vector::new(1, 0));
+    assert_eq!(world.player().pos, Vector::new(2, 1));
+    assert_eq!(world.boulder_at(Vector::new(3, 1)), Some(1));
+    assert!(world.is_walkable(Vector::new(2, 1)));
+}
+
+#[test]
+fn a_boulder_wont_budge_into_a_wall_another_boulder_or_diagonally() {
+    let mut world = fixture::world(MAP);
+    world.entities[1].pos = Vector::new(5, 3);
+    world.entities[world.player_id].pos = Vector::new(2, 1);
+    assert!(!world.move_player(Vector::new(0, 1)));
+    assert!(world.events.contains(&Event::Wedged { entity: 2 }));
+
+    let mut world = fixture::world(MAP);
+    world.entities[world.player_id].pos = Vector::new(1, 2);
+    assert!(!world.move_player(Vector::new(1, -1)));
+    world.entities[1].pos = Vector::new(3, 2);
+    assert!(!world.move_player(Vector::new(1, 0)));
+    assert_eq!(world.player().pos, Vector::new(1, 2));
+    assert_eq!(world.boulder_at(Vector::new(2, 2)), Some(2));
+}
+
+#[test]
+fn a_boulder_rolled_into_a_trap_fills_it() {
+    let mut world = fixture::world(MAP);
+    push(&mut world, Vector::new(1, 0));
+    push(&mut world, Vector::new(1, 0));
+    assert!(world.events.contains(&Event::Plugged { entity: 1 }));
+    let trap = Vector::new(4, 1);
+    assert_ne!(world.tile_at(trap).map(|tile| tile.glyph), Some(mechanisms::TRAP));
+    assert_eq!(world.boulder_at(trap), None);
+    push(&mut world, Vector::new(1, 0));
+    assert_eq!(world.player().pos, trap);
+    assert_eq!(world.player().hp, fixture::PLAYER_HP);
+}
+
+#[test]
+fn a_boulder_crushes_a_monster_in_its_way() {
+    let mut world = fixture::world(
+        "
+        ######
+        #@0g.#
+        ######
+        ",
+    );
+    world.entities[2].hp = CRUSH_DAMAGE;
+    push(&mut world, Vector::new(1, 0));
+    assert!(!world.entities[2].is_alive());
+    assert_eq!(world.boulder_at(Vector::new(3, 1)), Some(1));
+    assert_eq!(world.player().pos, Vector::new(2, 1));
+
+    let mut world = fixture::world(
+        "
+        ######
+        #@0G.#
+        ######
+        ",
+    );
+    world.entities[2].ai = Ai::Shopkeeper;
+    assert!(!world.move_player(Vector::new(1, 0)));
+    assert!(world.entities[2].is_alive());
+}
@@ -0,0 +1,84 @@
+use quicksilver::{geom::Vector, graphics::Color};
+use rogue_like::{
+    branches::{self, Entrance},
+    data,
+    dungeon::{self, Arrival, Section},
+    fixture,
+    rng::Rng,
+    weather::Weather,
+    world::{Biome, Entity, World},
+};
+use std::collections::BTreeSet;
+
+fn generate(depth: u32, branch: Option<&str>, arrival: Arrival, artifacts: &BTreeSet<String>) -> World {
+    let content = data::Content::load(None, false);
+    let branch = branch.and_then(|name| branches::find(&content.branches, name));
+    let section = branch.map_or(Section::Main, Section::Branch);
+    let player = Entity::new(Vector::new(0, 0), '@', Color::WHITE, 5);
+    dungeon::generate(depth, section, player, Rng::new(7), artifacts, arrival, &content)
+}
+
+#[test]
+fn branches_start_from_their_own_stairs_down() {
+    let level = generate(3, None, Arrival::Descending, &BTreeSet::new());
+    let entrance = level.entrances.iter().find(|entrance| entrance.branch == "swamp");
+    let entrance = entrance.expect("the swamp starts at depth 3");
+    assert_eq!(level.tile_at(entrance.pos).map(|tile| tile.glyph), Some('>'));
+    assert!(level.map.iter().any(|tile| tile.glyph == '>' && tile.pos != entrance.pos));
+
+    let level = generate(4, None, Arrival::Descending, &BTreeSet::new());
+    assert!(level.entrances.is_empty());
+}
+
+#[test]
+fn stairs_lead_into_and_back_out_of_a_branch() {
+    let content = data::Content::load(None, false);
+    let mut world = fixture::world("#@>#");
+    world.depth = 3;
+    world.entrances.push(Entrance {
+        pos: Vector::new(1, 0),
+        branch: "swamp".to_string(),
+    });
+    let (depth, branch, arrival) = branches::destination(&world, &content.branches, false);
+    assert_eq!((depth, branch.map(|def| def.name.as_str()), arrival), (4, Some("swamp"), Arrival::Descending));
+
+    world.entities[world.player_id].pos = Vector::new(2, 0);
+    let (depth, branch, _) = branches::destination(&world, &content.branches, false);
+    assert_eq!((depth, branch), (4, None));
+
+    world.branch = Some("swamp".to_string());
+    world.entrances.clear();
+    world.depth = 4;
+    let (depth, branch, arrival) = branches::destination(&world, &content.branches, true);
+    assert_eq!((depth, branch, arrival), (3, None, Arrival::LeavingBranch("swamp".to_string())));
+    world.depth = 5;
+    let (depth, branch, arrival) = branches::destination(&world, &content.branches, true);
+    assert_eq!((depth, branch.map(|def| def.name.as_str()), arrival), (4, Some("swamp"), Arrival::Climbing));
+}
+
+#[test]
+fn leaving_a_branch_arrives_on_its_entrance() {
+    let level = generate(3, None, Arrival::LeavingBranch("swamp".to_string()), &BTreeSet::new());
+    let entrance = level.entrances.iter().find(|entrance| entrance.branch == "swamp");
+    assert_eq!(entrance.map(|entrance| entrance.pos), Some(level.player().pos));
+}
+
+#[test]
+fn branch_levels_have_their_own_look() {
+    let level = generate(4, Some("swamp"), Arrival::Descending, &BTreeSet::new());
+    assert_eq!(level.branch.as_deref(), Some("swamp"));
+    assert_eq!(level.biome, Biome::Swamp);
+    assert_eq!(level.weather, Weather::Fog);
+}
+
+#[test]
+fn the_bottom_of_a_branch_holds_its_reward_once_a_run() {
+    let level = generate(6, Some("swamp"), Arrival::Descending, &BTreeSet::new());
+    assert!(level.map.iter().all(|tile| tile.glyph != '>'));
+    let rewards = level.entities.iter().filter(|entity| entity.item.as_deref() == Some("bog mail"));
+    assert_eq!(rewards.count(), 1);
+    assert!(level.artifacts.contains("bog mail"));
+
+    let level = generate(6, Some("swamp"), Arrival::Descending, &level.artifacts);
+    assert!(level.entities.iter().all(|entity| entity.item.as_deref() != Some("bog mail")));
+}
@@ -0,0 +1,80 @@
+use quicksilver::{geom::Vector, graphics::Color};
+use rogue_like::{
+    classes::Talent,
+    fixture::{self, EAST},
+    replay::Command,
+    spells::{DamageType, SpellDef, Targeting},
+};
+
+#[test]
+fn ambush_doubles_damage_on_unhurt_monsters() {
+    let mut world = fixture::world(
+        "
+        #####
+        #@G.#
+        #####
+        ",
+    );
+    world.entities[world.player_id].talents.push(Talent::Ambush);
+    fixture::play(&mut world, &[Command::MovePlayer(EAST)]);
+    assert_eq!(world.entities[1].hp, fixture::MONSTER_HP - 2);
+    fixture::play(&mut world, &[Command::MovePlayer(EAST)]);
+    assert!(!world.entities[1].is_alive());
+}
+
+#[test]
+fn cleave_also_hits_a_monster_alongside() {
+    let mut world = fixture::world(
+        "
+        #####
+        #@G.#
+        #.H.#
+        #...#
+        #####
+        ",
+    );
+    world.entities[world.player_id].talents.push(Talent::Cleave);
+    fixture::play(&mut world, &[Command::MovePlayer(EAST)]);
+    assert_eq!(world.entities[1].hp, fixture::MONSTER_HP - 1);
+    assert_eq!(world.entities[2].hp, fixture::MONSTER_HP - 1);
+}
+
+#[test]
+fn enough_defense_blocks_every_attack() {
+    let mut world = fixture::world(
+        "
+        #####
+        #@g.#
+        #####
+        ",
+    );
+    world.entities[world.player_id].defense = 5;
+    fixture::play(&mut world, &[Command::MonsterTurns, Command::MonsterTurns]);
+    assert_eq!(world.player().hp, fixture::PLAYER_HP);
+}
+
+#[test]
+fn spellpower_adds_to_spell_damage() {
+    let mut world = fixture::world(
+        "
+        ######
+        #@.G.#
+        ######
+        ",
+    );
+    world.entities[world.player_id].talents.push(Talent::Spellpower);
+    let bolt = SpellDef {
+        name: "test bolt".to_string(),
+        glyph: '*',
+        color: Color::RED,
+        targeting: Targeting::Bolt,
+        range: 6,
+        damage: 1,
+        damage_type: DamageType::Physical,
+        status: None,
+        cooldown: 0,
+        mana: 0,
+    };
+    fixture::play(&mut world, &[Command::Cast(Box::new(bolt), Vector::new(3, 1))]);
+    assert_eq!(world.entities[1].hp, fixture::MONSTER_HP - 2);
+}
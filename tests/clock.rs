@@ -0,0 +1,29 @@
+use rogue_like::{
+    fixture,
+    replay::Command,
+    world::{World, TURN_SECONDS},
+};
+
+#[test]
+fn turns_are_counted_across_levels_and_saves() {
+    let mut world = fixture::world("#@.#");
+    fixture::play(&mut world, &[Command::MonsterTurns, Command::MonsterTurns]);
+    let level = fixture::world("#.@#");
+    world.change_level(&level);
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert_eq!(world.turn(), 3);
+
+    let saved = serde_json::to_string(&world).unwrap();
+    let loaded: World = serde_json::from_str(&saved).unwrap();
+    assert_eq!(loaded.turn(), 3);
+}
+
+#[test]
+fn the_clock_runs_from_morning_into_the_next_day() {
+    let mut world = fixture::world("#@.#");
+    assert_eq!(world.clock(), (1, 8, 0));
+    world.turns = 10 * 60 / TURN_SECONDS;
+    assert_eq!(world.clock(), (1, 8, 10));
+    world.turns = 16 * 60 * 60 / TURN_SECONDS;
+    assert_eq!(world.clock(), (2, 0, 0));
+}
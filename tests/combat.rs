@@ -0,0 +1,119 @@
+use quicksilver::{geom::Vector, graphics::Color};
+use rogue_like::{
+    fixture::{self, EAST},
+    replay::Command,
+    spells::{DamageType, SpellDef, Status, StatusEffect, Targeting},
+    wizard::Cheat,
+    world::{Entity, Event},
+};
+
+/// A straight corridor with the player at one end and a chasing monster next to them.
+const SIDE_BY_SIDE: &str = "
+    #####
+    #@g.#
+    #####
+";
+
+#[test]
+fn killing_a_monster_leaves_a_corpse() {
+    let mut world = fixture::world(SIDE_BY_SIDE);
+    for _ in 0..fixture::MONSTER_HP {
+        fixture::play(&mut world, &[Command::MovePlayer(EAST)]);
+    }
+    let monster = &world.entities[1];
+    assert!(!monster.is_alive());
+    assert_eq!(monster.glyph, '%');
+    assert!(world.events.iter().any(|event| matches!(event, Event::Died { target: 1, .. })));
+}
+
+#[test]
+fn loot_drops_where_the_monster_died() {
+    let mut world = fixture::world(SIDE_BY_SIDE);
+    world.entities[1].loot = Some(Box::new(Entity::new(Vector::ZERO, '!', Color::WHITE, 0)));
+    world.entities[1].hp = 1;
+    fixture::play(&mut world, &[Command::MovePlayer(EAST)]);
+    let loot = world.entities.iter().find(|entity| entity.glyph == '!').expect("the loot dropped");
+    assert_eq!(loot.pos, Vector::new(2, 1));
+}
+
+#[test]
+fn adjacent_monsters_attack_the_player() {
+    let mut world = fixture::world(SIDE_BY_SIDE);
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert_eq!(world.player().hp, fixture::PLAYER_HP - 1);
+    assert_eq!(world.entities[1].pos, Vector::new(2, 1));
+}
+
+#[test]
+fn the_player_can_die() {
+    let mut world = fixture::world(SIDE_BY_SIDE);
+    world.entities[world.player_id].hp = 1;
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert!(world.player_is_dead());
+}
+
+#[test]
+fn an_invincible_player_takes_no_damage() {
+    let mut world = fixture::world(SIDE_BY_SIDE);
+    fixture::play(&mut world, &[Command::Cheat(Cheat::ToggleInvincible), Command::MonsterTurns]);
+    assert_eq!(world.player().hp, fixture::PLAYER_HP);
+    assert!(world.wizard);
+}
+
+#[test]
+fn frozen_monsters_lose_their_turns_until_it_wears_off() {
+    let mut world = fixture::world(SIDE_BY_SIDE);
+    world.entities[1].statuses.push(StatusEffect {
+        status: Status::Frozen,
+        turns: 2,
+    });
+    fixture::play(&mut world, &[Command::MonsterTurns, Command::MonsterTurns]);
+    assert_eq!(world.player().hp, fixture::PLAYER_HP);
+    assert!(world.entities[1].statuses.is_empty());
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert_eq!(world.player().hp, fixture::PLAYER_HP - 1);
+}
+
+#[test]
+fn burning_monsters_take_damage_every_turn() {
+    let mut world = fixture::world(
+        "
+        #########
+        #@.....G#
+        #########
+        ",
+    );
+    world.entities[1].statuses.push(StatusEffect {
+        status: Status::Burning,
+        turns: 2,
+    });
+    fixture::play(&mut world, &[Command::MonsterTurns, Command::MonsterTurns, Command::MonsterTurns]);
+    assert_eq!(world.entities[1].hp, fixture::MONSTER_HP - 2);
+}
+
+#[test]
+fn resisted_spells_do_half_damage() {
+    let mut world = fixture::world(
+        "
+        #######
+        #@.gh.#
+        #######
+        ",
+    );
+    world.entities[2].resists.push(DamageType::Fire);
+    let beam = SpellDef {
+        name: "test beam".to_string(),
+        glyph: '*',
+        color: Color::RED,
+        targeting: Targeting::Beam,
+        range: 6,
+        damage: 2,
+        damage_type: DamageType::Fire,
+        status: None,
+        cooldown: 0,
+        mana: 0,
+    };
+    fixture::play(&mut world, &[Command::Cast(Box::new(beam), Vector::new(5, 1))]);
+    assert_eq!(world.entities[1].hp, fixture::MONSTER_HP - 2);
+    assert_eq!(world.entities[2].hp, fixture::MONSTER_HP - 1);
+}
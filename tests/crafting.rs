@@ -0,0 +1,58 @@
+use quicksilver::geom::Vector;
+use rogue_like::{
+    crafting::{Ingredient, RecipeDef, WORKBENCH},
+    fixture,
+    replay::Command,
+};
+
+fn recipe(workbench: bool) -> RecipeDef {
+    RecipeDef {
+        name: "test sword".to_string(),
+        ingredients: vec![
+            Ingredient {
+                item: "ore".to_string(),
+                count: 2,
+            },
+            Ingredient {
+                item: "hide".to_string(),
+                count: 1,
+            },
+        ],
+        makes: "sword".to_string(),
+        workbench,
+    }
+}
+
+#[test]
+fn crafting_uses_up_the_components() {
+    let mut world = fixture::world("#@#");
+    fixture::carry(&mut world, &["ore", "ration", "hide", "ore", "ore"]);
+    fixture::play(&mut world, &[Command::Craft(Box::new(recipe(false)))]);
+    assert_eq!(world.player().inventory, ["ration", "ore", "sword"]);
+}
+
+#[test]
+fn nothing_is_crafted_without_every_component() {
+    let mut world = fixture::world("#@#");
+    fixture::carry(&mut world, &["ore", "hide"]);
+    let missing = recipe(false).missing(world.player());
+    assert_eq!(missing.len(), 1);
+    assert_eq!((missing[0].item.as_str(), missing[0].count), ("ore", 1));
+    assert!(!world.craft(&recipe(false)));
+    assert_eq!(world.player().inventory, ["ore", "hide"]);
+}
+
+#[test]
+fn some_recipes_need_a_workbench_nearby() {
+    let mut world = fixture::world("#@..#");
+    fixture::carry(&mut world, &["ore", "ore", "hide", "ore", "ore", "hide"]);
+    assert!(!world.craft(&recipe(true)));
+    if let Some(tile) = world.tile_at_mut(Vector::new(3, 0)) {
+        tile.glyph = WORKBENCH;
+    }
+    assert!(!world.craft(&recipe(true)));
+    world.entities[world.player_id].pos = Vector::new(2, 0);
+    assert!(world.craft(&recipe(true)));
+    assert!(world.craft(&recipe(false)));
+    assert_eq!(world.player().inventory, ["sword", "sword"]);
+}
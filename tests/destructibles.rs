@@ -0,0 +1,75 @@
+use quicksilver::geom::Vector;
+use rogue_like::{
+    data,
+    destructibles::{self, RUBBLE, WALL_HP},
+    fixture,
+    replay::Command,
+    spells,
+    world::{Event, World},
+};
+
+const MAP: &str = "
+    #######
+    #@#...#
+    #.#...#
+    #######
+";
+
+fn marked(map: &str) -> World {
+    let content = data::Content::load(None, false);
+    let mut world = fixture::world(map);
+    destructibles::mark(&mut world, &content.glyphs);
+    world
+}
+
+#[test]
+fn only_walls_inside_the_outer_one_can_be_knocked_down() {
+    let world = marked(MAP);
+    assert_eq!(world.destructibles.len(), 2);
+    assert!(world.destructible_at(Vector::new(2, 1)).is_some());
+    assert!(world.destructible_at(Vector::new(0, 1)).is_none());
+    assert!(world.destructibles.iter().all(|destructible| destructible.hp == WALL_HP));
+}
+
+#[test]
+fn a_weak_blow_just_bumps_into_a_wall() {
+    let mut world = marked(MAP);
+    world.entities[world.player_id].attack = 1;
+    world.entities[world.player_id].attributes.strength = 0;
+    assert!(!world.move_player(Vector::new(1, 0)));
+    assert_eq!(world.destructibles[0].hp, WALL_HP);
+}
+
+#[test]
+fn heavy_blows_knock_a_wall_down_into_rubble() {
+    let mut world = marked(MAP);
+    world.entities[world.player_id].attack = WALL_HP / 2;
+    let wall = Vector::new(2, 1);
+    fixture::play(&mut world, &[Command::MovePlayer(Vector::new(1, 0))]);
+    assert!(world.events.contains(&Event::Battered { entity: world.player_id }));
+    assert_eq!(world.player().pos, Vector::new(1, 1));
+
+    fixture::play(&mut world, &[Command::MovePlayer(Vector::new(1, 0))]);
+    assert!(world.events.contains(&Event::Collapsed { entity: world.player_id }));
+    assert!(world.destructible_at(wall).is_none());
+    assert_eq!(world.tile_at(wall).map(|tile| tile.glyph), Some(RUBBLE));
+    assert!(world.is_walkable(wall));
+    fixture::play(&mut world, &[Command::MovePlayer(Vector::new(1, 0))]);
+    assert_eq!(world.player().pos, wall);
+}
+
+#[test]
+fn explosions_knock_down_what_they_reach() {
+    let content = data::Content::load(None, false);
+    let mut world = marked(MAP);
+    for destructible in &mut world.destructibles {
+        destructible.hp = 1;
+    }
+    let nova = spells::find(&content.spells, "frost nova").unwrap().clone();
+    world.entities[world.player_id].mana = nova.mana;
+    let target = world.player().pos;
+    fixture::play(&mut world, &[Command::Cast(Box::new(nova), target)]);
+    assert!(world.destructibles.is_empty());
+    let rubble = world.map.iter().filter(|tile| tile.glyph == RUBBLE).count();
+    assert!(rubble >= 2);
+}
@@ -0,0 +1,63 @@
+use quicksilver::{geom::Vector, graphics::Color};
+use rogue_like::{
+    dialogue::DialogueEffect,
+    fixture::{self, EAST},
+    monsters::Ai,
+    replay::Command,
+    spells::{DamageType, SpellDef, Targeting},
+    world::World,
+};
+
+/// A corridor with a friendly hermit next to the player.
+fn hermit() -> World {
+    let mut world = fixture::world("#@G.#");
+    world.entities[1].ai = Ai::Friendly;
+    world.entities[1].dialogue = Some("hermit".to_string());
+    world
+}
+
+#[test]
+fn walking_into_a_friendly_npc_does_not_attack_it() {
+    let mut world = hermit();
+    assert_eq!(world.talker_at(Vector::new(2, 0)), Some(1));
+    assert!(world.visible_hostiles().is_empty());
+    fixture::play(&mut world, &[Command::MovePlayer(EAST), Command::MonsterTurns]);
+    assert_eq!(world.entities[1].hp, fixture::MONSTER_HP);
+    assert_eq!(world.player().pos, Vector::new(1, 0));
+    assert_eq!(world.player().hp, fixture::PLAYER_HP);
+}
+
+#[test]
+fn answers_can_give_items_and_gold_and_move_the_conversation_on() {
+    let mut world = hermit();
+    let effects = [
+        DialogueEffect::Give("ration".to_string()),
+        DialogueEffect::Gold(10),
+        DialogueEffect::Resume("again".to_string()),
+    ];
+    fixture::play(&mut world, &[Command::Talk(1, effects.to_vec())]);
+    assert_eq!(world.player().inventory, ["ration"]);
+    assert_eq!(world.player().gold, 10);
+    assert_eq!(world.dialogue_starts.get(&1).map(String::as_str), Some("again"));
+}
+
+#[test]
+fn hurting_a_friendly_npc_turns_it_hostile() {
+    let mut world = hermit();
+    let bolt = SpellDef {
+        name: "test bolt".to_string(),
+        glyph: '*',
+        color: Color::RED,
+        targeting: Targeting::Bolt,
+        range: 6,
+        damage: 1,
+        damage_type: DamageType::Physical,
+        status: None,
+        cooldown: 0,
+        mana: 0,
+    };
+    fixture::play(&mut world, &[Command::Cast(Box::new(bolt), Vector::new(2, 0))]);
+    assert_eq!(world.entities[1].ai, Ai::Chase);
+    assert_eq!(world.talker_at(Vector::new(2, 0)), None);
+    assert_eq!(world.visible_hostiles(), [1]);
+}
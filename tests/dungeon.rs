@@ -0,0 +1,178 @@
+use quicksilver::{geom::Vector, graphics::Color};
+use rogue_like::{
+    data,
+    dungeon::{self, Arrival, Section},
+    fixture,
+    replay::Command,
+    rng::Rng,
+    world::{Biome, Entity},
+};
+use std::collections::BTreeSet;
+
+#[test]
+fn changing_level_brings_the_player_along_and_leaves_the_rest() {
+    let mut world = fixture::world("#<@g#");
+    world.entities[world.player_id].hp = 2;
+    world.entities[world.player_id].inventory.push("ration".to_string());
+    let mut level = fixture::world(
+        "
+        ####
+        #G.#
+        #.@#
+        #>.#
+        ####
+        ",
+    );
+    level.depth = 2;
+    fixture::play(&mut world, &[Command::ChangeLevel(Box::new(level))]);
+    assert_eq!(world.depth, 2);
+    assert_eq!(world.map_size, Vector::new(4, 5));
+    assert_eq!(world.entities.len(), 2);
+    assert_eq!(world.player().pos, Vector::new(2, 2));
+    assert_eq!(world.player().hp, 2);
+    assert_eq!(world.player().inventory, ["ration"]);
+    assert!(world.is_visible(Vector::new(1, 1)));
+}
+
+#[test]
+fn only_the_amulet_lets_the_player_out() {
+    let mut world = fixture::world("#@.#");
+    fixture::play(&mut world, &[Command::Escape]);
+    assert!(!world.won);
+    assert!(world.log.is_empty());
+
+    world.entities[world.player_id].inventory.push(dungeon::AMULET.to_string());
+    fixture::play(&mut world, &[Command::Escape]);
+    assert!(world.won);
+    assert!(world.is_over());
+}
+
+#[test]
+fn the_last_level_has_the_amulet_and_no_way_further_down() {
+    let content = data::Content::load(None, false);
+    let player = Entity::new(Vector::new(0, 0), '@', Color::WHITE, 5);
+    let level = dungeon::generate(
+        dungeon::DEEPEST_DEPTH,
+        Section::Main,
+        player,
+        Rng::new(7),
+        &BTreeSet::new(),
+        Arrival::Descending,
+        &content,
+    );
+    assert!(level.map.iter().all(|tile| tile.glyph != '>'));
+    assert_eq!(level.tile_at(level.player().pos).map(|tile| tile.glyph), Some('<'));
+    let amulet = Some(dungeon::AMULET.to_string());
+    assert_eq!(level.entities.iter().filter(|entity| entity.item == amulet).count(), 1);
+}
+
+#[test]
+fn the_last_level_is_built_around_the_lair_and_its_boss() {
+    let content = data::Content::load(None, false);
+    let player = Entity::new(Vector::new(0, 0), '@', Color::WHITE, 5);
+    let level = dungeon::generate(
+        dungeon::DEEPEST_DEPTH,
+        Section::Main,
+        player,
+        Rng::new(7),
+        &BTreeSet::new(),
+        Arrival::Descending,
+        &content,
+    );
+    assert_eq!(level.biome, Biome::Abyss);
+    assert!(level.no_teleport);
+    let boss = level.entities.iter().find(|entity| entity.kind.as_deref() == Some("lich"));
+    assert!(boss.is_some_and(|boss| boss.elite && boss.name.is_some()));
+}
+
+#[test]
+fn the_lair_is_only_ever_on_the_last_level() {
+    let mut content = data::Content::load(None, false);
+    content.prefabs.retain(|prefab| prefab.is_final());
+    for depth in 1..dungeon::DEEPEST_DEPTH {
+        let player = Entity::new(Vector::new(0, 0), '@', Color::WHITE, 5);
+        let level = dungeon::generate(
+            depth,
+            Section::Main,
+            player,
+            Rng::new(depth.into()),
+            &BTreeSet::new(),
+            Arrival::Descending,
+            &content,
+        );
+        assert!(level.entities.iter().all(|entity| entity.kind.as_deref() != Some("lich")));
+        assert!(!level.no_teleport);
+    }
+}
+
+#[test]
+fn teleporting_is_held_back_where_the_level_says() {
+    let mut world = fixture::world("#@..#");
+    assert!(world.teleport(world.player_id, Vector::new(3, 0)));
+    assert_eq!(world.player().pos, Vector::new(3, 0));
+    world.no_teleport = true;
+    assert!(!world.teleport(world.player_id, Vector::new(1, 0)));
+    assert_eq!(world.player().pos, Vector::new(3, 0));
+}
+
+#[test]
+fn going_up_arrives_on_the_stairs_down() {
+    let content = data::Content::load(None, false);
+    let player = Entity::new(Vector::new(0, 0), '@', Color::WHITE, 5);
+    let level = dungeon::generate(2, Section::Main, player, Rng::new(7), &BTreeSet::new(), Arrival::Climbing, &content);
+    assert_eq!(level.tile_at(level.player().pos).map(|tile| tile.glyph), Some('>'));
+    assert!(level.entities.iter().all(|entity| entity.item.as_deref() != Some(dungeon::AMULET)));
+}
+
+#[test]
+fn a_level_left_behind_is_there_as_it_was_to_come_back_to() {
+    let mut world = fixture::world("#>@g#");
+    world.entities[1].hp = 1;
+    let mut level = fixture::world("#<@#");
+    level.depth = 2;
+    fixture::play(&mut world, &[Command::ChangeLevel(Box::new(level))]);
+    assert_eq!(world.depth, 2);
+    assert!(world.visited_level(2, None).is_none());
+    world.entities[world.player_id].inventory.push("ration".to_string());
+    let left = world.visited_level(1, None).expect("the first level was kept");
+    assert_eq!(left.entities[1].hp, 1);
+    let back = dungeon::revisit(left, world.player().clone(), world.rng.clone(), &world.artifacts, Arrival::Climbing);
+    fixture::play(&mut world, &[Command::ChangeLevel(Box::new(back))]);
+    assert_eq!(world.depth, 1);
+    assert_eq!(world.player().pos, Vector::new(1, 0));
+    assert_eq!(world.player().inventory, ["ration"]);
+    assert_eq!(world.entities[1].hp, 1);
+    assert_eq!(world.visited.len(), 1);
+    assert!(world.visited_level(2, None).is_some());
+}
+
+#[test]
+fn a_creature_on_the_stairs_moves_aside_for_the_player_coming_back() {
+    let mut world = fixture::world("#>@g.........#");
+    world.entities[1].pos = Vector::new(1, 0);
+    let mut level = fixture::world("#<@#");
+    level.depth = 2;
+    fixture::play(&mut world, &[Command::ChangeLevel(Box::new(level))]);
+    let left = world.visited_level(1, None).unwrap();
+    let back = dungeon::revisit(left, world.player().clone(), world.rng.clone(), &world.artifacts, Arrival::Climbing);
+    assert_eq!(back.player().pos, Vector::new(1, 0));
+    assert_ne!(back.entities[1].pos, Vector::new(1, 0));
+}
+
+#[test]
+fn coming_back_up_the_stairs_does_not_rewind_the_dice() {
+    let mut world = fixture::world("#>@g#");
+    let mut level = fixture::world("#<@#");
+    level.depth = 2;
+    level.rng = world.rng.clone();
+    fixture::play(&mut world, &[Command::ChangeLevel(Box::new(level))]);
+    for _ in 0..3 {
+        world.rng.next_u64();
+    }
+    let left = world.visited_level(1, None).unwrap();
+    let mut replayed = left.rng.clone();
+    let back = dungeon::revisit(left, world.player().clone(), world.rng.clone(), &world.artifacts, Arrival::Climbing);
+    fixture::play(&mut world, &[Command::ChangeLevel(Box::new(back))]);
+    assert_eq!(world.depth, 1);
+    assert_ne!(world.rng.next_u64(), replayed.next_u64());
+}
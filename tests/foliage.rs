@@ -0,0 +1,74 @@
+use quicksilver::geom::Vector;
+use rogue_like::{
+    data,
+    fixture,
+    foliage::TALL_GRASS,
+    replay::Command,
+    spells::{self, Status, StatusEffect},
+    world::{Event, World},
+};
+
+const MAP: &str = "
+    #########
+    #@..♣♣.g#
+    #...♣♣..#
+    #########
+";
+
+fn cast(world: &mut World, spell: &str, target: Vector) {
+    let content = data::Content::load(None, false);
+    let spell = spells::find(&content.spells, spell).unwrap().clone();
+    world.entities[world.player_id].mana = spell.mana;
+    fixture::play(world, &[Command::Cast(Box::new(spell), target)]);
+}
+
+fn grass(world: &World) -> usize {
+    world.map.iter().filter(|tile| tile.glyph == TALL_GRASS).count()
+}
+
+#[test]
+fn tall_grass_hides_what_is_beyond_it_but_can_be_walked_through() {
+    let world = fixture::world(MAP);
+    assert!(world.is_visible(Vector::new(4, 1)));
+    assert!(world.is_walkable(Vector::new(4, 1)));
+    assert!(!world.is_visible(Vector::new(7, 1)));
+    assert_eq!(world.monster_path(1), None);
+
+    let mut world = fixture::world(MAP);
+    world.entities[world.player_id].pos = Vector::new(4, 1);
+    world.update_fov();
+    assert!(world.is_visible(Vector::new(5, 1)));
+    assert!(!world.is_visible(Vector::new(7, 1)));
+}
+
+#[test]
+fn fire_burns_away_the_whole_patch_and_whoever_is_in_it() {
+    let mut world = fixture::world(MAP);
+    world.entities[1].pos = Vector::new(5, 2);
+    world.entities[1].hp = 100;
+    cast(&mut world, "firebolt", Vector::new(4, 1));
+    assert_eq!(grass(&world), 0);
+    assert!(world.beneath_grass.is_empty());
+    assert_eq!(world.tile_at(Vector::new(5, 1)).map(|tile| tile.glyph), Some('.'));
+    assert!(world.events.contains(&Event::Kindled { entity: world.player_id }));
+    assert!(world.entities[1].has_status(Status::Burning));
+    assert!(world.is_visible(Vector::new(7, 1)));
+}
+
+#[test]
+fn frost_leaves_the_grass_standing() {
+    let mut world = fixture::world(MAP);
+    world.entities[world.player_id].pos = Vector::new(3, 1);
+    cast(&mut world, "frost nova", Vector::new(3, 1));
+    assert_eq!(grass(&world), 4);
+}
+
+#[test]
+fn anyone_on_fire_sets_the_grass_alight_walking_into_it() {
+    let mut world = fixture::world(MAP);
+    world.entities[world.player_id].pos = Vector::new(3, 1);
+    world.entities[world.player_id].statuses.push(StatusEffect { status: Status::Burning, turns: 3 });
+    fixture::play(&mut world, &[Command::MovePlayer(Vector::new(1, 0))]);
+    assert_eq!(grass(&world), 0);
+    assert_eq!(world.player().pos, Vector::new(4, 1));
+}
@@ -0,0 +1,57 @@
+use quicksilver::geom::Vector;
+use rogue_like::{
+    fixture::{self, EAST, WEST},
+    fov::FOV_RADIUS,
+    replay::Command,
+};
+
+#[test]
+fn walls_block_sight() {
+    let mut world = fixture::world(
+        "
+        #######
+        #@.#.g#
+        #######
+        ",
+    );
+    world.update_fov();
+    assert!(world.is_visible(Vector::new(2, 1)));
+    assert!(world.is_visible(Vector::new(3, 1)), "walls themselves are seen");
+    assert!(!world.is_visible(Vector::new(4, 1)));
+    assert!(world.visible_hostiles().is_empty());
+}
+
+#[test]
+fn sight_ends_at_the_fov_radius() {
+    let corridor = ".".repeat(FOV_RADIUS as usize + 4);
+    let mut world = fixture::world(&format!("@{}", corridor));
+    world.update_fov();
+    assert!(world.is_visible(Vector::new(FOV_RADIUS, 0)));
+    assert!(!world.is_visible(Vector::new(FOV_RADIUS + 2, 0)));
+}
+
+#[test]
+fn explored_tiles_stay_explored_out_of_sight() {
+    let corridor = ".".repeat(FOV_RADIUS as usize + 4);
+    let mut world = fixture::world(&format!("@{}", corridor));
+    let far = Vector::new(FOV_RADIUS + 2, 0);
+    assert!(!world.is_explored(far));
+    fixture::play(&mut world, &[Command::MovePlayer(EAST), Command::MovePlayer(EAST)]);
+    assert!(world.is_visible(far));
+    fixture::play(&mut world, &[Command::MovePlayer(WEST), Command::MovePlayer(WEST)]);
+    assert!(!world.is_visible(far));
+    assert!(world.is_explored(far));
+}
+
+#[test]
+fn monsters_out_of_sight_stay_put() {
+    let mut world = fixture::world(
+        "
+        #######
+        #@.#.g#
+        #######
+        ",
+    );
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert_eq!(world.entities[1].pos, Vector::new(5, 1));
+}
@@ -0,0 +1,99 @@
+use quicksilver::{geom::Vector, graphics::Color};
+use rogue_like::{
+    fixture,
+    gods::{self, Altar, Gift, GodDef, Offering, Prayer, PrayerEffect, Rite},
+    replay::Command,
+};
+
+fn god() -> GodDef {
+    GodDef {
+        name: "test god".to_string(),
+        color: Color::RED,
+        corpse_piety: 3,
+        item_piety: 50,
+        prayers: vec![
+            Prayer {
+                name: "smite".to_string(),
+                piety: 2,
+                effect: PrayerEffect::Smite(2),
+            },
+            Prayer {
+                name: "heal".to_string(),
+                piety: 5,
+                effect: PrayerEffect::Heal(2),
+            },
+        ],
+        gifts: vec![
+            Gift {
+                piety: 3,
+                item: "first gift".to_string(),
+            },
+            Gift {
+                piety: 6,
+                item: "second gift".to_string(),
+            },
+        ],
+    }
+}
+
+#[test]
+fn offerings_earn_piety_and_gifts_in_order() {
+    let mut world = fixture::world("#@g#");
+    world.entities[1].hp = 0;
+    world.entities[world.player_id].inventory.push("trinket".to_string());
+    fixture::play(&mut world, &[Command::Sacrifice(Box::new(god()), Offering::Corpse(1))]);
+    assert_eq!(world.devotion["test god"].piety, 3);
+    assert_eq!(world.player().inventory, ["trinket", "first gift"]);
+    assert!(world.corpses_near(world.player().pos).is_empty());
+
+    let offering = Offering::Item { index: 0, value: 4 };
+    fixture::play(&mut world, &[Command::Sacrifice(Box::new(god()), offering)]);
+    assert_eq!(world.devotion["test god"].piety, 5);
+    assert_eq!(world.player().inventory, ["first gift"]);
+}
+
+#[test]
+fn prayers_cost_piety_and_need_enough_of_it() {
+    let mut world = fixture::world("#@.g#");
+    world.entities[world.player_id].hp = 1;
+    let heal = &god().prayers[1];
+    assert!(!world.pray("test god", heal));
+
+    world.devotion.entry("test god".to_string()).or_default().piety = 7;
+    assert!(world.pray("test god", heal));
+    assert_eq!(world.player().hp, 3);
+    assert_eq!(world.devotion["test god"].piety, 2);
+
+    let smite = &god().prayers[0];
+    assert!(world.pray("test god", smite));
+    assert_eq!(world.entities[1].hp, fixture::MONSTER_HP - 2);
+    assert_eq!(world.devotion["test god"].piety, 0);
+}
+
+#[test]
+fn smiting_needs_something_in_view() {
+    let mut world = fixture::world("#@.#");
+    world.devotion.entry("test god".to_string()).or_default().piety = 5;
+    assert!(!world.pray("test god", &god().prayers[0]));
+    assert_eq!(world.devotion["test god"].piety, 5);
+    assert!(world.log.is_empty());
+}
+
+#[test]
+fn offerings_can_only_be_made_on_an_altar() {
+    let mut world = fixture::world("#@.g#");
+    world.entities[1].hp = 0;
+    let gods = [god()];
+    assert!(gods::rites(&world, &gods, &[]).is_empty());
+
+    world.altars.push(Altar {
+        pos: Vector::new(2, 0),
+        god: "test god".to_string(),
+    });
+    assert!(gods::rites(&world, &gods, &[]).is_empty());
+    world.entities[world.player_id].pos = Vector::new(2, 0);
+    assert_eq!(gods::rites(&world, &gods, &[]), [Rite::Offer(0, Offering::Corpse(1))]);
+
+    fixture::play(&mut world, &[Command::Sacrifice(Box::new(god()), Offering::Corpse(1))]);
+    assert_eq!(gods::rites(&world, &gods, &[]), [Rite::Pray(0, 0), Rite::Pray(0, 1)]);
+}
@@ -0,0 +1,111 @@
+use rogue_like::{
+    data::{self, Content},
+    fixture,
+    happenings::{self, Happening, Scheduled},
+    replay::Command,
+    rng::Rng,
+    world::World,
+};
+
+const MAP: &str = "
+    ################
+    #..............#
+    #..............#
+    #.....####.....#
+    #.....#..#.....#
+    #.....####.....#
+    #......@.......#
+    #..............#
+    #..............#
+    ################
+";
+
+fn prepare(world: &World, name: &str, content: &Content) -> Happening {
+    let def = happenings::find(&content.happenings, name).unwrap();
+    happenings::prepare(world, def, Rng::new(3), content)
+}
+
+#[test]
+fn an_earthquake_reshapes_the_map_but_not_its_edge_or_the_player() {
+    let content = data::Content::load(None, false);
+    let mut world = fixture::world(MAP);
+    let before = world.map.clone();
+    let happening = prepare(&world, "earthquake", &content);
+    assert!(!happening.tiles.is_empty());
+    world.happen(&happening);
+    let size = world.map_size;
+    for (old, new) in before.iter().zip(&world.map).filter(|(old, new)| old != new) {
+        assert!(new.pos.x > 0.0 && new.pos.y > 0.0 && new.pos.x < size.x - 1.0 && new.pos.y < size.y - 1.0);
+        assert!(new.pos.distance(world.player().pos) >= 2.0);
+        assert!(matches!((old.glyph, new.glyph), ('#', ':') | ('.', '#')));
+    }
+}
+
+#[test]
+fn a_migration_brings_in_monsters() {
+    let content = data::Content::load(None, false);
+    let mut world = fixture::world(MAP);
+    let happening = prepare(&world, "goblin migration", &content);
+    world.happen(&happening);
+    let goblins = world.entities.iter().filter(|entity| entity.kind.as_deref() == Some("goblin")).count();
+    assert_eq!(goblins, 4);
+}
+
+#[test]
+fn a_caravan_sets_up_shop_clear_of_the_player() {
+    let content = data::Content::load(None, false);
+    let mut world = fixture::world(MAP);
+    let happening = prepare(&world, "caravan", &content);
+    assert_eq!(happening.shops.len(), 1);
+    world.happen(&happening);
+    assert_eq!(world.shops.len(), 1);
+    let keeper = &world.entities[world.shops[0].keeper];
+    assert_eq!(keeper.kind.as_deref(), Some("shopkeeper"));
+    assert_ne!(world.player().pos, keeper.pos);
+}
+
+#[test]
+fn happenings_replay_from_the_log() {
+    let content = data::Content::load(None, false);
+    let mut played = fixture::world(MAP);
+    for name in ["earthquake", "goblin migration"] {
+        let happening = prepare(&played, name, &content);
+        played.happen(&happening);
+    }
+    let mut replayed = fixture::world(MAP);
+    fixture::play(&mut replayed, &played.log);
+    assert_eq!(replayed.map, played.map);
+    assert_eq!(replayed.entities, played.entities);
+    assert_eq!(replayed.rng, played.rng);
+}
+
+#[test]
+fn a_rising_flood_puts_more_of_the_floor_under_water() {
+    let content = data::Content::load(None, false);
+    let mut world = fixture::world(MAP);
+    let happening = prepare(&world, "rising flood", &content);
+    world.happen(&happening);
+    assert!(world.map.iter().any(|tile| tile.glyph == '~'));
+    assert!(world.map.iter().all(|tile| tile.glyph != '~' || tile.pos.distance(world.player().pos) > 2.0));
+}
+
+#[test]
+fn scheduled_happenings_fall_due_on_their_turn_and_again_if_they_repeat() {
+    let mut world = fixture::world(MAP);
+    let scheduled = |happening: &str, every| Scheduled {
+        happening: happening.to_string(),
+        turns: 2,
+        every,
+    };
+    world.schedule(scheduled("vault guards", None));
+    world.schedule(scheduled("rising flood", Some(3)));
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert!(world.due.is_empty());
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert_eq!(world.due, ["vault guards", "rising flood"]);
+    assert_eq!(world.schedule.len(), 1);
+    fixture::play(&mut world, &[Command::MonsterTurns, Command::MonsterTurns]);
+    assert!(world.due.is_empty());
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert_eq!(world.due, ["rising flood"]);
+}
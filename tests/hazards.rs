@@ -0,0 +1,90 @@
+use quicksilver::geom::Vector;
+use rogue_like::{
+    fixture,
+    hazards::{self, BREATH_TURNS, DEEP_WATER, LAVA_DAMAGE},
+    replay::Command,
+    spells::DamageType,
+    world::{Event, World},
+};
+
+const MAP: &str = "
+    ########
+    #@≋.≈≈.#
+    #......#
+    ########
+";
+
+fn step(world: &mut World, delta: Vector) {
+    world.events.clear();
+    fixture::play(world, &[Command::MovePlayer(delta), Command::MonsterTurns]);
+}
+
+fn wait(world: &mut World) {
+    world.events.clear();
+    fixture::play(world, &[Command::MonsterTurns]);
+}
+
+#[test]
+fn lava_sears_whoever_ends_a_turn_in_it() {
+    let mut world = fixture::world(MAP);
+    world.entities[world.player_id].hp = LAVA_DAMAGE * 2;
+    step(&mut world, Vector::new(1, 0));
+    assert!(world.events.contains(&Event::Burned { entity: world.player_id }));
+    assert_eq!(world.player().hp, LAVA_DAMAGE);
+
+    let mut world = fixture::world(MAP);
+    world.entities[world.player_id].hp = LAVA_DAMAGE * 2;
+    world.entities[world.player_id].resists.push(DamageType::Fire);
+    step(&mut world, Vector::new(1, 0));
+    assert_eq!(world.player().hp, LAVA_DAMAGE * 2 - LAVA_DAMAGE / 2);
+}
+
+#[test]
+fn swimming_too_long_drowns_the_player() {
+    let mut world = fixture::world(MAP);
+    world.entities[world.player_id].pos = Vector::new(4, 1);
+    world.entities[world.player_id].hp = 100;
+    for _ in 0..BREATH_TURNS {
+        wait(&mut world);
+    }
+    assert_eq!(world.swum, BREATH_TURNS);
+    assert_eq!(world.player().hp, 100);
+    wait(&mut world);
+    assert!(world.events.contains(&Event::Drowning { entity: world.player_id }));
+    assert_eq!(world.player().hp, 100 - hazards::DROWN_DAMAGE);
+
+    step(&mut world, Vector::new(0, 1));
+    assert_eq!(world.swum, 0);
+}
+
+#[test]
+fn what_the_player_carries_slips_away_as_they_swim() {
+    let mut world = fixture::world(MAP);
+    world.entities[world.player_id].pos = Vector::new(4, 1);
+    world.entities[world.player_id].hp = 1000;
+    world.entities[world.player_id].inventory = vec!["torch".to_string(); 3];
+    let mut sank = 0;
+    for _ in 0..100 {
+        wait(&mut world);
+        sank += world.events.iter().filter(|event| matches!(event, Event::Sank { .. })).count();
+    }
+    assert!(sank > 0);
+    assert_eq!(world.player().inventory.len(), 3 - sank);
+}
+
+#[test]
+fn monsters_go_around_lava_and_deep_water_when_they_can() {
+    let world = fixture::world(
+        "
+        #######
+        #g≋≋≈@#
+        #.....#
+        #######
+        ",
+    );
+    assert_eq!(world.tile_at(Vector::new(4, 1)).map(|tile| tile.glyph), Some(DEEP_WATER));
+    assert_eq!(world.step_cost(Vector::new(2, 1)), None);
+    assert_eq!(world.step_cost(Vector::new(4, 1)), Some(hazards::DEEP_WATER_COST));
+    let path = world.monster_path(1).expect("there's a way around");
+    assert!(path.iter().all(|&pos| pos.y == 2.0 || pos == world.player().pos));
+}
@@ -0,0 +1,56 @@
+use rogue_like::{
+    fixture,
+    hunger::{self, Hunger},
+    items::ItemEffect,
+    replay::Command,
+    spells::{Status, StatusEffect},
+    world::{Event, World},
+};
+
+/// How much hp the player gets back on their own over `turns` turns, starting
+/// from 1.
+fn healed(world: &mut World, turns: usize) -> i32 {
+    world.entities[world.player_id].hp = 1;
+    fixture::play(world, &vec![Command::MonsterTurns; turns]);
+    world.player().hp - 1
+}
+
+#[test]
+fn hunger_slows_healing_and_starving_stops_it() {
+    let mut world = fixture::world("#@#");
+    let turns = world.heal_turns().unwrap();
+    world.satiety = hunger::HUNGRY_TURNS + 1;
+    world.events.clear();
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert!(world.events.contains(&Event::Hungry { entity: world.player_id }));
+    assert_eq!(world.heal_turns(), Some(turns * 2));
+    world.satiety = 0;
+    assert_eq!(Hunger::of(world.satiety), Hunger::Starving);
+    assert_eq!(healed(&mut world, turns * 4), 0);
+}
+
+#[test]
+fn food_keeps_the_player_fed_up_to_a_limit() {
+    let mut world = fixture::world("#@#");
+    world.satiety = 0;
+    world.entities[world.player_id].inventory.extend(["ration".to_string(), "ration".to_string()]);
+    fixture::play(&mut world, &[Command::UseItem(0, vec![ItemEffect::Nourish(1000)])]);
+    assert_eq!(world.satiety, 1000);
+    world.satiety = hunger::MAX_FULL_TURNS - 10;
+    fixture::play(&mut world, &[Command::UseItem(0, vec![ItemEffect::Nourish(1000)])]);
+    assert_eq!(world.satiety, hunger::MAX_FULL_TURNS);
+}
+
+#[test]
+fn haste_does_not_heal_any_faster_in_the_worlds_time() {
+    let mut world = fixture::world("#@#");
+    world.entities[world.player_id].max_hp = 100;
+    let turns = world.heal_turns().unwrap() * 4;
+    let steady = healed(&mut world, turns);
+    assert_eq!(steady, 4);
+    world.entities[world.player_id].statuses.push(StatusEffect {
+        status: Status::Hasted,
+        turns: 1000,
+    });
+    assert!(healed(&mut world, turns) < steady);
+}
@@ -0,0 +1,65 @@
+use quicksilver::geom::Vector;
+use rogue_like::{
+    fixture,
+    hazards::{self, ICE},
+    mechanisms,
+    replay::Command,
+    world::{Event, World},
+};
+
+fn step_east(world: &mut World) {
+    world.events.clear();
+    fixture::play(world, &[Command::MovePlayer(Vector::new(1, 0))]);
+}
+
+#[test]
+fn the_player_slides_across_ice_until_off_it_or_stopped() {
+    let mut world = fixture::world("#@░░░..#");
+    step_east(&mut world);
+    assert_eq!(world.player().pos, Vector::new(5, 0));
+    assert!(world.events.contains(&Event::Slid { entity: world.player_id }));
+
+    let mut world = fixture::world("#@░░░#");
+    step_east(&mut world);
+    assert_eq!(world.player().pos, Vector::new(4, 0));
+
+    let mut world = fixture::world("#@.#");
+    step_east(&mut world);
+    assert!(!world.events.contains(&Event::Slid { entity: world.player_id }));
+}
+
+#[test]
+fn sliding_stops_short_of_a_creature_without_hitting_it() {
+    let mut world = fixture::world("#@░░g.#");
+    step_east(&mut world);
+    assert_eq!(world.player().pos, Vector::new(3, 0));
+    assert_eq!(world.entities[1].hp, fixture::MONSTER_HP);
+}
+
+#[test]
+fn whatever_is_slid_onto_goes_off() {
+    let mut world = fixture::world("#@░^..#");
+    step_east(&mut world);
+    assert_eq!(world.player().pos, Vector::new(3, 0));
+    assert_eq!(world.player().hp, fixture::PLAYER_HP - mechanisms::TRAP_DAMAGE);
+}
+
+#[test]
+fn monsters_slide_too_and_would_rather_go_around() {
+    let mut world = fixture::world("#g░░..@#");
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert_eq!(world.entities[1].pos, Vector::new(4, 0));
+
+    let world = fixture::world(
+        "
+        #######
+        #g░░░@#
+        #.....#
+        #######
+        ",
+    );
+    assert_eq!(world.tile_at(Vector::new(2, 1)).map(|tile| tile.glyph), Some(ICE));
+    assert_eq!(world.step_cost(Vector::new(2, 1)), Some(hazards::ICE_COST));
+    let path = world.monster_path(1).expect("there's a way around");
+    assert!(path.iter().all(|&pos| pos.y == 2.0 || pos == world.player().pos));
+}
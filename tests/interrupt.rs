@@ -0,0 +1,82 @@
+use quicksilver::{geom::Vector, graphics::Color};
+use rogue_like::{
+    data, fixture, hunger,
+    interrupt::{Interruption, Watch},
+    monsters::Ai,
+    replay::Command,
+    spells::{Status, StatusEffect},
+    world::{Entity, World},
+};
+
+const ROOM: &str = "
+    #########
+    #@......#
+    #########
+";
+
+fn wounded() -> World {
+    let mut world = fixture::world(ROOM);
+    world.entities[world.player_id].hp = 1;
+    world.update_fov();
+    world
+}
+
+#[test]
+fn a_hostile_coming_into_view_stops_a_rest() {
+    let content = data::Content::load(None, false);
+    let mut world = wounded();
+    let mut watch = Watch::new(&world, true);
+    assert_eq!(watch.check(&world, &content.items), None);
+    world.entities.push(Entity {
+        ai: Ai::Chase,
+        ..Entity::new(Vector::new(4, 1), 'g', Color::RED, fixture::MONSTER_HP)
+    });
+    world.update_fov();
+    assert_eq!(watch.check(&world, &content.items), Some(Interruption::HostileSpotted));
+}
+
+#[test]
+fn a_new_status_stops_a_rest_once() {
+    let content = data::Content::load(None, false);
+    let mut world = wounded();
+    let mut watch = Watch::new(&world, true);
+    world.entities[world.player_id].statuses.push(StatusEffect {
+        status: Status::Poisoned,
+        turns: 5,
+    });
+    assert_eq!(watch.check(&world, &content.items), Some(Interruption::Afflicted(Status::Poisoned)));
+    assert_eq!(watch.check(&world, &content.items), None);
+}
+
+#[test]
+fn a_rest_stops_at_full_hp_and_mana() {
+    let content = data::Content::load(None, false);
+    let mut world = wounded();
+    let mut watch = Watch::new(&world, true);
+    let turns = world.heal_turns().unwrap() * fixture::PLAYER_HP as usize;
+    for _ in 0..turns {
+        if watch.check(&world, &content.items).is_some() {
+            break;
+        }
+        fixture::play(&mut world, &[Command::MonsterTurns]);
+    }
+    assert_eq!(world.player().hp, fixture::PLAYER_HP);
+    assert_eq!(watch.check(&world, &content.items), Some(Interruption::Rested));
+    let mut walk = Watch::new(&world, false);
+    assert_eq!(walk.check(&world, &content.items), None);
+}
+
+#[test]
+fn a_hungry_rest_eats_and_stops_when_the_food_runs_out() {
+    let content = data::Content::load(None, false);
+    let mut world = wounded();
+    world.satiety = hunger::HUNGRY_TURNS;
+    world.entities[world.player_id].inventory.push("ration".to_string());
+    let mut watch = Watch::new(&world, true);
+    assert_eq!(watch.check(&world, &content.items), None);
+    let (index, ration) = hunger::meal(&world, &content.items).expect("a ration is food");
+    fixture::play(&mut world, &[Command::UseItem(index, ration.effects.clone())]);
+    assert!(world.satiety > hunger::HUNGRY_TURNS);
+    world.satiety = hunger::HUNGRY_TURNS;
+    assert_eq!(watch.check(&world, &content.items), Some(Interruption::OutOfFood));
+}
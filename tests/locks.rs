@@ -0,0 +1,101 @@
+use quicksilver::geom::Vector;
+use rogue_like::{
+    data, fixture,
+    locks::{self, Lock},
+    prefab::{self, Prefab},
+    replay::Command,
+    rng::Rng,
+    world::{Event, World},
+};
+
+const MAP: &str = "
+    #######
+    #@....#
+    ####+##
+    #.....#
+    #######
+";
+
+fn locked(map: &str) -> World {
+    let mut world = fixture::world(map);
+    world.locks.push(Lock {
+        pos: Vector::new(4, 2),
+        key: "iron key".to_string(),
+    });
+    world
+}
+
+#[test]
+fn a_locked_door_blocks_the_way_until_its_key_opens_it() {
+    let mut world = locked(MAP);
+    world.entities[world.player_id].pos = Vector::new(4, 1);
+    world.entities[world.player_id].attributes.strength = 0;
+    world.entities[world.player_id].attributes.dexterity = 0;
+    world.update_fov();
+    assert!(!world.is_walkable(Vector::new(4, 2)));
+    assert!(!world.is_visible(Vector::new(4, 3)));
+
+    world.entities[world.player_id].inventory.push("iron key".to_string());
+    fixture::play(&mut world, &[Command::MovePlayer(Vector::new(0, 1))]);
+    assert!(world.locks.is_empty());
+    assert!(world.player().inventory.is_empty());
+    assert!(world.events.contains(&Event::Unlocked {
+        entity: world.player_id,
+        key: Some("iron key".to_string()),
+    }));
+    assert_eq!(world.player().pos, Vector::new(4, 1));
+    assert!(world.is_visible(Vector::new(4, 3)));
+    fixture::play(&mut world, &[Command::MovePlayer(Vector::new(0, 1))]);
+    assert_eq!(world.player().pos, Vector::new(4, 2));
+}
+
+#[test]
+fn a_strong_player_can_bash_a_door_open_without_its_key() {
+    let mut world = locked(MAP);
+    world.entities[world.player_id].pos = Vector::new(4, 1);
+    world.entities[world.player_id].attributes.strength = 30;
+    assert!(world.move_player(Vector::new(0, 1)));
+    assert!(world.locks.is_empty());
+    assert!(world.events.contains(&Event::Unlocked {
+        entity: world.player_id,
+        key: None,
+    }));
+}
+
+#[test]
+fn keys_are_placed_on_the_near_side_of_every_locked_door() {
+    let content = data::Content::load(None, false);
+    for seed in 0..20 {
+        let mut world = locked(MAP);
+        world.rng = Rng::new(seed);
+        let key = content.items.iter().find(|item| item.key).unwrap().entity(Vector::new(0, 0));
+        locks::place_key(&mut world, key);
+        let key = world.entities.last().unwrap();
+        assert_eq!(key.pos.y, 1.0);
+        assert_ne!(key.pos, world.player().pos);
+    }
+}
+
+#[test]
+fn a_prefab_door_comes_with_its_key() {
+    let mut content = data::Content::load(None, false);
+    let vault = content.prefabs.iter().find(|prefab| prefab.is("vault.txt")).unwrap();
+    assert!(vault.text().contains("door 4 8 iron key"));
+    let prefab = Prefab::parse("gate.txt", "###\n#.#\ndoor 1 1 iron key\n").unwrap();
+    let mut world = fixture::world(
+        "
+        ##########
+        #@.......#
+        #........#
+        #........#
+        ##########
+        ",
+    );
+    content.prefabs = vec![prefab];
+    assert!(prefab::place_named(&mut world, "gate.txt", &content));
+    assert_eq!(world.locks.len(), 1);
+    let door = world.locks[0].pos;
+    assert_eq!(world.tile_at(door).map(|tile| tile.glyph), Some('+'));
+    let key = world.entities.iter().find(|entity| entity.item.as_deref() == Some("iron key")).unwrap();
+    assert_ne!(key.pos, door);
+}
@@ -0,0 +1,66 @@
+use quicksilver::{geom::Vector, graphics::Color};
+use rogue_like::{
+    fixture,
+    perks::{PerkDef, PerkEffect},
+    replay::Command,
+    spells::{self, DamageType, SpellDef, Targeting, MANA_REGEN_TURNS},
+};
+
+fn bolt(name: &str, mana: i32) -> SpellDef {
+    SpellDef {
+        name: name.to_string(),
+        glyph: '*',
+        color: Color::RED,
+        targeting: Targeting::Bolt,
+        range: 6,
+        damage: 1,
+        damage_type: DamageType::Fire,
+        status: None,
+        cooldown: 0,
+        mana,
+    }
+}
+
+#[test]
+fn casting_spends_mana() {
+    let mut world = fixture::world("#@.G.#");
+    world.entities[world.player_id].max_mana = 5;
+    world.entities[world.player_id].mana = 5;
+    fixture::play(&mut world, &[Command::Cast(Box::new(bolt("test bolt", 3)), Vector::new(3, 0))]);
+    assert_eq!(world.player().mana, 2);
+    assert_eq!(world.entities[1].hp, fixture::MONSTER_HP - 1);
+}
+
+#[test]
+fn mana_comes_back_over_time_up_to_the_max() {
+    let mut world = fixture::world("#@#");
+    world.entities[world.player_id].max_mana = 1;
+    for _ in 0..MANA_REGEN_TURNS - 1 {
+        fixture::play(&mut world, &[Command::MonsterTurns]);
+    }
+    assert_eq!(world.player().mana, 0);
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert_eq!(world.player().mana, 1);
+    for _ in 0..MANA_REGEN_TURNS {
+        fixture::play(&mut world, &[Command::MonsterTurns]);
+    }
+    assert_eq!(world.player().mana, 1);
+}
+
+#[test]
+fn the_spellbook_holds_only_known_spells() {
+    let mut world = fixture::world("#@#");
+    let all = [bolt("first", 1), bolt("second", 1), bolt("third", 1)];
+    assert!(spells::known(&all, world.player()).is_empty());
+
+    let lore = PerkDef {
+        name: "lore".to_string(),
+        requires: Vec::new(),
+        effects: vec![PerkEffect::Spell("third".to_string()), PerkEffect::MaxMana(2)],
+    };
+    world.entities[world.player_id].spells.push("first".to_string());
+    fixture::play(&mut world, &[Command::TakePerk(Box::new(lore))]);
+    let known: Vec<&str> = spells::known(&all, world.player()).iter().map(|spell| spell.name.as_str()).collect();
+    assert_eq!(known, ["first", "third"]);
+    assert_eq!(world.player().max_mana, 2);
+}
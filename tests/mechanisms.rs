@@ -0,0 +1,101 @@
+use quicksilver::geom::Vector;
+use rogue_like::{
+    data, fixture,
+    mechanisms::{self, Mechanism, Trigger},
+    prefab::{self, Prefab},
+    replay::Command,
+    world::{Event, World},
+};
+
+const MAP: &str = "
+    #######
+    #@|.^.#
+    ###}###
+    #..._.#
+    #######
+";
+
+/// `MAP` with the lever raising the portcullis, and the plate disarming the trap.
+fn linked() -> World {
+    let mut world = fixture::world(MAP);
+    let switched = |world: &World, pos: Vector| {
+        let mut tile = world.tile_at(pos).unwrap().clone();
+        tile.glyph = '.';
+        tile
+    };
+    let (portcullis, trap) = (switched(&world, Vector::new(3, 2)), switched(&world, Vector::new(4, 1)));
+    world.mechanisms.push(Mechanism {
+        pos: Vector::new(2, 1),
+        trigger: Trigger::Lever,
+        switches: vec![portcullis],
+    });
+    world.mechanisms.push(Mechanism {
+        pos: Vector::new(4, 3),
+        trigger: Trigger::Plate,
+        switches: vec![trap],
+    });
+    world
+}
+
+#[test]
+fn pulling_a_lever_raises_a_portcullis_and_pulling_it_again_lowers_it() {
+    let mut world = linked();
+    let gate = Vector::new(3, 2);
+    assert!(!world.is_walkable(gate));
+    assert!(!world.is_walkable(Vector::new(2, 1)));
+    fixture::play(&mut world, &[Command::MovePlayer(Vector::new(1, 0))]);
+    assert_eq!(world.player().pos, Vector::new(1, 1));
+    assert!(world.is_walkable(gate));
+    assert!(world.events.contains(&Event::Worked { entity: world.player_id }));
+    fixture::play(&mut world, &[Command::MovePlayer(Vector::new(1, 0))]);
+    assert_eq!(world.tile_at(gate).map(|tile| tile.glyph), Some(mechanisms::PORTCULLIS));
+}
+
+#[test]
+fn a_trap_hurts_whoever_steps_on_it_until_a_plate_disarms_it() {
+    let mut world = linked();
+    world.entities[world.player_id].pos = Vector::new(5, 1);
+    let hp = world.player().hp;
+    fixture::play(&mut world, &[Command::MovePlayer(Vector::new(-1, 0))]);
+    assert_eq!(world.player().hp, hp - mechanisms::TRAP_DAMAGE);
+    assert!(world.events.contains(&Event::Trapped { entity: world.player_id }));
+
+    world.entities[world.player_id].pos = Vector::new(5, 3);
+    fixture::play(&mut world, &[Command::MovePlayer(Vector::new(-1, 0))]);
+    assert_eq!(world.tile_at(Vector::new(4, 1)).map(|tile| tile.glyph), Some('.'));
+}
+
+#[test]
+fn links_must_run_from_a_lever_or_plate_to_a_drawn_tile() {
+    assert!(Prefab::parse("good.txt", "|#\nlink 0 0 1 0 .\n").is_ok());
+    assert!(Prefab::parse("from_wall.txt", "##\nlink 0 0 1 0 .\n").is_err());
+    assert!(Prefab::parse("to_nothing.txt", "| \nlink 0 0 1 0 .\n").is_err());
+    assert!(Prefab::parse("bad_tile.txt", "|#\nlink 0 0 1 0 x\n").is_err());
+    assert!(Prefab::parse("short.txt", "|#\nlink 0 0 1\n").is_err());
+}
+
+#[test]
+fn a_stamped_prefab_links_up_its_mechanisms() {
+    let mut content = data::Content::load(None, false);
+    content.prefabs.retain(|prefab| prefab.is("guardroom.txt"));
+    let mut world = fixture::world(
+        "
+        ################
+        #@.............#
+        #..............#
+        #..............#
+        #..............#
+        #..............#
+        #..............#
+        #..............#
+        #..............#
+        #..............#
+        ################
+        ",
+    );
+    assert!(prefab::place_named(&mut world, "guardroom.txt", &content));
+    assert_eq!(world.mechanisms.len(), 3);
+    let plate = world.mechanisms.iter().find(|mechanism| mechanism.trigger == Trigger::Plate).unwrap();
+    assert_eq!(plate.switches.len(), 4);
+    assert!(plate.switches.iter().all(|tile| tile.glyph == '.'));
+}
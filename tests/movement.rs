@@ -0,0 +1,110 @@
+use quicksilver::geom::Vector;
+use rogue_like::{
+    fixture::{self, EAST, WEST},
+    replay::Command,
+};
+
+#[test]
+fn the_player_steps_onto_floor() {
+    let mut world = fixture::world(
+        "
+        #####
+        #@..#
+        #####
+        ",
+    );
+    fixture::play(&mut world, &[Command::MovePlayer(EAST), Command::MovePlayer(EAST)]);
+    assert_eq!(world.player().pos, Vector::new(3, 1));
+    assert_eq!(world.log.len(), 2);
+}
+
+#[test]
+fn walls_stop_the_player_without_using_a_turn() {
+    let mut world = fixture::world(
+        "
+        ####
+        #@.#
+        ####
+        ",
+    );
+    assert!(!world.move_player(WEST));
+    assert_eq!(world.player().pos, Vector::new(1, 1));
+    assert!(world.log.is_empty());
+}
+
+#[test]
+fn water_and_stairs_can_be_walked_on() {
+    let mut world = fixture::world(
+        "
+        #####
+        #@~>#
+        #####
+        ",
+    );
+    fixture::play(&mut world, &[Command::MovePlayer(EAST), Command::MovePlayer(EAST)]);
+    assert_eq!(world.player().pos, Vector::new(3, 1));
+}
+
+#[test]
+fn bumping_a_monster_attacks_it_instead_of_moving() {
+    let mut world = fixture::world(
+        "
+        #####
+        #@g.#
+        #####
+        ",
+    );
+    fixture::play(&mut world, &[Command::MovePlayer(EAST)]);
+    assert_eq!(world.player().pos, Vector::new(1, 1));
+    assert_eq!(world.entities[1].hp, fixture::MONSTER_HP - 1);
+}
+
+#[test]
+fn chasers_close_in_and_guards_hold_their_ground() {
+    let mut world = fixture::world(
+        "
+        ########
+        #@...g.#
+        #....G.#
+        ########
+        ",
+    );
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert_eq!(world.entities[1].pos, Vector::new(4, 1));
+    assert_eq!(world.entities[2].pos, Vector::new(5, 2));
+}
+
+#[test]
+fn chasers_go_around_monsters_in_the_way() {
+    let mut world = fixture::world(
+        "
+        #######
+        #@.Gg.#
+        #.....#
+        #######
+        ",
+    );
+    for _ in 0..3 {
+        fixture::play(&mut world, &[Command::MonsterTurns]);
+    }
+    let monster = world.entities[2].pos;
+    assert!(monster.distance(world.player().pos) < 1.5, "the monster got stuck at {}", monster);
+    assert_eq!(world.entities[1].pos, Vector::new(3, 1));
+}
+
+#[test]
+fn diagonal_steps_cannot_cut_past_a_single_corner() {
+    let mut world = fixture::world(
+        "
+        #####
+        ##..#
+        #@..#
+        #####
+        ",
+    );
+    let north_east = Vector::new(1, -1);
+    assert!(!world.move_player(north_east));
+    assert_eq!(world.player().pos, Vector::new(1, 2));
+    fixture::play(&mut world, &[Command::MovePlayer(EAST), Command::MovePlayer(north_east)]);
+    assert_eq!(world.player().pos, Vector::new(3, 1));
+}
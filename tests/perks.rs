@@ -0,0 +1,62 @@
+use rogue_like::{
+    classes::Talent,
+    fixture::{self, EAST},
+    perks::{self, PerkDef, PerkEffect},
+    replay::Command,
+    world::{self, Event},
+};
+
+fn perk(name: &str, requires: &[&str], effect: PerkEffect) -> PerkDef {
+    PerkDef {
+        name: name.to_string(),
+        requires: requires.iter().map(|name| name.to_string()).collect(),
+        effects: vec![effect],
+    }
+}
+
+#[test]
+fn kills_level_the_player_up_and_earn_perks() {
+    let mut world = fixture::world(
+        "
+        #####
+        #@g.#
+        #####
+        ",
+    );
+    world.entities[1].max_hp = world::xp_needed(1) as i32;
+    world.entities[1].hp = 1;
+    fixture::play(&mut world, &[Command::MovePlayer(EAST)]);
+    let player = world.player();
+    assert_eq!(player.level, 2);
+    assert_eq!(player.xp, 0);
+    assert_eq!(player.max_hp, fixture::PLAYER_HP + 1);
+    assert_eq!(world.perk_points, 1);
+    assert!(world.events.iter().any(|event| matches!(event, Event::LevelUp { level: 2, .. })));
+}
+
+#[test]
+fn perks_open_up_once_what_they_require_is_taken() {
+    let mut world = fixture::world("#@#");
+    let tree = [
+        perk("weapon training", &[], PerkEffect::Attack(1)),
+        perk("cleave", &["weapon training"], PerkEffect::Talent(Talent::Cleave)),
+    ];
+    let names = |world: &world::World| -> Vec<String> {
+        perks::open_to(&tree, world.player()).iter().map(|perk| perk.name.clone()).collect()
+    };
+    assert_eq!(names(&world), ["weapon training"]);
+
+    world.perk_points = 1;
+    fixture::play(&mut world, &[Command::TakePerk(Box::new(tree[0].clone()))]);
+    assert_eq!(world.perk_points, 0);
+    assert_eq!(world.player().attack, 2);
+    assert_eq!(names(&world), ["cleave"]);
+}
+
+#[test]
+fn perks_that_give_nothing_new_are_not_offered() {
+    let mut world = fixture::world("#@#");
+    world.entities[world.player_id].talents.push(Talent::Cleave);
+    let tree = [perk("cleave", &[], PerkEffect::Talent(Talent::Cleave))];
+    assert!(perks::open_to(&tree, world.player()).is_empty());
+}
@@ -0,0 +1,18 @@
+use quicksilver::{geom::Vector, graphics::Color};
+use rogue_like::{
+    fixture,
+    replay::Command,
+    world::{Entity, Event},
+};
+
+#[test]
+fn picking_an_item_up_is_heard() {
+    let mut world = fixture::world("#@.#");
+    world.entities.push(Entity {
+        item: Some("ration".to_string()),
+        ..Entity::new(Vector::new(1, 0), '%', Color::WHITE, 0)
+    });
+    fixture::play(&mut world, &[Command::PickUp]);
+    assert_eq!(world.player().inventory, ["ration"]);
+    assert!(world.events.contains(&Event::PickedUp { entity: world.player_id }));
+}
@@ -0,0 +1,88 @@
+use quicksilver::{geom::Vector, graphics::Color};
+use rogue_like::{
+    data,
+    dungeon::{self, Arrival, Section},
+    fixture,
+    replay::Command,
+    rng::Rng,
+    world::{Entity, Event},
+};
+use std::collections::BTreeSet;
+
+const MAP: &str = "
+    ##########
+    #@Ω#...Ω.#
+    #..#.....#
+    ##########
+";
+
+#[test]
+fn stepping_into_a_portal_comes_out_of_the_other_end() {
+    let mut world = fixture::world(MAP);
+    assert_eq!(world.portals.len(), 2);
+    fixture::play(&mut world, &[Command::MovePlayer(Vector::new(1, 0))]);
+    assert_eq!(world.player().pos, Vector::new(7, 1));
+    assert!(world.events.contains(&Event::Warped { entity: world.player_id }));
+
+    // Standing on the far end doesn't send the player straight back
+    world.events.clear();
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert_eq!(world.player().pos, Vector::new(7, 1));
+    fixture::play(&mut world, &[Command::MovePlayer(Vector::new(1, 0)), Command::MovePlayer(Vector::new(-1, 0))]);
+    assert_eq!(world.player().pos, Vector::new(2, 1));
+}
+
+#[test]
+fn a_portal_does_nothing_while_its_other_end_is_taken() {
+    let mut world = fixture::world(
+        "
+        ##########
+        #@Ω#...Ωg#
+        ##########
+        ",
+    );
+    world.entities[1].pos = Vector::new(7, 1);
+    fixture::play(&mut world, &[Command::MovePlayer(Vector::new(1, 0))]);
+    assert_eq!(world.player().pos, Vector::new(2, 1));
+    assert!(world.events.iter().all(|event| !matches!(event, Event::Warped { .. })));
+}
+
+#[test]
+fn monsters_take_a_portal_when_it_is_the_quicker_way() {
+    let mut world = fixture::world(
+        "
+        #########
+        #gΩ.....#
+        #.≋≋≋≋≋≋#
+        #..Ω...@#
+        #########
+        ",
+    );
+    let path = world.monster_path(1).expect("the player is in sight");
+    assert_eq!(path, [Vector::new(2, 1)]);
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert_eq!(world.entities[1].pos, Vector::new(3, 3));
+    assert!(world.events.contains(&Event::Warped { entity: 1 }));
+}
+
+#[test]
+fn some_levels_get_a_pair_of_portals_well_apart() {
+    let content = data::Content::load(None, false);
+    let levels = (0..20).map(|seed| {
+        let player = Entity::new(Vector::new(0, 0), '@', Color::WHITE, 5);
+        dungeon::generate(2, Section::Main, player, Rng::new(seed), &BTreeSet::new(), Arrival::Descending, &content)
+    });
+    let mut with_portals = 0;
+    for level in levels {
+        if level.portals.is_empty() {
+            continue;
+        }
+        with_portals += 1;
+        assert_eq!(level.portals.len(), 2);
+        let (a, b) = (&level.portals[0], &level.portals[1]);
+        assert_eq!((a.exit, b.exit), (b.pos, a.pos));
+        assert!(level.portal_at(a.pos).is_some() && level.portal_at(b.pos).is_some());
+        assert!(a.pos.distance(b.pos) >= 12.0);
+    }
+    assert!(with_portals > 0);
+}
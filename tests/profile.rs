@@ -0,0 +1,52 @@
+use rogue_like::{
+    classes, data, fixture,
+    profile::{self, Profile, Unlock},
+};
+use std::collections::BTreeSet;
+
+#[test]
+fn getting_deep_enough_unlocks_a_class() {
+    let content = data::Content::load(None, false);
+    let mut profile = Profile::default();
+    let paladin = |profile: &Profile| {
+        let available = classes::available(&content.classes, &profile.unlocks);
+        available.iter().any(|class| class.name == "paladin")
+    };
+    assert!(!paladin(&profile));
+
+    let mut run = fixture::world("#@#");
+    run.depth = 5;
+    assert_eq!(profile.record_run_end(&run), [Unlock::Paladin]);
+    assert_eq!(profile.deepest, 5);
+    assert!(profile.unlocks.contains(&Unlock::Paladin));
+    assert!(paladin(&profile));
+    // It's only told the first time
+    assert!(profile.record_run_end(&run).is_empty());
+    // A class isn't a boon to pick at the start of a run
+    assert!(profile.boons().is_empty());
+}
+
+#[test]
+fn wizard_runs_earn_nothing() {
+    let mut profile = Profile::default();
+    let mut run = fixture::world("#@#");
+    run.won = true;
+    run.depth = 9;
+    run.wizard = true;
+    assert!(profile.record_run_end(&run).is_empty());
+    assert_eq!((profile.wins, profile.deepest), (0, 0));
+    assert!(profile.unlocks.is_empty());
+}
+
+#[test]
+fn runs_start_with_only_the_boons_picked() {
+    let mut run = fixture::world("#@.#");
+    let gold = run.player().gold;
+    let picked = BTreeSet::from([Unlock::Wealthy, Unlock::Heirloom]);
+    profile::apply(&picked, &mut run);
+    let player = run.player();
+    assert_eq!(player.gold, gold + 100);
+    assert_eq!(player.max_hp, fixture::PLAYER_HP);
+    assert_eq!(player.inventory, ["potion of healing"]);
+    assert_eq!(run.unlocks, picked);
+}
@@ -0,0 +1,88 @@
+use rogue_like::{
+    fixture::{self, EAST},
+    monsters::Ai,
+    quests::{Goal, QuestDef, Reward},
+    replay::Command,
+    world::World,
+};
+
+/// A corridor with a friendly quest giver behind the player and a goblin ahead.
+fn giver() -> World {
+    let mut world = fixture::world("#G@g.#");
+    world.entities[1].ai = Ai::Friendly;
+    world.entities[2].kind = Some("goblin".to_string());
+    world
+}
+
+fn quest(goal: Goal) -> QuestDef {
+    QuestDef {
+        name: "test quest".to_string(),
+        goal,
+        rewards: vec![Reward::Gold(25), Reward::Item("ration".to_string()), Reward::Xp(1)],
+        after: Some("thanks".to_string()),
+    }
+}
+
+#[test]
+fn killing_the_monsters_asked_for_completes_a_quest() {
+    let mut world = giver();
+    let kill = quest(Goal::Kill {
+        monster: "goblin".to_string(),
+        count: 1,
+    });
+    fixture::play(&mut world, &[Command::StartQuest(1, Box::new(kill), None)]);
+    assert!(world.quests_to_reward(1).is_empty());
+    for _ in 0..fixture::MONSTER_HP {
+        fixture::play(&mut world, &[Command::MovePlayer(EAST)]);
+    }
+    assert_eq!(world.quests[0].kills, 1);
+    assert_eq!(world.quests_to_reward(1), [0]);
+}
+
+#[test]
+fn other_kills_do_not_count() {
+    let mut world = giver();
+    world.entities[2].kind = Some("orc".to_string());
+    let kill = quest(Goal::Kill {
+        monster: "goblin".to_string(),
+        count: 1,
+    });
+    fixture::play(&mut world, &[Command::StartQuest(1, Box::new(kill), None)]);
+    for _ in 0..fixture::MONSTER_HP {
+        fixture::play(&mut world, &[Command::MovePlayer(EAST)]);
+    }
+    assert!(!world.entities[2].is_alive());
+    assert_eq!(world.quests[0].kills, 0);
+}
+
+#[test]
+fn fetched_items_are_handed_over_for_the_rewards() {
+    let mut world = giver();
+    let fetch = quest(Goal::Fetch {
+        item: "dagger".to_string(),
+        depth: 1,
+    });
+    fixture::play(&mut world, &[Command::StartQuest(1, Box::new(fetch), None)]);
+    world.entities[world.player_id].inventory.push("dagger".to_string());
+    assert_eq!(world.quests_to_reward(1), [0]);
+
+    let xp = world.player().xp;
+    fixture::play(&mut world, &[Command::RewardQuest(0)]);
+    assert_eq!(world.player().inventory, ["ration"]);
+    assert_eq!(world.player().gold, 25);
+    assert_eq!(world.player().xp, xp + 1);
+    assert_eq!(world.dialogue_starts.get(&1).map(String::as_str), Some("thanks"));
+    assert!(world.quests_to_reward(1).is_empty());
+}
+
+#[test]
+fn a_quest_is_only_taken_once() {
+    let mut world = giver();
+    let fetch = quest(Goal::Fetch {
+        item: "dagger".to_string(),
+        depth: 1,
+    });
+    let start = Command::StartQuest(1, Box::new(fetch), None);
+    fixture::play(&mut world, &[start.clone(), start]);
+    assert_eq!(world.quests.len(), 1);
+}
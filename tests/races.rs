@@ -0,0 +1,75 @@
+use quicksilver::geom::Vector;
+use rogue_like::{
+    fixture,
+    monsters::{Ability, VENOM_TURNS},
+    races::Trait,
+    replay::Command,
+    spells::{DamageType, Status},
+};
+
+/// A corridor with the player at one end and a venomous chasing monster next to them.
+const SIDE_BY_SIDE: &str = "
+    #####
+    #@g.#
+    #####
+";
+
+#[test]
+fn venomous_hits_poison_the_player() {
+    let mut world = fixture::world(SIDE_BY_SIDE);
+    world.entities[1].abilities.push(Ability::Venomous);
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    // One for the bite and one for the poison taking hold
+    assert_eq!(world.player().hp, fixture::PLAYER_HP - 2);
+    assert!(world.player().has_status(Status::Poisoned));
+}
+
+#[test]
+fn poison_wears_off() {
+    let mut world = fixture::world(SIDE_BY_SIDE);
+    world.entities[1].abilities.push(Ability::Venomous);
+    world.entities[world.player_id].hp = 100;
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    world.entities[1].hp = 0;
+    for _ in 0..VENOM_TURNS {
+        fixture::play(&mut world, &[Command::MonsterTurns]);
+    }
+    assert!(!world.player().has_status(Status::Poisoned));
+}
+
+#[test]
+fn resisting_poison_stops_venom() {
+    let mut world = fixture::world(SIDE_BY_SIDE);
+    world.entities[1].abilities.push(Ability::Venomous);
+    world.entities[world.player_id].resists.push(DamageType::Poison);
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert_eq!(world.player().hp, fixture::PLAYER_HP - 1);
+    assert!(world.player().statuses.is_empty());
+}
+
+#[test]
+fn quick_players_outpace_the_monsters() {
+    let mut world = fixture::world(SIDE_BY_SIDE);
+    world.entities[world.player_id].traits.push(Trait::Quick);
+    world.entities[world.player_id].hp = 100;
+    for _ in 0..5 {
+        fixture::play(&mut world, &[Command::MonsterTurns]);
+    }
+    assert_eq!(world.player().hp, 100 - 4);
+}
+
+#[test]
+fn infravision_senses_creatures_through_walls() {
+    let mut world = fixture::world(
+        "
+        #######
+        #@#.g.#
+        #######
+        ",
+    );
+    let monster = Vector::new(4, 1);
+    assert!(!world.is_visible(monster));
+    assert!(!world.is_sensed(monster));
+    world.entities[world.player_id].traits.push(Trait::Infravision);
+    assert!(world.is_sensed(monster));
+}
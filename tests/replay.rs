@@ -0,0 +1,25 @@
+use quicksilver::geom::Vector;
+use rogue_like::{fixture, replay::Command};
+
+const MAP: &str = "
+    ##########
+    #@.......#
+    #....#...#
+    #....#.gG#
+    ##########
+";
+
+#[test]
+fn redoing_the_log_on_a_fresh_world_gives_the_same_world() {
+    let mut played = fixture::world(MAP);
+    let moves = [Vector::new(1, 0), Vector::new(1, 1), Vector::new(1, 0), Vector::new(0, 1)];
+    for step in moves {
+        fixture::play(&mut played, &[Command::MovePlayer(step), Command::MonsterTurns]);
+    }
+
+    let mut replayed = fixture::world(MAP);
+    fixture::play(&mut replayed, &played.log);
+    assert_eq!(replayed.entities, played.entities);
+    assert_eq!(replayed.map, played.map);
+    assert_eq!(replayed.explored, played.explored);
+}
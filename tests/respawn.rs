@@ -0,0 +1,92 @@
+use rogue_like::{
+    data::{self, Content},
+    dungeon::{self, Arrival},
+    fixture,
+    replay::Command,
+    spawn,
+    world::{Biome, World},
+};
+
+const MAP: &str = "
+    ######################
+    #....#...............#
+    #.@..#...............#
+    #....#...............#
+    ######################
+";
+
+fn respawn_every(turns: usize) -> (World, Content) {
+    let mut content = data::Content::load(None, false);
+    content.loot.respawns.insert(Biome::Surface, turns);
+    let mut world = fixture::world(MAP);
+    world.update_fov();
+    (world, content)
+}
+
+#[test]
+fn monsters_come_back_out_of_sight_at_their_biomes_pace() {
+    let (mut world, content) = respawn_every(50);
+    world.turns = 49;
+    assert!(spawn::respawn(&world, world.turn(), &content).is_none());
+    world.turns = 50;
+    let (monster, rng) = spawn::respawn(&world, world.turn(), &content).expect("a monster comes back every 50 turns");
+    assert!(!world.is_visible(monster.pos));
+    assert!(monster.loot.is_none());
+    world.respawn(&monster, &rng);
+    assert_eq!(world.entities.last().map(|entity| entity.pos), Some(monster.pos));
+    assert!(matches!(world.log.last(), Some(Command::Respawn(..))));
+}
+
+#[test]
+fn a_level_never_gets_more_monsters_than_it_started_with() {
+    let (mut world, content) = respawn_every(10);
+    world.depth = 1;
+    for turn in 1..=20 {
+        world.turns = turn * 10;
+        if let Some((monster, rng)) = spawn::respawn(&world, world.turn(), &content) {
+            world.respawn(&monster, &rng);
+        }
+    }
+    assert_eq!(world.entities.iter().filter(|entity| entity.is_alive()).count(), 1 + 3);
+}
+
+#[test]
+fn monsters_never_come_back_to_a_biome_left_out() {
+    let (mut world, mut content) = respawn_every(10);
+    content.loot.respawns.remove(&Biome::Surface);
+    world.turns = 1000;
+    assert!(spawn::respawn(&world, world.turn(), &content).is_none());
+}
+
+#[test]
+fn levels_left_behind_fill_up_again_too() {
+    let (mut world, content) = respawn_every(10);
+    let mut level = fixture::world("#<@#");
+    level.depth = 2;
+    world.change_level(&level);
+    let kept = world.visited[0].entities.len();
+    world.turns = 10;
+    spawn::repopulate(&mut world, &content);
+    assert_eq!(world.visited[0].entities.len(), kept + 1);
+    assert!(matches!(world.log.last(), Some(Command::RespawnKept(0, ..))));
+    assert_eq!(world.entities.len(), level.entities.len());
+}
+
+#[test]
+fn a_level_come_back_to_gets_one_monster_per_interval() {
+    let (mut world, content) = respawn_every(10);
+    let mut level = fixture::world("#<@#");
+    level.depth = 2;
+    world.change_level(&level);
+    let kept = world.visited_level(1, None).unwrap();
+    let back = dungeon::revisit(kept, world.player().clone(), world.rng.clone(), &world.artifacts, Arrival::Climbing);
+    world.change_level(&back);
+    assert!(world.visited_level(1, None).is_none());
+    let monsters = world.entities.len();
+    world.log.clear();
+    world.turns = 10;
+    spawn::repopulate(&mut world, &content);
+    assert_eq!(world.entities.len(), monsters + 1);
+    let respawned = |command: &&Command| matches!(command, Command::Respawn(..) | Command::RespawnKept(..));
+    assert_eq!(world.log.iter().filter(respawned).count(), 1);
+}
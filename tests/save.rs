@@ -0,0 +1,83 @@
+use quicksilver::geom::Vector;
+use rogue_like::{
+    fixture::{self, EAST},
+    hunger, light,
+    replay::Command,
+    save::{self, SaveError},
+};
+use serde_json::{json, Value};
+
+fn color() -> Value {
+    json!({ "r": 1.0, "g": 1.0, "b": 1.0, "a": 1.0 })
+}
+
+/// A save as the first version of the format wrote it, before a save held the
+/// whole run: just the map and the player and monsters on it. The map is
+/// `#####`, `#@.g#` and `#####`, column by column.
+fn first_version_save() -> Vec<u8> {
+    let rows = ["#####", "#@.g#", "#####"];
+    let mut map = Vec::new();
+    for x in 0..5 {
+        for (y, row) in rows.iter().enumerate() {
+            let glyph = if row.as_bytes()[x] == b'#' { "#" } else { "." };
+            map.push(json!({ "pos": { "x": x, "y": y }, "glyph": glyph, "color": color() }));
+        }
+    }
+    let entity = |x, glyph, hp| {
+        json!({ "pos": { "x": x, "y": 1 }, "glyph": glyph, "color": color(), "hp": hp, "max_hp": 5 })
+    };
+    let file = json!({
+        "version": 1,
+        "data": {
+            "map_size": { "x": 5, "y": 3 },
+            "map": map,
+            "entities": [entity(3, "g", 2), entity(1, "@", 3)],
+            "player_id": 1,
+        },
+    });
+    serde_json::to_vec(&file).unwrap()
+}
+
+#[test]
+fn a_first_version_save_still_loads_and_plays() {
+    let mut world = save::from_bytes(&first_version_save()).unwrap();
+    assert_eq!(world.player().pos, Vector::new(1, 1));
+    assert_eq!((world.player().hp, world.player().max_hp), (3, 5));
+    assert_eq!(world.entities[0].hp, 2);
+    assert_eq!(world.depth, 1);
+    assert_eq!(world.torch, light::TORCH_TURNS);
+    assert_eq!(world.satiety, hunger::FULL_TURNS);
+    assert_eq!(world.explored.len(), world.map.len());
+    assert!(world.is_visible(Vector::new(3, 1)));
+
+    fixture::play(&mut world, &[Command::MovePlayer(EAST), Command::MonsterTurns]);
+    assert_eq!(world.player().pos, Vector::new(2, 1));
+}
+
+#[test]
+fn a_saved_run_loads_back_the_same() {
+    let mut world = fixture::world(
+        "
+        #####
+        #@.g#
+        #####
+        ",
+    );
+    fixture::play(&mut world, &[Command::MovePlayer(EAST), Command::MonsterTurns]);
+    let bytes = save::to_bytes(&world).unwrap();
+    let loaded = save::from_bytes(&bytes).unwrap();
+    assert_eq!(save::to_bytes(&loaded).unwrap(), bytes);
+    assert_eq!(loaded.visible, world.visible);
+}
+
+#[test]
+fn saves_from_unknown_versions_are_refused() {
+    for version in [0, save::SAVE_VERSION + 1] {
+        let file = serde_json::to_vec(&json!({ "version": version, "data": {} })).unwrap();
+        let found = match save::from_bytes(&file) {
+            Err(SaveError::Incompatible { found }) => found,
+            other => panic!("expected an incompatible save, got {:?}", other),
+        };
+        assert_eq!(found, version);
+    }
+}
@@ -0,0 +1,98 @@
+use quicksilver::{geom::Vector, graphics::Color};
+use rogue_like::{
+    attributes::Attributes,
+    fixture::{self, EAST, WEST},
+    items,
+    monsters::Ai,
+    replay::Command,
+    shops::{self, Shop, Trade},
+    world::{Entity, Event, World},
+};
+
+/// A corridor with a shopkeeper at the back of a three tile shop, the player
+/// inside it standing on a dagger for sale, and a way out to the east.
+fn shop() -> World {
+    let mut world = fixture::world(
+        "
+        #######
+        #K@...#
+        #######
+        ",
+    );
+    world.entities[1].ai = Ai::Shopkeeper;
+    world.shops.push(Shop::new(1, Vector::new(1, 1), Vector::new(3, 1)));
+    world.entities.push(Entity {
+        item: Some("dagger".to_string()),
+        ..Entity::new(Vector::new(2, 1), ')', Color::WHITE, 0)
+    });
+    world.entities[world.player_id].gold = 30;
+    world
+}
+
+#[test]
+fn paid_for_items_can_be_taken_away() {
+    let mut world = shop();
+    fixture::play(&mut world, &[Command::PickUp, Command::Trade(0, Trade::Buy { item: 0, price: 10 })]);
+    assert_eq!(world.player().gold, 20);
+    assert_eq!(world.player().inventory, ["dagger"]);
+    fixture::play(&mut world, &[Command::MovePlayer(EAST), Command::MovePlayer(EAST)]);
+    assert_eq!(world.entities[1].ai, Ai::Shopkeeper);
+    assert!(!world.events.iter().any(|event| matches!(event, Event::Robbed { .. })));
+}
+
+#[test]
+fn leaving_without_paying_turns_the_keeper_on_the_thief() {
+    let mut world = shop();
+    fixture::play(&mut world, &[Command::PickUp, Command::MovePlayer(EAST)]);
+    assert_eq!(world.entities[1].ai, Ai::Shopkeeper);
+    fixture::play(&mut world, &[Command::MovePlayer(EAST)]);
+    assert_eq!(world.entities[1].ai, Ai::Chase);
+    assert!(world.events.contains(&Event::Robbed { keeper: 1 }));
+    assert_eq!(world.player().inventory, ["dagger"]);
+    assert_eq!(world.player().gold, 30);
+}
+
+#[test]
+fn unaffordable_items_can_be_put_back() {
+    let mut world = shop();
+    world.entities[world.player_id].gold = 5;
+    fixture::play(&mut world, &[Command::PickUp]);
+    assert!(!world.trade(0, Trade::Buy { item: 0, price: 10 }));
+    assert_eq!(world.shops[0].unpaid.len(), 1);
+    assert!(world.trade(0, Trade::Return { item: 0 }));
+    assert!(world.player().inventory.is_empty());
+    assert!(world.item_at(world.player().pos).is_some());
+    fixture::play(&mut world, &[Command::MovePlayer(EAST), Command::MovePlayer(EAST)]);
+    assert_eq!(world.entities[1].ai, Ai::Shopkeeper);
+}
+
+#[test]
+fn walking_into_a_shopkeeper_does_not_attack() {
+    let mut world = shop();
+    assert!(!world.move_player(WEST));
+    assert_eq!(world.entities[1].hp, fixture::MONSTER_HP);
+}
+
+#[test]
+fn only_items_already_paid_for_can_be_sold() {
+    let mut world = shop();
+    let items = items::load(&mut Vec::new());
+    world.entities[world.player_id].inventory.push("dagger".to_string());
+    fixture::play(&mut world, &[Command::PickUp]);
+    let trades = shops::trades(&world, 0, &items);
+    let sells = trades.iter().filter(|trade| matches!(trade, Trade::Sell { .. })).count();
+    assert_eq!(sells, 1);
+    assert_eq!(trades[0], Trade::Buy { item: 0, price: 10 });
+}
+
+#[test]
+fn charisma_gets_better_prices() {
+    let mut attributes = Attributes::default();
+    assert_eq!(shops::buy_price(100, &attributes), 100);
+    assert_eq!(shops::sell_price(100, &attributes), 50);
+    attributes.charisma = 5;
+    assert_eq!(shops::buy_price(100, &attributes), 90);
+    assert_eq!(shops::sell_price(100, &attributes), 55);
+    attributes.charisma = 50;
+    assert!(shops::sell_price(100, &attributes) <= shops::buy_price(100, &attributes));
+}
@@ -0,0 +1,95 @@
+use quicksilver::{geom::Vector, graphics::Color};
+use rogue_like::{
+    data,
+    dungeon::{self, Arrival, Section},
+    fixture,
+    items::ItemEffect,
+    replay::Command,
+    rng::Rng,
+    shrines::{self, Outcome, Shrine, ShrineDef, ShrineKind},
+    world::{Entity, Event, World},
+};
+use std::collections::BTreeSet;
+
+/// A fountain east of the player, whose every visit brings about `outcome`.
+fn beside(outcome: Outcome, uses: u32) -> World {
+    let mut world = fixture::world("#@.#");
+    let def = ShrineDef {
+        name: "fountain".to_string(),
+        kind: ShrineKind::Fountain,
+        glyph: '{',
+        color: Color::BLUE,
+        uses,
+        outcomes: vec![outcome],
+    };
+    let pos = Vector::new(2, 0);
+    if let Some(tile) = world.tile_at_mut(pos) {
+        tile.glyph = def.glyph;
+    }
+    world.shrines.push(Shrine { pos, def, uses });
+    world
+}
+
+fn outcome(effects: Vec<ItemEffect>, damage: i32) -> Outcome {
+    Outcome { name: "test".to_string(), weight: 1, effects, damage }
+}
+
+#[test]
+fn a_fountain_gives_its_outcome_until_it_runs_dry() {
+    let mut world = beside(outcome(vec![ItemEffect::Heal(2)], 0), 2);
+    world.entities[world.player_id].hp = 1;
+    assert!(!world.is_walkable(Vector::new(2, 0)));
+    for _ in 0..2 {
+        fixture::play(&mut world, &[Command::MovePlayer(Vector::new(1, 0))]);
+    }
+    assert_eq!(world.player().hp, fixture::PLAYER_HP);
+    assert_eq!(world.player().pos, Vector::new(1, 0));
+    assert_eq!(world.shrines[0].uses, 0);
+    let visited = Event::Visited { entity: world.player_id, kind: ShrineKind::Fountain, outcome: "test".to_string() };
+    assert!(world.events.contains(&visited));
+
+    world.events.clear();
+    assert!(!world.move_player(Vector::new(1, 0)));
+    assert!(world.events.contains(&Event::Spent { entity: world.player_id, kind: ShrineKind::Fountain }));
+}
+
+#[test]
+fn a_visit_can_go_badly() {
+    let mut world = beside(outcome(Vec::new(), 2), 1);
+    fixture::play(&mut world, &[Command::MovePlayer(Vector::new(1, 0))]);
+    assert_eq!(world.player().hp, fixture::PLAYER_HP - 2);
+}
+
+#[test]
+fn outcomes_come_up_by_weight() {
+    let content = data::Content::load(None, false);
+    let def = shrines::find(&content.shrines, "fountain").expect("there's a fountain");
+    let mut weightless = def.clone();
+    for outcome in &mut weightless.outcomes {
+        outcome.weight = 0;
+    }
+    weightless.outcomes[0].weight = 1;
+    let mut rng = Rng::new(3);
+    for _ in 0..20 {
+        assert_eq!(weightless.roll(&mut rng), weightless.outcomes.first());
+    }
+    let rolled: BTreeSet<_> = (0..200).filter_map(|_| def.roll(&mut rng)).map(|outcome| &outcome.name).collect();
+    assert_eq!(rolled.len(), def.outcomes.len());
+}
+
+#[test]
+fn some_levels_get_a_fountain_or_shrine() {
+    let content = data::Content::load(None, false);
+    let mut placed = 0;
+    for seed in 0..20 {
+        let player = Entity::new(Vector::new(0, 0), '@', Color::WHITE, 5);
+        let rng = Rng::new(seed);
+        let level = dungeon::generate(2, Section::Main, player, rng, &BTreeSet::new(), Arrival::Descending, &content);
+        for shrine in &level.shrines {
+            placed += 1;
+            assert_eq!(level.shrine_at(shrine.pos), Some(0));
+            assert_eq!(shrine.uses, shrine.def.uses);
+        }
+    }
+    assert!(placed > 0);
+}
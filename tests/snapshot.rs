@@ -0,0 +1,43 @@
+use rogue_like::{
+    fixture,
+    replay::Command,
+    snapshot::{self, History, HISTORY_TURNS},
+};
+
+const SIDE_BY_SIDE: &str = "
+    #####
+    #@g.#
+    #####
+";
+
+#[test]
+fn the_diff_shows_what_a_turn_changed() {
+    let mut world = fixture::world(SIDE_BY_SIDE);
+    let mut history = History::default();
+    history.record(&world);
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    history.record(&world);
+
+    let (before, after) = history.pair(0).expect("two turns were recorded");
+    let changes = snapshot::diff(before, after);
+    let hp = format!("entities[0].hp: {} -> {}", fixture::PLAYER_HP, fixture::PLAYER_HP - 1);
+    assert!(changes.contains(&hp), "{:?}", changes);
+    assert!(changes.contains(&"log[0]: added \"MonsterTurns\"".to_string()), "{:?}", changes);
+    assert!(changes.iter().all(|change| !change.starts_with("entities[1]")), "{:?}", changes);
+}
+
+#[test]
+fn the_history_keeps_one_snapshot_a_turn_and_forgets_the_oldest() {
+    let mut world = fixture::world(SIDE_BY_SIDE);
+    world.invincible = true;
+    let mut history = History::default();
+    for _ in 0..HISTORY_TURNS + 5 {
+        history.record(&world);
+        history.record(&world);
+        fixture::play(&mut world, &[Command::MonsterTurns]);
+    }
+    assert_eq!(history.len(), HISTORY_TURNS);
+    let (before, after) = history.pair(HISTORY_TURNS - 2).expect("the oldest pair is there");
+    assert_eq!((before.turn, after.turn), (5, 6));
+    assert!(history.pair(HISTORY_TURNS - 1).is_none());
+}
@@ -0,0 +1,60 @@
+use quicksilver::{geom::Vector, graphics::Color};
+use rogue_like::{
+    data::{self, Content},
+    dungeon::{self, Arrival, Section},
+    rng::Rng,
+    specials,
+    world::{Entity, World},
+};
+use std::collections::BTreeSet;
+
+fn generate(depth: u32, section: Section, content: &Content) -> World {
+    let player = Entity::new(Vector::new(0, 0), '@', Color::WHITE, 5);
+    dungeon::generate(depth, section, player, Rng::new(7), &BTreeSet::new(), Arrival::Descending, content)
+}
+
+#[test]
+fn every_run_plans_each_special_level_at_its_own_depth() {
+    let content = data::Content::load(None, false);
+    for seed in 0..20 {
+        let planned = specials::plan(&mut Rng::new(seed), &content.specials);
+        assert_eq!(planned.len(), content.specials.len());
+        for (&depth, name) in &planned {
+            let special = specials::find(&content.specials, name).unwrap();
+            assert!((special.min_depth..=special.max_depth).contains(&depth));
+            assert!(depth > 1 && depth < dungeon::DEEPEST_DEPTH);
+        }
+    }
+}
+
+#[test]
+fn special_levels_are_built_around_their_prefab() {
+    let content = data::Content::load(None, false);
+    let arena = specials::find(&content.specials, "arena").unwrap();
+    let level = generate(4, Section::Special(arena), &content);
+    assert_eq!(level.special.as_deref(), Some("arena"));
+    let fighters = level.entities.iter().filter(|entity| entity.is_alive()).count() - 1;
+    assert_eq!(fighters, 7);
+}
+
+#[test]
+fn a_flooded_level_is_mostly_water() {
+    let content = data::Content::load(None, false);
+    let flooded = specials::find(&content.specials, "flooded level").unwrap();
+    let level = generate(4, Section::Special(flooded), &content);
+    let water = level.map.iter().filter(|tile| tile.glyph == '~').count();
+    let floor = level.map.iter().filter(|tile| tile.glyph == '.').count();
+    assert!(water > floor);
+    assert!(level.schedule.iter().any(|scheduled| scheduled.happening == "rising flood"));
+}
+
+#[test]
+fn special_prefabs_are_kept_for_their_levels() {
+    let mut content = data::Content::load(None, false);
+    content.prefabs.retain(|prefab| content.specials.iter().any(|special| special.uses(prefab)));
+    let reserved = generate(4, Section::Main, &content);
+    content.prefabs.clear();
+    let plain = generate(4, Section::Main, &content);
+    assert!(reserved.map.iter().zip(&plain.map).all(|(a, b)| a.glyph == b.glyph));
+    assert_eq!(reserved.entities.len(), plain.entities.len());
+}
@@ -0,0 +1,83 @@
+use rogue_like::{
+    fixture,
+    monsters::{Ability, CHILL_TURNS},
+    replay::Command,
+    spells::{Status, StatusEffect},
+    world::NORMAL_SPEED,
+};
+
+/// A corridor with the player at one end and a chasing monster next to them.
+const SIDE_BY_SIDE: &str = "
+    #####
+    #@g.#
+    #####
+";
+
+/// How many times the monster next to the player attacks over `turns` of the
+/// player's turns, when it moves at `speed`.
+fn attacks(speed: u32, turns: usize) -> i32 {
+    let mut world = fixture::world(SIDE_BY_SIDE);
+    world.entities[1].speed = speed;
+    world.entities[world.player_id].hp = 100;
+    for _ in 0..turns {
+        fixture::play(&mut world, &[Command::MonsterTurns]);
+    }
+    100 - world.player().hp
+}
+
+#[test]
+fn monsters_as_fast_as_the_player_take_turns_with_them() {
+    assert_eq!(attacks(NORMAL_SPEED, 4), 4);
+}
+
+#[test]
+fn fast_monsters_act_more_often() {
+    assert_eq!(attacks(NORMAL_SPEED * 2, 4), 8);
+    assert_eq!(attacks(NORMAL_SPEED * 3 / 2, 4), 6);
+}
+
+#[test]
+fn slow_monsters_act_less_often() {
+    assert_eq!(attacks(NORMAL_SPEED / 2, 4), 2);
+}
+
+#[test]
+fn a_fast_player_gets_more_done_between_monster_turns() {
+    let mut world = fixture::world(SIDE_BY_SIDE);
+    world.entities[world.player_id].speed = NORMAL_SPEED * 2;
+    world.entities[world.player_id].hp = 100;
+    for _ in 0..4 {
+        fixture::play(&mut world, &[Command::MonsterTurns]);
+    }
+    assert_eq!(world.player().hp, 98);
+}
+
+#[test]
+fn haste_doubles_speed_and_slow_halves_it() {
+    let mut world = fixture::world(SIDE_BY_SIDE);
+    let monster = &mut world.entities[1];
+    monster.statuses.push(StatusEffect {
+        status: Status::Hasted,
+        turns: 10,
+    });
+    assert_eq!(monster.energy_per_tick(), NORMAL_SPEED * 2);
+    monster.statuses[0].status = Status::Slowed;
+    assert_eq!(monster.energy_per_tick(), NORMAL_SPEED / 2);
+}
+
+#[test]
+fn slowing_hits_slow_the_player_down() {
+    let mut world = fixture::world(SIDE_BY_SIDE);
+    world.entities[1].abilities.push(Ability::Slowing);
+    world.entities[world.player_id].hp = 100;
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert!(world.player().has_status(Status::Slowed));
+    // Slowed, the player gets half as much done, so the monster gets twice the turns
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert_eq!(world.player().hp, 97);
+    world.entities[1].hp = 0;
+    for _ in 0..CHILL_TURNS {
+        fixture::play(&mut world, &[Command::MonsterTurns]);
+    }
+    assert!(!world.player().has_status(Status::Slowed));
+}
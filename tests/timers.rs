@@ -0,0 +1,72 @@
+use quicksilver::{geom::Vector, graphics::Color};
+use rogue_like::{
+    fixture,
+    items::ItemEffect,
+    replay::Command,
+    timers::Timed,
+    world::{Entity, Event},
+};
+
+#[test]
+fn a_lasting_boost_wears_off_after_its_turns() {
+    let mut world = fixture::world("#@.#");
+    world.entities[world.player_id].inventory.push("draught of might".to_string());
+    let attack = world.player().attack;
+    let might = ItemEffect::Lasting {
+        effect: Timed::Attack(2),
+        turns: 3,
+    };
+    fixture::play(&mut world, &[Command::UseItem(0, vec![might])]);
+    assert_eq!(world.player().attack, attack + 2);
+    fixture::play(&mut world, &[Command::MonsterTurns, Command::MonsterTurns]);
+    assert_eq!(world.player().attack, attack + 2);
+    world.events.clear();
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert_eq!(world.player().attack, attack);
+    assert!(world.timers.is_empty());
+    let wore_off = Event::WoreOff {
+        entity: world.player_id,
+        effect: Timed::Attack(2),
+    };
+    assert!(world.events.contains(&wore_off));
+}
+
+#[test]
+fn summoned_creatures_vanish_when_their_time_is_up() {
+    let mut world = fixture::world("#@..#");
+    let summoned = Entity::new(Vector::new(3, 0), 'z', Color::WHITE, 3);
+    let id = world.summon(summoned, 2);
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert!(world.entities[id].is_alive());
+    fixture::play(&mut world, &[Command::MonsterTurns]);
+    assert!(!world.entities[id].is_alive());
+    assert!(world.creature_at(Vector::new(3, 0)).is_none());
+}
+
+#[test]
+fn the_players_timers_go_with_them_to_the_next_level() {
+    let mut world = fixture::world("#@.#");
+    world.start_timer(world.player_id, Timed::Light(6), 10);
+    world.start_timer(world.player_id, Timed::Defense(1), 10);
+    world.summon(Entity::new(Vector::new(2, 0), 'z', Color::WHITE, 3), 10);
+    let mut level = fixture::world("#g@#");
+    level.player_id = 1;
+    level.entities.swap(0, 1);
+    world.change_level(&level);
+    assert_eq!(world.timers.len(), 2);
+    assert!(world.timers.iter().all(|timer| timer.entity == 1));
+    assert_eq!(world.player().defense, 1);
+}
+
+#[test]
+fn timers_on_a_level_come_back_with_it() {
+    let mut world = fixture::world("#@.#");
+    let id = world.summon(Entity::new(Vector::new(2, 0), 'z', Color::WHITE, 3), 2);
+    let level = world.clone();
+    world.change_level(&fixture::world("#@#"));
+    assert!(world.timers.is_empty());
+    world.change_level(&level);
+    assert_eq!(world.timers.len(), 1);
+    fixture::play(&mut world, &[Command::MonsterTurns, Command::MonsterTurns]);
+    assert!(!world.entities[id].is_alive());
+}
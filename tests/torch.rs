@@ -0,0 +1,57 @@
+use quicksilver::geom::Vector;
+use rogue_like::{
+    fixture, fov,
+    items::ItemEffect,
+    light,
+    replay::Command,
+    timers::Timed,
+    world::{Event, World},
+};
+
+const CORRIDOR: &str = "#@...........#";
+
+fn burn_down(world: &mut World, turns: u32) {
+    world.torch = turns;
+    world.events.clear();
+    fixture::play(world, &[Command::MonsterTurns]);
+}
+
+#[test]
+fn the_torch_warns_when_low_and_the_dark_closes_in_when_it_goes_out() {
+    let mut world = fixture::world(CORRIDOR);
+    let far = Vector::new(1 + fov::FOV_RADIUS, 0);
+    assert!(world.is_visible(far));
+
+    burn_down(&mut world, light::TORCH_LOW_TURNS + 1);
+    assert!(world.events.contains(&Event::TorchLow { entity: world.player_id }));
+
+    burn_down(&mut world, 1);
+    assert_eq!(world.torch, 0);
+    assert!(world.events.contains(&Event::TorchOut { entity: world.player_id }));
+    assert!(!world.is_visible(far));
+    assert!(world.is_visible(Vector::new(1 + light::DARK_SIGHT_RADIUS, 0)));
+}
+
+#[test]
+fn a_torch_item_keeps_the_light_going_up_to_a_limit() {
+    let mut world = fixture::world(CORRIDOR);
+    world.torch = 0;
+    world.entities[world.player_id].inventory.extend(["torch".to_string(), "torch".to_string()]);
+    fixture::play(&mut world, &[Command::UseItem(0, vec![ItemEffect::Fuel(800)])]);
+    assert_eq!(world.torch, 800);
+    world.torch = light::MAX_TORCH_TURNS - 10;
+    fixture::play(&mut world, &[Command::UseItem(0, vec![ItemEffect::Fuel(800)])]);
+    assert_eq!(world.torch, light::MAX_TORCH_TURNS);
+}
+
+#[test]
+fn a_flare_lights_the_way_with_the_torch_out() {
+    let mut world = fixture::world(CORRIDOR);
+    world.torch = 0;
+    world.update_fov();
+    assert!(!world.player_is_lit());
+    world.start_timer(world.player_id, Timed::Light(8), 5);
+    world.update_fov();
+    assert!(world.player_is_lit());
+    assert!(world.is_visible(Vector::new(1 + fov::FOV_RADIUS, 0)));
+}